@@ -0,0 +1,59 @@
+/// Keeps a machine-readable record of every change sent to JIRA, for compliance reviews and for debugging bulk runs.
+///
+/// Audit logs are JSON Lines files, with one entry per mutating request, whether or not it succeeded.  Unlike the
+/// journal, the audit log records what was asked of the API rather than how to reverse it.
+use std::{
+    fs::{File, OpenOptions},
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use serde::Serialize;
+use serde_json::Value as JSONValue;
+
+/// One request that changed, or tried to change, something in JIRA.
+#[derive(Debug, Serialize)]
+pub struct Mutation<'a> {
+    pub timestamp: String,
+
+    pub issues: &'a [String],
+
+    pub operation: &'a str,
+
+    pub request: JSONValue,
+
+    /// The HTTP status JIRA responded with, or nothing if the request never got a response.
+    pub status: Option<u16>,
+}
+
+/// An audit log that can be written to from many threads at once.
+pub struct AuditLog {
+    path: PathBuf,
+    writer: Mutex<BufWriter<File>>,
+}
+
+impl AuditLog {
+    /// Opens an audit log for writing, appending to any entries already there.
+    pub fn create<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+
+        Ok(AuditLog {
+            path: path.as_ref().to_owned(),
+            writer: Mutex::new(BufWriter::new(file)),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Appends an entry to the log, flushing it straight away.
+    pub fn record(&self, mutation: &Mutation) -> io::Result<()> {
+        // Unwrap here is considered safe since nothing can panic while holding the lock.
+        let mut writer = self.writer.lock().unwrap();
+        serde_json::to_writer(&mut *writer, mutation)?;
+        writer.write_all(b"\n")?;
+        writer.flush()
+    }
+}
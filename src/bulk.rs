@@ -0,0 +1,352 @@
+/// Helpers for running many independent JIRA requests at once, like the edits made by the tag command, and for
+/// reporting on how they went.
+use std::{
+    future::Future,
+    io::{self, IsTerminal, Write},
+    process,
+};
+
+use colored::Colorize;
+use futures_util::{stream, StreamExt};
+
+use crate::{
+    checkpoint::Checkpoint,
+    journal::{Entry, Journal},
+};
+
+/// Calls `work` on every item with at most `concurrency` of them in flight at once, passing each result to `report` as
+/// it completes.
+///
+/// `report` always runs on the calling task, so it can print or tally results without any locking.  Results arrive in
+/// completion order rather than the order of `items`.
+pub async fn for_each_concurrently<'a, T, R, F, Fut, G>(
+    items: &'a [T],
+    concurrency: usize,
+    work: F,
+    mut report: G,
+) where
+    F: Fn(&'a T) -> Fut,
+    Fut: Future<Output = R>,
+    G: FnMut(&'a T, R),
+{
+    let work = &work;
+    let mut results = stream::iter(items)
+        .map(|item| async move { (item, work(item).await) })
+        .buffer_unordered(concurrency.max(1));
+
+    while let Some((item, result)) = results.next().await {
+        report(item, result);
+    }
+}
+
+/// What a bulk command did with an issue it was able to look at.
+pub enum Applied {
+    /// The issue was edited; these are the journal entries for each field that changed.
+    Changed(Vec<Entry>),
+    /// The issue was left alone, for the given reason.
+    Skipped(String),
+}
+
+/// What happened when a bulk command tried to make one change to one issue.
+pub enum Status {
+    Changed,
+    Skipped(String),
+    Failed(String),
+}
+
+/// The result of making one change to one issue, kept so that the whole run can be summarized at the end.
+pub struct Outcome {
+    pub key: String,
+    pub change: String,
+    pub status: Status,
+}
+
+/// How wide the progress bar is, in characters.
+const PROGRESS_BAR_WIDTH: usize = 30;
+
+/// Shows how a bulk run is going while it happens: a line per change as each one finishes, followed by a progress bar
+/// counting them off.
+///
+/// The bar is only drawn when stderr is a terminal, so that it never ends up in logs.  Otherwise each line counts off
+/// the changes itself, and nothing written to stderr has control characters in it.
+pub struct Progress {
+    base_url: String,
+    total: usize,
+    finished: usize,
+    draw: bool,
+}
+
+impl Progress {
+    pub fn new(base_url: &str) -> Self {
+        Progress {
+            base_url: base_url.trim_end_matches('/').to_owned(),
+            total: 0,
+            finished: 0,
+            draw: io::stderr().is_terminal(),
+        }
+    }
+
+    /// Counts more changes towards the total, as each part of a run works out how many it has to make.
+    pub fn add_to_total(&mut self, changes: usize) {
+        self.total += changes;
+        self.redraw();
+    }
+
+    fn report(&mut self, outcome: &Outcome) {
+        self.clear();
+        self.finished += 1;
+
+        let line = self.line(outcome);
+        match outcome.status {
+            Status::Failed(_) => eprintln!("{}", line),
+            _ => println!("{}", line),
+        }
+
+        self.redraw();
+    }
+
+    /// The line printed for a finished change.  Without the bar, it starts with how far along the run is instead.
+    fn line(&self, outcome: &Outcome) -> String {
+        let counter = if self.draw || self.total == 0 {
+            String::new()
+        } else {
+            format!("[{}/{}] ", self.finished, self.total)
+        };
+        let url = format!("{}/browse/{}", self.base_url, outcome.key);
+
+        match &outcome.status {
+            Status::Changed => format!("{}{} {}: {}", counter, "✓".green(), url, outcome.change),
+            Status::Skipped(reason) => format!(
+                "{}{} {}: skipped {}, since {}",
+                counter,
+                "-".yellow(),
+                url,
+                outcome.change.to_lowercase(),
+                reason
+            ),
+            // Failures go to stderr, which may not be a terminal even when stdout is, so colors are decided by it.
+            Status::Failed(e) => format!(
+                "{}{} {}: could not {}: {}",
+                counter,
+                if self.draw {
+                    "✗".red()
+                } else {
+                    "✗".normal()
+                },
+                url,
+                outcome.change.to_lowercase(),
+                e
+            ),
+        }
+    }
+
+    fn bar(&self) -> String {
+        let filled = PROGRESS_BAR_WIDTH * self.finished.min(self.total) / self.total;
+        format!(
+            "\r[{}{}] {}/{}",
+            "#".repeat(filled),
+            " ".repeat(PROGRESS_BAR_WIDTH - filled),
+            self.finished,
+            self.total
+        )
+    }
+
+    fn redraw(&self) {
+        if !self.draw || self.total == 0 {
+            return;
+        }
+
+        eprint!("{}", self.bar());
+        let _ = io::stderr().flush();
+    }
+
+    fn clear(&self) {
+        if self.draw && self.total > 0 {
+            eprint!("\r\x1b[2K");
+        }
+    }
+
+    /// Takes the progress bar off the screen, once the run is over.
+    pub fn finish(&self) {
+        self.clear();
+    }
+}
+
+/// Turns the result of trying to change an issue into an outcome, reporting it to the progress display and writing any changes that were made
+/// to the journal.  Finished changes are also marked in the checkpoint, if there is one.
+///
+/// If the journal cannot be written, this exits rather than going on to make changes that could not be undone.
+pub fn settle(
+    journal: &mut Journal,
+    checkpoint: &mut Option<Checkpoint>,
+    progress: &mut Progress,
+    key: &str,
+    change: &str,
+    result: Result<Applied, String>,
+) -> Outcome {
+    let status = match result {
+        Ok(Applied::Changed(entries)) => {
+            for entry in &entries {
+                if let Err(e) = journal.record(entry) {
+                    eprintln!(
+                        "Could not write to the journal at {}, so stopping rather than making changes that cannot be undone: {}",
+                        journal.path().display(),
+                        e
+                    );
+                    process::exit(1);
+                }
+            }
+            Status::Changed
+        }
+        Ok(Applied::Skipped(reason)) => Status::Skipped(reason),
+        Err(e) => Status::Failed(e),
+    };
+
+    if let Some(checkpoint) = checkpoint {
+        if !matches!(status, Status::Failed(_)) {
+            if let Err(e) = checkpoint.record(key, change) {
+                eprintln!(
+                    "Could not write to the checkpoint at {}; resuming this run may redo some changes: {}",
+                    checkpoint.path().display(),
+                    e
+                );
+            }
+        }
+    }
+
+    let outcome = Outcome {
+        key: key.to_owned(),
+        change: change.to_owned(),
+        status,
+    };
+    progress.report(&outcome);
+    outcome
+}
+
+/// Leaves out the items the checkpoint says are already finished, telling the user how many were left out, and counts
+/// the rest towards the progress total.
+///
+/// `identify` gives the issue key and change description that each item is recorded in the checkpoint under.
+pub fn remaining<'a, T, K>(
+    items: &'a [T],
+    checkpoint: &Option<Checkpoint>,
+    progress: &mut Progress,
+    identify: K,
+) -> Vec<&'a T>
+where
+    K: Fn(&T) -> (&str, String),
+{
+    let remaining: Vec<&T> = match checkpoint {
+        Some(checkpoint) => items
+            .iter()
+            .filter(|item| {
+                let (key, change) = identify(item);
+                !checkpoint.is_done(key, &change)
+            })
+            .collect(),
+        None => items.iter().collect(),
+    };
+
+    if remaining.len() < items.len() {
+        progress.clear();
+        println!(
+            "Skipping {} changes that were already made before the run was interrupted.",
+            items.len() - remaining.len()
+        );
+    }
+    progress.add_to_total(remaining.len());
+
+    remaining
+}
+
+pub fn any_failed(outcomes: &[Outcome]) -> bool {
+    outcomes
+        .iter()
+        .any(|outcome| matches!(outcome.status, Status::Failed(_)))
+}
+
+/// Prints a table of every outcome, followed by a count of how many changes were made, skipped, and failed.
+pub fn print_summary(outcomes: &[Outcome]) {
+    let key_width = outcomes
+        .iter()
+        .map(|outcome| outcome.key.len())
+        .chain(["Issue".len()])
+        .max()
+        .unwrap_or(0);
+    let change_width = outcomes
+        .iter()
+        .map(|outcome| outcome.change.len())
+        .chain(["Change".len()])
+        .max()
+        .unwrap_or(0);
+
+    println!();
+    println!("{:key_width$}  {:change_width$}  Result", "Issue", "Change");
+    for outcome in outcomes {
+        let result = match &outcome.status {
+            Status::Changed => "ok".green(),
+            Status::Skipped(reason) => format!("skipped: {}", reason).yellow(),
+            Status::Failed(e) => format!("failed: {}", e).red(),
+        };
+        println!(
+            "{:key_width$}  {:change_width$}  {}",
+            outcome.key, outcome.change, result
+        );
+    }
+
+    let count = |matches: fn(&Status) -> bool| {
+        outcomes
+            .iter()
+            .filter(|outcome| matches(&outcome.status))
+            .count()
+    };
+    println!();
+    println!(
+        "{} changed, {} skipped, {} failed.",
+        format!("{}", count(|status| matches!(status, Status::Changed))).green(),
+        format!("{}", count(|status| matches!(status, Status::Skipped(_)))).yellow(),
+        format!("{}", count(|status| matches!(status, Status::Failed(_)))).red()
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn progress(draw: bool) -> Progress {
+        Progress {
+            base_url: "https://example.atlassian.net".to_owned(),
+            total: 4,
+            finished: 1,
+            draw,
+        }
+    }
+
+    fn failed() -> Outcome {
+        Outcome {
+            key: "TEAM-1".to_owned(),
+            change: "Add label x".to_owned(),
+            status: Status::Failed("JIRA said no".to_owned()),
+        }
+    }
+
+    #[test]
+    fn lines_count_changes_off_when_there_is_no_bar() {
+        assert_eq!(
+            progress(false).line(&failed()),
+            "[1/4] ✗ https://example.atlassian.net/browse/TEAM-1: could not add label x: JIRA said no"
+        );
+    }
+
+    #[test]
+    fn the_bar_fills_up_as_changes_finish() {
+        let mut progress = progress(true);
+        assert_eq!(
+            progress.bar(),
+            format!("\r[{}{}] 1/4", "#".repeat(7), " ".repeat(23))
+        );
+
+        progress.finished = 4;
+        assert_eq!(progress.bar(), format!("\r[{}] 4/4", "#".repeat(30)));
+    }
+}
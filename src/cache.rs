@@ -0,0 +1,193 @@
+/// Keeps JIRA's responses on disk for a while, so that runs close together, like several exports during one planning
+/// session, don't fetch thousands of identical issues again.
+///
+/// Each response is a JSON file named after a hash of the request it answers.  The file also holds the request itself,
+/// so a hash collision is a miss rather than the wrong answer.  Entries older than the time to live are stale: rather
+/// than being used as they are, they are checked with JIRA using the validators it sent along with them.
+use std::{
+    collections::hash_map::DefaultHasher,
+    env, fs,
+    hash::{Hash, Hasher},
+    io,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JSONValue;
+
+#[derive(Debug, Deserialize, Serialize)]
+struct CachedResponse {
+    request: String,
+    response: JSONValue,
+
+    #[serde(default)]
+    etag: Option<String>,
+
+    #[serde(default)]
+    last_modified: Option<String>,
+}
+
+/// A response found in the cache.
+pub struct Entry {
+    pub response: JSONValue,
+    /// The `ETag` header JIRA sent with the response, to ask whether it has changed with `If-None-Match`.
+    pub etag: Option<String>,
+    /// The `Last-Modified` header JIRA sent with the response, to ask whether it has changed with
+    /// `If-Modified-Since`.
+    pub last_modified: Option<String>,
+    /// Whether the entry is younger than the time to live, so it can be used without checking with JIRA.
+    pub fresh: bool,
+}
+
+pub struct ResponseCache {
+    dir: PathBuf,
+    ttl: Duration,
+}
+
+/// Where the cache lives by default: `$XDG_CACHE_HOME/statustracker`, or `~/.cache/statustracker`.
+pub fn default_dir() -> Option<PathBuf> {
+    let cache_home = env::var_os("XDG_CACHE_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))?;
+
+    Some(cache_home.join("statustracker"))
+}
+
+impl ResponseCache {
+    /// Opens the cache in the given directory, creating it if need be.
+    pub fn open<P: AsRef<Path>>(dir: P, ttl: Duration) -> io::Result<Self> {
+        fs::create_dir_all(&dir)?;
+
+        Ok(ResponseCache {
+            dir: dir.as_ref().to_owned(),
+            ttl,
+        })
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn path(&self, request: &str) -> PathBuf {
+        let mut hasher = DefaultHasher::new();
+        request.hash(&mut hasher);
+
+        self.dir.join(format!("{:016x}.json", hasher.finish()))
+    }
+
+    /// Looks up the response to a request, whether or not it is fresh.
+    ///
+    /// Anything that goes wrong reading the cache counts as a miss, since the request can always be sent instead.
+    pub fn get(&self, request: &str) -> Option<Entry> {
+        let path = self.path(request);
+        let age = fs::metadata(&path)
+            .and_then(|metadata| metadata.modified())
+            .ok()
+            .and_then(|modified| SystemTime::now().duration_since(modified).ok())?;
+
+        let cached: CachedResponse = serde_json::from_slice(&fs::read(&path).ok()?).ok()?;
+        if cached.request != request {
+            return None;
+        }
+
+        Some(Entry {
+            response: cached.response,
+            etag: cached.etag,
+            last_modified: cached.last_modified,
+            fresh: age <= self.ttl,
+        })
+    }
+
+    /// Saves the response to a request, along with the headers needed to check later whether it has changed.
+    ///
+    /// Saving an entry again makes it fresh again.  The entry is written to a temporary file and then moved into
+    /// place, so that concurrent runs never read half an entry.
+    pub fn put(
+        &self,
+        request: &str,
+        response: &JSONValue,
+        etag: Option<&str>,
+        last_modified: Option<&str>,
+    ) -> io::Result<()> {
+        let path = self.path(request);
+        let temporary_path = path.with_extension(format!("{}.tmp", std::process::id()));
+        let cached = CachedResponse {
+            request: request.to_owned(),
+            response: response.clone(),
+            etag: etag.map(|etag| etag.to_owned()),
+            last_modified: last_modified.map(|last_modified| last_modified.to_owned()),
+        };
+
+        fs::write(&temporary_path, serde_json::to_vec(&cached)?)?;
+        fs::rename(&temporary_path, &path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn cache(name: &str, ttl: Duration) -> ResponseCache {
+        let dir = env::temp_dir().join(format!(
+            "statustracker-cache-test-{}-{}",
+            name,
+            std::process::id()
+        ));
+        ResponseCache::open(dir, ttl).unwrap()
+    }
+
+    #[test]
+    fn entries_come_back_with_their_validators() {
+        let cache = cache("validators", Duration::from_secs(60));
+        let response = json!({"issues": []});
+        cache
+            .put(
+                "GET /search",
+                &response,
+                Some("\"v1\""),
+                Some("Wed, 21 Oct 2015 07:28:00 GMT"),
+            )
+            .unwrap();
+
+        let entry = cache.get("GET /search").unwrap();
+        fs::remove_dir_all(cache.dir()).unwrap();
+
+        assert_eq!(entry.response, response);
+        assert_eq!(entry.etag.as_deref(), Some("\"v1\""));
+        assert_eq!(
+            entry.last_modified.as_deref(),
+            Some("Wed, 21 Oct 2015 07:28:00 GMT")
+        );
+        assert!(entry.fresh);
+    }
+
+    #[test]
+    fn entries_older_than_the_time_to_live_are_stale() {
+        let cache = cache("stale", Duration::ZERO);
+        cache.put("GET /field", &json!([]), None, None).unwrap();
+        std::thread::sleep(Duration::from_millis(20));
+
+        let entry = cache.get("GET /field").unwrap();
+        fs::remove_dir_all(cache.dir()).unwrap();
+
+        assert!(!entry.fresh);
+        assert_eq!(entry.etag, None);
+    }
+
+    #[test]
+    fn a_file_for_another_request_is_a_miss() {
+        let cache = cache("collision", Duration::from_secs(60));
+        cache.put("GET /a", &json!(1), None, None).unwrap();
+        // As if another request hashed to the same file.
+        fs::rename(cache.path("GET /a"), cache.path("GET /b")).unwrap();
+
+        let entry = cache.get("GET /b");
+        fs::remove_dir_all(cache.dir()).unwrap();
+
+        assert!(entry.is_none());
+    }
+}
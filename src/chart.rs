@@ -0,0 +1,147 @@
+/// Draws line charts as SVG, for reports that are meant to be looked at rather than loaded into a spreadsheet.
+///
+/// The charts are plain enough to be written by hand, so this does not pull in a whole plotting library.  SVG opens in
+/// any browser, and can be dropped straight into a wiki page or a slide.
+const WIDTH: f64 = 800.0;
+const HEIGHT: f64 = 400.0;
+const MARGIN_LEFT: f64 = 60.0;
+const MARGIN_RIGHT: f64 = 150.0;
+const MARGIN_TOP: f64 = 40.0;
+const MARGIN_BOTTOM: f64 = 60.0;
+
+/// The most labels to put along the bottom, so that they don't run into each other.
+const MAX_X_LABELS: usize = 10;
+
+/// How many lines to divide the values up with.
+const Y_TICKS: usize = 5;
+
+/// A line on a chart, with a value for each label along the bottom.
+pub struct Series<'a> {
+    pub name: &'a str,
+
+    /// Any color SVG understands, like `#1f77b4`.
+    pub color: &'a str,
+
+    pub dashed: bool,
+
+    pub values: Vec<f64>,
+}
+
+/// Draws a chart with a line for each series, against labels spread evenly along the bottom.
+pub fn line_chart(title: &str, labels: &[String], series: &[Series]) -> String {
+    let plot_width = WIDTH - MARGIN_LEFT - MARGIN_RIGHT;
+    let plot_height = HEIGHT - MARGIN_TOP - MARGIN_BOTTOM;
+    let max_value = series
+        .iter()
+        .flat_map(|series| series.values.iter().copied())
+        .fold(0.0, f64::max);
+    // Round the top of the chart up, so the lines across it fall on whole numbers.
+    let top = (max_value / Y_TICKS as f64).ceil().max(1.0) * Y_TICKS as f64;
+    let step = plot_width / labels.len().saturating_sub(1).max(1) as f64;
+    let x = |i: usize| MARGIN_LEFT + i as f64 * step;
+    let y = |value: f64| MARGIN_TOP + plot_height * (1.0 - value / top);
+
+    let mut svg = format!(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" viewBox="0 0 {} {}" font-family="sans-serif" font-size="12">"#,
+        WIDTH, HEIGHT, WIDTH, HEIGHT
+    );
+    svg.push('\n');
+    svg.push_str(&format!(
+        "<rect width=\"{}\" height=\"{}\" fill=\"white\"/>\n",
+        WIDTH, HEIGHT
+    ));
+    svg.push_str(&format!(
+        "<text x=\"{}\" y=\"24\" text-anchor=\"middle\" font-size=\"16\">{}</text>\n",
+        MARGIN_LEFT + plot_width / 2.0,
+        escape(title)
+    ));
+
+    for tick in 0..=Y_TICKS {
+        let value = top * tick as f64 / Y_TICKS as f64;
+        svg.push_str(&format!(
+            "<line x1=\"{}\" y1=\"{:.1}\" x2=\"{}\" y2=\"{:.1}\" stroke=\"#ddd\"/>\n",
+            MARGIN_LEFT,
+            y(value),
+            MARGIN_LEFT + plot_width,
+            y(value)
+        ));
+        svg.push_str(&format!(
+            "<text x=\"{}\" y=\"{:.1}\" text-anchor=\"end\" dominant-baseline=\"middle\">{}</text>\n",
+            MARGIN_LEFT - 8.0,
+            y(value),
+            value
+        ));
+    }
+
+    let every = labels.len().div_ceil(MAX_X_LABELS).max(1);
+    for (i, label) in labels.iter().enumerate() {
+        if i % every != 0 && i != labels.len() - 1 {
+            continue;
+        }
+        svg.push_str(&format!(
+            "<text x=\"{:.1}\" y=\"{}\" text-anchor=\"end\" transform=\"rotate(-30 {:.1} {})\">{}</text>\n",
+            x(i),
+            HEIGHT - MARGIN_BOTTOM + 16.0,
+            x(i),
+            HEIGHT - MARGIN_BOTTOM + 16.0,
+            escape(label)
+        ));
+    }
+
+    for (n, series) in series.iter().enumerate() {
+        let points: Vec<String> = series
+            .values
+            .iter()
+            .enumerate()
+            .map(|(i, value)| format!("{:.1},{:.1}", x(i), y(*value)))
+            .collect();
+        let dash = if series.dashed {
+            " stroke-dasharray=\"6 4\""
+        } else {
+            ""
+        };
+        svg.push_str(&format!(
+            "<polyline points=\"{}\" fill=\"none\" stroke=\"{}\" stroke-width=\"2\"{}/>\n",
+            points.join(" "),
+            escape(series.color),
+            dash
+        ));
+
+        let legend_y = MARGIN_TOP + 20.0 * n as f64;
+        let legend_x = WIDTH - MARGIN_RIGHT + 16.0;
+        svg.push_str(&format!(
+            "<line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke=\"{}\" stroke-width=\"2\"{}/>\n",
+            legend_x,
+            legend_y,
+            legend_x + 20.0,
+            legend_y,
+            escape(series.color),
+            dash
+        ));
+        svg.push_str(&format!(
+            "<text x=\"{}\" y=\"{}\" dominant-baseline=\"middle\">{}</text>\n",
+            legend_x + 26.0,
+            legend_y,
+            escape(series.name)
+        ));
+    }
+
+    svg.push_str(&format!(
+        "<line x1=\"{0}\" y1=\"{1}\" x2=\"{0}\" y2=\"{2}\" stroke=\"black\"/>\n<line x1=\"{0}\" y1=\"{2}\" x2=\"{3}\" y2=\"{2}\" stroke=\"black\"/>\n",
+        MARGIN_LEFT,
+        MARGIN_TOP,
+        MARGIN_TOP + plot_height,
+        MARGIN_LEFT + plot_width
+    ));
+    svg.push_str("</svg>\n");
+
+    svg
+}
+
+/// Escapes text to go in SVG, or HTML, as an attribute value or between tags.
+pub fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
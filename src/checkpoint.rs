@@ -0,0 +1,155 @@
+/// Tracks which changes a bulk command has already finished, so that an interrupted run can pick up where it left off.
+///
+/// Checkpoints are JSON Lines files, with one line per change that was either made or deliberately skipped.  Failed
+/// changes are left out, so that resuming retries them.
+use std::{
+    collections::HashMap,
+    fs::{File, OpenOptions},
+    io::{self, BufRead, BufReader, BufWriter, Write},
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Deserialize, Serialize)]
+struct Done {
+    key: String,
+    change: String,
+    /// The key of the issue the change created, for changes that create one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    created: Option<String>,
+}
+
+pub struct Checkpoint {
+    path: PathBuf,
+    writer: BufWriter<File>,
+    /// Each finished change, with the key of the issue it created, if it created one.
+    done: HashMap<(String, String), Option<String>>,
+}
+
+impl Checkpoint {
+    /// Starts a fresh checkpoint, replacing anything already at the path.
+    pub fn create<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Ok(Checkpoint {
+            path: path.as_ref().to_owned(),
+            writer: BufWriter::new(File::create(path)?),
+            done: HashMap::new(),
+        })
+    }
+
+    /// Loads an existing checkpoint, and keeps adding to it.
+    pub fn resume<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let mut done = HashMap::new();
+        for line in BufReader::new(File::open(&path)?).lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let entry: Done = serde_json::from_str(&line)?;
+            done.insert((entry.key, entry.change), entry.created);
+        }
+
+        Ok(Checkpoint {
+            path: path.as_ref().to_owned(),
+            writer: BufWriter::new(OpenOptions::new().append(true).open(path)?),
+            done,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn is_done(&self, key: &str, change: &str) -> bool {
+        self.done.contains_key(&(key.to_owned(), change.to_owned()))
+    }
+
+    /// The key of the issue a finished change created, if it created one.
+    pub fn created(&self, key: &str, change: &str) -> Option<&str> {
+        self.done
+            .get(&(key.to_owned(), change.to_owned()))?
+            .as_deref()
+    }
+
+    /// Marks a change as finished.
+    ///
+    /// The checkpoint is flushed after every line, since its whole point is to survive the run being killed.
+    pub fn record(&mut self, key: &str, change: &str) -> io::Result<()> {
+        self.write(Done {
+            key: key.to_owned(),
+            change: change.to_owned(),
+            created: None,
+        })
+    }
+
+    /// Marks a change that created an issue as finished, along with the key of the issue.
+    pub fn record_created(&mut self, key: &str, change: &str, created: &str) -> io::Result<()> {
+        self.write(Done {
+            key: key.to_owned(),
+            change: change.to_owned(),
+            created: Some(created.to_owned()),
+        })
+    }
+
+    fn write(&mut self, entry: Done) -> io::Result<()> {
+        serde_json::to_writer(&mut self.writer, &entry)?;
+        self.writer.write_all(b"\n")?;
+        self.writer.flush()?;
+
+        self.done.insert((entry.key, entry.change), entry.created);
+        Ok(())
+    }
+}
+
+/// Opens the checkpoint for a run: resuming one if asked to, starting one if a path was given, or neither.
+pub fn open(checkpoint: Option<&Path>, resume: Option<&Path>) -> io::Result<Option<Checkpoint>> {
+    match (resume, checkpoint) {
+        (Some(path), _) => Checkpoint::resume(path).map(Some),
+        (None, Some(path)) => Checkpoint::create(path).map(Some),
+        (None, None) => Ok(None),
+    }
+}
+
+/// Where a command's checkpoint goes when it always keeps one and the user does not choose a path; timestamped so
+/// earlier runs are never overwritten.
+pub fn default_path(command: &str) -> PathBuf {
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or(0);
+
+    PathBuf::from(format!("{}-checkpoint-{}.jsonl", command, seconds))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resuming_reads_back_what_was_recorded() {
+        let path = std::env::temp_dir().join(format!(
+            "statustracker-checkpoint-test-{}.jsonl",
+            std::process::id()
+        ));
+        let mut checkpoint = Checkpoint::create(&path).unwrap();
+        checkpoint.record("TEAM-1", "Add label x").unwrap();
+        checkpoint
+            .record_created("Line 2", "Create \"Login\" (Story)", "TEAM-9")
+            .unwrap();
+        drop(checkpoint);
+
+        let resumed = Checkpoint::resume(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(resumed.is_done("TEAM-1", "Add label x"));
+        assert_eq!(resumed.created("TEAM-1", "Add label x"), None);
+        assert!(resumed.is_done("Line 2", "Create \"Login\" (Story)"));
+        assert_eq!(
+            resumed.created("Line 2", "Create \"Login\" (Story)"),
+            Some("TEAM-9")
+        );
+        assert!(!resumed.is_done("TEAM-1", "Add label y"));
+    }
+}
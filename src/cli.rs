@@ -1,21 +1,143 @@
-use std::{fmt, io};
+use std::{
+    error::Error,
+    fmt,
+    io::{self, Write},
+    process,
+};
 
-use argfile;
-use clap::Parser;
+use clap::{error::ErrorKind, CommandFactory, Parser, Subcommand};
 
+pub mod aging;
+pub mod apply;
+pub mod blocked;
+pub mod bugs;
+pub mod burndown;
+pub mod burnup;
+pub mod cfd;
+pub mod config;
+pub mod connection;
+pub mod counts;
+pub mod critical_path;
 pub mod csv;
+pub mod cycle_time;
+pub mod deps;
+pub mod detect_fields;
+pub mod doctor;
+pub mod epics;
 pub mod estimate;
+pub mod forecast;
+pub mod gantt;
+pub mod history;
+pub mod hygiene;
+pub mod ical;
+pub mod import;
+pub mod init;
+pub mod list_fields;
+pub mod list_issue_types;
+pub mod list_projects;
+pub mod list_statuses;
+pub mod output;
+pub mod publish_confluence;
+pub mod rank;
+pub mod release_readiness;
+pub mod report;
+pub mod roadmap;
+pub mod save_snapshot;
+pub mod scope;
+pub mod serve;
+pub mod sla;
+pub mod sprint_fit;
+pub mod sprint_report;
+pub mod standup;
 pub mod tag;
+pub mod throughput;
+pub mod time_in_status;
+pub mod timeline;
+pub mod watch;
+pub mod whoami;
 
 #[derive(Debug, Parser)]
 #[command(name = "statustracker")]
 #[command(author = "Jim Berlage <jamesberlage@gmail.com>")]
 #[command(version = "1.0.0")]
 #[command(about = "A suite of utilities to estimate time left to complete a project.  Based on team velocity and estimated story points.", long_about = None)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: StatusTracker,
+
+    #[command(flatten)]
+    pub watch: watch::Watch,
+}
+
+#[derive(Debug, Subcommand)]
 pub enum StatusTracker {
-    CSV(csv::CSV),
+    Aging(aging::Aging),
+    Apply(apply::Apply),
+    Audit(hygiene::Hygiene),
+    Blocked(blocked::Blocked),
+    Bugs(bugs::Bugs),
+    Burndown(burndown::Burndown),
+    Burnup(burndown::Burndown),
+    Cfd(cfd::Cfd),
+    Config(config::Config),
+    Counts(counts::Counts),
+    CriticalPath(critical_path::CriticalPath),
+    Csv(csv::Csv),
+    CycleTime(cycle_time::CycleTime),
+    Deps(deps::Deps),
+    DetectFields(detect_fields::DetectFields),
+    Doctor(doctor::Doctor),
+    Epics(epics::Epics),
     Estimate(estimate::Estimate),
-    Tag(tag::Tag),
+    Forecast(forecast::Forecast),
+    Gantt(gantt::Gantt),
+    History(history::History),
+    Ical(ical::Ical),
+    Import(import::Import),
+    Init(init::Init),
+    ListFields(list_fields::ListFields),
+    ListIssueTypes(list_issue_types::ListIssueTypes),
+    ListProjects(list_projects::ListProjects),
+    ListStatuses(list_statuses::ListStatuses),
+    PublishConfluence(publish_confluence::PublishConfluence),
+    Rank(rank::Rank),
+    ReleaseReadiness(release_readiness::ReleaseReadiness),
+    Report(report::Report),
+    Roadmap(roadmap::Roadmap),
+    Serve(serve::Serve),
+    Sla(sla::Sla),
+    Snapshot(save_snapshot::SaveSnapshot),
+    SprintFit(sprint_fit::SprintFit),
+    SprintReport(sprint_report::SprintReport),
+    Standup(standup::Standup),
+    Tag(Box<tag::Tag>),
+    Throughput(throughput::Throughput),
+    TimeInStatus(time_in_status::TimeInStatus),
+    Whoami(whoami::Whoami),
+}
+
+impl StatusTracker {
+    /// Why the command can't be run with --watch, if it can't: running it again every interval would repeat its
+    /// changes, or it never finishes a run to show.
+    fn why_not_watchable(&self) -> Option<&'static str> {
+        match self {
+            StatusTracker::Apply(_) | StatusTracker::Import(_) | StatusTracker::Tag(_) => {
+                Some("it changes issues in JIRA, and would make its changes again every interval")
+            }
+            StatusTracker::PublishConfluence(_) => {
+                Some("it publishes to Confluence, and would publish again every interval")
+            }
+            StatusTracker::Init(_)
+            | StatusTracker::Config(config::Config {
+                action: config::ConfigAction::Set { .. } | config::ConfigAction::Unset { .. },
+                ..
+            }) => Some("it changes the argfile, and would change it again every interval"),
+            StatusTracker::Serve(_) => {
+                Some("it keeps running until stopped, so there is no run to show")
+            }
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -37,9 +159,94 @@ impl fmt::Display for ParseError {
     }
 }
 
-pub fn parse() -> Result<StatusTracker, ParseError> {
+impl Error for ParseError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ParseError::ProblemUnwrappingArgfileError(inner) => Some(inner),
+            ParseError::CLIParseError(inner) => Some(inner),
+        }
+    }
+}
+
+pub fn parse() -> Result<Cli, ParseError> {
     let args = argfile::expand_args(argfile::parse_fromfile, argfile::PREFIX)
-        .map_err(|e| ParseError::ProblemUnwrappingArgfileError(e))?;
+        .map_err(ParseError::ProblemUnwrappingArgfileError)?;
+
+    parse_from(args).map_err(ParseError::CLIParseError)
+}
 
-    StatusTracker::try_parse_from(args).map_err(|e| ParseError::CLIParseError(e))
+fn parse_from<I, T>(args: I) -> Result<Cli, clap::Error>
+where
+    I: IntoIterator<Item = T>,
+    T: Into<std::ffi::OsString> + Clone,
+{
+    let cli = Cli::try_parse_from(args)?;
+    if cli.watch.watch {
+        if let Some(reason) = cli.command.why_not_watchable() {
+            return Err(Cli::command().error(
+                ErrorKind::ArgumentConflict,
+                format!("--watch can't be used with this command, since {}.", reason),
+            ));
+        }
+    }
+
+    Ok(cli)
+}
+
+/// Prints an error meant for the user to stderr, and exits with a failing status code.
+///
+/// The return type is generic so this can be passed straight to `unwrap_or_else`.
+pub fn exit_with_error<E: fmt::Display, T>(e: E) -> T {
+    eprintln!("{}", e);
+    process::exit(1);
+}
+
+/// Asks the user a yes or no question on the terminal, defaulting to no.
+///
+/// Anything other than "y" or "yes" counts as a no, including stdin being closed, so that scripts that forget to opt
+/// out of the prompt never make changes by accident.
+pub fn confirm(question: &str) -> bool {
+    print!("{} [y/N] ", question);
+    if io::stdout().flush().is_err() {
+        return false;
+    }
+
+    let mut answer = String::new();
+    if io::stdin().read_line(&mut answer).is_err() {
+        return false;
+    }
+
+    matches!(answer.trim().to_lowercase().as_str(), "y" | "yes")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn watch_is_refused_for_commands_that_make_changes() {
+        for args in [
+            &["import", "issues.csv", "--jira-project", "TEAM", "--watch"][..],
+            &["apply", "changes.csv", "--watch"],
+            &["init", "--watch"],
+            &[
+                "config",
+                "set",
+                "--watch",
+                "jira-url",
+                "https://example.com",
+            ],
+        ] {
+            let error = parse_from(["statustracker"].iter().chain(args)).unwrap_err();
+            assert_eq!(error.kind(), ErrorKind::ArgumentConflict, "{:?}", args);
+        }
+    }
+
+    #[test]
+    fn watch_is_allowed_for_commands_that_only_read() {
+        for args in [&["whoami", "--watch"][..], &["config", "list", "--watch"]] {
+            let cli = parse_from(["statustracker"].iter().chain(args)).unwrap();
+            assert!(cli.watch.watch);
+        }
+    }
 }
@@ -0,0 +1,131 @@
+use clap::Args;
+use colored::Colorize;
+use serde::Serialize;
+
+use crate::{
+    cli::{
+        connection::Connection,
+        exit_with_error,
+        output::{self, Format},
+        scope::Scope,
+    },
+    date::DateTime,
+    jira::{self, api::JiraApi, history, jql::SortDirection, AssigneeNames},
+};
+
+const SECONDS_PER_DAY: f64 = 24.0 * 60.0 * 60.0;
+
+#[derive(Debug, Args)]
+pub struct Aging {
+    /// csv or json, for a script to read.  Without this, the issues are shown as a table, with the stale ones in red.
+    #[arg(long)]
+    pub format: Option<Format>,
+
+    /// Flag issues that have been in their status for longer than this many days.
+    #[arg(long)]
+    #[arg(default_value_t = 5.0)]
+    pub stale_after_days: f64,
+
+    #[command(flatten)]
+    pub scope: Scope,
+
+    #[command(flatten)]
+    pub connection: Connection,
+}
+
+/// An issue being worked on, and how long it has sat in its status.
+#[derive(Debug, Serialize)]
+struct AgingIssue {
+    key: String,
+    status: String,
+    assignee: String,
+    days_in_status: f64,
+    stale: bool,
+    link: String,
+}
+
+/// Finds the issues in progress, longest in their status first.
+async fn aging(client: &impl JiraApi, args: &Aging) -> Result<Vec<AgingIssue>, String> {
+    let jql = args.scope.builder(client).await.and_then(|builder| {
+        builder
+            .status_category("In Progress")
+            .order_by("key", SortDirection::Ascending)
+            .build()
+    })?;
+
+    if args.scope.validate_jql {
+        jira::validate_jql(client, &jql)
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+
+    let fields = vec![
+        "status".to_owned(),
+        "assignee".to_owned(),
+        "created".to_owned(),
+    ];
+    let issues = client
+        .search_all_with_changelogs(&fields, &jql)
+        .await
+        .map_err(|e| format!("Could not search for issues: {}", e))?;
+
+    let now = DateTime::now().to_unix_seconds();
+    let assignee_names = AssigneeNames::new();
+    let mut aging = vec![];
+    for issue in &issues {
+        let days_in_status = history::entered_status_at(issue)
+            .map(|entered_at| (now - entered_at.to_unix_seconds()) as f64 / SECONDS_PER_DAY)
+            .unwrap_or(0.0);
+
+        aging.push(AgingIssue {
+            key: issue.key.clone(),
+            status: issue.status().unwrap_or_default(),
+            assignee: assignee_names
+                .assignee(client, issue)
+                .await
+                .unwrap_or_else(|| "Unassigned".to_owned()),
+            days_in_status: (days_in_status * 10.0).round() / 10.0,
+            stale: days_in_status > args.stale_after_days,
+            link: format!("{}/browse/{}", args.connection.jira_url, issue.key),
+        });
+    }
+
+    aging.sort_by(|a, b| b.days_in_status.total_cmp(&a.days_in_status));
+
+    Ok(aging)
+}
+
+fn show(args: &Aging, aging: &[AgingIssue]) {
+    for issue in aging {
+        let line = format!(
+            "{:>7}  {:<12} {:<20} {}",
+            format!("{:.1}d", issue.days_in_status),
+            issue.key,
+            issue.status,
+            issue.assignee
+        );
+        if issue.stale {
+            println!("{}", line.red().bold());
+        } else {
+            println!("{}", line);
+        }
+    }
+
+    let stale = aging.iter().filter(|issue| issue.stale).count();
+    println!(
+        "{} of {} issues in progress have been in their status for more than {} days.",
+        format!("{}", stale).bright_red(),
+        aging.len(),
+        args.stale_after_days
+    );
+}
+
+pub async fn run(args: &Aging) {
+    let client = args.connection.connect().unwrap_or_else(exit_with_error);
+    let aging = aging(&client, args).await.unwrap_or_else(exit_with_error);
+
+    match args.format {
+        Some(format) => output::write_rows(format, &aging).unwrap_or_else(exit_with_error),
+        None => show(args, &aging),
+    }
+}
@@ -0,0 +1,507 @@
+use std::{io, path::PathBuf, process};
+
+use clap::Args;
+use serde_json::{json, Map};
+
+use crate::{
+    audit::AuditLog,
+    bulk::{self, Applied, Outcome, Progress},
+    checkpoint,
+    cli::{self, connection::Connection, exit_with_error},
+    jira::{
+        self,
+        api::JiraApi,
+        client::{IssueEditUpdate, IssueEditUpdateLabel, RequestError},
+    },
+    journal::{self, Change, Entry, Journal},
+};
+
+#[derive(Debug, Args)]
+pub struct Apply {
+    /// A CSV file with a row for each issue to change, like the csv command writes.  It needs an ID column with the
+    /// issue keys, and can have Story Points, Labels, and Fix Versions columns.  An empty cell leaves the field alone,
+    /// while `-` or `(none)` clears it.
+    pub changes: PathBuf,
+
+    #[arg(long)]
+    pub audit_log: Option<PathBuf>,
+
+    #[arg(long)]
+    #[arg(conflicts_with = "resume")]
+    pub checkpoint: Option<PathBuf>,
+
+    #[arg(long)]
+    #[arg(default_value_t = 4)]
+    pub concurrency: usize,
+
+    #[arg(long)]
+    #[arg(default_value = "Story Points")]
+    pub jira_story_points_field: String,
+
+    #[arg(long)]
+    pub journal: Option<PathBuf>,
+
+    #[arg(long)]
+    pub resume: Option<PathBuf>,
+
+    #[arg(long)]
+    #[arg(default_value_t = false)]
+    pub yes: bool,
+
+    #[command(flatten)]
+    pub connection: Connection,
+}
+
+/// The changes to make to one issue, read from a row of the CSV file.
+///
+/// `None` means the column was missing or the cell was empty, and the field should be left alone.  An empty list means
+/// the cell held `CLEAR_MARKERS`, and the field should be cleared.
+struct Row {
+    key: String,
+    story_points: Option<f64>,
+    labels: Option<Vec<String>>,
+    fix_versions: Option<Vec<String>>,
+}
+
+impl Row {
+    fn describe(&self) -> String {
+        let mut changes = vec![];
+        if let Some(points) = self.story_points {
+            changes.push(format!("story points to {}", points));
+        }
+        if let Some(labels) = &self.labels {
+            changes.push(format!("labels to {}", describe_list(labels)));
+        }
+        if let Some(fix_versions) = &self.fix_versions {
+            changes.push(format!("fix versions to {}", describe_list(fix_versions)));
+        }
+
+        format!("Set {}", changes.join(", "))
+    }
+
+    fn is_empty(&self) -> bool {
+        self.story_points.is_none() && self.labels.is_none() && self.fix_versions.is_none()
+    }
+}
+
+/// What a labels or fix versions cell holds to clear the field, since an empty cell leaves it alone.
+const CLEAR_MARKERS: &[&str] = &["-", "(none)"];
+
+/// Splits a list cell into its values, or into none when the cell holds one of `CLEAR_MARKERS`.
+fn list_cell(cell: &str, separator: impl Fn(char) -> bool) -> Vec<String> {
+    if CLEAR_MARKERS.contains(&cell.to_lowercase().as_str()) {
+        return vec![];
+    }
+
+    cell.split(separator)
+        .map(|value| value.trim())
+        .filter(|value| !value.is_empty())
+        .map(|value| value.to_owned())
+        .collect()
+}
+
+fn describe_list(values: &[String]) -> String {
+    if values.is_empty() {
+        "(none)".to_owned()
+    } else {
+        values.join(" ")
+    }
+}
+
+/// Where each understood column is in the file.  Columns the command does not know about, like the status and link
+/// columns from the csv command, are ignored.
+struct Columns {
+    key: usize,
+    story_points: Option<usize>,
+    labels: Option<usize>,
+    fix_versions: Option<usize>,
+}
+
+impl Columns {
+    fn find(headers: &csv::StringRecord, story_points_field: &str) -> Result<Self, String> {
+        let position = |names: &[&str]| {
+            headers
+                .iter()
+                .position(|header| names.contains(&header.trim().to_lowercase().as_str()))
+        };
+        let story_points_field = story_points_field.to_lowercase();
+
+        Ok(Columns {
+            key: position(&["id", "key", "issue", "issue key"]).ok_or_else(|| {
+                "The file needs a column with the issue keys in it, named ID or Key.".to_owned()
+            })?,
+            story_points: position(&["story points", &story_points_field]),
+            labels: position(&["labels"]),
+            fix_versions: position(&["fix versions", "fix version", "fix version/s"]),
+        })
+    }
+}
+
+fn read_rows(args: &Apply) -> Result<Vec<Row>, String> {
+    let reader = csv::Reader::from_path(&args.changes)
+        .map_err(|e| format!("Could not open {}: {}", args.changes.display(), e))?;
+
+    parse_rows(reader, &args.jira_story_points_field)
+}
+
+fn parse_rows<R: io::Read>(
+    mut reader: csv::Reader<R>,
+    story_points_field: &str,
+) -> Result<Vec<Row>, String> {
+    let headers = reader
+        .headers()
+        .map_err(|e| format!("Could not read the header row: {}", e))?
+        .clone();
+    let columns = Columns::find(&headers, story_points_field)?;
+
+    let mut rows = vec![];
+    for (i, record) in reader.records().enumerate() {
+        // The header is line 1.
+        let line = i + 2;
+        let record = record.map_err(|e| format!("Could not read line {}: {}", line, e))?;
+        let cell = |column: Option<usize>| {
+            column
+                .and_then(|column| record.get(column))
+                .map(|value| value.trim())
+                .filter(|value| !value.is_empty())
+        };
+
+        let key = match cell(Some(columns.key)) {
+            Some(key) => key.to_owned(),
+            None => return Err(format!("Line {} does not have an issue key.", line)),
+        };
+        let story_points = match cell(columns.story_points) {
+            Some(points) => Some(points.parse::<f64>().map_err(|_| {
+                format!(
+                    "Line {} has \"{}\" for story points, which is not a number.",
+                    line, points
+                )
+            })?),
+            None => None,
+        };
+        // Labels cannot contain spaces, so they can be separated by either spaces or commas.
+        let labels = cell(columns.labels)
+            .map(|labels| list_cell(labels, |c: char| c == ',' || c.is_whitespace()));
+        let fix_versions =
+            cell(columns.fix_versions).map(|versions| list_cell(versions, |c: char| c == ','));
+
+        rows.push(Row {
+            key,
+            story_points,
+            labels,
+            fix_versions,
+        });
+    }
+
+    Ok(rows)
+}
+
+fn same_items(a: &[String], b: &[String]) -> bool {
+    a.len() == b.len() && a.iter().all(|item| b.contains(item))
+}
+
+/// Brings an issue in line with its row, in a single edit.
+async fn apply_row(
+    client: &impl JiraApi,
+    story_point_field_id: Option<&str>,
+    row: &Row,
+) -> Result<Applied, RequestError> {
+    let mut fields_to_get = vec!["labels".to_owned(), "fixVersions".to_owned()];
+    if let Some(field_id) = story_point_field_id {
+        fields_to_get.push(field_id.to_owned());
+    }
+    let issue = client.get_issue(&row.key, &fields_to_get).await?;
+
+    let mut fields = Map::new();
+    let mut update = IssueEditUpdate::default();
+    let mut entries = vec![];
+
+    if let (Some(points), Some(field_id)) = (row.story_points, story_point_field_id) {
+        let before = issue.numeric_field(field_id);
+        if before != Some(points) {
+            fields.insert(field_id.to_owned(), json!(points));
+            entries.push(Entry {
+                key: row.key.clone(),
+                change: Change::StoryPoints {
+                    field_id: field_id.to_owned(),
+                    before,
+                    after: Some(points),
+                },
+            });
+        }
+    }
+
+    if let Some(labels) = &row.labels {
+        let before = jira::labels(&issue);
+        for label in before.iter().filter(|label| !labels.contains(label)) {
+            update
+                .labels
+                .push(IssueEditUpdateLabel::Remove(label.clone()));
+        }
+        for label in labels.iter().filter(|label| !before.contains(label)) {
+            update.labels.push(IssueEditUpdateLabel::Add(label.clone()));
+        }
+
+        if !update.labels.is_empty() {
+            entries.push(Entry {
+                key: row.key.clone(),
+                change: Change::Labels {
+                    before,
+                    after: labels.clone(),
+                },
+            });
+        }
+    }
+
+    if let Some(fix_versions) = &row.fix_versions {
+        let before = jira::fix_versions(&issue);
+        if !same_items(&before, fix_versions) {
+            fields.insert(
+                "fixVersions".to_owned(),
+                jira::fix_versions_value(fix_versions),
+            );
+            entries.push(Entry {
+                key: row.key.clone(),
+                change: Change::FixVersions {
+                    before,
+                    after: fix_versions.clone(),
+                },
+            });
+        }
+    }
+
+    if entries.is_empty() {
+        return Ok(Applied::Skipped("it already matches the file".to_owned()));
+    }
+
+    client.edit_issue_fields(&row.key, &fields, &update).await?;
+
+    Ok(Applied::Changed(entries))
+}
+
+pub async fn run(args: &Apply) {
+    let rows: Vec<Row> = read_rows(args)
+        .unwrap_or_else(exit_with_error)
+        .into_iter()
+        .filter(|row| !row.is_empty())
+        .collect();
+    if rows.is_empty() {
+        println!("There are no changes in {}.", args.changes.display());
+        return;
+    }
+
+    let mut client = args.connection.connect().unwrap_or_else(exit_with_error);
+    if let Some(path) = &args.audit_log {
+        let audit_log = AuditLog::create(path).unwrap_or_else(|e| {
+            exit_with_error(format!(
+                "Could not open the audit log at {}: {}",
+                path.display(),
+                e
+            ))
+        });
+        client = client.with_audit_log(audit_log);
+    }
+
+    let story_point_field_id = if rows.iter().any(|row| row.story_points.is_some()) {
+        let fields = client
+            .get_fields()
+            .await
+            .unwrap_or_else(|e| exit_with_error(format!("Could not look up JIRA's fields: {}", e)));
+        let field_ids = jira::field_ids_named(&fields, &args.jira_story_points_field);
+        match field_ids.into_iter().next() {
+            Some(field_id) => Some(field_id),
+            None => exit_with_error(format!(
+                "There is no field named {} to set story points with.  Pass the right name with --jira-story-points-field.",
+                args.jira_story_points_field
+            )),
+        }
+    } else {
+        None
+    };
+
+    if !args.yes {
+        for row in &rows {
+            println!(
+                "{}/browse/{}: {}",
+                args.connection.jira_url,
+                row.key,
+                row.describe()
+            );
+        }
+
+        let question = format!(
+            "Apply the changes in {} to {} issues?",
+            args.changes.display(),
+            rows.len()
+        );
+        if !cli::confirm(&question) {
+            println!("Nothing was changed.");
+            return;
+        }
+    }
+
+    let journal_path = args
+        .journal
+        .clone()
+        .unwrap_or_else(|| journal::default_path("apply"));
+    let mut journal = Journal::create(&journal_path).unwrap_or_else(|e| {
+        exit_with_error(format!(
+            "Could not create the journal at {}: {}",
+            journal_path.display(),
+            e
+        ))
+    });
+
+    let mut checkpoint = checkpoint::open(args.checkpoint.as_deref(), args.resume.as_deref())
+        .unwrap_or_else(|e| exit_with_error(format!("Could not open the checkpoint: {}", e)));
+
+    let mut outcomes: Vec<Outcome> = vec![];
+    let mut progress = Progress::new(&args.connection.jira_url);
+
+    let rows = bulk::remaining(&rows, &checkpoint, &mut progress, |row| {
+        (&row.key, row.describe())
+    });
+
+    bulk::for_each_concurrently(
+        &rows,
+        args.concurrency,
+        |row| apply_row(&client, story_point_field_id.as_deref(), row),
+        |row, result| {
+            let result = result.map_err(|e| e.to_string());
+            outcomes.push(bulk::settle(
+                &mut journal,
+                &mut checkpoint,
+                &mut progress,
+                &row.key,
+                &row.describe(),
+                result,
+            ));
+        },
+    )
+    .await;
+
+    progress.finish();
+    bulk::print_summary(&outcomes);
+    println!(
+        "The changes were written to {}.  To reverse them, run statustracker tag --undo {}",
+        journal_path.display(),
+        journal_path.display()
+    );
+    if let Some(checkpoint) = &checkpoint {
+        println!(
+            "If this run was cut short, pick it back up with --resume {}",
+            checkpoint.path().display()
+        );
+    }
+
+    if bulk::any_failed(&outcomes) {
+        process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::{
+        export::IssueExporter,
+        jira::{
+            api::fake::{block_on, FakeJira},
+            jql::parser,
+        },
+    };
+
+    fn rows(file: &str) -> Vec<Row> {
+        parse_rows(csv::Reader::from_reader(file.as_bytes()), "Story Points").unwrap()
+    }
+
+    #[test]
+    fn empty_cells_are_left_alone_and_markers_clear() {
+        let rows = rows(
+            "\
+ID,Labels,Fix Versions
+TEAM-1,,
+TEAM-2,-,(None)
+TEAM-3,\"a, b c\",\"1.0, 2.0 beta\"
+",
+        );
+
+        assert!(rows[0].is_empty());
+        assert_eq!(rows[1].labels, Some(vec![]));
+        assert_eq!(rows[1].fix_versions, Some(vec![]));
+        assert_eq!(
+            rows[1].describe(),
+            "Set labels to (none), fix versions to (none)"
+        );
+        assert_eq!(
+            rows[2].labels,
+            Some(vec!["a".to_owned(), "b".to_owned(), "c".to_owned()])
+        );
+        assert_eq!(
+            rows[2].fix_versions,
+            Some(vec!["1.0".to_owned(), "2.0 beta".to_owned()])
+        );
+    }
+
+    #[test]
+    fn clearing_labels_removes_every_one() {
+        let client = FakeJira::with_issues(vec![json!({
+            "key": "TEAM-1",
+            "fields": {"labels": ["a", "b"]},
+        })]);
+        let rows = rows("ID,Labels\nTEAM-1,(none)\n");
+
+        match block_on(apply_row(&client, None, &rows[0])).unwrap() {
+            Applied::Changed(entries) => match &entries[..] {
+                [Entry {
+                    change: Change::Labels { before, after },
+                    ..
+                }] => {
+                    assert_eq!(before, &["a".to_owned(), "b".to_owned()]);
+                    assert!(after.is_empty());
+                }
+                _ => panic!("expected just the labels to change"),
+            },
+            Applied::Skipped(reason) => panic!("the row was skipped: {}", reason),
+        }
+    }
+
+    #[test]
+    fn a_file_written_by_csv_applies_back_without_changes() {
+        let client = FakeJira::with_issues(vec![
+            json!({
+                "key": "TEAM-1",
+                "fields": {
+                    "status": {"name": "Closed", "statusCategory": {"name": "Done"}},
+                    "customfield_10016": 3.5,
+                },
+            }),
+            json!({
+                "key": "TEAM-2",
+                "fields": {"status": {"name": "Open", "statusCategory": {"name": "To Do"}}},
+            }),
+        ]);
+        let jql = parser::parse("project = TEAM").unwrap();
+        let exporter = IssueExporter::new(
+            "https://example.atlassian.net",
+            vec!["customfield_10016".to_owned()],
+        );
+        let mut writer = csv::Writer::from_writer(vec![]);
+        block_on(exporter.export(&client, &jql, &mut writer)).unwrap();
+        let file = String::from_utf8(writer.into_inner().unwrap()).unwrap();
+
+        let rows: Vec<Row> = rows(&file)
+            .into_iter()
+            .filter(|row| !row.is_empty())
+            .collect();
+
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].key, "TEAM-1");
+        match block_on(apply_row(&client, Some("customfield_10016"), &rows[0])).unwrap() {
+            Applied::Skipped(_) => {}
+            Applied::Changed(entries) => panic!("the row changed {:?}", entries),
+        }
+        assert!(client.edits_to("TEAM-1").is_empty());
+    }
+}
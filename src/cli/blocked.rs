@@ -0,0 +1,198 @@
+use clap::Args;
+use colored::Colorize;
+use serde::Serialize;
+use serde_json::Value as JSONValue;
+
+use crate::{
+    cli::{
+        connection::Connection,
+        exit_with_error,
+        output::{self, Format},
+        scope::Scope,
+    },
+    date::DateTime,
+    jira::{
+        self,
+        api::JiraApi,
+        client::{ChangelogItem, LinkDirection, SearchIssue},
+        history,
+        jql::SortDirection,
+        AssigneeNames,
+    },
+};
+
+const SECONDS_PER_DAY: f64 = 24.0 * 60.0 * 60.0;
+
+#[derive(Debug, Args)]
+pub struct Blocked {
+    /// csv or json, for a script to read.  Without this, the issues are shown as a table, longest blocked first.
+    #[arg(long)]
+    pub format: Option<Format>,
+
+    #[command(flatten)]
+    pub scope: Scope,
+
+    #[command(flatten)]
+    pub connection: Connection,
+}
+
+/// An unfinished issue that is flagged, or waiting on another issue that isn't done.
+#[derive(Debug, Serialize)]
+struct BlockedIssue {
+    key: String,
+    status: String,
+    assignee: String,
+    flagged: bool,
+    /// The unfinished issues it's waiting on, separated by commas.
+    blocked_by: String,
+    /// How long since it was flagged or linked to the first of its blockers, if its history says.
+    days_blocked: Option<f64>,
+    link: String,
+}
+
+/// Whether an issue has the Flagged field set, which is how boards mark an impediment.
+fn is_flagged(issue: &SearchIssue, flagged_field_ids: &[String]) -> bool {
+    flagged_field_ids
+        .iter()
+        .any(|field_id| match issue.fields.get(field_id) {
+            Some(JSONValue::Array(values)) => !values.is_empty(),
+            Some(JSONValue::Null) | None => false,
+            Some(_) => true,
+        })
+}
+
+/// Finds the unfinished issues that are flagged or blocked, longest blocked first.
+async fn blocked(client: &impl JiraApi, args: &Blocked) -> Result<Vec<BlockedIssue>, String> {
+    let flagged_field_ids = jira::get_field_ids_named(client, "Flagged")
+        .await
+        .map_err(|e| format!("Could not get the flagged field: {}", e))?;
+    let mut fields = flagged_field_ids.clone();
+    for field in ["status", "assignee", "issuelinks"] {
+        fields.push(field.to_owned());
+    }
+
+    // Links can't be searched on without an app, so every unfinished issue is fetched and its links checked here.
+    let jql = args.scope.builder(client).await.and_then(|builder| {
+        builder
+            .status_category("To Do")
+            .status_category("In Progress")
+            .order_by("key", SortDirection::Ascending)
+            .build()
+    })?;
+    if args.scope.validate_jql {
+        jira::validate_jql(client, &jql)
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+    let issues = client
+        .search_all_with_changelogs(&fields, &jql)
+        .await
+        .map_err(|e| format!("Could not search for issues: {}", e))?;
+
+    let now = DateTime::now().to_unix_seconds();
+    let assignee_names = AssigneeNames::new();
+    let mut blocked = vec![];
+    for issue in &issues {
+        let flagged = is_flagged(issue, &flagged_field_ids);
+        let blockers: Vec<String> = issue
+            .issue_links()
+            .into_iter()
+            .filter(|link| link.link_type == "Blocks" && link.direction == LinkDirection::Inward)
+            .filter(|link| link.status_category.as_deref() != Some("Done"))
+            .map(|link| link.key)
+            .collect();
+        if !flagged && blockers.is_empty() {
+            continue;
+        }
+
+        // Blocked since the earliest of the reasons that still hold.
+        let is_flag = |item: &ChangelogItem| match &item.field_id {
+            Some(field_id) => flagged_field_ids.contains(field_id),
+            None => item.field == "Flagged",
+        };
+        let flagged_at = if flagged {
+            history::last_changed_at(issue, |item| {
+                is_flag(item)
+                    && item
+                        .to_string
+                        .as_ref()
+                        .map(|to| !to.is_empty())
+                        .unwrap_or(false)
+            })
+        } else {
+            None
+        };
+        let blocked_since = blockers
+            .iter()
+            .filter_map(|blocker| {
+                history::last_changed_at(issue, |item| {
+                    item.field == "Link" && item.to.as_deref() == Some(blocker)
+                })
+            })
+            .chain(flagged_at)
+            .map(DateTime::to_unix_seconds)
+            .min();
+
+        blocked.push(BlockedIssue {
+            key: issue.key.clone(),
+            status: issue.status().unwrap_or_default(),
+            assignee: assignee_names
+                .assignee(client, issue)
+                .await
+                .unwrap_or_else(|| "Unassigned".to_owned()),
+            flagged,
+            blocked_by: blockers.join(", "),
+            days_blocked: blocked_since
+                .map(|since| ((now - since) as f64 / SECONDS_PER_DAY * 10.0).round() / 10.0),
+            link: format!("{}/browse/{}", args.connection.jira_url, issue.key),
+        });
+    }
+
+    // Issues whose history doesn't say how long they've been blocked go last.
+    blocked.sort_by(|a, b| {
+        b.days_blocked
+            .unwrap_or(-1.0)
+            .total_cmp(&a.days_blocked.unwrap_or(-1.0))
+    });
+
+    Ok(blocked)
+}
+
+fn show(blocked: &[BlockedIssue]) {
+    for issue in blocked {
+        let mut reasons = vec![];
+        if issue.flagged {
+            reasons.push("flagged".to_owned());
+        }
+        if !issue.blocked_by.is_empty() {
+            reasons.push(format!("blocked by {}", issue.blocked_by));
+        }
+
+        println!(
+            "{:>7}  {:<12} {:<20} {:<20} {}",
+            issue
+                .days_blocked
+                .map(|days| format!("{:.1}d", days))
+                .unwrap_or_else(|| "?".to_owned()),
+            issue.key,
+            issue.status,
+            issue.assignee,
+            reasons.join(", ").red()
+        );
+    }
+
+    println!(
+        "{} unfinished issues are flagged or blocked.",
+        format!("{}", blocked.len()).bright_red()
+    );
+}
+
+pub async fn run(args: &Blocked) {
+    let client = args.connection.connect().unwrap_or_else(exit_with_error);
+    let blocked = blocked(&client, args).await.unwrap_or_else(exit_with_error);
+
+    match args.format {
+        Some(format) => output::write_rows(format, &blocked).unwrap_or_else(exit_with_error),
+        None => show(&blocked),
+    }
+}
@@ -0,0 +1,201 @@
+use clap::Args;
+use colored::Colorize;
+use serde::Serialize;
+
+use crate::{
+    cli::{
+        connection::Connection,
+        exit_with_error,
+        output::{self, Format},
+        report,
+        scope::Scope,
+        timeline::{self, Timeline},
+    },
+    date::DateTime,
+    jira::{self, api::JiraApi, history},
+};
+
+#[derive(Debug, Args)]
+pub struct Bugs {
+    /// An issue type that counts as a bug.  Can be given more than once, like for Bug and Defect.
+    #[arg(long)]
+    #[arg(default_value = "Bug")]
+    pub bug_type: Vec<String>,
+
+    /// The story points to count for issues that have none.
+    #[arg(long)]
+    #[arg(default_value_t = 3.0)]
+    pub default_story_points: f64,
+
+    /// csv or json, for a script to read.  Without this, the work is shown side by side, with the share that went to
+    /// bugs.
+    #[arg(long)]
+    pub format: Option<Format>,
+
+    #[arg(long)]
+    #[arg(default_value = "Story Points")]
+    pub jira_story_points_field: String,
+
+    #[command(flatten)]
+    pub timeline: Timeline,
+
+    #[command(flatten)]
+    pub scope: Scope,
+
+    #[command(flatten)]
+    pub connection: Connection,
+}
+
+/// How much of one kind of work was created and resolved in the window.
+#[derive(Debug, Serialize)]
+struct Work {
+    /// "bugs", or "other" for everything else, like stories and tasks.
+    kind: String,
+    created: u64,
+    created_points: f64,
+    resolved: u64,
+    resolved_points: f64,
+}
+
+impl Work {
+    fn new(kind: &str) -> Self {
+        Work {
+            kind: kind.to_owned(),
+            created: 0,
+            created_points: 0.0,
+            resolved: 0,
+            resolved_points: 0.0,
+        }
+    }
+}
+
+/// Counts up the bugs and the other work created and resolved in the window.  Issues are resolved when they were last
+/// moved into a done status.
+async fn bugs(client: &impl JiraApi, args: &Bugs) -> Result<[Work; 2], String> {
+    let days = args.timeline.days()?;
+    // Unwrap here is considered safe since there is always at least one day in the timeline.
+    let since = days.first().unwrap().to_unix_seconds();
+    let until = timeline::end_of_day(*days.last().unwrap()).to_unix_seconds();
+    let in_window = |at: Option<DateTime>| {
+        at.map(|at| at.to_unix_seconds() >= since && at.to_unix_seconds() <= until)
+            .unwrap_or(false)
+    };
+
+    let story_point_field_ids = jira::get_field_ids_named(client, &args.jira_story_points_field)
+        .await
+        .map_err(|e| format!("Could not get the story point fields: {}", e))?;
+    let mut fields = story_point_field_ids.clone();
+    for field in ["status", "issuetype"] {
+        fields.push(field.to_owned());
+    }
+
+    let categories = history::status_categories(client)
+        .await
+        .map_err(|e| format!("Could not get the statuses: {}", e))?;
+    let issues = timeline::search_with_changelogs(client, &args.scope, &fields).await?;
+
+    let mut bugs = Work::new("bugs");
+    let mut other = Work::new("other");
+    for issue in &issues {
+        let work = if args
+            .bug_type
+            .iter()
+            .any(|bug_type| report::is_type(issue, bug_type))
+        {
+            &mut bugs
+        } else {
+            &mut other
+        };
+        // Like estimating, a zero is treated as not having been pointed yet.
+        let points = jira::story_points(issue, &story_point_field_ids)
+            .filter(|points| *points != 0.0)
+            .unwrap_or(args.default_story_points);
+
+        if in_window(history::created_at(issue)) {
+            work.created += 1;
+            work.created_points += points;
+        }
+        if in_window(history::completed_at(issue, &categories)) {
+            work.resolved += 1;
+            work.resolved_points += points;
+        }
+    }
+
+    Ok([bugs, other])
+}
+
+/// The part of a whole, as a percentage, or zero when there is no whole.
+fn share(part: f64, whole: f64) -> f64 {
+    if whole > 0.0 {
+        part / whole * 100.0
+    } else {
+        0.0
+    }
+}
+
+fn show(args: &Bugs, work: &[Work; 2]) {
+    let [bugs, other] = work;
+    let days = args.timeline.days().unwrap_or_default();
+    if let (Some(first), Some(last)) = (days.first(), days.last()) {
+        println!(
+            "{}",
+            format!(
+                "From {} to {}",
+                first.to_date_string(),
+                last.to_date_string()
+            )
+            .bold()
+        );
+    }
+
+    println!(
+        "              {:>8} {:>8}  {:>8} {:>8}",
+        "created", "points", "resolved", "points"
+    );
+    for work in [bugs, other] {
+        println!(
+            "  {:<10}  {:>8} {:>8.1}  {:>8} {:>8.1}",
+            work.kind, work.created, work.created_points, work.resolved, work.resolved_points
+        );
+    }
+
+    let resolved_share = share(
+        bugs.resolved_points,
+        bugs.resolved_points + other.resolved_points,
+    );
+    let created_share = share(
+        bugs.created_points,
+        bugs.created_points + other.created_points,
+    );
+    let colored_share = |share: f64| {
+        let text = format!("{:.0}%", share);
+        if share > 50.0 {
+            text.bright_red()
+        } else if share > 25.0 {
+            text.yellow()
+        } else {
+            text.bright_green()
+        }
+    };
+    println!(
+        "{} of the points resolved went to bugs, and {} of the points created were bugs.",
+        colored_share(resolved_share),
+        colored_share(created_share)
+    );
+    if bugs.created > bugs.resolved {
+        println!(
+            "{} more bugs were found than fixed.",
+            format!("{}", bugs.created - bugs.resolved).bright_red()
+        );
+    }
+}
+
+pub async fn run(args: &Bugs) {
+    let client = args.connection.connect().unwrap_or_else(exit_with_error);
+    let work = bugs(&client, args).await.unwrap_or_else(exit_with_error);
+
+    match args.format {
+        Some(format) => output::write_rows(format, &work).unwrap_or_else(exit_with_error),
+        None => show(args, &work),
+    }
+}
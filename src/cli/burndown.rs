@@ -0,0 +1,175 @@
+use std::{collections::HashMap, fs, path::PathBuf};
+
+use clap::Args;
+use serde::Serialize;
+
+use crate::{
+    chart::{self, Series},
+    cli::{
+        connection::Connection,
+        exit_with_error,
+        output::{self, Format},
+        scope::Scope,
+        timeline::{self, Timeline},
+    },
+    date::DateTime,
+    jira::{self, api::JiraApi, client::SearchIssue, history},
+};
+
+#[derive(Debug, Args)]
+pub struct Burndown {
+    /// The story points to count for issues that have none.
+    #[arg(long)]
+    #[arg(default_value_t = 3.0)]
+    pub default_story_points: f64,
+
+    /// csv or json.
+    #[arg(long)]
+    #[arg(default_value = "csv")]
+    pub format: Format,
+
+    #[arg(long)]
+    #[arg(default_value = "Story Points")]
+    pub jira_story_points_field: String,
+
+    /// Also draw the points as a chart, and save it as an SVG file here.
+    #[arg(long)]
+    pub svg: Option<PathBuf>,
+
+    #[command(flatten)]
+    pub timeline: Timeline,
+
+    #[command(flatten)]
+    pub scope: Scope,
+
+    #[command(flatten)]
+    pub connection: Connection,
+}
+
+/// Where the issues stood at the end of a day, in UTC.
+#[derive(Debug, Serialize)]
+pub struct Day {
+    pub date: String,
+    pub remaining_points: f64,
+    pub completed_points: f64,
+    pub total_points: f64,
+    pub remaining_issues: u64,
+}
+
+/// Works out the points left on each day, by winding each issue's status and story points back to how they were.
+///
+/// Issues only count from the day they were created.  The scope is today's search, so issues that have since been moved
+/// out of it, like into another sprint, are left out of the earlier days too.
+///
+/// Burnups are drawn from the same days, so they share these options.
+pub async fn days(client: &impl JiraApi, args: &Burndown) -> Result<Vec<Day>, String> {
+    let field_ids = jira::get_field_ids_named(client, &args.jira_story_points_field)
+        .await
+        .map_err(|e| format!("Could not get the story point fields: {}", e))?;
+    let mut fields = field_ids.clone();
+    fields.push("status".to_owned());
+
+    let categories = history::status_categories(client)
+        .await
+        .map_err(|e| format!("Could not get the statuses: {}", e))?;
+    let issues = timeline::search_with_changelogs(client, &args.scope, &fields).await?;
+
+    Ok(tally_days(
+        &issues,
+        &categories,
+        &field_ids,
+        &args.jira_story_points_field,
+        args.default_story_points,
+        &args.timeline.days()?,
+    ))
+}
+
+/// Works out where issues already fetched with their histories stood at the end of each day.
+pub fn tally_days(
+    issues: &[SearchIssue],
+    categories: &HashMap<String, String>,
+    field_ids: &[String],
+    field_name: &str,
+    default_story_points: f64,
+    days: &[DateTime],
+) -> Vec<Day> {
+    let mut rows = vec![];
+    for day in days {
+        let end_of_day = timeline::end_of_day(*day);
+        let mut row = Day {
+            date: day.to_date_string(),
+            remaining_points: 0.0,
+            completed_points: 0.0,
+            total_points: 0.0,
+            remaining_issues: 0,
+        };
+
+        for issue in issues
+            .iter()
+            .filter(|issue| history::existed_at(issue, end_of_day))
+        {
+            // Like estimating, a zero is treated as not having been pointed yet.
+            let points = history::story_points_at(issue, field_ids, field_name, end_of_day)
+                .filter(|points| *points != 0.0)
+                .unwrap_or(default_story_points);
+            row.total_points += points;
+
+            match history::status_category_at(issue, categories, end_of_day) {
+                Some(category) if category == "Done" => row.completed_points += points,
+                _ => {
+                    row.remaining_points += points;
+                    row.remaining_issues += 1;
+                }
+            }
+        }
+
+        rows.push(row);
+    }
+
+    rows
+}
+
+/// Draws the points left each day, against a straight line from where the first day started down to nothing.
+pub fn draw(days: &[Day]) -> String {
+    let labels: Vec<String> = days.iter().map(|day| day.date.clone()).collect();
+    let start = days.first().map(|day| day.remaining_points).unwrap_or(0.0);
+    let last = days.len().saturating_sub(1).max(1) as f64;
+
+    chart::line_chart(
+        "Burndown",
+        &labels,
+        &[
+            Series {
+                name: "Remaining",
+                color: "#1f77b4",
+                dashed: false,
+                values: days.iter().map(|day| day.remaining_points).collect(),
+            },
+            Series {
+                name: "Ideal",
+                color: "#999999",
+                dashed: true,
+                values: (0..days.len())
+                    .map(|i| start * (1.0 - i as f64 / last))
+                    .collect(),
+            },
+        ],
+    )
+}
+
+pub async fn run(args: &Burndown) {
+    let client = args.connection.connect().unwrap_or_else(exit_with_error);
+    let days = days(&client, args).await.unwrap_or_else(exit_with_error);
+
+    if let Some(path) = &args.svg {
+        fs::write(path, draw(&days)).unwrap_or_else(|e| {
+            exit_with_error(format!(
+                "Could not save the chart to {}: {}",
+                path.display(),
+                e
+            ))
+        });
+    }
+
+    output::write_rows(args.format, &days).unwrap_or_else(exit_with_error);
+}
@@ -0,0 +1,53 @@
+use std::fs;
+
+use crate::{
+    chart::{self, Series},
+    cli::{
+        burndown::{self, Burndown, Day},
+        exit_with_error, output,
+    },
+};
+
+/// Draws the points done each day against the points in all, so that growth in scope shows up as the lines parting
+/// rather than as work that was never burned down.
+pub fn draw(days: &[Day]) -> String {
+    let labels: Vec<String> = days.iter().map(|day| day.date.clone()).collect();
+
+    chart::line_chart(
+        "Burnup",
+        &labels,
+        &[
+            Series {
+                name: "Scope",
+                color: "#d62728",
+                dashed: true,
+                values: days.iter().map(|day| day.total_points).collect(),
+            },
+            Series {
+                name: "Completed",
+                color: "#2ca02c",
+                dashed: false,
+                values: days.iter().map(|day| day.completed_points).collect(),
+            },
+        ],
+    )
+}
+
+pub async fn run(args: &Burndown) {
+    let client = args.connection.connect().unwrap_or_else(exit_with_error);
+    let days = burndown::days(&client, args)
+        .await
+        .unwrap_or_else(exit_with_error);
+
+    if let Some(path) = &args.svg {
+        fs::write(path, draw(&days)).unwrap_or_else(|e| {
+            exit_with_error(format!(
+                "Could not save the chart to {}: {}",
+                path.display(),
+                e
+            ))
+        });
+    }
+
+    output::write_rows(args.format, &days).unwrap_or_else(exit_with_error);
+}
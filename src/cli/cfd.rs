@@ -0,0 +1,86 @@
+use clap::Args;
+use serde::Serialize;
+
+use crate::{
+    cli::{
+        connection::Connection,
+        exit_with_error,
+        output::{self, Format},
+        scope::Scope,
+        timeline::{self, Timeline},
+    },
+    jira::{api::JiraApi, history},
+};
+
+#[derive(Debug, Args)]
+pub struct Cfd {
+    /// csv or json.
+    #[arg(long)]
+    #[arg(default_value = "csv")]
+    pub format: Format,
+
+    #[command(flatten)]
+    pub timeline: Timeline,
+
+    #[command(flatten)]
+    pub scope: Scope,
+
+    #[command(flatten)]
+    pub connection: Connection,
+}
+
+/// How many issues were in each status category at the end of a day, in UTC, ready to be stacked into a cumulative
+/// flow diagram.
+#[derive(Debug, Serialize)]
+struct Day {
+    date: String,
+    to_do: u64,
+    in_progress: u64,
+    done: u64,
+}
+
+/// Works out how many issues were in each status category on each day, by winding each issue's status back to how it
+/// was.
+///
+/// Statuses in a category other than "In Progress" or "Done", like one that has since been deleted, are counted as to
+/// do.
+async fn cfd(client: &impl JiraApi, args: &Cfd) -> Result<Vec<Day>, String> {
+    let categories = history::status_categories(client)
+        .await
+        .map_err(|e| format!("Could not get the statuses: {}", e))?;
+    let issues =
+        timeline::search_with_changelogs(client, &args.scope, &["status".to_owned()]).await?;
+
+    let mut days = vec![];
+    for day in args.timeline.days()? {
+        let end_of_day = timeline::end_of_day(day);
+        let mut row = Day {
+            date: day.to_date_string(),
+            to_do: 0,
+            in_progress: 0,
+            done: 0,
+        };
+
+        for issue in issues
+            .iter()
+            .filter(|issue| history::existed_at(issue, end_of_day))
+        {
+            match history::status_category_at(issue, &categories, end_of_day).as_deref() {
+                Some("Done") => row.done += 1,
+                Some("In Progress") => row.in_progress += 1,
+                _ => row.to_do += 1,
+            }
+        }
+
+        days.push(row);
+    }
+
+    Ok(days)
+}
+
+pub async fn run(args: &Cfd) {
+    let client = args.connection.connect().unwrap_or_else(exit_with_error);
+    let days = cfd(&client, args).await.unwrap_or_else(exit_with_error);
+
+    output::write_rows(args.format, &days).unwrap_or_else(exit_with_error);
+}
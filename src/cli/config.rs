@@ -0,0 +1,277 @@
+/// Views and edits the argfile that `init` writes, which is where the defaults for every command live.
+use std::{
+    collections::BTreeSet,
+    fs::{self, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+    process,
+};
+
+use clap::{Args, Command, CommandFactory, Subcommand};
+
+use crate::cli::{exit_with_error, Cli};
+
+#[derive(Debug, Args)]
+pub struct Config {
+    #[command(subcommand)]
+    pub action: ConfigAction,
+
+    /// The argfile to look at, as written by init.
+    #[arg(long)]
+    #[arg(default_value = "statustracker.args.txt")]
+    #[arg(global = true)]
+    pub file: PathBuf,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ConfigAction {
+    /// Prints the value of an option, like jira-url.  Options given more than once, like jira-project, print a line for
+    /// each value.  Fails if the option isn't set.
+    Get {
+        #[arg(allow_hyphen_values = true)]
+        name: String,
+    },
+
+    /// Prints every option in the file.  Tokens are hidden.
+    List {
+        #[arg(long)]
+        #[arg(default_value_t = false)]
+        show_secrets: bool,
+    },
+
+    /// Prints where the file is.
+    Path,
+
+    /// Sets an option, replacing any values it had.  Give more than one value for an option that can be given more than
+    /// once, like jira-project, or none for a switch, like match-any.
+    Set {
+        #[arg(allow_hyphen_values = true)]
+        name: String,
+        #[arg(allow_hyphen_values = true)]
+        values: Vec<String>,
+    },
+
+    /// Takes an option out of the file, so that commands go back to its default.
+    Unset {
+        #[arg(allow_hyphen_values = true)]
+        name: String,
+    },
+}
+
+/// A line of the argfile, with an option's value joined to it.
+#[derive(Debug)]
+enum Line {
+    Option {
+        name: String,
+        value: Option<String>,
+    },
+    /// Anything that isn't an option, which is kept as it is.
+    Other(String),
+}
+
+/// Reads an argfile, which has an argument on each line.
+fn read(path: &Path) -> Result<Vec<Line>, String> {
+    let contents = fs::read_to_string(path).map_err(|e| {
+        format!(
+            "Could not read {}: {}.  Run statustracker init to make one.",
+            path.display(),
+            e
+        )
+    })?;
+
+    let mut lines = vec![];
+    let mut args = contents.lines().peekable();
+    while let Some(arg) = args.next() {
+        let name = match arg.strip_prefix("--") {
+            Some(name) => name,
+            None => {
+                lines.push(Line::Other(arg.to_owned()));
+                continue;
+            }
+        };
+
+        let line = match name.split_once('=') {
+            Some((name, value)) => Line::Option {
+                name: name.to_owned(),
+                value: Some(value.to_owned()),
+            },
+            None => Line::Option {
+                name: name.to_owned(),
+                value: args
+                    .next_if(|next| !next.starts_with("--"))
+                    .map(str::to_owned),
+            },
+        };
+        lines.push(line);
+    }
+
+    Ok(lines)
+}
+
+fn write(path: &Path, lines: &[Line]) -> Result<(), String> {
+    let mut options = OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    // The argfile holds the token, so only its owner should be able to read it.
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let mut file = options
+        .open(path)
+        .map_err(|e| format!("Could not open {}: {}", path.display(), e))?;
+
+    let write_error = |e| format!("Could not write to {}: {}", path.display(), e);
+    for line in lines {
+        match line {
+            Line::Option { name, value } => {
+                writeln!(file, "--{}", name).map_err(write_error)?;
+                if let Some(value) = value {
+                    writeln!(file, "{}", value).map_err(write_error)?;
+                }
+            }
+            Line::Other(arg) => writeln!(file, "{}", arg).map_err(write_error)?,
+        }
+    }
+
+    Ok(())
+}
+
+/// Every long option any command takes, so that a typo isn't written into the file where it would break every command.
+fn known_options() -> BTreeSet<String> {
+    fn collect(command: &Command, options: &mut BTreeSet<String>) {
+        for arg in command.get_arguments() {
+            if let Some(long) = arg.get_long() {
+                options.insert(long.to_owned());
+            }
+        }
+        for subcommand in command.get_subcommands() {
+            collect(subcommand, options);
+        }
+    }
+
+    let mut options = BTreeSet::new();
+    collect(&Cli::command(), &mut options);
+    options
+}
+
+/// Whether an option's value shouldn't be shown unless asked for.
+fn is_secret(name: &str) -> bool {
+    name.contains("token") || name.contains("password")
+}
+
+fn values<'a>(lines: &'a [Line], name: &'a str) -> impl Iterator<Item = Option<&'a str>> {
+    lines.iter().filter_map(move |line| match line {
+        Line::Option {
+            name: option,
+            value,
+        } if option == name => Some(value.as_deref()),
+        _ => None,
+    })
+}
+
+/// Puts an option's new values where it was first found, or at the end if it wasn't.
+fn set(lines: Vec<Line>, name: &str, values: &[String]) -> Vec<Line> {
+    let new_lines = || -> Vec<Line> {
+        if values.is_empty() {
+            vec![Line::Option {
+                name: name.to_owned(),
+                value: None,
+            }]
+        } else {
+            values
+                .iter()
+                .map(|value| Line::Option {
+                    name: name.to_owned(),
+                    value: Some(value.clone()),
+                })
+                .collect()
+        }
+    };
+
+    let mut replaced = false;
+    let mut result = vec![];
+    for line in lines {
+        match &line {
+            Line::Option { name: option, .. } if option == name => {
+                if !replaced {
+                    result.extend(new_lines());
+                    replaced = true;
+                }
+            }
+            _ => result.push(line),
+        }
+    }
+    if !replaced {
+        result.extend(new_lines());
+    }
+
+    result
+}
+
+fn config(args: &Config) -> Result<(), String> {
+    match &args.action {
+        ConfigAction::Path => {
+            let path = fs::canonicalize(&args.file).unwrap_or_else(|_| args.file.clone());
+            println!("{}", path.display());
+            if !args.file.exists() {
+                eprintln!("It doesn't exist yet.  Run statustracker init to make it.");
+            }
+        }
+        ConfigAction::List { show_secrets } => {
+            for line in read(&args.file)? {
+                match line {
+                    Line::Option { name, value } => match value {
+                        Some(_) if is_secret(&name) && !show_secrets => {
+                            println!("{} = (hidden)", name)
+                        }
+                        Some(value) => println!("{} = {}", name, value),
+                        None => println!("{}", name),
+                    },
+                    Line::Other(arg) => println!("{}", arg),
+                }
+            }
+        }
+        ConfigAction::Get { name } => {
+            let lines = read(&args.file)?;
+            let name = name.trim_start_matches('-');
+            let found: Vec<Option<&str>> = values(&lines, name).collect();
+            if found.is_empty() {
+                eprintln!("{} isn't set in {}.", name, args.file.display());
+                process::exit(1);
+            }
+            for value in found {
+                // A switch has no value, but being set is enough to say.
+                println!("{}", value.unwrap_or("true"));
+            }
+        }
+        ConfigAction::Set { name, values } => {
+            let name = name.trim_start_matches('-');
+            if !known_options().contains(name) {
+                return Err(format!(
+                    "No command takes --{}.  Run a command with --help to see its options.",
+                    name
+                ));
+            }
+            let lines = read(&args.file)?;
+            write(&args.file, &set(lines, name, values))?;
+        }
+        ConfigAction::Unset { name } => {
+            let name = name.trim_start_matches('-');
+            let lines = read(&args.file)?;
+            let before = lines.len();
+            let lines: Vec<Line> = lines
+                .into_iter()
+                .filter(|line| !matches!(line, Line::Option { name: option, .. } if option == name))
+                .collect();
+            if lines.len() == before {
+                eprintln!("{} wasn't set in {}.", name, args.file.display());
+                return Ok(());
+            }
+            write(&args.file, &lines)?;
+        }
+    }
+
+    Ok(())
+}
+
+pub async fn run(args: &Config) {
+    config(args).unwrap_or_else(exit_with_error);
+}
@@ -0,0 +1,421 @@
+/// The options every command uses to connect to JIRA.
+use std::{
+    ffi::OsString,
+    fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::Duration,
+};
+
+use clap::{Args, Parser};
+use colored::Colorize;
+use reqwest::Certificate;
+
+use crate::{
+    cache::{self, ResponseCache},
+    jira::{
+        self,
+        client::{ApiVersion, Client, Credentials, HttpSettings, RetryPolicy},
+    },
+};
+
+/// How to send the token to JIRA.
+#[derive(Clone, Copy, Debug)]
+pub enum AuthScheme {
+    /// With the username, as JIRA Cloud API tokens are.
+    Basic,
+    /// On its own, as JIRA Data Center personal access tokens are.
+    Bearer,
+}
+
+impl FromStr for AuthScheme {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.eq_ignore_ascii_case("basic") {
+            Ok(AuthScheme::Basic)
+        } else if s.eq_ignore_ascii_case("bearer") {
+            Ok(AuthScheme::Bearer)
+        } else {
+            Err("the auth scheme has to be basic or bearer".to_owned())
+        }
+    }
+}
+
+/// How long JIRA's fields are used from the cache before checking with JIRA whether they have changed.
+const FIELD_CACHE_TTL: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+#[derive(Clone, Debug, Args)]
+pub struct Connection {
+    /// Which version of JIRA's REST API to use: 2, 3, or auto for 3 on JIRA Cloud and 2 on Server and Data Center.
+    #[arg(long)]
+    #[arg(default_value = "auto")]
+    pub api_version: ApiVersion,
+
+    /// basic to log in with --jira-username and an API token, as on JIRA Cloud, or bearer to send --jira-token on its
+    /// own, as with personal access tokens on JIRA Data Center.
+    #[arg(long)]
+    #[arg(default_value = "basic")]
+    pub auth_scheme: AuthScheme,
+
+    /// Reuse search results fetched within this long, like 30m or 2h, from a cache in ~/.cache/statustracker.  Older
+    /// entries are only downloaded again if JIRA says they have changed.
+    #[arg(long)]
+    #[arg(value_parser = parse_duration)]
+    pub cache_ttl: Option<Duration>,
+
+    /// A PEM file of certificate authorities to trust, for JIRA instances with certificates from a private CA.
+    #[arg(long)]
+    pub ca_cert: Vec<PathBuf>,
+
+    /// Write every request sent to JIRA and every response to stderr, with the token blanked out, to help diagnose
+    /// problems with JIRA's API.
+    #[arg(long)]
+    #[arg(default_value_t = false)]
+    pub debug_http: bool,
+
+    /// How long to wait for JIRA to accept a connection, and then to answer each request.
+    #[arg(long)]
+    #[arg(default_value_t = 30)]
+    pub http_timeout_secs: u64,
+
+    /// Don't check JIRA's certificate at all.  Anyone between you and JIRA could read your token; prefer --ca-cert.
+    #[arg(long)]
+    #[arg(default_value_t = false)]
+    pub insecure_skip_tls_verify: bool,
+
+    /// Needed for anything that connects to JIRA, which is everything but reading a snapshot.
+    #[arg(long)]
+    #[arg(default_value = "")]
+    #[arg(hide_default_value = true)]
+    pub jira_token: String,
+
+    /// Needed for anything that connects to JIRA, which is everything but reading a snapshot.
+    #[arg(long)]
+    #[arg(default_value = "")]
+    #[arg(hide_default_value = true)]
+    pub jira_url: String,
+
+    /// The email address or username to log in with.  Not needed with --auth-scheme bearer.
+    #[arg(long)]
+    pub jira_username: Option<String>,
+
+    /// Stop instead of fetching a search that matches more issues than this, so that a mistaken search doesn't crawl
+    /// a huge filter.
+    #[arg(long)]
+    pub max_issues: Option<u64>,
+
+    /// The most requests to send to JIRA a second, like 5, or 0.5 for one every two seconds.  JIRA's own rate limits
+    /// are followed either way.
+    #[arg(long)]
+    #[arg(value_parser = parse_requests_per_second)]
+    pub max_requests_per_second: Option<f64>,
+
+    /// How many times to retry a request that was rate limited, hit an unavailable gateway, or timed out.
+    #[arg(long)]
+    #[arg(default_value_t = 5)]
+    pub max_retries: u32,
+
+    /// Always wait the full backoff between retries, instead of a random part of it.
+    #[arg(long)]
+    #[arg(default_value_t = false)]
+    pub no_retry_jitter: bool,
+
+    /// Don't contact JIRA at all, and answer everything from the cache instead, however old.  Anything that isn't in
+    /// the cache is an error.
+    #[arg(long)]
+    #[arg(default_value_t = false)]
+    pub offline: bool,
+
+    /// How many issues to ask JIRA for in each page of search results.  Bigger pages mean fewer requests, but each one
+    /// takes longer.  JIRA Cloud sends at most 100 at a time, however many are asked for.
+    #[arg(long)]
+    #[arg(default_value_t = 100)]
+    pub page_size: u64,
+
+    /// Check with JIRA for new or renamed fields, instead of using the ones saved by an earlier run.
+    #[arg(long)]
+    #[arg(default_value_t = false)]
+    pub refresh_fields: bool,
+
+    /// How long to wait before the first retry, in milliseconds.  This doubles with each retry.
+    #[arg(long)]
+    #[arg(default_value_t = 2000)]
+    pub retry_base_delay_ms: u64,
+}
+
+/// The options in an argfile given to --instance, which are only ever connection options.
+#[derive(Debug, Parser)]
+struct Instance {
+    #[command(flatten)]
+    connection: Connection,
+}
+
+impl Connection {
+    /// Reads the options for connecting to another JIRA site from an argfile, laid out like the ones given with @.
+    pub fn from_argfile(path: &Path) -> Result<Self, String> {
+        let mut argfile = OsString::from(argfile::PREFIX.to_string());
+        argfile.push(path);
+        // The path stands in for the name of the program, so that problems with the options say which file they're in.
+        let args = argfile::expand_args_from(
+            vec![OsString::from(path.display().to_string()), argfile].into_iter(),
+            argfile::parse_fromfile,
+            argfile::PREFIX,
+        )
+        .map_err(|e| format!("Could not read the instance at {}: {}", path.display(), e))?;
+
+        Instance::try_parse_from(args)
+            .map(|instance| instance.connection)
+            .map_err(|e| e.to_string())
+    }
+
+    /// Reads the options for connecting to JIRA from a list of them, laid out as they would be on the command line.
+    pub fn from_args(args: &[String]) -> Result<Self, String> {
+        let program = "statustracker".to_owned();
+        Instance::try_parse_from(std::iter::once(&program).chain(args))
+            .map(|instance| instance.connection)
+            .map_err(|e| e.to_string())
+    }
+
+    /// Puts these options together with the ones for each JIRA site given with --instance.
+    pub fn with_instances(&self, paths: &[PathBuf]) -> Result<Vec<Self>, String> {
+        let mut connections = vec![self.clone()];
+        for path in paths {
+            connections.push(Connection::from_argfile(path)?);
+        }
+
+        Ok(connections)
+    }
+
+    /// Builds a client for JIRA from these options.
+    pub fn connect(&self) -> Result<Client, String> {
+        // These are only optional so that commands can read a snapshot instead.
+        if self.jira_url.is_empty() {
+            return Err("--jira-url is required to connect to JIRA.".to_owned());
+        }
+        if self.jira_token.is_empty() {
+            return Err("--jira-token is required to connect to JIRA.".to_owned());
+        }
+        if self.http_timeout_secs == 0 {
+            return Err("--http-timeout-secs has to be greater than zero.".to_owned());
+        }
+
+        let mut root_certificates = vec![];
+        for path in &self.ca_cert {
+            root_certificates.extend(read_certificates(path)?);
+        }
+
+        if self.insecure_skip_tls_verify {
+            eprintln!(
+                "{}",
+                "WARNING: --insecure-skip-tls-verify is on, so JIRA's certificate is not being checked.  Anyone able to intercept the connection can read your API token and everything sent to JIRA.  Use --ca-cert with your organization's certificate authority instead if you can."
+                    .red()
+                    .bold()
+            );
+        }
+
+        let credentials = self.credentials()?;
+        let mut client = jira::connect(
+            &self.jira_url,
+            &credentials,
+            HttpSettings {
+                timeout: Duration::from_secs(self.http_timeout_secs),
+                root_certificates,
+                accept_invalid_certs: self.insecure_skip_tls_verify,
+            },
+        )
+        .map_err(|e| e.to_string())?;
+
+        if let Some(max_requests_per_second) = self.max_requests_per_second {
+            client = client.with_max_requests_per_second(max_requests_per_second);
+        }
+
+        if self.page_size == 0 {
+            return Err("--page-size has to be greater than zero.".to_owned());
+        }
+        client = client.with_page_size(self.page_size);
+
+        if let Some(max_issues) = self.max_issues {
+            client = client.with_max_issues(max_issues);
+        }
+
+        // Offline, every entry is used however old it is, so the time to live doesn't matter.
+        if self.cache_ttl.is_some() || self.offline {
+            let flag = if self.offline {
+                "--offline"
+            } else {
+                "--cache-ttl"
+            };
+            let dir = cache::default_dir().ok_or_else(|| {
+                format!(
+                    "{} needs a home directory to keep the cache in, but neither HOME nor XDG_CACHE_HOME is set.",
+                    flag
+                )
+            })?;
+            let cache = ResponseCache::open(&dir, self.cache_ttl.unwrap_or_default())
+                .map_err(|e| format!("Could not open the cache in {}: {}", dir.display(), e))?;
+            client = client.with_cache(cache);
+        }
+
+        // Field IDs practically never change, so they're kept between runs even without --cache-ttl.  The cache is only a
+        // shortcut here, so if it can't be opened, the fields are just fetched every time like before.
+        if let Some(dir) = cache::default_dir() {
+            let ttl = if self.refresh_fields {
+                Duration::ZERO
+            } else {
+                FIELD_CACHE_TTL
+            };
+            if let Ok(field_cache) = ResponseCache::open(&dir, ttl) {
+                client = client.with_field_cache(field_cache);
+            }
+        }
+
+        Ok(client
+            .with_api_version(self.api_version)
+            .with_debug_http(self.debug_http)
+            .with_offline(self.offline)
+            .with_retry_policy(RetryPolicy {
+                max_retries: self.max_retries,
+                base_delay: Duration::from_millis(self.retry_base_delay_ms),
+                jitter: !self.no_retry_jitter,
+            }))
+    }
+
+    fn credentials(&self) -> Result<Credentials, String> {
+        // Tokens copied out of a file or a password manager often come with a stray newline.
+        if !self.jira_token.chars().all(|c| c.is_ascii_graphic()) {
+            return Err("--jira-token can only have printable ASCII characters in it, without any spaces or newlines.".to_owned());
+        }
+        let token = self.jira_token.clone();
+
+        match self.auth_scheme {
+            AuthScheme::Basic => match &self.jira_username {
+                Some(username) => Ok(Credentials::Basic {
+                    username: username.clone(),
+                    token,
+                }),
+                None => Err("--jira-username is required with --auth-scheme basic.  If your token is a personal access token from JIRA Data Center, use --auth-scheme bearer instead.".to_owned()),
+            },
+            AuthScheme::Bearer => Ok(Credentials::Bearer { token }),
+        }
+    }
+}
+
+/// Reads a length of time like 90s, 30m, 2h, or 1d.  A bare number is in seconds.
+pub fn parse_duration(duration: &str) -> Result<Duration, String> {
+    let duration = duration.trim();
+    let (amount, seconds_per_unit) = match duration.char_indices().last() {
+        Some((i, 's')) => (&duration[..i], 1),
+        Some((i, 'm')) => (&duration[..i], 60),
+        Some((i, 'h')) => (&duration[..i], 60 * 60),
+        Some((i, 'd')) => (&duration[..i], 24 * 60 * 60),
+        _ => (duration, 1),
+    };
+
+    let amount = amount.parse::<u64>().map_err(|_| {
+        format!(
+            "\"{}\" is not a length of time; it should look like 90s, 30m, 2h, or 1d",
+            duration
+        )
+    })?;
+
+    amount
+        .checked_mul(seconds_per_unit)
+        .map(Duration::from_secs)
+        .ok_or_else(|| format!("\"{}\" is too long a time", duration))
+}
+
+/// Reads a rate of requests, which has to be more than zero, and not so small that the time between requests can't be
+/// counted.
+fn parse_requests_per_second(rate: &str) -> Result<f64, String> {
+    let rate: f64 = rate
+        .trim()
+        .parse()
+        .map_err(|_| format!("\"{}\" is not a number", rate))?;
+    if !(rate.is_finite() && rate > 0.0) {
+        return Err("it has to be a number greater than zero".to_owned());
+    }
+    if Duration::try_from_secs_f64(1.0 / rate).is_err() {
+        return Err(format!("{} is too few requests a second", rate));
+    }
+
+    Ok(rate)
+}
+
+/// Reads every certificate in a PEM file, since a CA's chain is often bundled into one file.
+fn read_certificates(path: &Path) -> Result<Vec<Certificate>, String> {
+    let pem = fs::read_to_string(path).map_err(|e| {
+        format!(
+            "Could not read the certificate at {}: {}",
+            path.display(),
+            e
+        )
+    })?;
+
+    const END: &str = "-----END CERTIFICATE-----";
+    let mut certificates = vec![];
+    let mut rest = pem.as_str();
+    while let Some(start) = rest.find("-----BEGIN CERTIFICATE-----") {
+        let end = match rest[start..].find(END) {
+            Some(end) => start + end + END.len(),
+            None => break,
+        };
+        let certificate = Certificate::from_pem(&rest.as_bytes()[start..end]).map_err(|e| {
+            format!(
+                "{} has a certificate that could not be read: {}",
+                path.display(),
+                e
+            )
+        })?;
+        certificates.push(certificate);
+        rest = &rest[end..];
+    }
+
+    if certificates.is_empty() {
+        return Err(format!(
+            "There are no PEM certificates in {}.  It should have at least one block starting with -----BEGIN CERTIFICATE-----.",
+            path.display()
+        ));
+    }
+
+    Ok(certificates)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_duration_reads_each_unit() {
+        assert_eq!(parse_duration("90"), Ok(Duration::from_secs(90)));
+        assert_eq!(parse_duration("90s"), Ok(Duration::from_secs(90)));
+        assert_eq!(parse_duration("30m"), Ok(Duration::from_secs(30 * 60)));
+        assert_eq!(parse_duration(" 2h "), Ok(Duration::from_secs(2 * 60 * 60)));
+        assert_eq!(parse_duration("1d"), Ok(Duration::from_secs(24 * 60 * 60)));
+        assert!(parse_duration("soon").is_err());
+        assert!(parse_duration("-1h").is_err());
+    }
+
+    #[test]
+    fn parse_duration_refuses_lengths_that_overflow() {
+        assert!(parse_duration("99999999999999999h").is_err());
+        assert!(parse_duration(&format!("{}d", u64::MAX / (24 * 60 * 60) + 1)).is_err());
+        assert_eq!(
+            parse_duration(&u64::MAX.to_string()),
+            Ok(Duration::from_secs(u64::MAX))
+        );
+    }
+
+    #[test]
+    fn parse_requests_per_second_refuses_what_the_throttle_cannot_use() {
+        assert_eq!(parse_requests_per_second("5"), Ok(5.0));
+        assert_eq!(parse_requests_per_second("0.5"), Ok(0.5));
+        for rate in ["0", "-1", "NaN", "inf", "-inf", "1e-300", "many"] {
+            assert!(
+                parse_requests_per_second(rate).is_err(),
+                "{} was accepted",
+                rate
+            );
+        }
+    }
+}
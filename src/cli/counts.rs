@@ -0,0 +1,199 @@
+use std::{cmp::Reverse, collections::BTreeMap};
+
+use clap::Args;
+use colored::Colorize;
+use serde::Serialize;
+
+use crate::{
+    cli::{
+        connection::Connection,
+        exit_with_error,
+        output::{self, Format},
+        release_readiness,
+        scope::Scope,
+    },
+    jira::{self, api::JiraApi, client::SearchIssue, jql::SortDirection},
+};
+
+/// The order status categories go in, from not started to finished.
+const STATUS_CATEGORIES: [&str; 3] = ["To Do", "In Progress", "Done"];
+
+#[derive(Debug, Args)]
+pub struct Counts {
+    /// csv or json, for a script to read.  Without this, the counts are shown as a table for each breakdown.
+    #[arg(long)]
+    pub format: Option<Format>,
+
+    #[arg(long)]
+    #[arg(default_value = "Story Points")]
+    pub jira_story_points_field: String,
+
+    #[command(flatten)]
+    pub scope: Scope,
+
+    #[command(flatten)]
+    pub connection: Connection,
+}
+
+/// How many of the issues have one value of a field, and how many points they add up to.
+#[derive(Debug, Serialize)]
+struct Count {
+    /// What the issues are broken down by: status category, issue type, or priority.
+    breakdown: String,
+    value: String,
+    issues: u64,
+    /// Issues without story points, which aren't in the points.
+    unpointed: u64,
+    points: f64,
+}
+
+/// The name of a field that is an object with a name, like the issue type.
+fn name(issue: &SearchIssue, field: &str) -> Option<String> {
+    issue
+        .fields
+        .get(field)?
+        .get("name")?
+        .as_str()
+        .map(|name| name.to_owned())
+}
+
+/// Adds up the issues by each value a key picks out of them, in the order the key sorts them.
+fn count_by<K: Ord>(
+    breakdown: &str,
+    issues: &[SearchIssue],
+    story_point_field_ids: &[String],
+    key: impl Fn(&SearchIssue) -> (K, String),
+) -> Vec<Count> {
+    let mut counts: BTreeMap<K, Count> = BTreeMap::new();
+    for issue in issues {
+        let (order, value) = key(issue);
+        let count = counts.entry(order).or_insert_with(|| Count {
+            breakdown: breakdown.to_owned(),
+            value,
+            issues: 0,
+            unpointed: 0,
+            points: 0.0,
+        });
+        count.issues += 1;
+        match jira::story_points(issue, story_point_field_ids) {
+            Some(points) => count.points += points,
+            None => count.unpointed += 1,
+        }
+    }
+
+    counts.into_values().collect()
+}
+
+fn counts(issues: &[SearchIssue], story_point_field_ids: &[String]) -> Vec<Count> {
+    let mut counts = count_by("status category", issues, story_point_field_ids, |issue| {
+        let category = issue
+            .status_category()
+            .unwrap_or_else(|| "No status".to_owned());
+        let order = STATUS_CATEGORIES
+            .iter()
+            .position(|known| *known == category)
+            .unwrap_or(STATUS_CATEGORIES.len());
+        ((order, category.clone()), category)
+    });
+
+    // Issue types have no order of their own, so the most common go first.
+    let mut by_type = count_by("issue type", issues, story_point_field_ids, |issue| {
+        let issue_type = name(issue, "issuetype").unwrap_or_else(|| "No issue type".to_owned());
+        (issue_type.clone(), issue_type)
+    });
+    by_type.sort_by_key(|count| Reverse(count.issues));
+    counts.append(&mut by_type);
+
+    counts.append(&mut count_by(
+        "priority",
+        issues,
+        story_point_field_ids,
+        |issue| {
+            let priority = release_readiness::priority(issue);
+            (priority.clone(), priority.1)
+        },
+    ));
+
+    counts
+}
+
+async fn search(client: &impl JiraApi, args: &Counts) -> Result<Vec<Count>, String> {
+    let story_point_field_ids = jira::get_field_ids_named(client, &args.jira_story_points_field)
+        .await
+        .map_err(|e| format!("Could not get the story point fields: {}", e))?;
+    let mut fields = story_point_field_ids.clone();
+    for field in ["status", "issuetype", "priority"] {
+        fields.push(field.to_owned());
+    }
+
+    let jql = args
+        .scope
+        .builder(client)
+        .await
+        .and_then(|builder| builder.order_by("key", SortDirection::Ascending).build())?;
+    if args.scope.validate_jql {
+        jira::validate_jql(client, &jql)
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+    let issues = client
+        .search_all(&fields, &jql)
+        .await
+        .map_err(|e| format!("Could not search for issues: {}", e))?;
+
+    Ok(counts(&issues, &story_point_field_ids))
+}
+
+fn show(counts: &[Count]) {
+    let total: u64 = counts
+        .iter()
+        .filter(|count| count.breakdown == "status category")
+        .map(|count| count.issues)
+        .sum();
+    let width = counts
+        .iter()
+        .map(|count| count.value.chars().count())
+        .max()
+        .unwrap_or(0);
+
+    let mut breakdown = "";
+    for count in counts {
+        if count.breakdown != breakdown {
+            if !breakdown.is_empty() {
+                println!();
+            }
+            breakdown = &count.breakdown;
+            println!("{}", format!("By {}", breakdown).bold());
+        }
+
+        let unpointed = if count.unpointed > 0 {
+            format!("{} unpointed", count.unpointed)
+                .yellow()
+                .to_string()
+        } else {
+            String::new()
+        };
+        println!(
+            "  {:<width$}  {:>5} issues {:>4.0}%  {:>7.1} pts  {}",
+            count.value,
+            count.issues,
+            count.issues as f64 / total.max(1) as f64 * 100.0,
+            count.points,
+            unpointed,
+            width = width
+        );
+    }
+
+    println!();
+    println!("{} issues in all.", format!("{}", total).bright_yellow());
+}
+
+pub async fn run(args: &Counts) {
+    let client = args.connection.connect().unwrap_or_else(exit_with_error);
+    let counts = search(&client, args).await.unwrap_or_else(exit_with_error);
+
+    match args.format {
+        Some(format) => output::write_rows(format, &counts).unwrap_or_else(exit_with_error),
+        None => show(&counts),
+    }
+}
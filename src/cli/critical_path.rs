@@ -0,0 +1,277 @@
+use std::collections::{HashMap, HashSet};
+
+use clap::Args;
+use colored::Colorize;
+use serde::Serialize;
+
+use crate::{
+    cli::{
+        connection::Connection,
+        exit_with_error,
+        output::{self, Format},
+        report,
+        scope::Scope,
+    },
+    date::DateTime,
+    jira::{self, api::JiraApi, client::SearchIssue, jql::SortDirection},
+};
+
+#[derive(Debug, Args)]
+pub struct CriticalPath {
+    /// The story points to count for issues that have none.
+    #[arg(long)]
+    #[arg(default_value_t = 3.0)]
+    pub default_story_points: f64,
+
+    /// csv or json, for a script to read.  Without this, the chain is shown in the order it has to be worked in, with
+    /// how it compares to the rest of the work.
+    #[arg(long)]
+    pub format: Option<Format>,
+
+    #[arg(long)]
+    #[arg(default_value = "Story Points")]
+    pub jira_story_points_field: String,
+
+    #[arg(long)]
+    #[arg(default_value_t = 14)]
+    pub sprint_length_days: u32,
+
+    #[arg(long)]
+    pub velocity_in_story_points: f64,
+
+    #[command(flatten)]
+    pub scope: Scope,
+
+    #[command(flatten)]
+    pub connection: Connection,
+}
+
+/// An issue on the critical path, in the order the chain has to be worked in.
+#[derive(Debug, Serialize)]
+struct Step {
+    key: String,
+    summary: String,
+    status: String,
+    points: f64,
+    /// When the chain is projected to get through this issue, at the team's velocity.
+    projected_finish: String,
+    link: String,
+}
+
+/// The longest chain of unfinished issues blocking each other, and how it compares to all the work that's left.
+struct Path {
+    steps: Vec<Step>,
+    chain_points: f64,
+    total_points: f64,
+}
+
+/// Works out the most points that have to be done, one after another, to finish an issue and everything blocking it.
+///
+/// Each issue's best chain is remembered in `longest`, along with the blocker it goes through.  An issue that is
+/// reached again while its own chain is still being worked out is part of a loop, and the link back to it is ignored.
+fn longest_chain<'a>(
+    key: &'a str,
+    points: &HashMap<&'a str, f64>,
+    blockers: &HashMap<&'a str, Vec<&'a str>>,
+    longest: &mut HashMap<&'a str, (f64, Option<&'a str>)>,
+    visiting: &mut HashSet<&'a str>,
+) -> f64 {
+    if let Some((chain, _)) = longest.get(key) {
+        return *chain;
+    }
+    if !visiting.insert(key) {
+        eprintln!(
+            "{} is part of a loop of issues blocking each other, so one of its links is ignored.",
+            key
+        );
+        return 0.0;
+    }
+
+    let mut best: (f64, Option<&str>) = (0.0, None);
+    for blocker in blockers.get(key).into_iter().flatten() {
+        let chain = longest_chain(blocker, points, blockers, longest, visiting);
+        if chain > best.0 {
+            best = (chain, Some(blocker));
+        }
+    }
+
+    visiting.remove(key);
+    let chain = points[key] + best.0;
+    longest.insert(key, (chain, best.1));
+
+    chain
+}
+
+fn critical_path(
+    issues: &[SearchIssue],
+    story_point_field_ids: &[String],
+    args: &CriticalPath,
+) -> Path {
+    // Finished issues don't hold anything up any more, so they're left out of the chains.
+    let unfinished: Vec<&SearchIssue> = issues
+        .iter()
+        .filter(|issue| issue.status_category().as_deref() != Some("Done"))
+        .collect();
+    // Like the burndown, a zero is treated as not having been pointed yet.
+    let points: HashMap<&str, f64> = unfinished
+        .iter()
+        .map(|issue| {
+            let points = jira::story_points(issue, story_point_field_ids)
+                .filter(|points| *points != 0.0)
+                .unwrap_or(args.default_story_points);
+            (issue.key.as_str(), points)
+        })
+        .collect();
+
+    // Blockers outside the search, or already finished, aren't part of any chain.
+    let dependencies = jira::dependencies(issues);
+    let blockers: HashMap<&str, Vec<&str>> = unfinished
+        .iter()
+        .map(|issue| {
+            let keys = dependencies
+                .get(&issue.key)
+                .into_iter()
+                .flatten()
+                .filter_map(|blocker| points.get_key_value(blocker.as_str()))
+                .map(|(blocker, _)| *blocker)
+                .collect();
+            (issue.key.as_str(), keys)
+        })
+        .collect();
+
+    let mut longest = HashMap::new();
+    let mut visiting = HashSet::new();
+    let mut end: Option<(&str, f64)> = None;
+    for issue in &unfinished {
+        let chain = longest_chain(&issue.key, &points, &blockers, &mut longest, &mut visiting);
+        if end.map(|(_, longest)| chain > longest).unwrap_or(true) {
+            end = Some((&issue.key, chain));
+        }
+    }
+
+    let mut chain = vec![];
+    let mut next = end.map(|(key, _)| key);
+    while let Some(key) = next {
+        chain.push(key);
+        next = longest.get(key).and_then(|(_, blocker)| *blocker);
+    }
+    chain.reverse();
+
+    let by_key: HashMap<&str, &SearchIssue> = unfinished
+        .iter()
+        .map(|issue| (issue.key.as_str(), *issue))
+        .collect();
+    let today = DateTime::now().start_of_day();
+    let days_per_point = args.sprint_length_days as f64 / args.velocity_in_story_points;
+    let mut points_so_far = 0.0;
+    let steps = chain
+        .into_iter()
+        .map(|key| {
+            let issue = by_key[key];
+            points_so_far += points[key];
+            Step {
+                key: key.to_owned(),
+                summary: report::summary(issue),
+                status: issue.status().unwrap_or_default(),
+                points: points[key],
+                projected_finish: today
+                    .plus_days((points_so_far * days_per_point).ceil() as i64)
+                    .to_date_string(),
+                link: format!("{}/browse/{}", args.connection.jira_url, key),
+            }
+        })
+        .collect();
+
+    Path {
+        steps,
+        chain_points: points_so_far,
+        total_points: points.values().fold(0.0, |total, points| total + points),
+    }
+}
+
+async fn search(client: &impl JiraApi, args: &CriticalPath) -> Result<Path, String> {
+    let story_point_field_ids = jira::get_field_ids_named(client, &args.jira_story_points_field)
+        .await
+        .map_err(|e| format!("Could not get the story point fields: {}", e))?;
+    let mut fields = story_point_field_ids.clone();
+    for field in ["summary", "status", "issuelinks"] {
+        fields.push(field.to_owned());
+    }
+
+    let jql = args
+        .scope
+        .builder(client)
+        .await
+        .and_then(|builder| builder.order_by("key", SortDirection::Ascending).build())?;
+    if args.scope.validate_jql {
+        jira::validate_jql(client, &jql)
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+    let issues = client
+        .search_all(&fields, &jql)
+        .await
+        .map_err(|e| format!("Could not search for issues: {}", e))?;
+
+    Ok(critical_path(&issues, &story_point_field_ids, args))
+}
+
+fn show(args: &CriticalPath, path: &Path) {
+    if path.steps.is_empty() {
+        println!("There is no unfinished work in the search.");
+        return;
+    }
+
+    for step in &path.steps {
+        println!(
+            "{}  {:<12} {:>5.1}  {:<20} {}",
+            step.projected_finish, step.key, step.points, step.status, step.summary
+        );
+    }
+
+    let chain_sprints = path.chain_points / args.velocity_in_story_points;
+    let total_sprints = path.total_points / args.velocity_in_story_points;
+    println!(
+        "The longest chain of blocked work is {} issues and {:.1} points, which takes {} sprints on its own.",
+        path.steps.len(),
+        path.chain_points,
+        format!("{:.1}", chain_sprints).bold()
+    );
+
+    // The chain can't go any faster than one issue after another, so it has to be started early enough to be done by
+    // the time the rest of the work is.
+    let slack_sprints = total_sprints - chain_sprints;
+    if slack_sprints <= 0.0 {
+        println!(
+            "Everything left is on the chain, so {} gates the end date.",
+            "every issue on it".bright_red()
+        );
+    } else {
+        let start_by = DateTime::now()
+            .start_of_day()
+            .plus_days((slack_sprints * args.sprint_length_days as f64).floor() as i64);
+        println!(
+            "All {:.1} points left take {:.1} sprints, so the chain gates the end date if it isn't started by {}.",
+            path.total_points,
+            total_sprints,
+            start_by.to_date_string().yellow()
+        );
+    }
+}
+
+pub async fn run(args: &CriticalPath) {
+    if args.velocity_in_story_points <= 0.0 {
+        exit_with_error("--velocity-in-story-points has to be more than zero.")
+    }
+    if args.sprint_length_days == 0 {
+        exit_with_error("--sprint-length-days has to be more than zero.")
+    }
+
+    let client = args.connection.connect().unwrap_or_else(exit_with_error);
+    let path = search(&client, args).await.unwrap_or_else(exit_with_error);
+
+    match args.format {
+        Some(format) => output::write_rows(format, &path.steps).unwrap_or_else(exit_with_error),
+        None => show(args, &path),
+    }
+}
@@ -1,77 +1,118 @@
-use std::{io, process};
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
 
 use clap::Args;
-use csv;
-use jimberlage_jira_client::{self, RestClient};
-use serde::Serialize;
 
-use crate::jira;
+use crate::{
+    cli::{connection::Connection, detect_fields, exit_with_error, scope::Scope},
+    export::IssueExporter,
+    jira::{self, api::JiraApi, jql::SortDirection},
+    snapshot::Snapshot,
+};
 
 #[derive(Debug, Args)]
-pub struct CSV {
+pub struct Csv {
+    /// Export the issues in a snapshot saved by the snapshot command, instead of searching JIRA.  The snapshot's
+    /// issues are used as they are, so the options that pick out issues don't apply, and no connection options are
+    /// needed.
     #[arg(long)]
-    pub jira_story_points_field: String,
+    #[arg(conflicts_with = "instance")]
+    pub from_snapshot: Option<PathBuf>,
 
+    /// An argfile with the connection options for another JIRA site, like --jira-url and --jira-token, to export the
+    /// same search from.  Each site's rows come after the ones before it.
     #[arg(long)]
-    pub jira_label: Vec<String>,
+    pub instance: Vec<PathBuf>,
 
+    /// The name of the field story points are kept in.  Without this, the field is picked by its name and by which
+    /// number fields the issues have values in, as detect-fields shows.
     #[arg(long)]
-    pub jira_project: Vec<String>,
+    pub jira_story_points_field: Option<String>,
 
-    #[arg(long)]
-    pub jira_issue_type: Vec<String>,
-
-    #[arg(long)]
-    pub jira_token: String,
+    #[command(flatten)]
+    pub scope: Scope,
 
-    #[arg(long)]
-    pub jira_url: String,
-
-    #[arg(long)]
-    pub jira_username: String,
+    #[command(flatten)]
+    pub connection: Connection,
 }
 
-#[derive(Debug, Serialize)]
-struct CSVIssue {
-    #[serde(rename(serialize = "ID"))]
-    key: String,
-
-    #[serde(rename(serialize = "Story Points"))]
-    story_points: Option<f64>,
+/// Writes a row for every issue the options pick out on one JIRA site.
+async fn export<W: io::Write>(
+    client: &impl JiraApi,
+    args: &Csv,
+    jira_url: &str,
+    writer: &mut csv::Writer<W>,
+) -> Result<(), String> {
+    // Sorting by key keeps the rows in the same order from one export to the next, so exports can be diffed.
+    let jql = args
+        .scope
+        .builder(client)
+        .await
+        .and_then(|builder| builder.order_by("key", SortDirection::Ascending).build())?;
+
+    if args.scope.validate_jql {
+        jira::validate_jql(client, &jql)
+            .await
+            .map_err(|e| e.to_string())?;
+    }
 
-    #[serde(rename(serialize = "Status"))]
-    status: Option<String>,
+    let story_point_field_ids =
+        detect_fields::story_point_field_ids(client, args.jira_story_points_field.as_deref(), &jql)
+            .await?;
+    IssueExporter::new(jira_url, story_point_field_ids)
+        .export(client, &jql, writer)
+        .await
+        .map_err(|e| e.to_string())?;
 
-    #[serde(rename(serialize = "Link"))]
-    link: String,
+    Ok(())
 }
 
-pub fn run(args: &CSV) {
-    let client = RestClient::new(&args.jira_url, &args.jira_username, &args.jira_token).unwrap();
-
-    let mut field_ids =
-        jira::get_story_point_field_ids(&client, &args.jira_story_points_field).unwrap();
-    field_ids.push("status".to_owned());
+/// Writes a row for every issue in a snapshot, linking to the site it was saved from.
+fn export_snapshot<W: io::Write>(
+    path: &Path,
+    args: &Csv,
+    writer: &mut csv::Writer<W>,
+) -> Result<(), String> {
+    let snapshot = Snapshot::read(path).map_err(|e| e.to_string())?;
+    let story_point_field_ids = detect_fields::snapshot_story_point_field_ids(
+        &snapshot,
+        args.jira_story_points_field.as_deref(),
+    )?;
+    IssueExporter::new(&snapshot.jira_url, story_point_field_ids)
+        .write_issues(&snapshot.issues, writer)
+        .map_err(|e| e.to_string())?;
+
+    Ok(())
+}
 
-    let jql =
-        jira::build_issue_search_jql(&args.jira_project, &args.jira_label, &args.jira_issue_type);
-    if jql.is_err() {
-        eprintln!("{}", jql.unwrap_err());
-        process::exit(1);
+pub async fn run(args: &Csv) {
+    if let Some(path) = &args.from_snapshot {
+        let mut writer = csv::Writer::from_writer(io::stdout());
+        export_snapshot(path, args, &mut writer).unwrap_or_else(exit_with_error);
+        writer.flush().unwrap();
+        return;
     }
 
-    let issues = client.search_all(&field_ids, &jql.unwrap()).unwrap();
-    let mut writer = csv::Writer::from_writer(io::stdout());
+    let connections = args
+        .connection
+        .with_instances(&args.instance)
+        .unwrap_or_else(exit_with_error);
 
-    for issue in issues {
-        writer
-            .serialize(CSVIssue {
-                key: issue.key.clone(),
-                story_points: jira::story_points(&issue, &field_ids),
-                status: issue.status_category(),
-                link: format!("{}/browse/{}", &args.jira_url, &issue.key),
+    let mut writer = csv::Writer::from_writer(io::stdout());
+    for connection in &connections {
+        let client = connection.connect().unwrap_or_else(exit_with_error);
+
+        if let Err(e) = export(&client, args, &connection.jira_url, &mut writer).await {
+            // Keep whatever was exported before the failure.
+            let _ = writer.flush();
+            exit_with_error(if connections.len() > 1 {
+                format!("{}: {}", connection.jira_url, e)
+            } else {
+                e
             })
-            .unwrap();
+        }
     }
 
     writer.flush().unwrap();
@@ -0,0 +1,187 @@
+use std::{collections::BTreeMap, str::FromStr};
+
+use clap::Args;
+use serde::Serialize;
+
+use crate::{
+    cli::{
+        connection::Connection,
+        exit_with_error,
+        output::{self, Format},
+        scope::Scope,
+        timeline::{self, Timeline},
+    },
+    date::DateTime,
+    jira::{api::JiraApi, client::SearchIssue, history, AssigneeNames},
+};
+
+const SECONDS_PER_DAY: f64 = 24.0 * 60.0 * 60.0;
+
+/// What to split the issues up by, so that each group gets its own percentiles.
+#[derive(Clone, Copy, Debug)]
+pub enum GroupBy {
+    IssueType,
+    Assignee,
+}
+
+impl FromStr for GroupBy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.eq_ignore_ascii_case("issue-type") {
+            Ok(GroupBy::IssueType)
+        } else if s.eq_ignore_ascii_case("assignee") {
+            Ok(GroupBy::Assignee)
+        } else {
+            Err("issues can only be grouped by issue-type or assignee".to_owned())
+        }
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct CycleTime {
+    /// csv or json.
+    #[arg(long)]
+    #[arg(default_value = "csv")]
+    pub format: Format,
+
+    /// issue-type or assignee, to report on each separately rather than on every issue together.
+    #[arg(long)]
+    pub group_by: Option<GroupBy>,
+
+    #[command(flatten)]
+    pub timeline: Timeline,
+
+    #[command(flatten)]
+    pub scope: Scope,
+
+    #[command(flatten)]
+    pub connection: Connection,
+}
+
+/// How long the issues finished in the window took, in days.
+///
+/// Cycle time runs from when work on an issue started to when it was done, and lead time from when it was created.
+/// Issues that never went through an in progress status have no cycle time, so there may be fewer of those.
+#[derive(Debug, Serialize)]
+struct Times {
+    group: String,
+    issues: usize,
+    cycle_time_p50: Option<f64>,
+    cycle_time_p85: Option<f64>,
+    cycle_time_p95: Option<f64>,
+    lead_time_p50: Option<f64>,
+    lead_time_p85: Option<f64>,
+    lead_time_p95: Option<f64>,
+}
+
+/// The issue's type, like "Story", as long as the `issuetype` field was requested.
+pub fn issue_type(issue: &SearchIssue) -> Option<String> {
+    Some(
+        issue
+            .fields
+            .get("issuetype")?
+            .get("name")?
+            .as_str()?
+            .to_owned(),
+    )
+}
+
+pub fn days_between(start: DateTime, end: DateTime) -> f64 {
+    (end.to_unix_seconds() - start.to_unix_seconds()) as f64 / SECONDS_PER_DAY
+}
+
+/// The value that `percent` of the values are at or below, by the nearest rank.  The values have to be sorted.
+pub fn percentile(sorted: &[f64], percent: f64) -> Option<f64> {
+    if sorted.is_empty() {
+        return None;
+    }
+
+    let rank = (percent / 100.0 * sorted.len() as f64).ceil() as usize;
+    let value = sorted[rank.clamp(1, sorted.len()) - 1];
+
+    // A tenth of a day is as precise as anyone planning with these needs.
+    Some((value * 10.0).round() / 10.0)
+}
+
+impl Times {
+    fn new(group: String, mut cycle_times: Vec<f64>, mut lead_times: Vec<f64>) -> Self {
+        cycle_times.sort_by(f64::total_cmp);
+        lead_times.sort_by(f64::total_cmp);
+
+        Times {
+            group,
+            issues: lead_times.len(),
+            cycle_time_p50: percentile(&cycle_times, 50.0),
+            cycle_time_p85: percentile(&cycle_times, 85.0),
+            cycle_time_p95: percentile(&cycle_times, 95.0),
+            lead_time_p50: percentile(&lead_times, 50.0),
+            lead_time_p85: percentile(&lead_times, 85.0),
+            lead_time_p95: percentile(&lead_times, 95.0),
+        }
+    }
+}
+
+async fn cycle_times(client: &impl JiraApi, args: &CycleTime) -> Result<Vec<Times>, String> {
+    let days = args.timeline.days()?;
+    // Unwrap here is considered safe since there is always at least one day in the timeline.
+    let since = *days.first().unwrap();
+    let until = timeline::end_of_day(*days.last().unwrap());
+
+    let categories = history::status_categories(client)
+        .await
+        .map_err(|e| format!("Could not get the statuses: {}", e))?;
+    let fields = vec![
+        "status".to_owned(),
+        "issuetype".to_owned(),
+        "assignee".to_owned(),
+    ];
+    let issues =
+        timeline::search_completed_with_changelogs(client, &args.scope, &fields, since).await?;
+
+    let assignee_names = AssigneeNames::new();
+    // Each group's cycle times and lead times.
+    let mut groups: BTreeMap<String, (Vec<f64>, Vec<f64>)> = BTreeMap::new();
+    for issue in &issues {
+        let completed_at = match history::completed_at(issue, &categories) {
+            Some(completed_at)
+                if completed_at.to_unix_seconds() >= since.to_unix_seconds()
+                    && completed_at.to_unix_seconds() <= until.to_unix_seconds() =>
+            {
+                completed_at
+            }
+            _ => continue,
+        };
+
+        let group = match args.group_by {
+            None => "All".to_owned(),
+            Some(GroupBy::IssueType) => issue_type(issue).unwrap_or_else(|| "None".to_owned()),
+            Some(GroupBy::Assignee) => assignee_names
+                .assignee(client, issue)
+                .await
+                .unwrap_or_else(|| "Unassigned".to_owned()),
+        };
+        let (cycle_times, lead_times) = groups.entry(group).or_default();
+
+        if let Some(started_at) = history::started_at(issue, &categories) {
+            cycle_times.push(days_between(started_at, completed_at));
+        }
+        if let Some(created_at) = history::created_at(issue) {
+            lead_times.push(days_between(created_at, completed_at));
+        }
+    }
+
+    Ok(groups
+        .into_iter()
+        .map(|(group, (cycle_times, lead_times))| Times::new(group, cycle_times, lead_times))
+        .collect())
+}
+
+pub async fn run(args: &CycleTime) {
+    let client = args.connection.connect().unwrap_or_else(exit_with_error);
+    let times = cycle_times(&client, args)
+        .await
+        .unwrap_or_else(exit_with_error);
+
+    output::write_rows(args.format, &times).unwrap_or_else(exit_with_error);
+}
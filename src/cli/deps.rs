@@ -0,0 +1,286 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    str::FromStr,
+};
+
+use clap::Args;
+
+use crate::{
+    cli::{connection::Connection, exit_with_error, report, scope::Scope},
+    jira::{self, api::JiraApi, client::SearchIssue, jql::SortDirection},
+};
+
+#[derive(Clone, Copy, Debug)]
+pub enum GraphFormat {
+    /// Graphviz, to draw with a command like `dot -Tsvg`.
+    Dot,
+    /// A Mermaid flowchart, which Markdown on GitHub, GitLab, and many wikis draws on its own.
+    Mermaid,
+}
+
+impl FromStr for GraphFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.eq_ignore_ascii_case("dot") {
+            Ok(GraphFormat::Dot)
+        } else if s.eq_ignore_ascii_case("mermaid") {
+            Ok(GraphFormat::Mermaid)
+        } else {
+            Err("the format has to be dot or mermaid".to_owned())
+        }
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct Deps {
+    /// dot, or mermaid for a flowchart to put in Markdown.
+    #[arg(long)]
+    #[arg(default_value = "dot")]
+    pub format: GraphFormat,
+
+    #[command(flatten)]
+    pub scope: Scope,
+
+    #[command(flatten)]
+    pub connection: Connection,
+}
+
+/// The status category of an issue, which decides the color it's drawn in.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Category {
+    ToDo,
+    InProgress,
+    Done,
+    /// The issue is blocking or blocked by one in the search, without being in it, so how it stands isn't known.
+    Outside,
+}
+
+/// An issue with a link in or out of it.
+struct Node {
+    key: String,
+    summary: String,
+    category: Category,
+}
+
+/// The issues that block or are blocked, and the links between them, blockers first.
+struct Graph {
+    nodes: Vec<Node>,
+    edges: Vec<(String, String)>,
+}
+
+/// Sorts issue keys by project, and then by number, so that A-2 comes before A-10.
+fn key_order(key: &str) -> (String, u64) {
+    match key.rsplit_once('-') {
+        Some((project, number)) => (project.to_owned(), number.parse().unwrap_or(u64::MAX)),
+        None => (key.to_owned(), u64::MAX),
+    }
+}
+
+/// Works out the graph of blocking links.  Issues without any are left out, since they don't wait on anything.
+fn graph(issues: &[SearchIssue]) -> Graph {
+    let mut edges = BTreeSet::new();
+    for (blocked, blockers) in jira::dependencies(issues) {
+        for blocker in blockers {
+            edges.insert((
+                key_order(&blocker),
+                blocker,
+                key_order(&blocked),
+                blocked.clone(),
+            ));
+        }
+    }
+
+    let in_search: HashMap<&str, &SearchIssue> = issues
+        .iter()
+        .map(|issue| (issue.key.as_str(), issue))
+        .collect();
+    let mut keys: Vec<&String> = edges
+        .iter()
+        .flat_map(|(_, blocker, _, blocked)| [blocker, blocked])
+        .collect();
+    keys.sort_by_key(|key| (key_order(key), key.to_string()));
+    keys.dedup();
+
+    let nodes = keys
+        .into_iter()
+        .map(|key| match in_search.get(key.as_str()) {
+            Some(issue) => Node {
+                key: key.clone(),
+                summary: report::summary(issue),
+                category: match issue.status_category().as_deref() {
+                    Some("Done") => Category::Done,
+                    Some("In Progress") => Category::InProgress,
+                    _ => Category::ToDo,
+                },
+            },
+            None => Node {
+                key: key.clone(),
+                summary: String::new(),
+                category: Category::Outside,
+            },
+        })
+        .collect();
+
+    Graph {
+        nodes,
+        edges: edges
+            .into_iter()
+            .map(|(_, blocker, _, blocked)| (blocker, blocked))
+            .collect(),
+    }
+}
+
+/// The fill and border colors for each kind of issue, matching the colors JIRA gives status categories.
+fn colors(category: Category) -> (&'static str, &'static str) {
+    match category {
+        Category::Done => ("#e3fcef", "#006644"),
+        Category::InProgress => ("#deebff", "#0747a6"),
+        Category::ToDo => ("#f4f5f7", "#42526e"),
+        Category::Outside => ("#ffffff", "#97a0af"),
+    }
+}
+
+fn dot_text(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn dot(graph: &Graph, jira_url: &str) -> String {
+    let mut dot = String::new();
+    dot.push_str("digraph dependencies {\n");
+    dot.push_str("    rankdir=LR;\n");
+    dot.push_str("    node [shape=box, style=\"rounded,filled\", fontname=\"sans-serif\"];\n");
+    for node in &graph.nodes {
+        let (fill, border) = colors(node.category);
+        let label = if node.summary.is_empty() {
+            dot_text(&node.key)
+        } else {
+            format!("{}\\n{}", dot_text(&node.key), dot_text(&node.summary))
+        };
+        let style = if node.category == Category::Outside {
+            ", style=\"rounded,dashed\""
+        } else {
+            ""
+        };
+        dot.push_str(&format!(
+            "    \"{}\" [label=\"{}\", fillcolor=\"{}\", color=\"{}\", URL=\"{}/browse/{}\"{}];\n",
+            dot_text(&node.key),
+            label,
+            fill,
+            border,
+            dot_text(jira_url),
+            dot_text(&node.key),
+            style
+        ));
+    }
+    for (blocker, blocked) in &graph.edges {
+        dot.push_str(&format!(
+            "    \"{}\" -> \"{}\";\n",
+            dot_text(blocker),
+            dot_text(blocked)
+        ));
+    }
+    dot.push_str("}\n");
+
+    dot
+}
+
+/// Mermaid IDs can only be letters, digits, and underscores.
+fn mermaid_id(key: &str) -> String {
+    key.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect()
+}
+
+fn mermaid_text(text: &str) -> String {
+    text.replace('"', "#quot;")
+}
+
+fn mermaid(graph: &Graph) -> String {
+    let mut chart = String::new();
+    chart.push_str("flowchart LR\n");
+    for node in &graph.nodes {
+        let label = if node.summary.is_empty() {
+            mermaid_text(&node.key)
+        } else {
+            format!(
+                "{}: {}",
+                mermaid_text(&node.key),
+                mermaid_text(&node.summary)
+            )
+        };
+        chart.push_str(&format!("    {}[\"{}\"]\n", mermaid_id(&node.key), label));
+    }
+    for (blocker, blocked) in &graph.edges {
+        chart.push_str(&format!(
+            "    {} --> {}\n",
+            mermaid_id(blocker),
+            mermaid_id(blocked)
+        ));
+    }
+
+    for (class, category) in [
+        ("done", Category::Done),
+        ("inProgress", Category::InProgress),
+        ("toDo", Category::ToDo),
+        ("outside", Category::Outside),
+    ] {
+        let ids: Vec<String> = graph
+            .nodes
+            .iter()
+            .filter(|node| node.category == category)
+            .map(|node| mermaid_id(&node.key))
+            .collect();
+        if ids.is_empty() {
+            continue;
+        }
+
+        let (fill, border) = colors(category);
+        let dashes = if category == Category::Outside {
+            ",stroke-dasharray:4 4"
+        } else {
+            ""
+        };
+        chart.push_str(&format!(
+            "    classDef {} fill:{},stroke:{}{}\n",
+            class, fill, border, dashes
+        ));
+        chart.push_str(&format!("    class {} {}\n", ids.join(","), class));
+    }
+
+    chart
+}
+
+async fn deps(client: &impl JiraApi, args: &Deps) -> Result<Graph, String> {
+    let fields: Vec<String> = ["summary", "status", "issuelinks"]
+        .iter()
+        .map(|field| field.to_string())
+        .collect();
+
+    let jql = args
+        .scope
+        .builder(client)
+        .await
+        .and_then(|builder| builder.order_by("key", SortDirection::Ascending).build())?;
+    if args.scope.validate_jql {
+        jira::validate_jql(client, &jql)
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+    let issues = client
+        .search_all(&fields, &jql)
+        .await
+        .map_err(|e| format!("Could not search for issues: {}", e))?;
+
+    Ok(graph(&issues))
+}
+
+pub async fn run(args: &Deps) {
+    let client = args.connection.connect().unwrap_or_else(exit_with_error);
+    let graph = deps(&client, args).await.unwrap_or_else(exit_with_error);
+
+    match args.format {
+        GraphFormat::Dot => print!("{}", dot(&graph, &args.connection.jira_url)),
+        GraphFormat::Mermaid => print!("{}", mermaid(&graph)),
+    }
+}
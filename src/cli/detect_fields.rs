@@ -0,0 +1,183 @@
+use clap::Args;
+use colored::Colorize;
+use serde::Serialize;
+
+use crate::{
+    cli::{
+        connection::Connection,
+        exit_with_error,
+        output::{self, Format},
+        scope::Scope,
+    },
+    jira::{self, api::JiraApi, jql::JQLStatement, NameMatch, StoryPointsCandidate},
+    snapshot::Snapshot,
+};
+
+#[derive(Debug, Args)]
+pub struct DetectFields {
+    /// csv or json, for a script to read.  Without this, the fields are shown most likely first.
+    #[arg(long)]
+    pub format: Option<Format>,
+
+    #[command(flatten)]
+    pub scope: Scope,
+
+    #[command(flatten)]
+    pub connection: Connection,
+}
+
+#[derive(Debug, Serialize)]
+struct CandidateRow {
+    name: String,
+    id: String,
+    /// Why the field was looked at: its name is JIRA's, looks like story points, or it's a number field.
+    name_match: String,
+    issues_with_values: usize,
+    /// Whether commands would use this field when --jira-story-points-field isn't given.
+    chosen: bool,
+}
+
+fn describe(name_match: NameMatch) -> &'static str {
+    match name_match {
+        NameMatch::Known => "JIRA's name for story points",
+        NameMatch::Similar => "named like story points",
+        NameMatch::Custom => "a number field",
+    }
+}
+
+/// Says which field was picked for story points, on stderr so that it doesn't end up in exported data.
+fn report(candidate: &StoryPointsCandidate) {
+    eprintln!(
+        "{}",
+        format!(
+            "Using {} ({}) for story points, which has values on {} of the issues looked at.  Pass --jira-story-points-field to use another.",
+            candidate.name, candidate.id, candidate.issues_with_values
+        )
+        .dimmed()
+    );
+}
+
+fn not_found() -> String {
+    "Could not find a field with story points in it.  Pass its name with --jira-story-points-field, or run detect-fields to see the fields that were looked at.".to_owned()
+}
+
+/// Gets the IDs of the story points field named, or finds the field by looking at the issues a search finds when none
+/// is.
+pub async fn story_point_field_ids(
+    client: &impl JiraApi,
+    field_name: Option<&str>,
+    jql: &JQLStatement,
+) -> Result<Vec<String>, String> {
+    if let Some(field_name) = field_name {
+        return jira::get_field_ids_named(client, field_name)
+            .await
+            .map_err(|e| format!("Could not get the story point fields: {}", e));
+    }
+
+    let candidates = jira::detect_story_point_fields(client, jql)
+        .await
+        .map_err(|e| format!("Could not look for the story point field: {}", e))?;
+    let chosen = candidates.first().ok_or_else(not_found)?;
+    report(chosen);
+
+    Ok(vec![chosen.id.clone()])
+}
+
+/// Like [story_point_field_ids], for the fields and issues saved in a snapshot.
+pub fn snapshot_story_point_field_ids(
+    snapshot: &Snapshot,
+    field_name: Option<&str>,
+) -> Result<Vec<String>, String> {
+    if let Some(field_name) = field_name {
+        return Ok(snapshot.field_ids_named(field_name));
+    }
+
+    let candidates = jira::rank_story_point_fields(&snapshot.fields, &snapshot.issues);
+    let chosen = candidates.first().ok_or_else(not_found)?;
+    report(chosen);
+
+    Ok(vec![chosen.id.clone()])
+}
+
+async fn detect_fields(
+    client: &impl JiraApi,
+    args: &DetectFields,
+) -> Result<Vec<CandidateRow>, String> {
+    let jql = args.scope.builder(client).await?.build()?;
+    if args.scope.validate_jql {
+        jira::validate_jql(client, &jql)
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+
+    let candidates = jira::detect_story_point_fields(client, &jql)
+        .await
+        .map_err(|e| format!("Could not look for the story point field: {}", e))?;
+
+    Ok(candidates
+        .into_iter()
+        .enumerate()
+        .map(|(i, candidate)| CandidateRow {
+            name_match: describe(candidate.name_match).to_owned(),
+            name: candidate.name,
+            id: candidate.id,
+            issues_with_values: candidate.issues_with_values,
+            chosen: i == 0,
+        })
+        .collect())
+}
+
+fn show(rows: &[CandidateRow]) {
+    let name_width = rows
+        .iter()
+        .map(|row| row.name.chars().count())
+        .max()
+        .unwrap_or(0);
+    let id_width = rows
+        .iter()
+        .map(|row| row.id.chars().count())
+        .max()
+        .unwrap_or(0);
+
+    for row in rows {
+        let line = format!(
+            "{} {:<name_width$}  {:<id_width$}  {:>4} issues with values  {}",
+            if row.chosen { "→" } else { " " },
+            row.name,
+            row.id,
+            row.issues_with_values,
+            row.name_match.dimmed(),
+            name_width = name_width,
+            id_width = id_width
+        );
+        if row.chosen {
+            println!("{}", line.bright_green());
+        } else {
+            println!("{}", line);
+        }
+    }
+
+    match rows.first() {
+        Some(row) => println!(
+            "Without --jira-story-points-field, \"{}\" is used, from looking at up to {} of the issues the search finds.",
+            row.name,
+            jira::STORY_POINT_SAMPLE_SIZE
+        ),
+        None => println!(
+            "{}",
+            "There are no number fields that look like they hold story points.".bright_red()
+        ),
+    }
+}
+
+pub async fn run(args: &DetectFields) {
+    let client = args.connection.connect().unwrap_or_else(exit_with_error);
+    let rows = detect_fields(&client, args)
+        .await
+        .unwrap_or_else(exit_with_error);
+
+    match args.format {
+        Some(format) => output::write_rows(format, &rows).unwrap_or_else(exit_with_error),
+        None => show(&rows),
+    }
+}
@@ -0,0 +1,299 @@
+use std::process;
+
+use clap::Args;
+use colored::Colorize;
+use futures_util::StreamExt;
+use reqwest::StatusCode;
+
+use crate::{
+    cli::connection::Connection,
+    jira::{self, api::JiraApi, client::RequestError, jql::parser},
+};
+
+#[derive(Debug, Args)]
+pub struct Doctor {
+    #[arg(long)]
+    #[arg(default_value = "Story Points")]
+    pub jira_story_points_field: String,
+
+    /// A search to try out, to check that searching works.
+    #[arg(long)]
+    #[arg(default_value = "created >= -30d ORDER BY created DESC")]
+    pub jql: String,
+
+    #[command(flatten)]
+    pub connection: Connection,
+}
+
+/// How a check went.  Warnings are for things only some commands need.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Outcome {
+    Pass,
+    Warn,
+    Fail,
+}
+
+struct Check {
+    name: &'static str,
+    outcome: Outcome,
+    detail: String,
+    /// What to try next, for checks that didn't pass.
+    hint: Option<String>,
+}
+
+impl Check {
+    fn pass(name: &'static str, detail: String) -> Self {
+        Check {
+            name,
+            outcome: Outcome::Pass,
+            detail,
+            hint: None,
+        }
+    }
+
+    fn problem(name: &'static str, outcome: Outcome, detail: String, hint: String) -> Self {
+        Check {
+            name,
+            outcome,
+            detail,
+            hint: Some(hint),
+        }
+    }
+
+    fn show(&self) {
+        let mark = match self.outcome {
+            Outcome::Pass => "✓".bright_green(),
+            Outcome::Warn => "!".bright_yellow(),
+            Outcome::Fail => "✗".bright_red(),
+        };
+        println!("{} {}: {}", mark, self.name.bold(), self.detail);
+        if let Some(hint) = &self.hint {
+            println!("    {}", hint.dimmed());
+        }
+    }
+}
+
+/// What to try when a request fails, going by how far it got.
+fn request_hint(e: &RequestError, jira_url: &str) -> String {
+    match e.status() {
+        Some(StatusCode::UNAUTHORIZED) => "Check --jira-username and the API token.  JIRA Cloud needs the email address you sign in with and a token from https://id.atlassian.com/manage-profile/security/api-tokens.".to_owned(),
+        Some(StatusCode::FORBIDDEN) => "The credentials were accepted, but not allowed to do this.  JIRA may be asking for a CAPTCHA after too many failed sign ins; signing in through the browser once clears it.".to_owned(),
+        Some(StatusCode::NOT_FOUND) => format!("{} doesn't look like a JIRA site.  --jira-url should be the address of the site itself, like https://your-domain.atlassian.net, without any path.", jira_url),
+        Some(_) => "JIRA is reachable, but something went wrong on its end.  Try again shortly, or run with --debug-http to see the whole response.".to_owned(),
+        None => format!("Check that {} is right, and that it can be reached from here, through any proxy or VPN it needs.", jira_url),
+    }
+}
+
+async fn sign_in(client: &impl JiraApi, jira_url: &str) -> Check {
+    let name = "Signing in";
+    match client.get_myself().await {
+        Ok(user) => {
+            let who = if user.email_address.is_some() {
+                format!(
+                    "{} ({})",
+                    user.display_name,
+                    user.email_address.unwrap_or_default()
+                )
+            } else {
+                user.display_name
+            };
+            Check::pass(name, format!("signed in to {} as {}", jira_url, who))
+        }
+        Err(e) => Check::problem(
+            name,
+            Outcome::Fail,
+            e.to_string(),
+            request_hint(&e, jira_url),
+        ),
+    }
+}
+
+async fn permissions(client: &impl JiraApi, jira_url: &str) -> Vec<Check> {
+    let permissions = match client
+        .get_my_permissions(None, &["BROWSE_PROJECTS", "EDIT_ISSUES"])
+        .await
+    {
+        Ok(permissions) => permissions,
+        Err(e) => {
+            return vec![Check::problem(
+                "Permissions",
+                Outcome::Fail,
+                e.to_string(),
+                request_hint(&e, jira_url),
+            )]
+        }
+    };
+    let has = |permission: &str| permissions.get(permission).copied().unwrap_or(false);
+
+    vec![
+        if has("BROWSE_PROJECTS") {
+            Check::pass("Browsing projects", "allowed".to_owned())
+        } else {
+            Check::problem(
+                "Browsing projects",
+                Outcome::Fail,
+                "not allowed in any project".to_owned(),
+                "Every command needs to see issues.  Ask a JIRA administrator for the Browse Projects permission."
+                    .to_owned(),
+            )
+        },
+        if has("EDIT_ISSUES") {
+            Check::pass("Editing issues", "allowed".to_owned())
+        } else {
+            Check::problem(
+                "Editing issues",
+                Outcome::Warn,
+                "not allowed in any project".to_owned(),
+                "Reports will work, but tag and apply need the Edit Issues permission to change anything."
+                    .to_owned(),
+            )
+        },
+    ]
+}
+
+async fn story_points_field(client: &impl JiraApi, field_name: &str, jira_url: &str) -> Check {
+    let name = "Story points field";
+    let fields = match client.get_fields().await {
+        Ok(fields) => fields,
+        Err(e) => {
+            return Check::problem(
+                name,
+                Outcome::Fail,
+                e.to_string(),
+                request_hint(&e, jira_url),
+            )
+        }
+    };
+
+    let ids: Vec<&str> = fields
+        .iter()
+        .filter(|field| field.name == field_name)
+        .map(|field| field.id.as_str())
+        .collect();
+    if !ids.is_empty() {
+        return Check::pass(name, format!("\"{}\" is {}", field_name, ids.join(", ")));
+    }
+
+    let similar: Vec<String> = fields
+        .iter()
+        .filter(|field| {
+            let lowercase = field.name.to_lowercase();
+            lowercase.contains("point") || lowercase.contains("estimat")
+        })
+        .map(|field| format!("\"{}\"", field.name))
+        .collect();
+    let hint = if similar.is_empty() {
+        "Pass the name of the field your team estimates in with --jira-story-points-field."
+            .to_owned()
+    } else {
+        format!(
+            "Pass the name of the field your team estimates in with --jira-story-points-field.  These look like they could be it: {}",
+            similar.join(", ")
+        )
+    };
+
+    Check::problem(
+        name,
+        Outcome::Fail,
+        format!("there is no field named \"{}\"", field_name),
+        hint,
+    )
+}
+
+async fn search(client: &impl JiraApi, jql: &str, jira_url: &str) -> Check {
+    let name = "Searching";
+    let statement = match parser::parse(jql) {
+        Ok(statement) => statement,
+        Err(e) => {
+            return Check::problem(
+                name,
+                Outcome::Fail,
+                e.to_string(),
+                "Fix the search given with --jql.".to_owned(),
+            )
+        }
+    };
+
+    if let Err(e) = jira::validate_jql(client, &statement).await {
+        return Check::problem(
+            name,
+            Outcome::Fail,
+            e.to_string(),
+            "Fix the search given with --jql.".to_owned(),
+        );
+    }
+
+    // Only the first page is fetched, since all that matters is that a search goes through.
+    let fields = vec!["summary".to_owned()];
+    let issues = client.search_stream(&fields, &statement);
+    tokio::pin!(issues);
+    match issues.next().await {
+        None => Check::pass(name, format!("\"{}\" ran, and found no issues", jql)),
+        Some(Ok(issue)) => Check::pass(
+            name,
+            format!("\"{}\" ran, and found {} among others", jql, issue.key),
+        ),
+        Some(Err(e)) => Check::problem(
+            name,
+            Outcome::Fail,
+            e.to_string(),
+            request_hint(&e, jira_url),
+        ),
+    }
+}
+
+/// Runs each check in turn, stopping early only when there's no way the rest could pass.
+async fn checks(args: &Doctor) -> Vec<Check> {
+    let jira_url = args.connection.jira_url.as_str();
+    let client = match args.connection.connect() {
+        Ok(client) => client,
+        Err(e) => {
+            return vec![Check::problem(
+                "Configuration",
+                Outcome::Fail,
+                e,
+                "Fix the connection options, which are listed by statustracker doctor --help."
+                    .to_owned(),
+            )]
+        }
+    };
+    let mut checks = vec![Check::pass(
+        "Configuration",
+        "the connection options are all valid".to_owned(),
+    )];
+
+    let signed_in = sign_in(&client, jira_url).await;
+    let outcome = signed_in.outcome;
+    checks.push(signed_in);
+    if outcome == Outcome::Fail {
+        return checks;
+    }
+
+    checks.extend(permissions(&client, jira_url).await);
+    checks.push(story_points_field(&client, &args.jira_story_points_field, jira_url).await);
+    checks.push(search(&client, &args.jql, jira_url).await);
+
+    checks
+}
+
+pub async fn run(args: &Doctor) {
+    let checks = checks(args).await;
+    for check in &checks {
+        check.show();
+    }
+
+    let failed = checks
+        .iter()
+        .filter(|check| check.outcome == Outcome::Fail)
+        .count();
+    if failed > 0 {
+        println!(
+            "{} of {} checks failed.",
+            format!("{}", failed).bright_red(),
+            checks.len()
+        );
+        process::exit(1);
+    }
+
+    println!("{}", "Everything looks good.".bright_green());
+}
@@ -0,0 +1,195 @@
+use clap::Args;
+use colored::Colorize;
+use serde::Serialize;
+
+use crate::{
+    cli::{
+        connection::Connection,
+        exit_with_error,
+        output::{self, Format},
+        report,
+        scope::Scope,
+    },
+    date::DateTime,
+    jira::{self, api::JiraApi, client::SearchIssue, jql::SortDirection},
+};
+
+/// How wide the progress bar is in the table.
+const BAR_WIDTH: usize = 20;
+
+#[derive(Debug, Args)]
+pub struct Epics {
+    /// The story points to count for issues that have none.
+    #[arg(long)]
+    #[arg(default_value_t = 3.0)]
+    pub default_story_points: f64,
+
+    /// csv or json, for a script to read.  Without this, the epics are shown as a table with a progress bar each.
+    #[arg(long)]
+    pub format: Option<Format>,
+
+    #[arg(long)]
+    #[arg(default_value = "Story Points")]
+    pub jira_story_points_field: String,
+
+    #[arg(long)]
+    #[arg(default_value_t = 14)]
+    pub sprint_length_days: u32,
+
+    #[arg(long)]
+    pub velocity_in_story_points: f64,
+
+    #[command(flatten)]
+    pub scope: Scope,
+
+    #[command(flatten)]
+    pub connection: Connection,
+}
+
+#[derive(Debug, Serialize)]
+pub struct EpicRow {
+    pub epic: String,
+    pub summary: String,
+    pub issues: u64,
+    pub done_issues: u64,
+    pub points_done: f64,
+    pub points_left: f64,
+    pub percent_done: f64,
+    /// When the epic is projected to be done, or "done" when it is already.
+    pub projected_completion: String,
+    pub link: String,
+}
+
+/// Adds up each epic in the search, and works through them in order at the team's velocity to project when each will
+/// be done.  The issues outside of any epic go last, so that they don't push back the dates of the epics.
+///
+/// The issues need to have been fetched with the fields `report::epics` needs, and the story point fields.
+pub fn rows(
+    issues: &[SearchIssue],
+    story_point_field_ids: &[String],
+    epic_link_field_ids: &[String],
+    default_story_points: f64,
+    days_per_point: f64,
+    jira_url: &str,
+) -> Vec<EpicRow> {
+    let today = DateTime::now().start_of_day();
+    let mut days_so_far: f64 = 0.0;
+    report::epics(
+        issues,
+        story_point_field_ids,
+        epic_link_field_ids,
+        default_story_points,
+    )
+    .into_iter()
+    .map(|epic| {
+        let points_left = epic.total_points - epic.completed_points;
+        let projected_completion = if points_left > 0.0 {
+            days_so_far += points_left * days_per_point;
+            today.plus_days(days_so_far.ceil() as i64).to_date_string()
+        } else {
+            "done".to_owned()
+        };
+
+        EpicRow {
+            link: epic
+                .key
+                .as_ref()
+                .map(|key| format!("{}/browse/{}", jira_url, key))
+                .unwrap_or_default(),
+            epic: epic
+                .key
+                .clone()
+                .unwrap_or_else(|| "Not in an epic".to_owned()),
+            summary: epic.summary.clone(),
+            issues: epic.issues,
+            done_issues: epic.done_issues,
+            points_done: epic.completed_points,
+            points_left,
+            percent_done: (epic.percent_done() * 10.0).round() / 10.0,
+            projected_completion,
+        }
+    })
+    .collect()
+}
+
+async fn epics(client: &impl JiraApi, args: &Epics) -> Result<Vec<EpicRow>, String> {
+    let story_point_field_ids = jira::get_field_ids_named(client, &args.jira_story_points_field)
+        .await
+        .map_err(|e| format!("Could not get the story point fields: {}", e))?;
+    let epic_link_field_ids = jira::get_field_ids_named(client, "Epic Link")
+        .await
+        .map_err(|e| format!("Could not get the epic link field: {}", e))?;
+    let mut fields = story_point_field_ids.clone();
+    fields.extend(epic_link_field_ids.iter().cloned());
+    for field in ["summary", "status", "issuetype", "parent"] {
+        fields.push(field.to_owned());
+    }
+
+    let jql = args
+        .scope
+        .builder(client)
+        .await
+        .and_then(|builder| builder.order_by("key", SortDirection::Ascending).build())?;
+    if args.scope.validate_jql {
+        jira::validate_jql(client, &jql)
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+    let issues = client
+        .search_all(&fields, &jql)
+        .await
+        .map_err(|e| format!("Could not search for issues: {}", e))?;
+
+    Ok(rows(
+        &issues,
+        &story_point_field_ids,
+        &epic_link_field_ids,
+        args.default_story_points,
+        args.sprint_length_days as f64 / args.velocity_in_story_points,
+        &args.connection.jira_url,
+    ))
+}
+
+fn show(rows: &[EpicRow]) {
+    let width = rows
+        .iter()
+        .map(|row| row.epic.chars().count())
+        .max()
+        .unwrap_or(0);
+    for row in rows {
+        let filled = ((row.percent_done / 100.0) * BAR_WIDTH as f64).round() as usize;
+        let bar = format!(
+            "{}{}",
+            "█".repeat(filled.min(BAR_WIDTH)).bright_green(),
+            "░".repeat(BAR_WIDTH - filled.min(BAR_WIDTH))
+        );
+        println!(
+            "{:<width$}  {} {:>5.1}%  {:>7} issues  {:>6.1} pts left  {:<10}  {}",
+            row.epic,
+            bar,
+            row.percent_done,
+            format!("{}/{}", row.done_issues, row.issues),
+            row.points_left,
+            row.projected_completion,
+            row.summary,
+            width = width
+        );
+    }
+}
+
+pub async fn run(args: &Epics) {
+    if args.velocity_in_story_points <= 0.0 {
+        exit_with_error("--velocity-in-story-points has to be more than zero.")
+    }
+    if args.sprint_length_days == 0 {
+        exit_with_error("--sprint-length-days has to be more than zero.")
+    }
+
+    let client = args.connection.connect().unwrap_or_else(exit_with_error);
+    let rows = epics(&client, args).await.unwrap_or_else(exit_with_error);
+
+    match args.format {
+        Some(format) => output::write_rows(format, &rows).unwrap_or_else(exit_with_error),
+        None => show(&rows),
+    }
+}
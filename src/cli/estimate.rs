@@ -1,10 +1,16 @@
-use std::process;
+use std::path::{Path, PathBuf};
 
 use clap::Args;
 use colored::Colorize;
-use jimberlage_jira_client::{self, jql::SerializableToJQL, RestClient, SearchIssue};
+use serde_json::{json, Value as JSONValue};
 
-use crate::jira;
+use crate::{
+    cli::{connection::Connection, detect_fields, exit_with_error, scope::Scope},
+    estimator::{Estimator, Results},
+    jira::{self, api::JiraApi, client::SearchIssue, jql::SerializableToJQL},
+    slack,
+    snapshot::Snapshot,
+};
 
 #[derive(Debug, Args)]
 pub struct Estimate {
@@ -12,27 +18,29 @@ pub struct Estimate {
     #[arg(default_value_t = 3.0)]
     pub default_story_points: f64,
 
+    /// Tally the issues in a snapshot saved by the snapshot command, instead of searching JIRA.  The snapshot's issues
+    /// are used as they are, so the options that pick out issues don't apply, and no connection options are needed.
     #[arg(long)]
-    #[arg(default_value = "Story Points")]
-    pub jira_story_points_field: String,
+    #[arg(conflicts_with = "instance")]
+    pub from_snapshot: Option<PathBuf>,
 
+    /// An argfile with the connection options for another JIRA site, like --jira-url and --jira-token, to estimate the
+    /// same search on.  The issues from every site are added up together.
     #[arg(long)]
-    pub jira_label: Vec<String>,
+    pub instance: Vec<PathBuf>,
 
+    /// The name of the field story points are kept in.  Without this, the field is picked by its name and by which
+    /// number fields the issues have values in, as detect-fields shows.
     #[arg(long)]
-    pub jira_project: Vec<String>,
+    pub jira_story_points_field: Option<String>,
 
+    /// Also post the estimate to Slack, through this incoming webhook URL.
     #[arg(long)]
-    pub jira_issue_type: Vec<String>,
+    pub notify_slack: Option<String>,
 
     #[arg(long)]
-    pub jira_token: String,
-
-    #[arg(long)]
-    pub jira_url: String,
-
-    #[arg(long)]
-    pub jira_username: String,
+    #[arg(default_value_t = false)]
+    pub only_unpointed: bool,
 
     #[arg(long)]
     pub velocity_in_story_points: f64,
@@ -40,42 +48,12 @@ pub struct Estimate {
     #[arg(long)]
     #[arg(default_value_t = false)]
     pub verbose: bool,
-}
 
-enum ClassifiedIssue {
-    Complete,
-    IncompleteAndPointed(f64),
-    IncompleteAndUnpointed,
-}
-
-fn classify(issue: &SearchIssue, field_ids: &Vec<String>) -> ClassifiedIssue {
-    if let Some(status) = &issue.status_category() {
-        if status == "Done" {
-            return ClassifiedIssue::Complete;
-        }
-    }
-
-    if let Some(points) = jira::story_points(issue, field_ids) {
-        if points == 0.0 {
-            return ClassifiedIssue::IncompleteAndUnpointed;
-        }
+    #[command(flatten)]
+    pub scope: Scope,
 
-        return ClassifiedIssue::IncompleteAndPointed(points);
-    }
-
-    ClassifiedIssue::IncompleteAndUnpointed
-}
-
-struct Results {
-    default_story_points: f64,
-    num_complete: f64,
-    num_incomplete_and_pointed: f64,
-    num_incomplete_and_unpointed: f64,
-    num_sprints_remaining: f64,
-    unfinished_estimated_story_points: f64,
-    unfinished_story_points: f64,
-    unfinished_unestimated_story_points: f64,
-    velocity_in_story_points: f64,
+    #[command(flatten)]
+    pub connection: Connection,
 }
 
 impl Results {
@@ -112,81 +90,191 @@ impl Results {
         );
     }
 
-    fn tally(
-        issues: &Vec<SearchIssue>,
-        field_ids: &Vec<String>,
-        default_story_points: f64,
-        velocity_in_story_points: f64,
-    ) -> Results {
-        let mut results = Results {
-            default_story_points,
-            num_complete: 0.0,
-            num_incomplete_and_pointed: 0.0,
-            num_incomplete_and_unpointed: 0.0,
-            num_sprints_remaining: 0.0,
-            unfinished_estimated_story_points: 0.0,
-            unfinished_story_points: 0.0,
-            unfinished_unestimated_story_points: 0.0,
-            velocity_in_story_points,
+    /// Lays the estimate out as a Slack message.  The bar down its side is green while most of the work left has
+    /// estimates, and turns yellow and then red as more of it is counted with the default.
+    fn slack_message(&self, source: &str) -> JSONValue {
+        let unestimated_share = if self.unfinished_story_points > 0.0 {
+            self.unfinished_unestimated_story_points / self.unfinished_story_points
+        } else {
+            0.0
         };
+        let color = if unestimated_share <= 0.25 {
+            slack::GOOD
+        } else if unestimated_share <= 0.5 {
+            slack::WARNING
+        } else {
+            slack::DANGER
+        };
+        let headline = format!("{:.1} sprints left", self.num_sprints_remaining);
+        let field = |label: &str, value: String| json!({ "type": "mrkdwn", "text": format!("*{}*\n{}", label, value) });
 
-        for issue in issues {
-            match classify(issue, field_ids) {
-                ClassifiedIssue::Complete => {
-                    results.num_complete = results.num_complete + 1.0;
-                }
-                ClassifiedIssue::IncompleteAndPointed(points) => {
-                    results.num_incomplete_and_pointed = results.num_incomplete_and_pointed + 1.0;
-                    results.unfinished_estimated_story_points =
-                        results.unfinished_estimated_story_points + points;
-                }
-                ClassifiedIssue::IncompleteAndUnpointed => {
-                    results.num_incomplete_and_unpointed =
-                        results.num_incomplete_and_unpointed + 1.0;
-                }
-            };
-        }
-
-        results.unfinished_unestimated_story_points =
-            results.num_incomplete_and_unpointed * results.default_story_points;
-        results.unfinished_story_points =
-            results.unfinished_estimated_story_points + results.unfinished_unestimated_story_points;
-        results.num_sprints_remaining =
-            results.unfinished_story_points / results.velocity_in_story_points;
-
-        results
+        json!({
+            "text": headline,
+            "attachments": [{
+                "color": color,
+                "blocks": [
+                    {
+                        "type": "header",
+                        "text": { "type": "plain_text", "text": headline },
+                    },
+                    {
+                        "type": "section",
+                        "fields": [
+                            field("Completed", format!("{:.0} cards", self.num_complete)),
+                            field(
+                                "Left, estimated",
+                                format!(
+                                    "{:.0} cards, {:.0} points",
+                                    self.num_incomplete_and_pointed,
+                                    self.unfinished_estimated_story_points
+                                ),
+                            ),
+                            field(
+                                "Left, unestimated",
+                                format!(
+                                    "{:.0} cards × {:.0} = {:.0} points",
+                                    self.num_incomplete_and_unpointed,
+                                    self.default_story_points,
+                                    self.unfinished_unestimated_story_points
+                                ),
+                            ),
+                            field(
+                                "Velocity",
+                                format!("{:.0} points / sprint", self.velocity_in_story_points),
+                            ),
+                        ],
+                    },
+                    {
+                        "type": "context",
+                        "elements": [{ "type": "mrkdwn", "text": format!("From {}", slack::escape(source)) }],
+                    },
+                ],
+            }],
+        })
     }
 }
 
-pub fn run(args: &Estimate) {
-    let client = RestClient::new(&args.jira_url, &args.jira_username, &args.jira_token).unwrap();
+fn estimator(args: &Estimate) -> Estimator {
+    Estimator::new(args.velocity_in_story_points).default_story_points(args.default_story_points)
+}
 
-    let mut field_ids =
-        jira::get_story_point_field_ids(&client, &args.jira_story_points_field).unwrap();
-    field_ids.push("status".to_owned());
+/// Searches one JIRA site for the issues the options pick out, and tallies up how much work is left in them.
+async fn estimate(
+    client: &impl JiraApi,
+    args: &Estimate,
+    results: &mut Results,
+) -> Result<(), String> {
+    // Building the scope can look up every epic's issues, so it's only done once.
+    let mut builder = args.scope.builder(client).await?;
+    let story_point_field_ids = detect_fields::story_point_field_ids(
+        client,
+        args.jira_story_points_field.as_deref(),
+        &builder.clone().build()?,
+    )
+    .await?;
 
-    let maybe_jql =
-        jira::build_issue_search_jql(&args.jira_project, &args.jira_label, &args.jira_issue_type);
-    if maybe_jql.is_err() {
-        eprintln!("{}", maybe_jql.unwrap_err());
-        process::exit(1);
+    if args.only_unpointed {
+        // Narrowing the search down to issues without story points means JIRA filters out the rest instead of
+        // sending them.
+        for field_id in &story_point_field_ids {
+            builder = builder.empty(field_id);
+        }
     }
-
-    let jql = maybe_jql.unwrap();
+    let jql = builder.build()?;
     if args.verbose {
         println!("Searching for issues with the following JQL:");
         println!("{}", jql.serialize_to_jql());
     }
+    if args.scope.validate_jql {
+        jira::validate_jql(client, &jql)
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+
+    estimator(args)
+        .tally(client, &jql, &story_point_field_ids, results)
+        .await
+        .map_err(|e| format!("Could not search for issues: {}", e))
+}
+
+/// Tallies up how much work is left in the issues of a snapshot, without contacting JIRA.
+fn estimate_snapshot(path: &Path, args: &Estimate, results: &mut Results) -> Result<(), String> {
+    let snapshot = Snapshot::read(path).map_err(|e| e.to_string())?;
+    let story_point_field_ids = detect_fields::snapshot_story_point_field_ids(
+        &snapshot,
+        args.jira_story_points_field.as_deref(),
+    )?;
+    let mut field_ids = story_point_field_ids.clone();
+    field_ids.push("status".to_owned());
 
-    let issues = client.search_all(&field_ids, &jql).unwrap();
+    if args.verbose {
+        println!(
+            "Tallying the {} issues saved from {} with the following JQL:",
+            snapshot.issues.len(),
+            snapshot.jira_url
+        );
+        println!("{}", snapshot.jql);
+    }
 
-    let results = Results::tally(
-        &issues,
-        &field_ids,
-        args.default_story_points,
-        args.velocity_in_story_points,
-    );
+    if args.only_unpointed {
+        let unpointed: Vec<SearchIssue> = snapshot
+            .issues
+            .into_iter()
+            .filter(|issue| jira::story_points(issue, &story_point_field_ids).is_none())
+            .collect();
+        results.tally(&unpointed, &field_ids);
+    } else {
+        results.tally(&snapshot.issues, &field_ids);
+    }
+
+    Ok(())
+}
+
+pub async fn run(args: &Estimate) {
+    let mut results = estimator(args).results();
+    // Where the issues came from, to say in notifications.
+    let source = match &args.from_snapshot {
+        Some(path) => {
+            estimate_snapshot(path, args, &mut results).unwrap_or_else(exit_with_error);
+            format!("the snapshot at {}", path.display())
+        }
+        None => {
+            let connections = args
+                .connection
+                .with_instances(&args.instance)
+                .unwrap_or_else(exit_with_error);
+
+            for connection in &connections {
+                let client = connection.connect().unwrap_or_else(exit_with_error);
+                estimate(&client, args, &mut results)
+                    .await
+                    .unwrap_or_else(|e| {
+                        exit_with_error(if connections.len() > 1 {
+                            format!("{}: {}", connection.jira_url, e)
+                        } else {
+                            e
+                        })
+                    });
+            }
+
+            let urls: Vec<&str> = connections
+                .iter()
+                .map(|connection| connection.jira_url.as_str())
+                .collect();
+            urls.join(", ")
+        }
+    };
+
+    show(args, &results);
+
+    if let Some(webhook_url) = &args.notify_slack {
+        slack::post(webhook_url, &results.slack_message(&source))
+            .await
+            .unwrap_or_else(exit_with_error);
+    }
+}
 
+fn show(args: &Estimate, results: &Results) {
     if args.verbose {
         results.explain();
     } else {
@@ -0,0 +1,385 @@
+use std::path::{Path, PathBuf};
+
+use clap::Args;
+use colored::Colorize;
+use serde::Serialize;
+
+use crate::{
+    cli::{
+        connection::Connection,
+        detect_fields, exit_with_error,
+        output::{self, Format},
+        scope::Scope,
+    },
+    date::DateTime,
+    estimator::Results,
+    jira::{self, api::JiraApi, client::SearchIssue},
+};
+
+#[derive(Debug, Args)]
+pub struct Forecast {
+    #[arg(long)]
+    #[arg(default_value_t = 3.0)]
+    pub default_story_points: f64,
+
+    /// How many engineers the team has now, for scenarios that add or take some away.  Velocity is taken to grow and
+    /// shrink in proportion.
+    #[arg(long)]
+    pub engineers: Option<u32>,
+
+    /// csv or json, for a script to read.  Without this, the scenarios are shown as a table.
+    #[arg(long)]
+    pub format: Option<Format>,
+
+    /// The name of the field story points are kept in.  Without this, the field is picked by its name and by which
+    /// number fields the issues have values in, as detect-fields shows.
+    #[arg(long)]
+    pub jira_story_points_field: Option<String>,
+
+    /// A CSV file with a row for each scenario to forecast.  The columns are Scenario, for its name, and optionally
+    /// Engineers, like +1, Capacity, like -20%, and Cut, the keys of issues or epics to leave out.  Without this, the
+    /// scenarios are 20% less capacity, and one more engineer when --engineers is given.  The current velocity is
+    /// always forecast first.
+    #[arg(long)]
+    pub scenarios: Option<PathBuf>,
+
+    #[arg(long)]
+    #[arg(default_value_t = 14)]
+    pub sprint_length_days: u32,
+
+    #[arg(long)]
+    pub velocity_in_story_points: f64,
+
+    #[command(flatten)]
+    pub scope: Scope,
+
+    #[command(flatten)]
+    pub connection: Connection,
+}
+
+/// A change to plan for, read from a row of the scenarios file.
+#[derive(Debug, Default)]
+struct Scenario {
+    name: String,
+    /// How many engineers join, or leave when negative.
+    engineers: i64,
+    /// The change in capacity, as a percentage, like -20 for holidays.
+    capacity_percent: f64,
+    /// The keys of issues, or of epics whose issues, are cut from scope.
+    cut: Vec<String>,
+}
+
+impl Scenario {
+    fn current() -> Self {
+        Scenario {
+            name: "Current velocity".to_owned(),
+            ..Scenario::default()
+        }
+    }
+
+    /// The scenarios to forecast when there is no scenarios file.
+    fn defaults(engineers: Option<u32>) -> Vec<Self> {
+        let mut scenarios = vec![Scenario::current()];
+        if engineers.is_some() {
+            scenarios.push(Scenario {
+                name: "+1 engineer".to_owned(),
+                engineers: 1,
+                ..Scenario::default()
+            });
+        }
+        scenarios.push(Scenario {
+            name: "-20% capacity".to_owned(),
+            capacity_percent: -20.0,
+            ..Scenario::default()
+        });
+
+        scenarios
+    }
+
+    /// The team's velocity under the scenario.
+    fn velocity(&self, args: &Forecast) -> Result<f64, String> {
+        let mut velocity = args.velocity_in_story_points * (1.0 + self.capacity_percent / 100.0);
+        if self.engineers != 0 {
+            let engineers = args.engineers.ok_or_else(|| {
+                format!(
+                    "The {} scenario changes the number of engineers, so --engineers has to say how many there are now.",
+                    self.name
+                )
+            })? as i64;
+            velocity = velocity * (engineers + self.engineers) as f64 / engineers as f64;
+        }
+        if velocity <= 0.0 {
+            return Err(format!(
+                "The {} scenario leaves the team without any velocity.",
+                self.name
+            ));
+        }
+
+        Ok(velocity)
+    }
+
+    fn keeps(&self, issue: &SearchIssue, epic_link_field_ids: &[String]) -> bool {
+        !self.cut.contains(&issue.key)
+            && jira::epic_key(issue, epic_link_field_ids)
+                .map(|epic| !self.cut.contains(&epic))
+                .unwrap_or(true)
+    }
+}
+
+/// Parses a signed number, like +1 or -20%, where the sign and the percent sign are optional.
+fn signed(value: &str) -> Option<f64> {
+    value
+        .trim()
+        .trim_end_matches('%')
+        .trim_start_matches('+')
+        .trim()
+        .parse()
+        .ok()
+}
+
+/// Reads every scenario in the file, failing with a message for each row that can't be forecast as it is.
+fn read_scenarios(path: &Path) -> Result<Vec<Scenario>, String> {
+    let mut reader = csv::Reader::from_path(path)
+        .map_err(|e| format!("Could not open {}: {}", path.display(), e))?;
+    let headers = reader
+        .headers()
+        .map_err(|e| format!("Could not read the header row: {}", e))?
+        .clone();
+    let position = |names: &[&str]| {
+        headers
+            .iter()
+            .position(|header| names.contains(&header.trim().to_lowercase().as_str()))
+    };
+    let name_column = position(&["scenario", "name"]).ok_or_else(|| {
+        "The scenarios file needs a column with the name of each scenario, named Scenario."
+            .to_owned()
+    })?;
+    let engineers_column = position(&["engineers"]);
+    let capacity_column = position(&["capacity"]);
+    let cut_column = position(&["cut"]);
+
+    let mut scenarios = vec![Scenario::current()];
+    let mut problems = vec![];
+    for (i, record) in reader.records().enumerate() {
+        // The header is line 1.
+        let line = i + 2;
+        let record = match record {
+            Ok(record) => record,
+            Err(e) => {
+                problems.push(format!("Line {} could not be read: {}", line, e));
+                continue;
+            }
+        };
+        if record.iter().all(|value| value.trim().is_empty()) {
+            continue;
+        }
+        let cell = |column: Option<usize>| {
+            column
+                .and_then(|column| record.get(column))
+                .map(|value| value.trim())
+                .filter(|value| !value.is_empty())
+        };
+
+        let name = match cell(Some(name_column)) {
+            Some(name) => name.to_owned(),
+            None => {
+                problems.push(format!("Line {} does not have a name.", line));
+                continue;
+            }
+        };
+        let engineers = match cell(engineers_column).map(signed) {
+            Some(Some(engineers)) if engineers.fract() == 0.0 => engineers as i64,
+            Some(_) => {
+                problems.push(format!(
+                    "Line {} has \"{}\" for engineers, which should be a whole number like +1.",
+                    line,
+                    cell(engineers_column).unwrap_or_default()
+                ));
+                continue;
+            }
+            None => 0,
+        };
+        let capacity_percent = match cell(capacity_column).map(signed) {
+            Some(Some(capacity_percent)) => capacity_percent,
+            Some(None) => {
+                problems.push(format!(
+                    "Line {} has \"{}\" for capacity, which should be a percentage like -20%.",
+                    line,
+                    cell(capacity_column).unwrap_or_default()
+                ));
+                continue;
+            }
+            None => 0.0,
+        };
+        let cut = cell(cut_column)
+            .map(|cut| {
+                cut.split(|c: char| c == ',' || c.is_whitespace())
+                    .filter(|key| !key.is_empty())
+                    .map(|key| key.to_uppercase())
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        scenarios.push(Scenario {
+            name,
+            engineers,
+            capacity_percent,
+            cut,
+        });
+    }
+
+    if !problems.is_empty() {
+        return Err(format!(
+            "{} can't be forecast:\n{}",
+            path.display(),
+            problems.join("\n")
+        ));
+    }
+
+    Ok(scenarios)
+}
+
+/// When the work left is projected to be done under one scenario.
+#[derive(Debug, Serialize)]
+struct Projection {
+    scenario: String,
+    velocity: f64,
+    points_left: f64,
+    sprints_left: f64,
+    projected_completion: String,
+    /// How many days sooner, when negative, or later the scenario finishes than the current velocity does.
+    days_vs_current: i64,
+}
+
+async fn forecast(
+    client: &impl JiraApi,
+    args: &Forecast,
+    scenarios: &[Scenario],
+) -> Result<Vec<Projection>, String> {
+    let jql = args.scope.builder(client).await?.build()?;
+    if args.scope.validate_jql {
+        jira::validate_jql(client, &jql)
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+    let story_point_field_ids =
+        detect_fields::story_point_field_ids(client, args.jira_story_points_field.as_deref(), &jql)
+            .await?;
+    let epic_link_field_ids = jira::get_field_ids_named(client, "Epic Link")
+        .await
+        .map_err(|e| format!("Could not get the epic link field: {}", e))?;
+    let mut fields = story_point_field_ids.clone();
+    fields.extend(epic_link_field_ids.iter().cloned());
+    for field in ["status", "issuetype", "parent"] {
+        fields.push(field.to_owned());
+    }
+
+    let issues = client
+        .search_all(&fields, &jql)
+        .await
+        .map_err(|e| format!("Could not search for issues: {}", e))?;
+
+    let today = DateTime::now().start_of_day();
+    // The first scenario is always the current velocity, which the rest are compared to.
+    let mut current_days = None;
+    let mut projections = vec![];
+    for scenario in scenarios {
+        let velocity = scenario.velocity(args)?;
+        let mut results = Results::new(args.default_story_points, velocity);
+        for issue in issues
+            .iter()
+            .filter(|issue| scenario.keeps(issue, &epic_link_field_ids))
+        {
+            results.tally(std::slice::from_ref(issue), &story_point_field_ids);
+        }
+
+        let days = (results.num_sprints_remaining * args.sprint_length_days as f64).ceil() as i64;
+        let current_days = *current_days.get_or_insert(days);
+        projections.push(Projection {
+            scenario: scenario.name.clone(),
+            velocity: (velocity * 10.0).round() / 10.0,
+            points_left: results.unfinished_story_points,
+            sprints_left: (results.num_sprints_remaining * 10.0).round() / 10.0,
+            projected_completion: today.plus_days(days).to_date_string(),
+            days_vs_current: days - current_days,
+        });
+    }
+
+    Ok(projections)
+}
+
+fn show(projections: &[Projection]) {
+    let name_width = projections
+        .iter()
+        .map(|projection| projection.scenario.chars().count())
+        .max()
+        .unwrap_or(0)
+        .max("Scenario".len());
+
+    println!(
+        "{:<name_width$}  {:>8}  {:>11}  {:>7}  {:<10}  Change",
+        "Scenario",
+        "Velocity",
+        "Points left",
+        "Sprints",
+        "Done by",
+        name_width = name_width
+    );
+    for (i, projection) in projections.iter().enumerate() {
+        let change = if i == 0 {
+            "".normal()
+        } else if projection.days_vs_current < 0 {
+            format!("{} days sooner", -projection.days_vs_current).bright_green()
+        } else if projection.days_vs_current > 0 {
+            format!("{} days later", projection.days_vs_current).bright_red()
+        } else {
+            "no change".normal()
+        };
+        let line = format!(
+            "{:<name_width$}  {:>8.1}  {:>11.1}  {:>7.1}  {:<10}  {}",
+            projection.scenario,
+            projection.velocity,
+            projection.points_left,
+            projection.sprints_left,
+            projection.projected_completion,
+            change,
+            name_width = name_width
+        );
+        if i == 0 {
+            println!("{}", line.bold());
+        } else {
+            println!("{}", line);
+        }
+    }
+}
+
+pub async fn run(args: &Forecast) {
+    if args.velocity_in_story_points <= 0.0 {
+        exit_with_error("--velocity-in-story-points has to be more than zero.")
+    }
+    if args.sprint_length_days == 0 {
+        exit_with_error("--sprint-length-days has to be more than zero.")
+    }
+    if args.engineers == Some(0) {
+        exit_with_error("--engineers has to be more than zero.")
+    }
+
+    let scenarios = match &args.scenarios {
+        Some(path) => read_scenarios(path).unwrap_or_else(exit_with_error),
+        None => Scenario::defaults(args.engineers),
+    };
+    // Checked before searching, so that a mistake in the file doesn't wait on JIRA to be found.
+    for scenario in &scenarios {
+        scenario.velocity(args).unwrap_or_else(exit_with_error);
+    }
+
+    let client = args.connection.connect().unwrap_or_else(exit_with_error);
+    let projections = forecast(&client, args, &scenarios)
+        .await
+        .unwrap_or_else(exit_with_error);
+
+    match args.format {
+        Some(format) => output::write_rows(format, &projections).unwrap_or_else(exit_with_error),
+        None => show(&projections),
+    }
+}
@@ -0,0 +1,234 @@
+use std::collections::{HashMap, HashSet};
+
+use clap::Args;
+
+use crate::{
+    cli::{connection::Connection, exit_with_error, report, scope::Scope},
+    date::DateTime,
+    jira::{self, api::JiraApi, client::SearchIssue, jql::SortDirection},
+};
+
+#[derive(Debug, Args)]
+pub struct Gantt {
+    /// The story points to count for issues that have none.
+    #[arg(long)]
+    #[arg(default_value_t = 3.0)]
+    pub default_story_points: f64,
+
+    #[arg(long)]
+    #[arg(default_value = "Story Points")]
+    pub jira_story_points_field: String,
+
+    /// Wrap the chart in a ```mermaid block, ready to paste into Markdown.
+    #[arg(long)]
+    #[arg(default_value_t = false)]
+    pub markdown: bool,
+
+    #[arg(long)]
+    #[arg(default_value_t = 14)]
+    pub sprint_length_days: u32,
+
+    /// The day work on the epics starts from, like 2024-01-15.  Defaults to today.
+    #[arg(long)]
+    pub start: Option<DateTime>,
+
+    #[arg(long)]
+    #[arg(default_value = "Projected epics")]
+    pub title: String,
+
+    #[arg(long)]
+    pub velocity_in_story_points: f64,
+
+    #[command(flatten)]
+    pub scope: Scope,
+
+    #[command(flatten)]
+    pub connection: Connection,
+}
+
+/// An epic placed on the chart.
+struct Bar {
+    label: String,
+    /// Whether some of the epic is done already.
+    active: bool,
+    start: DateTime,
+    end: DateTime,
+}
+
+/// Takes out the characters Mermaid reads as part of a task's syntax rather than its name.
+fn mermaid_text(text: &str) -> String {
+    let text = text.replace([':', ';', '#'], " ");
+    text.split_whitespace().collect::<Vec<&str>>().join(" ")
+}
+
+/// Works out which other epics each epic is waiting on, from the `Blocks` links between the issues in them.
+fn epic_dependencies(
+    issues: &[SearchIssue],
+    epic_keys: &HashMap<String, Option<String>>,
+) -> HashMap<String, HashSet<String>> {
+    let mut dependencies: HashMap<String, HashSet<String>> = HashMap::new();
+    for (blocked, blockers) in jira::dependencies(issues) {
+        let blocked_epic = match epic_keys.get(&blocked).cloned().flatten() {
+            Some(epic) => epic,
+            None => continue,
+        };
+        // Blockers outside the search aren't known to be in any epic, so they are left out.
+        for blocker in blockers {
+            if let Some(blocker_epic) = epic_keys.get(&blocker).cloned().flatten() {
+                if blocker_epic != blocked_epic {
+                    dependencies
+                        .entry(blocked_epic.clone())
+                        .or_default()
+                        .insert(blocker_epic);
+                }
+            }
+        }
+    }
+
+    dependencies
+}
+
+/// Lays the epics out one after another, as the team would work through them at its velocity, with every epic after
+/// the ones it's waiting on.  Epics that are done are left out.
+fn schedule(
+    epics: Vec<report::EpicProgress>,
+    dependencies: &HashMap<String, HashSet<String>>,
+    args: &Gantt,
+) -> Vec<Bar> {
+    let start = args.start.unwrap_or_else(DateTime::now).start_of_day();
+    let days_per_point = args.sprint_length_days as f64 / args.velocity_in_story_points;
+
+    let mut remaining = epics;
+    let mut days_so_far: f64 = 0.0;
+    let mut bars = vec![];
+    while !remaining.is_empty() {
+        // The first epic, in key order, that isn't waiting on one still to come.
+        let ready = remaining.iter().position(|epic| {
+            epic.key
+                .as_ref()
+                .and_then(|key| dependencies.get(key))
+                .map(|blockers| {
+                    blockers.iter().all(|blocker| {
+                        !remaining
+                            .iter()
+                            .any(|epic| epic.key.as_ref() == Some(blocker))
+                    })
+                })
+                .unwrap_or(true)
+        });
+        let next = match ready {
+            Some(i) => remaining.remove(i),
+            None => {
+                let epic = remaining.remove(0);
+                eprintln!(
+                    "{} is part of a loop of epics blocking each other, so it's placed first.",
+                    epic.key.as_deref().unwrap_or_default()
+                );
+                epic
+            }
+        };
+
+        let points_left = next.total_points - next.completed_points;
+        if points_left <= 0.0 {
+            continue;
+        }
+
+        let start_day = days_so_far.round() as i64;
+        days_so_far += points_left * days_per_point;
+        // Every epic with work left gets at least a day, so that it shows up on the chart.
+        let end_day = (days_so_far.round() as i64).max(start_day + 1);
+
+        let label = match &next.key {
+            Some(key) if next.summary.is_empty() => key.clone(),
+            Some(key) => format!("{} {}", key, next.summary),
+            None => "Not in an epic".to_owned(),
+        };
+        bars.push(Bar {
+            label: mermaid_text(&label),
+            active: next.completed_points > 0.0,
+            start: start.plus_days(start_day),
+            end: start.plus_days(end_day),
+        });
+    }
+
+    bars
+}
+
+fn mermaid(title: &str, bars: &[Bar]) -> String {
+    let mut chart = String::new();
+    chart.push_str("gantt\n");
+    chart.push_str(&format!("    title {}\n", mermaid_text(title)));
+    chart.push_str("    dateFormat YYYY-MM-DD\n");
+    chart.push_str("    section Epics\n");
+    for bar in bars {
+        chart.push_str(&format!(
+            "    {} :{}{}, {}\n",
+            bar.label,
+            if bar.active { "active, " } else { "" },
+            bar.start.to_date_string(),
+            bar.end.to_date_string()
+        ));
+    }
+
+    chart
+}
+
+async fn gantt(client: &impl JiraApi, args: &Gantt) -> Result<String, String> {
+    let story_point_field_ids = jira::get_field_ids_named(client, &args.jira_story_points_field)
+        .await
+        .map_err(|e| format!("Could not get the story point fields: {}", e))?;
+    let epic_link_field_ids = jira::get_field_ids_named(client, "Epic Link")
+        .await
+        .map_err(|e| format!("Could not get the epic link field: {}", e))?;
+    let mut fields = story_point_field_ids.clone();
+    fields.extend(epic_link_field_ids.iter().cloned());
+    for field in ["summary", "status", "issuetype", "parent", "issuelinks"] {
+        fields.push(field.to_owned());
+    }
+
+    let jql = args
+        .scope
+        .builder(client)
+        .await
+        .and_then(|builder| builder.order_by("key", SortDirection::Ascending).build())?;
+    if args.scope.validate_jql {
+        jira::validate_jql(client, &jql)
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+    let issues = client
+        .search_all(&fields, &jql)
+        .await
+        .map_err(|e| format!("Could not search for issues: {}", e))?;
+
+    let epic_keys = report::epic_keys(&issues, &epic_link_field_ids);
+    let dependencies = epic_dependencies(&issues, &epic_keys);
+    let epics = report::epics(
+        &issues,
+        &story_point_field_ids,
+        &epic_link_field_ids,
+        args.default_story_points,
+    );
+
+    Ok(mermaid(&args.title, &schedule(epics, &dependencies, args)))
+}
+
+pub async fn run(args: &Gantt) {
+    if args.velocity_in_story_points <= 0.0 {
+        exit_with_error("--velocity-in-story-points has to be more than zero.")
+    }
+    if args.sprint_length_days == 0 {
+        exit_with_error("--sprint-length-days has to be more than zero.")
+    }
+
+    let client = args.connection.connect().unwrap_or_else(exit_with_error);
+    let chart = gantt(&client, args).await.unwrap_or_else(exit_with_error);
+
+    if args.markdown {
+        println!("```mermaid");
+        print!("{}", chart);
+        println!("```");
+    } else {
+        print!("{}", chart);
+    }
+}
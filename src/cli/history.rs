@@ -0,0 +1,286 @@
+use std::{fs, path::PathBuf};
+
+use clap::Args;
+use colored::Colorize;
+use serde::Serialize;
+
+use crate::{
+    chart::{self, Series},
+    cli::{
+        exit_with_error,
+        output::{self, Format},
+    },
+    date::DateTime,
+    estimator::{self, ClassifiedIssue},
+    jira,
+    snapshot::Snapshot,
+};
+
+const SECONDS_PER_DAY: f64 = 24.0 * 60.0 * 60.0;
+
+/// How wide the longest bar is in the chart drawn in the terminal.
+const BAR_WIDTH: usize = 40;
+
+#[derive(Debug, Args)]
+pub struct History {
+    /// The story points to count for issues that have none.
+    #[arg(long)]
+    #[arg(default_value_t = 3.0)]
+    pub default_story_points: f64,
+
+    /// csv or json, for a script to read.  Without this, the snapshots are drawn as a chart in the terminal.
+    #[arg(long)]
+    pub format: Option<Format>,
+
+    #[arg(long)]
+    #[arg(default_value = "Story Points")]
+    pub jira_story_points_field: String,
+
+    /// A snapshot saved by the snapshot command, or a directory of them, where every file ending in .json.gz is read.
+    /// Can be given more than once.  The snapshots are put in the order they were taken, whatever order they're given
+    /// in.
+    #[arg(long)]
+    #[arg(required = true)]
+    pub snapshot: Vec<PathBuf>,
+
+    #[arg(long)]
+    #[arg(default_value_t = 14)]
+    pub sprint_length_days: u32,
+
+    /// Also draw the points and forecasts as a chart, and save it as an SVG file here.  The forecast is drawn as the
+    /// number of days from the first snapshot to the day it forecast.
+    #[arg(long)]
+    pub svg: Option<PathBuf>,
+
+    #[arg(long)]
+    pub velocity_in_story_points: f64,
+}
+
+/// How much work was left when a snapshot was taken, and when it would have been forecast to be done.
+#[derive(Debug, Serialize)]
+struct Point {
+    taken_at: String,
+    issues: u64,
+    issues_left: u64,
+    total_points: f64,
+    points_left: f64,
+    forecast: String,
+    /// How far the forecast moved since the snapshot before, in days.  Later is positive.
+    forecast_moved_days: i64,
+    #[serde(skip)]
+    taken_on: DateTime,
+    #[serde(skip)]
+    forecast_at: DateTime,
+}
+
+/// Finds the snapshots to read, opening up any directories.
+fn snapshot_paths(args: &History) -> Result<Vec<PathBuf>, String> {
+    let mut paths = vec![];
+    for path in &args.snapshot {
+        if !path.is_dir() {
+            paths.push(path.clone());
+            continue;
+        }
+
+        let entries = fs::read_dir(path)
+            .map_err(|e| format!("Could not read the directory {}: {}", path.display(), e))?;
+        for entry in entries {
+            let entry = entry
+                .map_err(|e| format!("Could not read the directory {}: {}", path.display(), e))?;
+            let file = entry.path();
+            if file.is_file() && file.to_string_lossy().ends_with(".json.gz") {
+                paths.push(file);
+            }
+        }
+    }
+
+    if paths.is_empty() {
+        return Err(
+            "No snapshots were found.  Save some with the snapshot command first.".to_owned(),
+        );
+    }
+
+    Ok(paths)
+}
+
+/// Tallies up each snapshot the way the estimate command would have on the day it was taken.
+fn history(args: &History) -> Result<Vec<Point>, String> {
+    let mut snapshots = vec![];
+    for path in snapshot_paths(args)? {
+        let snapshot = Snapshot::read(&path).map_err(|e| e.to_string())?;
+        let taken_at = snapshot.taken_at().ok_or_else(|| {
+            format!(
+                "{} doesn't say when it was taken, so it can't be put in order.",
+                path.display()
+            )
+        })?;
+        snapshots.push((taken_at, snapshot));
+    }
+    snapshots.sort_by_key(|(taken_at, _)| taken_at.to_unix_seconds());
+
+    let days_per_point = args.sprint_length_days as f64 / args.velocity_in_story_points;
+    let mut points: Vec<Point> = vec![];
+    for (taken_at, snapshot) in &snapshots {
+        let story_point_field_ids = snapshot.field_ids_named(&args.jira_story_points_field);
+        let mut point = Point {
+            taken_at: taken_at.to_rfc3339(),
+            issues: snapshot.issues.len() as u64,
+            issues_left: 0,
+            total_points: 0.0,
+            points_left: 0.0,
+            forecast: String::new(),
+            forecast_moved_days: 0,
+            taken_on: taken_at.start_of_day(),
+            forecast_at: taken_at.start_of_day(),
+        };
+
+        for issue in &snapshot.issues {
+            match estimator::classify(issue, &story_point_field_ids) {
+                ClassifiedIssue::Complete => {
+                    // Like the burndown, a zero is treated as not having been pointed yet.
+                    point.total_points += jira::story_points(issue, &story_point_field_ids)
+                        .filter(|points| *points != 0.0)
+                        .unwrap_or(args.default_story_points);
+                }
+                ClassifiedIssue::IncompleteAndPointed(points) => {
+                    point.issues_left += 1;
+                    point.points_left += points;
+                    point.total_points += points;
+                }
+                ClassifiedIssue::IncompleteAndUnpointed => {
+                    point.issues_left += 1;
+                    point.points_left += args.default_story_points;
+                    point.total_points += args.default_story_points;
+                }
+            }
+        }
+
+        point.forecast_at = point
+            .taken_on
+            .plus_days((point.points_left * days_per_point).ceil() as i64);
+        point.forecast = point.forecast_at.to_date_string();
+        if let Some(before) = points.last() {
+            point.forecast_moved_days = (point.forecast_at.to_unix_seconds()
+                - before.forecast_at.to_unix_seconds())
+                / SECONDS_PER_DAY as i64;
+        }
+        points.push(point);
+    }
+
+    Ok(points)
+}
+
+/// Draws a bar for the points left in each snapshot, with the forecast and how far it moved beside it.
+fn show(points: &[Point]) {
+    let most = points
+        .iter()
+        .map(|point| point.total_points)
+        .fold(0.0, f64::max);
+    let scale = if most > 0.0 {
+        BAR_WIDTH as f64 / most
+    } else {
+        0.0
+    };
+
+    for point in points {
+        let left = ((point.points_left * scale).round() as usize).min(BAR_WIDTH);
+        let done = (((point.total_points - point.points_left) * scale).round() as usize)
+            .min(BAR_WIDTH - left);
+        let moved = match point.forecast_moved_days {
+            0 => String::new(),
+            days if days > 0 => format!("+{}d", days).bright_red().to_string(),
+            days => format!("{}d", days).bright_green().to_string(),
+        };
+        println!(
+            "{}  {}{}{} {:>7.1} of {:>7.1} pts left  {}  {}",
+            point.taken_on.to_date_string(),
+            "█".repeat(left).bright_yellow(),
+            "░".repeat(done),
+            " ".repeat(BAR_WIDTH - left - done),
+            point.points_left,
+            point.total_points,
+            point.forecast,
+            moved
+        );
+    }
+
+    if let (Some(first), Some(last)) = (points.first(), points.last()) {
+        println!(
+            "From {} to {}, the scope went from {:.1} to {:.1} points, and the forecast moved from {} to {}.",
+            first.taken_on.to_date_string(),
+            last.taken_on.to_date_string(),
+            first.total_points,
+            last.total_points,
+            first.forecast,
+            last.forecast.bold()
+        );
+    }
+}
+
+/// Draws the points in each snapshot, and how many days after the first snapshot each one forecast the work to be done.
+fn draw(points: &[Point]) -> String {
+    let labels: Vec<String> = points
+        .iter()
+        .map(|point| point.taken_on.to_date_string())
+        .collect();
+    let first = points
+        .first()
+        .map(|point| point.taken_on.to_unix_seconds())
+        .unwrap_or(0);
+
+    chart::line_chart(
+        "History",
+        &labels,
+        &[
+            Series {
+                name: "Total points",
+                color: "#999999",
+                dashed: true,
+                values: points.iter().map(|point| point.total_points).collect(),
+            },
+            Series {
+                name: "Points left",
+                color: "#1f77b4",
+                dashed: false,
+                values: points.iter().map(|point| point.points_left).collect(),
+            },
+            Series {
+                name: "Forecast day",
+                color: "#d62728",
+                dashed: false,
+                values: points
+                    .iter()
+                    .map(|point| {
+                        (point.forecast_at.to_unix_seconds() - first) as f64 / SECONDS_PER_DAY
+                    })
+                    .collect(),
+            },
+        ],
+    )
+}
+
+pub async fn run(args: &History) {
+    if args.velocity_in_story_points <= 0.0 {
+        exit_with_error("--velocity-in-story-points has to be more than zero.")
+    }
+    if args.sprint_length_days == 0 {
+        exit_with_error("--sprint-length-days has to be more than zero.")
+    }
+
+    let points = history(args).unwrap_or_else(exit_with_error);
+
+    if let Some(path) = &args.svg {
+        fs::write(path, draw(&points)).unwrap_or_else(|e| {
+            exit_with_error(format!(
+                "Could not save the chart to {}: {}",
+                path.display(),
+                e
+            ))
+        });
+    }
+
+    match args.format {
+        Some(format) => output::write_rows(format, &points).unwrap_or_else(exit_with_error),
+        None => show(&points),
+    }
+}
@@ -0,0 +1,232 @@
+use std::fmt::{self, Display};
+
+use clap::Args;
+use colored::Colorize;
+use serde::Serialize;
+
+use crate::{
+    cli::{
+        connection::Connection,
+        exit_with_error,
+        output::{self, Format},
+        scope::Scope,
+    },
+    date::DateTime,
+    jira::{self, api::JiraApi, client::SearchIssue, jql::SortDirection},
+};
+
+const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+
+#[derive(Debug, Args)]
+pub struct Hygiene {
+    /// csv or json, for a script to read, with a row for each problem found.  Without this, the problems are listed
+    /// under each kind, with how many there are.
+    #[arg(long)]
+    pub format: Option<Format>,
+
+    #[arg(long)]
+    #[arg(default_value = "Story Points")]
+    pub jira_story_points_field: String,
+
+    /// Flag issues that are still open but haven't been updated in this many days.
+    #[arg(long)]
+    #[arg(default_value_t = 30)]
+    pub stale_after_days: u32,
+
+    #[command(flatten)]
+    pub scope: Scope,
+
+    #[command(flatten)]
+    pub connection: Connection,
+}
+
+/// The things an issue can be missing that make reports on it less trustworthy.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum Problem {
+    /// Open, with no estimate, so estimates fall back to the default.
+    NoStoryPoints,
+    NoAssignee,
+    /// Not in an epic, so it is left out of anything reported by epic.
+    NoEpic,
+    /// Open, but nobody has touched it in a while.
+    Stale,
+    /// Done, but with no resolution, so JIRA's own reports still count it as unresolved.
+    DoneWithoutResolution,
+}
+
+impl Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let description = match self {
+            Problem::NoStoryPoints => "No story points",
+            Problem::NoAssignee => "No assignee",
+            Problem::NoEpic => "Not in an epic",
+            Problem::Stale => "Stale",
+            Problem::DoneWithoutResolution => "Done without a resolution",
+        };
+        write!(f, "{}", description)
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct Violation {
+    key: String,
+    problem: Problem,
+    summary: String,
+    link: String,
+}
+
+fn is_empty(issue: &SearchIssue, field_id: &str) -> bool {
+    issue
+        .fields
+        .get(field_id)
+        .map(|value| value.is_null())
+        .unwrap_or(true)
+}
+
+fn issue_type(issue: &SearchIssue) -> Option<&str> {
+    issue.fields.get("issuetype")?.get("name")?.as_str()
+}
+
+fn is_subtask(issue: &SearchIssue) -> bool {
+    issue
+        .fields
+        .get("issuetype")
+        .and_then(|issue_type| issue_type.get("subtask"))
+        .and_then(|subtask| subtask.as_bool())
+        .unwrap_or(false)
+}
+
+async fn audit(client: &impl JiraApi, args: &Hygiene) -> Result<Vec<Violation>, String> {
+    let jql = args
+        .scope
+        .builder(client)
+        .await
+        .and_then(|builder| builder.order_by("key", SortDirection::Ascending).build())?;
+
+    if args.scope.validate_jql {
+        jira::validate_jql(client, &jql)
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+
+    let story_point_field_ids = jira::get_field_ids_named(client, &args.jira_story_points_field)
+        .await
+        .map_err(|e| format!("Could not get the story point fields: {}", e))?;
+    // Company-managed projects link issues to their epic with this, and team-managed ones with the parent.
+    let epic_link_field_ids = jira::get_field_ids_named(client, "Epic Link")
+        .await
+        .map_err(|e| format!("Could not get the epic link field: {}", e))?;
+    let mut fields = story_point_field_ids.clone();
+    fields.extend(epic_link_field_ids.iter().cloned());
+    for field in [
+        "summary",
+        "status",
+        "issuetype",
+        "assignee",
+        "parent",
+        "resolution",
+        "updated",
+    ] {
+        fields.push(field.to_owned());
+    }
+
+    let issues = client
+        .search_all(&fields, &jql)
+        .await
+        .map_err(|e| format!("Could not search for issues: {}", e))?;
+
+    let stale_before =
+        DateTime::now().to_unix_seconds() - args.stale_after_days as i64 * SECONDS_PER_DAY;
+    let mut violations = vec![];
+    for issue in &issues {
+        let done = issue.status_category().as_deref() == Some("Done");
+        // Epics aren't in epics, and subtasks are estimated and planned as part of their parent.
+        let is_epic = issue_type(issue) == Some("Epic");
+        let subtask = is_subtask(issue);
+
+        let mut problems = vec![];
+        if !done
+            && !is_epic
+            && !subtask
+            && jira::story_points(issue, &story_point_field_ids).unwrap_or(0.0) == 0.0
+        {
+            problems.push(Problem::NoStoryPoints);
+        }
+        if is_empty(issue, "assignee") {
+            problems.push(Problem::NoAssignee);
+        }
+        if !is_epic
+            && !subtask
+            && is_empty(issue, "parent")
+            && epic_link_field_ids
+                .iter()
+                .all(|field_id| is_empty(issue, field_id))
+        {
+            problems.push(Problem::NoEpic);
+        }
+        let updated_at = issue
+            .fields
+            .get("updated")
+            .and_then(|updated| updated.as_str())
+            .and_then(DateTime::parse_rfc3339);
+        if !done && matches!(updated_at, Some(at) if at.to_unix_seconds() < stale_before) {
+            problems.push(Problem::Stale);
+        }
+        if done && is_empty(issue, "resolution") {
+            problems.push(Problem::DoneWithoutResolution);
+        }
+
+        let summary = issue
+            .fields
+            .get("summary")
+            .and_then(|summary| summary.as_str())
+            .unwrap_or_default();
+        for problem in problems {
+            violations.push(Violation {
+                key: issue.key.clone(),
+                problem,
+                summary: summary.to_owned(),
+                link: format!("{}/browse/{}", args.connection.jira_url, issue.key),
+            });
+        }
+    }
+
+    // Grouped by problem, so that each kind can be fixed in one go.
+    violations.sort_by_key(|violation| violation.problem);
+
+    Ok(violations)
+}
+
+fn show(violations: &[Violation]) {
+    if violations.is_empty() {
+        println!("{}", "Every issue looks good.".bright_green());
+        return;
+    }
+
+    for group in violations.chunk_by(|a, b| a.problem == b.problem) {
+        println!(
+            "{} ({})",
+            group[0].problem.to_string().bold(),
+            format!("{}", group.len()).bright_red()
+        );
+        for violation in group {
+            println!(
+                "  {:<12} {}  {}",
+                violation.key,
+                violation.summary,
+                violation.link.dimmed()
+            );
+        }
+    }
+}
+
+pub async fn run(args: &Hygiene) {
+    let client = args.connection.connect().unwrap_or_else(exit_with_error);
+    let violations = audit(&client, args).await.unwrap_or_else(exit_with_error);
+
+    match args.format {
+        Some(format) => output::write_rows(format, &violations).unwrap_or_else(exit_with_error),
+        None => show(&violations),
+    }
+}
@@ -0,0 +1,286 @@
+use std::{fs, path::PathBuf, str::FromStr};
+
+use clap::Args;
+
+use crate::{
+    cli::{connection::Connection, epics, exit_with_error, roadmap, scope::Scope},
+    date::DateTime,
+    jira::{self, api::JiraApi, jql::SortDirection},
+};
+
+/// The longest a line of an iCalendar file can be, in bytes, before it has to be folded onto the next.
+const MAX_LINE_LENGTH: usize = 75;
+
+/// What to put on the calendar.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Milestones {
+    Epic,
+    FixVersion,
+}
+
+impl FromStr for Milestones {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.eq_ignore_ascii_case("epic") {
+            Ok(Milestones::Epic)
+        } else if s.eq_ignore_ascii_case("fix-version") {
+            Ok(Milestones::FixVersion)
+        } else {
+            Err("milestones can only be by epic or fix-version".to_owned())
+        }
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct Ical {
+    /// epic or fix-version, to only put those on the calendar.  Without this, both are.
+    #[arg(long)]
+    pub by: Option<Milestones>,
+
+    /// The story points to count for issues that have none.
+    #[arg(long)]
+    #[arg(default_value_t = 3.0)]
+    pub default_story_points: f64,
+
+    #[arg(long)]
+    #[arg(default_value = "Story Points")]
+    pub jira_story_points_field: String,
+
+    /// Where to save the calendar, like milestones.ics.  Without this, it is written to stdout.
+    #[arg(long)]
+    pub output: Option<PathBuf>,
+
+    #[arg(long)]
+    #[arg(default_value_t = 14)]
+    pub sprint_length_days: u32,
+
+    /// The name calendar apps show for the feed.
+    #[arg(long)]
+    #[arg(default_value = "Projected milestones")]
+    pub title: String,
+
+    #[arg(long)]
+    pub velocity_in_story_points: f64,
+
+    #[command(flatten)]
+    pub scope: Scope,
+
+    #[command(flatten)]
+    pub connection: Connection,
+}
+
+/// An all-day event on the day something is projected to be done.
+struct Event {
+    /// Stays the same from one run to the next, so that calendar apps move the event rather than adding another.
+    uid: String,
+    day: DateTime,
+    summary: String,
+    description: String,
+    url: Option<String>,
+}
+
+/// Escapes text to go in an iCalendar property value.
+fn text(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+/// Folds a line that is too long into several, each continued with a space, without splitting a character.
+fn fold(line: &str) -> String {
+    let mut folded = String::new();
+    let mut length = 0;
+    for c in line.chars() {
+        if length + c.len_utf8() > MAX_LINE_LENGTH {
+            folded.push_str("\r\n ");
+            // The space at the start of the continuation counts towards its length.
+            length = 1;
+        }
+        folded.push(c);
+        length += c.len_utf8();
+    }
+    folded.push_str("\r\n");
+
+    folded
+}
+
+/// Makes a part of a UID out of a name, like a fix version, that could have anything in it.
+fn uid_part(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect()
+}
+
+fn calendar(args: &Ical, events: &[Event]) -> String {
+    let host = args
+        .connection
+        .jira_url
+        .split("://")
+        .last()
+        .unwrap_or_default()
+        .trim_end_matches('/');
+    // Unwraps here are considered safe since the patterns only use supported specifiers.
+    let stamp = DateTime::now().format("%Y%m%dT%H%M%SZ").unwrap();
+
+    let mut ics = String::new();
+    ics.push_str(&fold("BEGIN:VCALENDAR"));
+    ics.push_str(&fold("VERSION:2.0"));
+    ics.push_str(&fold("PRODID:-//statustracker//Projected milestones//EN"));
+    ics.push_str(&fold("CALSCALE:GREGORIAN"));
+    ics.push_str(&fold(&format!("X-WR-CALNAME:{}", text(&args.title))));
+    for event in events {
+        ics.push_str(&fold("BEGIN:VEVENT"));
+        ics.push_str(&fold(&format!("UID:{}@{}", event.uid, host)));
+        ics.push_str(&fold(&format!("DTSTAMP:{}", stamp)));
+        ics.push_str(&fold(&format!(
+            "DTSTART;VALUE=DATE:{}",
+            event.day.format("%Y%m%d").unwrap()
+        )));
+        ics.push_str(&fold(&format!(
+            "DTEND;VALUE=DATE:{}",
+            event.day.plus_days(1).format("%Y%m%d").unwrap()
+        )));
+        ics.push_str(&fold(&format!("SUMMARY:{}", text(&event.summary))));
+        ics.push_str(&fold(&format!("DESCRIPTION:{}", text(&event.description))));
+        if let Some(url) = &event.url {
+            ics.push_str(&fold(&format!("URL:{}", url)));
+        }
+        // Projections shouldn't make anyone look busy.
+        ics.push_str(&fold("TRANSP:TRANSPARENT"));
+        ics.push_str(&fold("END:VEVENT"));
+    }
+    ics.push_str(&fold("END:VCALENDAR"));
+
+    ics
+}
+
+/// Projects when each epic and fix version will be done, and makes an event for each one that isn't already.
+async fn events(client: &impl JiraApi, args: &Ical) -> Result<Vec<Event>, String> {
+    let story_point_field_ids = jira::get_field_ids_named(client, &args.jira_story_points_field)
+        .await
+        .map_err(|e| format!("Could not get the story point fields: {}", e))?;
+    let epic_link_field_ids = jira::get_field_ids_named(client, "Epic Link")
+        .await
+        .map_err(|e| format!("Could not get the epic link field: {}", e))?;
+    let mut fields = story_point_field_ids.clone();
+    fields.extend(epic_link_field_ids.iter().cloned());
+    for field in ["summary", "status", "issuetype", "parent", "fixVersions"] {
+        fields.push(field.to_owned());
+    }
+
+    let jql = args
+        .scope
+        .builder(client)
+        .await
+        .and_then(|builder| builder.order_by("key", SortDirection::Ascending).build())?;
+    if args.scope.validate_jql {
+        jira::validate_jql(client, &jql)
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+    let issues = client
+        .search_all(&fields, &jql)
+        .await
+        .map_err(|e| format!("Could not search for issues: {}", e))?;
+
+    let days_per_point = args.sprint_length_days as f64 / args.velocity_in_story_points;
+    let mut events = vec![];
+    if args.by != Some(Milestones::FixVersion) {
+        let rows = epics::rows(
+            &issues,
+            &story_point_field_ids,
+            &epic_link_field_ids,
+            args.default_story_points,
+            days_per_point,
+            &args.connection.jira_url,
+        );
+        // Issues outside of any epic have no milestone to mark.
+        for row in rows.into_iter().filter(|row| !row.link.is_empty()) {
+            let day = match row.projected_completion.parse() {
+                Ok(day) => day,
+                Err(_) => continue,
+            };
+            events.push(Event {
+                uid: format!("epic-{}", uid_part(&row.epic)),
+                day,
+                summary: format!("{} projected done: {}", row.epic, row.summary),
+                description: format!(
+                    "{:.1} points left in {} of {} issues, at {} points a sprint.",
+                    row.points_left,
+                    row.issues - row.done_issues,
+                    row.issues,
+                    args.velocity_in_story_points
+                ),
+                url: Some(row.link),
+            });
+        }
+    }
+
+    if args.by != Some(Milestones::Epic) {
+        let releases = roadmap::roadmap(
+            &issues,
+            &story_point_field_ids,
+            args.default_story_points,
+            days_per_point,
+        );
+        // Issues without a version have no milestone to mark either.
+        for release in releases
+            .into_iter()
+            .filter(|release| release.version != "No version")
+        {
+            let day = match release.projected_completion.parse() {
+                Ok(day) => day,
+                Err(_) => continue,
+            };
+            let mut description = format!(
+                "{:.1} points left in {} of {} issues, at {} points a sprint.",
+                release.points_left,
+                release.issues_left,
+                release.issues,
+                args.velocity_in_story_points
+            );
+            if release.late {
+                description.push_str(&format!(
+                    "\nThat is after its release date of {}.",
+                    release.release_date
+                ));
+            }
+            events.push(Event {
+                uid: format!("fix-version-{}", uid_part(&release.version)),
+                day,
+                summary: format!("{} projected done", release.version),
+                description,
+                url: None,
+            });
+        }
+    }
+
+    Ok(events)
+}
+
+pub async fn run(args: &Ical) {
+    if args.velocity_in_story_points <= 0.0 {
+        exit_with_error("--velocity-in-story-points has to be more than zero.")
+    }
+    if args.sprint_length_days == 0 {
+        exit_with_error("--sprint-length-days has to be more than zero.")
+    }
+
+    let client = args.connection.connect().unwrap_or_else(exit_with_error);
+    let events = events(&client, args).await.unwrap_or_else(exit_with_error);
+    let ics = calendar(args, &events);
+
+    match &args.output {
+        Some(path) => fs::write(path, ics).unwrap_or_else(|e| {
+            exit_with_error(format!(
+                "Could not save the calendar to {}: {}",
+                path.display(),
+                e
+            ))
+        }),
+        None => print!("{}", ics),
+    }
+}
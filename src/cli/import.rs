@@ -0,0 +1,443 @@
+use std::{collections::HashMap, path::PathBuf, process};
+
+use clap::Args;
+use colored::Colorize;
+use serde_json::{json, Map, Value as JSONValue};
+
+use crate::{
+    audit::AuditLog,
+    checkpoint::{self, Checkpoint},
+    cli::{self, connection::Connection, exit_with_error},
+    jira::{self, api::JiraApi},
+};
+
+#[derive(Debug, Args)]
+pub struct Import {
+    /// A CSV file with a row for each issue to create.  The columns are Summary, and optionally Type, Story Points,
+    /// Labels, and Epic, in any order.
+    pub issues: PathBuf,
+
+    #[arg(long)]
+    pub audit_log: Option<PathBuf>,
+
+    /// Where to record each issue as it's created, so that running the import again with --resume only creates the
+    /// rest.  Without this, a checkpoint is still kept, named after the time.
+    #[arg(long)]
+    #[arg(conflicts_with = "resume")]
+    pub checkpoint: Option<PathBuf>,
+
+    /// The issue type to create rows without a type as.
+    #[arg(long)]
+    #[arg(default_value = "Story")]
+    pub default_issue_type: String,
+
+    /// Check the file and show the issues that would be created, without creating any.
+    #[arg(long)]
+    #[arg(default_value_t = false)]
+    pub dry_run: bool,
+
+    /// The project to create the issues in, like PROJ.
+    #[arg(long)]
+    pub jira_project: String,
+
+    #[arg(long)]
+    #[arg(default_value = "Story Points")]
+    pub jira_story_points_field: String,
+
+    /// A checkpoint from an earlier import of the same file, to skip the issues it already created.
+    #[arg(long)]
+    pub resume: Option<PathBuf>,
+
+    #[arg(long)]
+    #[arg(default_value_t = false)]
+    pub yes: bool,
+
+    #[command(flatten)]
+    pub connection: Connection,
+}
+
+/// An issue to create, read from a row of the CSV file.
+struct Row {
+    line: usize,
+    summary: String,
+    issue_type: String,
+    story_points: Option<f64>,
+    labels: Vec<String>,
+    /// The key of the epic to put the issue in.
+    epic: Option<String>,
+}
+
+impl Row {
+    fn describe(&self) -> String {
+        let mut details = vec![self.issue_type.clone()];
+        if let Some(points) = self.story_points {
+            details.push(format!("{} points", points));
+        }
+        if !self.labels.is_empty() {
+            details.push(format!("labels {}", self.labels.join(" ")));
+        }
+        if let Some(epic) = &self.epic {
+            details.push(format!("in {}", epic));
+        }
+
+        format!("\"{}\" ({})", self.summary, details.join(", "))
+    }
+}
+
+/// Where each understood column is in the file.  Columns the command does not know about are ignored, so that a
+/// planning spreadsheet can keep its notes alongside.
+struct Columns {
+    summary: usize,
+    issue_type: Option<usize>,
+    story_points: Option<usize>,
+    labels: Option<usize>,
+    epic: Option<usize>,
+}
+
+impl Columns {
+    fn find(headers: &csv::StringRecord, story_points_field: &str) -> Result<Self, String> {
+        let position = |names: &[&str]| {
+            headers
+                .iter()
+                .position(|header| names.contains(&header.trim().to_lowercase().as_str()))
+        };
+        let story_points_field = story_points_field.to_lowercase();
+
+        Ok(Columns {
+            summary: position(&["summary", "title"]).ok_or_else(|| {
+                "The file needs a column with the summary of each issue, named Summary.".to_owned()
+            })?,
+            issue_type: position(&["type", "issue type", "issuetype"]),
+            story_points: position(&["points", "story points", &story_points_field]),
+            labels: position(&["labels"]),
+            epic: position(&["epic", "epic link", "parent"]),
+        })
+    }
+}
+
+/// Reads every row of the file, along with a message for each row that can't be created as it is.
+fn read_rows(args: &Import) -> Result<(Vec<Row>, Vec<String>), String> {
+    let mut reader = csv::Reader::from_path(&args.issues)
+        .map_err(|e| format!("Could not open {}: {}", args.issues.display(), e))?;
+    let headers = reader
+        .headers()
+        .map_err(|e| format!("Could not read the header row: {}", e))?
+        .clone();
+    let columns = Columns::find(&headers, &args.jira_story_points_field)?;
+
+    let mut rows = vec![];
+    let mut problems = vec![];
+    for (i, record) in reader.records().enumerate() {
+        // The header is line 1.
+        let line = i + 2;
+        let record = match record {
+            Ok(record) => record,
+            Err(e) => {
+                problems.push(format!("Line {} could not be read: {}", line, e));
+                continue;
+            }
+        };
+        let cell = |column: Option<usize>| {
+            column
+                .and_then(|column| record.get(column))
+                .map(|value| value.trim())
+                .filter(|value| !value.is_empty())
+        };
+
+        // Blank lines, like the ones spreadsheets leave at the bottom, are skipped.
+        if record.iter().all(|value| value.trim().is_empty()) {
+            continue;
+        }
+        let summary = match cell(Some(columns.summary)) {
+            Some(summary) => summary.to_owned(),
+            None => {
+                problems.push(format!("Line {} does not have a summary.", line));
+                continue;
+            }
+        };
+        let story_points = match cell(columns.story_points).map(str::parse::<f64>) {
+            Some(Ok(points)) => Some(points),
+            Some(Err(_)) => {
+                problems.push(format!(
+                    "Line {} has \"{}\" for story points, which is not a number.",
+                    line,
+                    cell(columns.story_points).unwrap_or_default()
+                ));
+                continue;
+            }
+            None => None,
+        };
+        // Labels cannot contain spaces, so they can be separated by either spaces or commas.
+        let labels = cell(columns.labels)
+            .map(|labels| {
+                labels
+                    .split(|c: char| c == ',' || c.is_whitespace())
+                    .filter(|label| !label.is_empty())
+                    .map(|label| label.to_owned())
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        rows.push(Row {
+            line,
+            summary,
+            issue_type: cell(columns.issue_type)
+                .unwrap_or(&args.default_issue_type)
+                .to_owned(),
+            story_points,
+            labels,
+            epic: cell(columns.epic).map(|epic| epic.to_uppercase()),
+        });
+    }
+
+    Ok((rows, problems))
+}
+
+/// The fields to create an issue with.
+///
+/// Sites with an Epic Link field put issues in epics through it, while the rest use the parent, the way team-managed
+/// projects and newer sites do.
+fn fields(
+    row: &Row,
+    project: &str,
+    story_point_field_id: Option<&str>,
+    epic_link_field_id: Option<&str>,
+) -> Map<String, JSONValue> {
+    let mut fields = Map::new();
+    fields.insert("project".to_owned(), json!({ "key": project }));
+    fields.insert("issuetype".to_owned(), json!({ "name": row.issue_type }));
+    fields.insert("summary".to_owned(), json!(row.summary));
+    if !row.labels.is_empty() {
+        fields.insert("labels".to_owned(), json!(row.labels));
+    }
+    if let (Some(points), Some(field_id)) = (row.story_points, story_point_field_id) {
+        fields.insert(field_id.to_owned(), json!(points));
+    }
+    if let Some(epic) = &row.epic {
+        match epic_link_field_id {
+            Some(field_id) => fields.insert(field_id.to_owned(), json!(epic)),
+            None => fields.insert("parent".to_owned(), json!({ "key": epic })),
+        };
+    }
+
+    fields
+}
+
+/// What each row is recorded in the checkpoint under.
+///
+/// Rows are told apart by the issue they describe rather than by their line, so that fixing or removing one line of
+/// the file doesn't make the rest look new.  Rows that describe the same issue are numbered.
+fn checkpoint_ids(rows: &[Row], project: &str) -> Vec<(String, String)> {
+    let change = format!("Create in {}", project);
+    let mut seen: HashMap<String, usize> = HashMap::new();
+
+    rows.iter()
+        .map(|row| {
+            let description = row.describe();
+            let count = seen.entry(description.clone()).or_insert(0);
+            *count += 1;
+            let id = if *count == 1 {
+                description
+            } else {
+                format!("{} #{}", description, count)
+            };
+            (id, change.clone())
+        })
+        .collect()
+}
+
+fn open_checkpoint(args: &Import) -> Checkpoint {
+    let checkpoint = match checkpoint::open(args.checkpoint.as_deref(), args.resume.as_deref()) {
+        Ok(Some(checkpoint)) => Ok(checkpoint),
+        Ok(None) => Checkpoint::create(checkpoint::default_path("import")),
+        Err(e) => Err(e),
+    };
+
+    checkpoint.unwrap_or_else(|e| exit_with_error(format!("Could not open the checkpoint: {}", e)))
+}
+
+pub async fn run(args: &Import) {
+    // Every line is checked before anything is created, so that a mistake halfway down the file doesn't leave half of
+    // it imported.
+    let (rows, problems) = read_rows(args).unwrap_or_else(exit_with_error);
+    if !problems.is_empty() {
+        for problem in &problems {
+            eprintln!("{} {}", "✗".red(), problem);
+        }
+        exit_with_error(format!(
+            "{} lines of {} can't be imported, so no issues were created.  Fix them and try again.",
+            problems.len(),
+            args.issues.display()
+        ))
+    }
+    if rows.is_empty() {
+        println!("There are no issues in {}.", args.issues.display());
+        return;
+    }
+
+    for row in &rows {
+        println!("Line {}: {}", row.line, row.describe());
+    }
+    if args.dry_run {
+        println!(
+            "{} issues would be created in {}.",
+            rows.len(),
+            args.jira_project
+        );
+        return;
+    }
+
+    let mut client = args.connection.connect().unwrap_or_else(exit_with_error);
+    if let Some(path) = &args.audit_log {
+        let audit_log = AuditLog::create(path).unwrap_or_else(|e| {
+            exit_with_error(format!(
+                "Could not open the audit log at {}: {}",
+                path.display(),
+                e
+            ))
+        });
+        client = client.with_audit_log(audit_log);
+    }
+
+    let fields_on_site = client
+        .get_fields()
+        .await
+        .unwrap_or_else(|e| exit_with_error(format!("Could not look up JIRA's fields: {}", e)));
+    let story_point_field_id = if rows.iter().any(|row| row.story_points.is_some()) {
+        match jira::field_ids_named(&fields_on_site, &args.jira_story_points_field)
+            .into_iter()
+            .next()
+        {
+            Some(field_id) => Some(field_id),
+            None => exit_with_error(format!(
+                "There is no field named {} to set story points with.  Pass the right name with --jira-story-points-field.",
+                args.jira_story_points_field
+            )),
+        }
+    } else {
+        None
+    };
+    let epic_link_field_id = jira::field_ids_named(&fields_on_site, "Epic Link")
+        .into_iter()
+        .next();
+
+    if !args.yes {
+        let question = format!(
+            "Create these {} issues in {}?",
+            rows.len(),
+            args.jira_project
+        );
+        if !cli::confirm(&question) {
+            println!("Nothing was created.");
+            return;
+        }
+    }
+
+    let mut checkpoint = open_checkpoint(args);
+    let ids = checkpoint_ids(&rows, &args.jira_project);
+    println!(
+        "Recording each issue as it's created in {}, so that --resume can pick up without creating any twice.",
+        checkpoint.path().display()
+    );
+
+    // The issues are created one at a time, in the order of the file, so that they land in the backlog in that order.
+    let mut failed = 0;
+    let mut already_created = 0;
+    for (row, (id, change)) in rows.iter().zip(&ids) {
+        if checkpoint.is_done(id, change) {
+            already_created += 1;
+            println!(
+                "{} Line {}: already created {}",
+                "✓".green(),
+                row.line,
+                checkpoint.created(id, change).unwrap_or_default()
+            );
+            continue;
+        }
+
+        let fields = fields(
+            row,
+            &args.jira_project,
+            story_point_field_id.as_deref(),
+            epic_link_field_id.as_deref(),
+        );
+        match client.create_issue(&fields).await {
+            Ok(key) => {
+                println!(
+                    "{} Line {}: created {}/browse/{}",
+                    "✓".green(),
+                    row.line,
+                    args.connection.jira_url,
+                    key
+                );
+                if let Err(e) = checkpoint.record_created(id, change, &key) {
+                    eprintln!(
+                        "Could not write to the checkpoint at {}: {}",
+                        checkpoint.path().display(),
+                        e
+                    );
+                }
+            }
+            Err(e) => {
+                failed += 1;
+                eprintln!(
+                    "{} Line {}: could not create {}: {}",
+                    "✗".red(),
+                    row.line,
+                    row.describe(),
+                    e
+                );
+            }
+        }
+    }
+
+    println!();
+    println!(
+        "{} created, {} already created, {} failed.",
+        format!("{}", rows.len() - failed - already_created).green(),
+        already_created,
+        format!("{}", failed).red()
+    );
+    if failed > 0 {
+        println!(
+            "To create the rest without creating these again, run this command again with --resume {}",
+            checkpoint.path().display()
+        );
+        process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(line: usize, summary: &str) -> Row {
+        Row {
+            line,
+            summary: summary.to_owned(),
+            issue_type: "Story".to_owned(),
+            story_points: None,
+            labels: vec![],
+            epic: None,
+        }
+    }
+
+    #[test]
+    fn checkpoint_ids_survive_lines_moving() {
+        let before = checkpoint_ids(&[row(2, "Log in"), row(3, "Log out")], "TEAM");
+        // The first line was removed, so "Log out" moved up.
+        let after = checkpoint_ids(&[row(2, "Log out")], "TEAM");
+
+        assert_eq!(after[0], before[1]);
+        assert_eq!(after[0].1, "Create in TEAM");
+    }
+
+    #[test]
+    fn checkpoint_ids_tell_identical_rows_apart() {
+        let ids = checkpoint_ids(&[row(2, "Spike"), row(3, "Spike"), row(4, "Spike")], "TEAM");
+
+        assert_eq!(ids[0].0, "\"Spike\" (Story)");
+        assert_eq!(ids[1].0, "\"Spike\" (Story) #2");
+        assert_eq!(ids[2].0, "\"Spike\" (Story) #3");
+    }
+}
@@ -0,0 +1,242 @@
+use std::{
+    fs::OpenOptions,
+    io::{self, Write},
+    path::PathBuf,
+};
+
+use clap::Args;
+use colored::Colorize;
+
+use crate::{
+    cli::{confirm, connection::Connection, exit_with_error},
+    jira::{api::JiraApi, client::Client},
+};
+
+#[derive(Debug, Args)]
+pub struct Init {
+    /// Where to write the argfile, which is then used by passing it to any command with @, like
+    /// `statustracker estimate @statustracker.args.txt`.
+    #[arg(long)]
+    #[arg(default_value = "statustracker.args.txt")]
+    pub output: PathBuf,
+}
+
+/// Asks a question on the terminal, and returns the trimmed answer, or the default if nothing was typed.
+fn ask(question: &str, default: Option<&str>) -> Result<String, String> {
+    match default {
+        Some(default) if !default.is_empty() => print!("{} [{}] ", question, default),
+        _ => print!("{} ", question),
+    }
+    io::stdout()
+        .flush()
+        .map_err(|e| format!("Could not write to the terminal: {}", e))?;
+
+    let mut answer = String::new();
+    let read = io::stdin()
+        .read_line(&mut answer)
+        .map_err(|e| format!("Could not read from the terminal: {}", e))?;
+    // Without this, a closed stdin would ask the same question forever.
+    if read == 0 {
+        return Err("Stopped, since there are no more answers to read.".to_owned());
+    }
+
+    let answer = answer.trim();
+    Ok(if answer.is_empty() {
+        default.unwrap_or_default().to_owned()
+    } else {
+        answer.to_owned()
+    })
+}
+
+/// Splits an answer like "TEAM1, TEAM2" into its parts.
+fn list(answer: &str) -> Vec<String> {
+    answer
+        .split(',')
+        .map(str::trim)
+        .filter(|part| !part.is_empty())
+        .map(str::to_owned)
+        .collect()
+}
+
+/// Asks for the site and credentials until JIRA accepts them, returning the connection options for them.
+async fn connect() -> Result<(Vec<String>, Client), String> {
+    loop {
+        let jira_url = ask(
+            "What is the address of your JIRA site, like https://your-domain.atlassian.net?",
+            None,
+        )?
+        .trim_end_matches('/')
+        .to_owned();
+        let username = ask(
+            "What email address or username do you sign in with?  Leave this empty to use a JIRA Data Center personal access token instead.",
+            None,
+        )?;
+        let token = ask(
+            "What is your API token?  It will be shown as you type it, and saved in the argfile.",
+            None,
+        )?;
+
+        let mut args = vec!["--jira-url".to_owned(), jira_url];
+        if username.is_empty() {
+            args.push("--auth-scheme".to_owned());
+            args.push("bearer".to_owned());
+        } else {
+            args.push("--jira-username".to_owned());
+            args.push(username);
+        }
+        args.push("--jira-token".to_owned());
+        args.push(token);
+
+        let client = match Connection::from_args(&args).and_then(|connection| connection.connect())
+        {
+            Ok(client) => client,
+            Err(e) => {
+                println!("{}", e.red());
+                continue;
+            }
+        };
+        match client.get_myself().await {
+            Ok(user) => {
+                println!(
+                    "{}",
+                    format!("Signed in as {}.", user.display_name).bright_green()
+                );
+                return Ok((args, client));
+            }
+            Err(e) => println!(
+                "{}",
+                format!("JIRA didn't accept those: {}  Try again.", e).red()
+            ),
+        }
+    }
+}
+
+/// Asks which projects to look at, until every one given exists.
+async fn projects(client: &impl JiraApi) -> Result<Vec<String>, String> {
+    let projects = client
+        .get_projects()
+        .await
+        .map_err(|e| format!("Could not get the projects: {}", e))?;
+    println!("These are the projects you can see:");
+    for project in &projects {
+        println!("  {:<12} {}", project.key.bold(), project.name);
+    }
+
+    loop {
+        let chosen = list(&ask(
+            "Which projects should the commands look at?  Give their keys, separated by commas.",
+            None,
+        )?);
+        let unknown: Vec<&String> = chosen
+            .iter()
+            .filter(|key| !projects.iter().any(|project| &project.key == *key))
+            .collect();
+
+        if chosen.is_empty() {
+            println!("{}", "Pick at least one project.".red());
+        } else if !unknown.is_empty() {
+            let unknown: Vec<&str> = unknown.iter().map(|key| key.as_str()).collect();
+            println!(
+                "{}",
+                format!("There are no projects called {}.", unknown.join(", ")).red()
+            );
+        } else {
+            return Ok(chosen);
+        }
+    }
+}
+
+/// Asks which field holds story points, suggesting the first one that looks like it, until it names a field that
+/// exists.
+async fn story_points_field(client: &impl JiraApi) -> Result<String, String> {
+    let fields = client
+        .get_fields()
+        .await
+        .map_err(|e| format!("Could not get the fields: {}", e))?;
+    let suggestion = fields
+        .iter()
+        .find(|field| field.name == "Story Points")
+        .or_else(|| {
+            fields.iter().find(|field| {
+                field.value_type() == "number" && field.name.to_lowercase().contains("point")
+            })
+        })
+        .map(|field| field.name.clone());
+
+    loop {
+        let name = ask(
+            "What is the name of the field your team estimates in?",
+            suggestion.as_deref(),
+        )?;
+        if fields.iter().any(|field| field.name == name) {
+            return Ok(name);
+        }
+
+        println!(
+            "{}",
+            format!(
+                "There is no field named \"{}\".  statustracker list-fields shows them all.",
+                name
+            )
+            .red()
+        );
+    }
+}
+
+async fn init(args: &Init) -> Result<(), String> {
+    if args.output.exists()
+        && !confirm(&format!(
+            "{} already exists.  Overwrite it?",
+            args.output.display()
+        ))
+    {
+        return Err("Stopped without writing anything.".to_owned());
+    }
+
+    let (mut argfile, client) = connect().await?;
+
+    for project in projects(&client).await? {
+        argfile.push("--jira-project".to_owned());
+        argfile.push(project);
+    }
+
+    for label in list(&ask(
+        "Should they only look at issues with certain labels?  Give them separated by commas, or leave this empty for all issues.",
+        None,
+    )?) {
+        argfile.push("--jira-label".to_owned());
+        argfile.push(label);
+    }
+
+    let field = story_points_field(&client).await?;
+    // It's the default, so there's no need to spell it out.
+    if field != "Story Points" {
+        argfile.push("--jira-story-points-field".to_owned());
+        argfile.push(field);
+    }
+
+    let mut options = OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    // The argfile holds the token, so only its owner should be able to read it.
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let mut file = options
+        .open(&args.output)
+        .map_err(|e| format!("Could not open {}: {}", args.output.display(), e))?;
+    for arg in &argfile {
+        writeln!(file, "{}", arg)
+            .map_err(|e| format!("Could not write to {}: {}", args.output.display(), e))?;
+    }
+
+    println!(
+        "Wrote {}.  Pass it to any command with @, like {}",
+        args.output.display(),
+        format!("statustracker burndown @{}", args.output.display()).bold()
+    );
+
+    Ok(())
+}
+
+pub async fn run(args: &Init) {
+    init(args).await.unwrap_or_else(exit_with_error);
+}
@@ -0,0 +1,98 @@
+use clap::Args;
+use colored::Colorize;
+use serde::Serialize;
+
+use crate::{
+    cli::{
+        connection::Connection,
+        exit_with_error,
+        output::{self, Format},
+    },
+    jira::api::JiraApi,
+};
+
+#[derive(Debug, Args)]
+pub struct ListFields {
+    /// Only list fields whose name or ID contains this, ignoring case.
+    #[arg(long)]
+    pub filter: Option<String>,
+
+    /// csv or json, for a script to read.  Without this, the fields are shown as a table.
+    #[arg(long)]
+    pub format: Option<Format>,
+
+    #[command(flatten)]
+    pub connection: Connection,
+}
+
+#[derive(Debug, Serialize)]
+struct FieldRow {
+    name: String,
+    id: String,
+    #[serde(rename = "type")]
+    field_type: String,
+    custom: bool,
+}
+
+async fn list_fields(client: &impl JiraApi, args: &ListFields) -> Result<Vec<FieldRow>, String> {
+    let fields = client
+        .get_fields()
+        .await
+        .map_err(|e| format!("Could not get the fields: {}", e))?;
+
+    let filter = args.filter.as_ref().map(|filter| filter.to_lowercase());
+    let mut rows: Vec<FieldRow> = fields
+        .into_iter()
+        .filter(|field| match &filter {
+            Some(filter) => {
+                field.name.to_lowercase().contains(filter)
+                    || field.id.to_lowercase().contains(filter)
+            }
+            None => true,
+        })
+        .map(|field| FieldRow {
+            field_type: field.value_type(),
+            name: field.name,
+            id: field.id,
+            custom: field.custom,
+        })
+        .collect();
+    rows.sort_by_key(|row| row.name.to_lowercase());
+
+    Ok(rows)
+}
+
+fn show(rows: &[FieldRow]) {
+    let width = |column: fn(&FieldRow) -> &str| {
+        rows.iter()
+            .map(|row| column(row).chars().count())
+            .max()
+            .unwrap_or(0)
+    };
+    let name_width = width(|row| &row.name);
+    let id_width = width(|row| &row.id);
+
+    for row in rows {
+        println!(
+            "{:<name_width$}  {:<id_width$}  {}",
+            row.name,
+            row.id,
+            row.field_type.dimmed(),
+            name_width = name_width,
+            id_width = id_width
+        );
+    }
+    println!("{} fields.", rows.len());
+}
+
+pub async fn run(args: &ListFields) {
+    let client = args.connection.connect().unwrap_or_else(exit_with_error);
+    let rows = list_fields(&client, args)
+        .await
+        .unwrap_or_else(exit_with_error);
+
+    match args.format {
+        Some(format) => output::write_rows(format, &rows).unwrap_or_else(exit_with_error),
+        None => show(&rows),
+    }
+}
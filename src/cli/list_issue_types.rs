@@ -0,0 +1,104 @@
+use clap::Args;
+use colored::Colorize;
+use serde::Serialize;
+
+use crate::{
+    cli::{
+        connection::Connection,
+        exit_with_error,
+        output::{self, Format},
+    },
+    jira::api::JiraApi,
+};
+
+#[derive(Debug, Args)]
+pub struct ListIssueTypes {
+    /// csv or json, for a script to read.  Without this, the issue types are shown as a table.
+    #[arg(long)]
+    pub format: Option<Format>,
+
+    /// The projects to list the issue types of.
+    #[arg(long)]
+    #[arg(required = true)]
+    pub jira_project: Vec<String>,
+
+    #[command(flatten)]
+    pub connection: Connection,
+}
+
+#[derive(Debug, Serialize)]
+struct IssueTypeRow {
+    project: String,
+    issue_type: String,
+    subtask: bool,
+    /// The statuses the issue type's workflow can put issues in, in the order JIRA lists them.
+    statuses: String,
+}
+
+async fn list_issue_types(
+    client: &impl JiraApi,
+    args: &ListIssueTypes,
+) -> Result<Vec<IssueTypeRow>, String> {
+    let mut rows = vec![];
+    for project in &args.jira_project {
+        let issue_types = client.get_project_statuses(project).await.map_err(|e| {
+            format!(
+                "Could not get the issue types of the {} project: {}",
+                project, e
+            )
+        })?;
+
+        for issue_type in issue_types {
+            let statuses: Vec<&str> = issue_type
+                .statuses
+                .iter()
+                .map(|status| status.name.as_str())
+                .collect();
+            rows.push(IssueTypeRow {
+                project: project.clone(),
+                statuses: statuses.join(", "),
+                issue_type: issue_type.name,
+                subtask: issue_type.subtask,
+            });
+        }
+    }
+
+    Ok(rows)
+}
+
+fn show(rows: &[IssueTypeRow]) {
+    let width = |column: fn(&IssueTypeRow) -> &str| {
+        rows.iter()
+            .map(|row| column(row).chars().count())
+            .max()
+            .unwrap_or(0)
+    };
+    let project_width = width(|row| &row.project);
+    let issue_type_width = width(|row| &row.issue_type);
+
+    for row in rows {
+        let subtask = if row.subtask { "subtask" } else { "       " };
+        println!(
+            "{:<project_width$}  {:<issue_type_width$}  {}  {}",
+            row.project,
+            row.issue_type,
+            subtask,
+            row.statuses.dimmed(),
+            project_width = project_width,
+            issue_type_width = issue_type_width
+        );
+    }
+    println!("{} issue types.", rows.len());
+}
+
+pub async fn run(args: &ListIssueTypes) {
+    let client = args.connection.connect().unwrap_or_else(exit_with_error);
+    let rows = list_issue_types(&client, args)
+        .await
+        .unwrap_or_else(exit_with_error);
+
+    match args.format {
+        Some(format) => output::write_rows(format, &rows).unwrap_or_else(exit_with_error),
+        None => show(&rows),
+    }
+}
@@ -0,0 +1,115 @@
+use clap::Args;
+use colored::Colorize;
+use serde::Serialize;
+
+use crate::{
+    cli::{
+        connection::Connection,
+        exit_with_error,
+        output::{self, Format},
+    },
+    jira::api::JiraApi,
+};
+
+#[derive(Debug, Args)]
+pub struct ListProjects {
+    /// Only list projects whose key or name contains this, ignoring case.
+    #[arg(long)]
+    pub filter: Option<String>,
+
+    /// csv or json, for a script to read.  Without this, the projects are shown as a table.
+    #[arg(long)]
+    pub format: Option<Format>,
+
+    #[command(flatten)]
+    pub connection: Connection,
+}
+
+#[derive(Debug, Serialize)]
+struct ProjectRow {
+    key: String,
+    name: String,
+    /// Like software or business, followed by whether it is company-managed or team-managed.
+    #[serde(rename = "type")]
+    project_type: String,
+    lead: String,
+}
+
+async fn list_projects(
+    client: &impl JiraApi,
+    args: &ListProjects,
+) -> Result<Vec<ProjectRow>, String> {
+    let projects = client
+        .get_projects()
+        .await
+        .map_err(|e| format!("Could not get the projects: {}", e))?;
+
+    let filter = args.filter.as_ref().map(|filter| filter.to_lowercase());
+    let mut rows: Vec<ProjectRow> = projects
+        .into_iter()
+        .filter(|project| match &filter {
+            Some(filter) => {
+                project.key.to_lowercase().contains(filter)
+                    || project.name.to_lowercase().contains(filter)
+            }
+            None => true,
+        })
+        .map(|project| {
+            let management = match project.style.as_str() {
+                "next-gen" => " (team-managed)",
+                "classic" => " (company-managed)",
+                _ => "",
+            };
+            ProjectRow {
+                project_type: format!("{}{}", project.project_type_key, management),
+                lead: project
+                    .lead
+                    .map(|lead| lead.display_name)
+                    .unwrap_or_default(),
+                key: project.key,
+                name: project.name,
+            }
+        })
+        .collect();
+    rows.sort_by(|a, b| a.key.cmp(&b.key));
+
+    Ok(rows)
+}
+
+fn show(rows: &[ProjectRow]) {
+    let width = |column: fn(&ProjectRow) -> &str| {
+        rows.iter()
+            .map(|row| column(row).chars().count())
+            .max()
+            .unwrap_or(0)
+    };
+    let key_width = width(|row| &row.key);
+    let name_width = width(|row| &row.name);
+    let type_width = width(|row| &row.project_type);
+
+    for row in rows {
+        println!(
+            "{:<key_width$}  {:<name_width$}  {:<type_width$}  {}",
+            row.key.bold(),
+            row.name,
+            row.project_type,
+            row.lead.dimmed(),
+            key_width = key_width,
+            name_width = name_width,
+            type_width = type_width
+        );
+    }
+    println!("{} projects.", rows.len());
+}
+
+pub async fn run(args: &ListProjects) {
+    let client = args.connection.connect().unwrap_or_else(exit_with_error);
+    let rows = list_projects(&client, args)
+        .await
+        .unwrap_or_else(exit_with_error);
+
+    match args.format {
+        Some(format) => output::write_rows(format, &rows).unwrap_or_else(exit_with_error),
+        None => show(&rows),
+    }
+}
@@ -0,0 +1,148 @@
+use std::collections::BTreeMap;
+
+use clap::Args;
+use colored::Colorize;
+use serde::Serialize;
+
+use crate::{
+    cli::{
+        connection::Connection,
+        exit_with_error,
+        output::{self, Format},
+    },
+    jira::api::JiraApi,
+};
+
+#[derive(Debug, Args)]
+pub struct ListStatuses {
+    /// csv or json, for a script to read.  Without this, the statuses are shown as a table.
+    #[arg(long)]
+    pub format: Option<Format>,
+
+    /// Only list the statuses used in these projects, along with the issue types that use each one.  Without this,
+    /// every status in JIRA is listed.
+    #[arg(long)]
+    pub jira_project: Vec<String>,
+
+    #[command(flatten)]
+    pub connection: Connection,
+}
+
+#[derive(Debug, Serialize)]
+struct StatusRow {
+    /// Empty when listing every status in JIRA.
+    project: String,
+    status: String,
+    id: String,
+    /// To Do, In Progress, or Done, which is what the reports go by.
+    category: String,
+    /// The issue types in the project whose workflows use the status.
+    issue_types: String,
+}
+
+async fn list_statuses(
+    client: &impl JiraApi,
+    args: &ListStatuses,
+) -> Result<Vec<StatusRow>, String> {
+    if args.jira_project.is_empty() {
+        let statuses = client
+            .get_statuses()
+            .await
+            .map_err(|e| format!("Could not get the statuses: {}", e))?;
+        let mut rows: Vec<StatusRow> = statuses
+            .into_iter()
+            .map(|status| StatusRow {
+                project: "".to_owned(),
+                status: status.name,
+                id: status.id,
+                category: status.status_category.name,
+                issue_types: "".to_owned(),
+            })
+            .collect();
+        rows.sort_by_key(|row| row.status.to_lowercase());
+        return Ok(rows);
+    }
+
+    let mut rows = vec![];
+    for project in &args.jira_project {
+        let issue_types = client.get_project_statuses(project).await.map_err(|e| {
+            format!(
+                "Could not get the statuses of the {} project: {}",
+                project, e
+            )
+        })?;
+
+        // Keyed by status ID, since issue types mostly share statuses.
+        let mut statuses: BTreeMap<String, StatusRow> = BTreeMap::new();
+        for issue_type in &issue_types {
+            for status in &issue_type.statuses {
+                let row = statuses
+                    .entry(status.id.clone())
+                    .or_insert_with(|| StatusRow {
+                        project: project.clone(),
+                        status: status.name.clone(),
+                        id: status.id.clone(),
+                        category: status.status_category.name.clone(),
+                        issue_types: "".to_owned(),
+                    });
+                if !row.issue_types.is_empty() {
+                    row.issue_types.push_str(", ");
+                }
+                row.issue_types.push_str(&issue_type.name);
+            }
+        }
+
+        let mut project_rows: Vec<StatusRow> = statuses.into_values().collect();
+        project_rows.sort_by_key(|row| row.status.to_lowercase());
+        rows.extend(project_rows);
+    }
+
+    Ok(rows)
+}
+
+fn show(rows: &[StatusRow]) {
+    let width = |column: fn(&StatusRow) -> &str| {
+        rows.iter()
+            .map(|row| column(row).chars().count())
+            .max()
+            .unwrap_or(0)
+    };
+    let project_width = width(|row| &row.project);
+    let status_width = width(|row| &row.status);
+    let category_width = width(|row| &row.category);
+
+    for row in rows {
+        let category = format!("{:<width$}", row.category, width = category_width);
+        let category = match row.category.as_str() {
+            "Done" => category.green(),
+            "In Progress" => category.yellow(),
+            _ => category.normal(),
+        };
+        let project = if project_width == 0 {
+            "".to_owned()
+        } else {
+            format!("{:<width$}  ", row.project, width = project_width)
+        };
+        println!(
+            "{}{:<status_width$}  {}  {}",
+            project,
+            row.status,
+            category,
+            row.issue_types.dimmed(),
+            status_width = status_width
+        );
+    }
+    println!("{} statuses.", rows.len());
+}
+
+pub async fn run(args: &ListStatuses) {
+    let client = args.connection.connect().unwrap_or_else(exit_with_error);
+    let rows = list_statuses(&client, args)
+        .await
+        .unwrap_or_else(exit_with_error);
+
+    match args.format {
+        Some(format) => output::write_rows(format, &rows).unwrap_or_else(exit_with_error),
+        None => show(&rows),
+    }
+}
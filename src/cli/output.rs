@@ -0,0 +1,49 @@
+/// Writes out the rows of data reports are made of, in whichever format the user asked for.
+use std::{io, str::FromStr};
+
+use serde::Serialize;
+
+#[derive(Clone, Copy, Debug)]
+pub enum Format {
+    /// With a header row, for spreadsheets.
+    Csv,
+    /// As an array of objects, for scripts.
+    Json,
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.eq_ignore_ascii_case("csv") {
+            Ok(Format::Csv)
+        } else if s.eq_ignore_ascii_case("json") {
+            Ok(Format::Json)
+        } else {
+            Err("the format has to be csv or json".to_owned())
+        }
+    }
+}
+
+/// Writes rows to stdout.
+pub fn write_rows<T: Serialize>(format: Format, rows: &[T]) -> Result<(), String> {
+    match format {
+        Format::Csv => {
+            let mut writer = csv::Writer::from_writer(io::stdout());
+            for row in rows {
+                writer
+                    .serialize(row)
+                    .map_err(|e| format!("Could not write a row: {}", e))?;
+            }
+            writer
+                .flush()
+                .map_err(|e| format!("Could not write a row: {}", e))
+        }
+        Format::Json => {
+            serde_json::to_writer_pretty(io::stdout(), rows)
+                .map_err(|e| format!("Could not write the rows: {}", e))?;
+            println!();
+            Ok(())
+        }
+    }
+}
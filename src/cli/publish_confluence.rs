@@ -0,0 +1,132 @@
+use clap::Args;
+
+use crate::{
+    cli::{
+        exit_with_error,
+        report::{self, Contents},
+    },
+    date::DateTime,
+    jira::client::Client,
+    template::{self, Context},
+};
+
+#[derive(Debug, Args)]
+pub struct PublishConfluence {
+    /// The title of the page to put new pages under.  Without this, they go at the top of the space.
+    #[arg(long)]
+    pub confluence_parent: Option<String>,
+
+    /// The key of the space to publish to, like TEAM.
+    #[arg(long)]
+    pub confluence_space: String,
+
+    /// Where Confluence is, if it isn't at /wiki on the JIRA site, as it is on Atlassian Cloud.  It's signed in to with
+    /// the same credentials as JIRA.
+    #[arg(long)]
+    pub confluence_url: Option<String>,
+
+    /// The title of the page, with {today} or {today:FORMAT} filled in with the date.  A page that already has the
+    /// title is updated, so a title without the date keeps one page up to date, while one with it starts a new page on
+    /// each new day.
+    #[arg(long)]
+    #[arg(default_value = "Status report {today}")]
+    pub title: String,
+
+    #[command(flatten)]
+    pub contents: Contents,
+}
+
+/// Finds the ID of the page to put a new page under.
+async fn find_parent(
+    confluence: &Client,
+    args: &PublishConfluence,
+    parent_title: &str,
+) -> Result<String, String> {
+    let parent = confluence
+        .find_confluence_page(&args.confluence_space, parent_title)
+        .await
+        .map_err(|e| format!("Could not look for the parent page in Confluence: {}", e))?;
+
+    match parent {
+        Some(parent) => Ok(parent.id),
+        None => Err(format!(
+            "There is no page called \"{}\" in the {} space to put the report under.",
+            parent_title, args.confluence_space
+        )),
+    }
+}
+
+/// Creates the page, or updates it if it's there already, and attaches the charts to it.  Returns the page's ID.
+async fn publish(
+    jira: &Client,
+    confluence: &Client,
+    args: &PublishConfluence,
+) -> Result<String, String> {
+    let title = template::expand(
+        &args.title,
+        &Context {
+            today: DateTime::now(),
+            sprint: None,
+        },
+    )
+    .map_err(|e| e.to_string())?;
+
+    let report = report::gather(jira, &args.contents).await?;
+    let body = report::confluence(&report, &args.contents.connection.jira_url, true);
+
+    let existing = confluence
+        .find_confluence_page(&args.confluence_space, &title)
+        .await
+        .map_err(|e| format!("Could not look for the page in Confluence: {}", e))?;
+    let page = match existing {
+        Some(page) => confluence
+            .update_confluence_page(&page, &title, &body)
+            .await
+            .map_err(|e| format!("Could not update the page in Confluence: {}", e))?,
+        None => {
+            let parent_id = match &args.confluence_parent {
+                Some(parent_title) => Some(find_parent(confluence, args, parent_title).await?),
+                None => None,
+            };
+
+            confluence
+                .create_confluence_page(&args.confluence_space, parent_id.as_deref(), &title, &body)
+                .await
+                .map_err(|e| format!("Could not create the page in Confluence: {}", e))?
+        }
+    };
+
+    for (file_name, chart) in report.charts() {
+        confluence
+            .attach_to_confluence_page(&page.id, file_name, "image/svg+xml", chart.as_bytes())
+            .await
+            .map_err(|e| format!("Could not attach {} to the page: {}", file_name, e))?;
+    }
+
+    Ok(page.id)
+}
+
+pub async fn run(args: &PublishConfluence) {
+    let connection = &args.contents.connection;
+    let jira = connection.connect().unwrap_or_else(exit_with_error);
+
+    let confluence_url = args
+        .confluence_url
+        .clone()
+        .unwrap_or_else(|| format!("{}/wiki", connection.jira_url.trim_end_matches('/')));
+    let mut confluence_connection = connection.clone();
+    confluence_connection.jira_url = confluence_url.clone();
+    let confluence = confluence_connection
+        .connect()
+        .unwrap_or_else(exit_with_error);
+
+    let page_id = publish(&jira, &confluence, args)
+        .await
+        .unwrap_or_else(exit_with_error);
+
+    println!(
+        "{}/pages/viewpage.action?pageId={}",
+        confluence_url.trim_end_matches('/'),
+        page_id
+    );
+}
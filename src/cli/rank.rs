@@ -0,0 +1,176 @@
+use clap::Args;
+use colored::Colorize;
+use serde::Serialize;
+
+use crate::{
+    cli::{
+        connection::Connection,
+        exit_with_error,
+        output::{self, Format},
+        report,
+        scope::Scope,
+    },
+    date::DateTime,
+    jira::{self, api::JiraApi, jql::SortDirection},
+};
+
+#[derive(Debug, Args)]
+pub struct Rank {
+    /// The story points to count for issues that have none.
+    #[arg(long)]
+    #[arg(default_value_t = 3.0)]
+    pub default_story_points: f64,
+
+    /// csv or json, for a script to read.  Without this, the backlog is shown as a table, with a line where each sprint
+    /// is projected to end.
+    #[arg(long)]
+    pub format: Option<Format>,
+
+    #[arg(long)]
+    #[arg(default_value = "Story Points")]
+    pub jira_story_points_field: String,
+
+    #[arg(long)]
+    #[arg(default_value_t = 14)]
+    pub sprint_length_days: u32,
+
+    /// Only show the issues projected to be done in this many sprints, like 6 for a quarter.
+    #[arg(long)]
+    pub sprints: Option<u32>,
+
+    #[arg(long)]
+    pub velocity_in_story_points: f64,
+
+    #[command(flatten)]
+    pub scope: Scope,
+
+    #[command(flatten)]
+    pub connection: Connection,
+}
+
+/// An unfinished issue, in the order it's ranked in the backlog.
+#[derive(Debug, Serialize)]
+struct RankedIssue {
+    rank: u64,
+    key: String,
+    summary: String,
+    status: String,
+    points: f64,
+    /// The points of this issue and every issue ranked above it.
+    cumulative_points: f64,
+    /// Which sprint from now the issue is projected to be done in, counting from 1.
+    sprint: u64,
+    /// When that sprint ends.
+    sprint_ends: String,
+    link: String,
+}
+
+/// Fetches the unfinished issues in rank order, and works out which sprint each is projected to land in.
+async fn rank(client: &impl JiraApi, args: &Rank) -> Result<Vec<RankedIssue>, String> {
+    let story_point_field_ids = jira::get_field_ids_named(client, &args.jira_story_points_field)
+        .await
+        .map_err(|e| format!("Could not get the story point fields: {}", e))?;
+    let mut fields = story_point_field_ids.clone();
+    for field in ["summary", "status"] {
+        fields.push(field.to_owned());
+    }
+
+    // JIRA keeps the backlog order in the rank field, so sorting on it gives the order the board shows.
+    let jql = args.scope.builder(client).await.and_then(|builder| {
+        builder
+            .status_category("To Do")
+            .status_category("In Progress")
+            .order_by("rank", SortDirection::Ascending)
+            .build()
+    })?;
+    if args.scope.validate_jql {
+        jira::validate_jql(client, &jql)
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+    let issues = client
+        .search_all(&fields, &jql)
+        .await
+        .map_err(|e| format!("Could not search for issues: {}", e))?;
+
+    let today = DateTime::now().start_of_day();
+    let mut cumulative_points = 0.0;
+    let mut ranked = vec![];
+    for (i, issue) in issues.iter().enumerate() {
+        // Like estimating, a zero is treated as not having been pointed yet.
+        let points = jira::story_points(issue, &story_point_field_ids)
+            .filter(|points| *points != 0.0)
+            .unwrap_or(args.default_story_points);
+        cumulative_points += points;
+        let sprint = ((cumulative_points / args.velocity_in_story_points).ceil() as u64).max(1);
+        if args
+            .sprints
+            .map(|sprints| sprint > sprints as u64)
+            .unwrap_or(false)
+        {
+            break;
+        }
+
+        ranked.push(RankedIssue {
+            rank: i as u64 + 1,
+            key: issue.key.clone(),
+            summary: report::summary(issue),
+            status: issue.status().unwrap_or_default(),
+            points,
+            cumulative_points,
+            sprint,
+            sprint_ends: today
+                .plus_days(sprint as i64 * args.sprint_length_days as i64)
+                .to_date_string(),
+            link: format!("{}/browse/{}", args.connection.jira_url, issue.key),
+        });
+    }
+
+    Ok(ranked)
+}
+
+fn show(ranked: &[RankedIssue]) {
+    for (i, issue) in ranked.iter().enumerate() {
+        println!(
+            "{:>5}  {:<12} {:>5.1} {:>7.1}  {:<20} {}",
+            issue.rank,
+            issue.key,
+            issue.points,
+            issue.cumulative_points,
+            issue.status,
+            issue.summary
+        );
+
+        let last_in_sprint = ranked
+            .get(i + 1)
+            .map(|next| next.sprint != issue.sprint)
+            .unwrap_or(true);
+        if last_in_sprint {
+            println!(
+                "{}",
+                format!(
+                    "────── end of sprint {}, {} ──────",
+                    issue.sprint, issue.sprint_ends
+                )
+                .yellow()
+            );
+        }
+    }
+}
+
+pub async fn run(args: &Rank) {
+    if args.velocity_in_story_points <= 0.0 {
+        exit_with_error("--velocity-in-story-points has to be more than zero.")
+    }
+    if args.sprint_length_days == 0 {
+        exit_with_error("--sprint-length-days has to be more than zero.")
+    }
+
+    let client = args.connection.connect().unwrap_or_else(exit_with_error);
+    let ranked = rank(&client, args).await.unwrap_or_else(exit_with_error);
+
+    match args.format {
+        Some(format) => output::write_rows(format, &ranked).unwrap_or_else(exit_with_error),
+        None => show(&ranked),
+    }
+}
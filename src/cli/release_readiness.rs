@@ -0,0 +1,319 @@
+use std::{collections::BTreeMap, process, str::FromStr};
+
+use clap::Args;
+use colored::Colorize;
+
+use crate::{
+    cli::{connection::Connection, exit_with_error, report, scope::Scope},
+    date::DateTime,
+    estimator::{self, ClassifiedIssue},
+    jira::{self, api::JiraApi, client::SearchIssue, jql::SortDirection},
+};
+
+/// The most open bugs a release can have at one priority, like Highest=0.
+#[derive(Clone, Debug)]
+pub struct PriorityLimit {
+    pub priority: String,
+    pub max: u64,
+}
+
+impl FromStr for PriorityLimit {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (priority, max) = s
+            .split_once('=')
+            .ok_or_else(|| format!("\"{}\" should look like Highest=0", s))?;
+        let max = max
+            .trim()
+            .parse()
+            .map_err(|_| format!("\"{}\" is not a number of bugs", max))?;
+
+        Ok(PriorityLimit {
+            priority: priority.trim().to_owned(),
+            max,
+        })
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct ReleaseReadiness {
+    /// The story points to count for issues that have none.
+    #[arg(long)]
+    #[arg(default_value_t = 3.0)]
+    pub default_story_points: f64,
+
+    /// The release to check, by the name of its fix version, like 2.4.
+    #[arg(long)]
+    pub fix_version: String,
+
+    #[arg(long)]
+    #[arg(default_value = "Story Points")]
+    pub jira_story_points_field: String,
+
+    /// Fail if more bugs than this are open, at any priority.
+    #[arg(long)]
+    pub max_open_bugs: Option<u64>,
+
+    /// Fail if more bugs than this are open at a priority, like Highest=0.  Can be given once for each priority.
+    #[arg(long)]
+    pub max_open_bugs_at: Vec<PriorityLimit>,
+
+    /// Fail if more open issues than this have no story points.
+    #[arg(long)]
+    pub max_unpointed: Option<u64>,
+
+    /// Fail if the release isn't projected to be ready by this day, like 2024-03-01.
+    #[arg(long)]
+    pub ready_by: Option<DateTime>,
+
+    #[arg(long)]
+    #[arg(default_value_t = 14)]
+    pub sprint_length_days: u32,
+
+    #[arg(long)]
+    pub velocity_in_story_points: f64,
+
+    #[command(flatten)]
+    pub scope: Scope,
+
+    #[command(flatten)]
+    pub connection: Connection,
+}
+
+/// How far along the release is.
+struct Readiness {
+    issues: u64,
+    done: u64,
+    /// Open bugs by priority, keyed by JIRA's ID for the priority so that they go from most to least urgent.
+    open_bugs: BTreeMap<(u64, String), u64>,
+    unpointed: u64,
+    points_left: f64,
+    ready_on: DateTime,
+}
+
+impl Readiness {
+    fn open_bugs_total(&self) -> u64 {
+        self.open_bugs.values().sum()
+    }
+
+    fn open_bugs_at(&self, priority: &str) -> u64 {
+        self.open_bugs
+            .iter()
+            .filter(|((_, name), _)| name.eq_ignore_ascii_case(priority))
+            .map(|(_, count)| count)
+            .sum()
+    }
+}
+
+/// A gate the release has to pass, and whether it did.
+struct Gate {
+    description: String,
+    passed: bool,
+}
+
+/// The priority of an issue, as its ID and name.  Issues without one go after every priority.
+pub fn priority(issue: &SearchIssue) -> (u64, String) {
+    let priority = issue.fields.get("priority");
+    (
+        priority
+            .and_then(|priority| priority.get("id"))
+            .and_then(|id| id.as_str())
+            .and_then(|id| id.parse().ok())
+            .unwrap_or(u64::MAX),
+        priority
+            .and_then(|priority| priority.get("name"))
+            .and_then(|name| name.as_str())
+            .unwrap_or("No priority")
+            .to_owned(),
+    )
+}
+
+fn readiness(
+    issues: &[SearchIssue],
+    story_point_field_ids: &[String],
+    args: &ReleaseReadiness,
+) -> Readiness {
+    let mut readiness = Readiness {
+        issues: issues.len() as u64,
+        done: 0,
+        open_bugs: BTreeMap::new(),
+        unpointed: 0,
+        points_left: 0.0,
+        ready_on: DateTime::now().start_of_day(),
+    };
+
+    for issue in issues {
+        match estimator::classify(issue, story_point_field_ids) {
+            ClassifiedIssue::Complete => {
+                readiness.done += 1;
+                continue;
+            }
+            ClassifiedIssue::IncompleteAndPointed(points) => readiness.points_left += points,
+            ClassifiedIssue::IncompleteAndUnpointed => {
+                readiness.unpointed += 1;
+                readiness.points_left += args.default_story_points;
+            }
+        }
+
+        if report::is_type(issue, "Bug") {
+            *readiness.open_bugs.entry(priority(issue)).or_insert(0) += 1;
+        }
+    }
+
+    let days_per_point = args.sprint_length_days as f64 / args.velocity_in_story_points;
+    readiness.ready_on = readiness
+        .ready_on
+        .plus_days((readiness.points_left * days_per_point).ceil() as i64);
+
+    readiness
+}
+
+fn gates(readiness: &Readiness, args: &ReleaseReadiness) -> Vec<Gate> {
+    let mut gates = vec![];
+    if let Some(max) = args.max_open_bugs {
+        let open = readiness.open_bugs_total();
+        gates.push(Gate {
+            description: format!("{} open bugs, of at most {}", open, max),
+            passed: open <= max,
+        });
+    }
+    for limit in &args.max_open_bugs_at {
+        let open = readiness.open_bugs_at(&limit.priority);
+        gates.push(Gate {
+            description: format!(
+                "{} open {} bugs, of at most {}",
+                open, limit.priority, limit.max
+            ),
+            passed: open <= limit.max,
+        });
+    }
+    if let Some(max) = args.max_unpointed {
+        gates.push(Gate {
+            description: format!(
+                "{} open issues without story points, of at most {}",
+                readiness.unpointed, max
+            ),
+            passed: readiness.unpointed <= max,
+        });
+    }
+    if let Some(ready_by) = args.ready_by {
+        gates.push(Gate {
+            description: format!(
+                "Projected ready on {}, for {}",
+                readiness.ready_on.to_date_string(),
+                ready_by.to_date_string()
+            ),
+            passed: readiness.ready_on.to_unix_seconds()
+                <= ready_by.start_of_day().to_unix_seconds(),
+        });
+    }
+
+    gates
+}
+
+async fn search(client: &impl JiraApi, args: &ReleaseReadiness) -> Result<Readiness, String> {
+    let story_point_field_ids = jira::get_field_ids_named(client, &args.jira_story_points_field)
+        .await
+        .map_err(|e| format!("Could not get the story point fields: {}", e))?;
+    let mut fields = story_point_field_ids.clone();
+    for field in ["status", "issuetype", "priority"] {
+        fields.push(field.to_owned());
+    }
+
+    let jql = args.scope.builder(client).await.and_then(|builder| {
+        builder
+            .fix_version(&args.fix_version)
+            .order_by("key", SortDirection::Ascending)
+            .build()
+    })?;
+    if args.scope.validate_jql {
+        jira::validate_jql(client, &jql)
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+    let issues = client
+        .search_all(&fields, &jql)
+        .await
+        .map_err(|e| format!("Could not search for issues: {}", e))?;
+    if issues.is_empty() {
+        return Err(format!(
+            "No issues are in {}.  Check that it's the name of a fix version in the projects searched.",
+            args.fix_version
+        ));
+    }
+
+    Ok(readiness(&issues, &story_point_field_ids, args))
+}
+
+fn show(args: &ReleaseReadiness, readiness: &Readiness) {
+    println!("{}", format!("Release {}", args.fix_version).bold());
+    println!(
+        "  Done:             {} of {} issues ({:.0}%)",
+        readiness.done,
+        readiness.issues,
+        readiness.done as f64 / readiness.issues as f64 * 100.0
+    );
+    let by_priority: Vec<String> = readiness
+        .open_bugs
+        .iter()
+        .map(|((_, name), count)| format!("{} {}", count, name))
+        .collect();
+    if by_priority.is_empty() {
+        println!("  Open bugs:        0");
+    } else {
+        println!(
+            "  Open bugs:        {} ({})",
+            readiness.open_bugs_total(),
+            by_priority.join(", ")
+        );
+    }
+    println!("  Unpointed:        {} open issues", readiness.unpointed);
+    println!(
+        "  Projected ready:  {} ({:.1} points left, {:.1} sprints)",
+        readiness.ready_on.to_date_string(),
+        readiness.points_left,
+        readiness.points_left / args.velocity_in_story_points
+    );
+}
+
+pub async fn run(args: &ReleaseReadiness) {
+    if args.velocity_in_story_points <= 0.0 {
+        exit_with_error("--velocity-in-story-points has to be more than zero.")
+    }
+    if args.sprint_length_days == 0 {
+        exit_with_error("--sprint-length-days has to be more than zero.")
+    }
+
+    let client = args.connection.connect().unwrap_or_else(exit_with_error);
+    let readiness = search(&client, args).await.unwrap_or_else(exit_with_error);
+    show(args, &readiness);
+
+    let gates = gates(&readiness, args);
+    if gates.is_empty() {
+        return;
+    }
+
+    println!();
+    for gate in &gates {
+        let mark = if gate.passed {
+            "✓".bright_green()
+        } else {
+            "✗".bright_red()
+        };
+        println!("{} {}", mark, gate.description);
+    }
+
+    let failed = gates.iter().filter(|gate| !gate.passed).count();
+    if failed > 0 {
+        println!(
+            "{} of {} gates failed, so {} isn't ready.",
+            format!("{}", failed).bright_red(),
+            gates.len(),
+            args.fix_version
+        );
+        process::exit(1);
+    }
+
+    println!("{}", "Every gate passed.".bright_green());
+}
@@ -0,0 +1,633 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+    io::{self, Write},
+    path::PathBuf,
+    str::FromStr,
+};
+
+use clap::Args;
+
+use crate::{
+    chart::escape,
+    cli::{
+        burndown::{self, Day},
+        burnup,
+        connection::Connection,
+        exit_with_error,
+        scope::Scope,
+        timeline::{self, Timeline},
+    },
+    date::DateTime,
+    email,
+    estimator::{self, ClassifiedIssue, Results},
+    jira::{self, api::JiraApi, client::SearchIssue, history},
+};
+
+#[derive(Clone, Copy, Debug)]
+pub enum ReportFormat {
+    /// A single HTML file with the charts drawn inside it, which opens in any browser and can be attached to an email.
+    Html,
+    /// Confluence's storage format, to paste into a page's source.  Confluence won't show SVG written into a page, so
+    /// this leaves out the charts, which publish-confluence attaches to the page instead.
+    Confluence,
+}
+
+impl FromStr for ReportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.eq_ignore_ascii_case("html") {
+            Ok(ReportFormat::Html)
+        } else if s.eq_ignore_ascii_case("confluence") {
+            Ok(ReportFormat::Confluence)
+        } else {
+            Err("the format has to be html or confluence".to_owned())
+        }
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct Report {
+    /// Who the email says it's from, like "Status <status@example.com>".  Without this, sendmail decides.
+    #[arg(long)]
+    pub email_from: Option<String>,
+
+    /// Email the report to this address, through sendmail.  Only HTML reports can be emailed.
+    #[arg(long)]
+    pub email_to: Vec<String>,
+
+    /// html, or confluence to paste into the source of a Confluence page.
+    #[arg(long)]
+    #[arg(default_value = "html")]
+    pub format: ReportFormat,
+
+    /// Where to save the report, like status.html.  Without this, it is written to stdout, unless it's emailed.
+    #[arg(long)]
+    pub output: Option<PathBuf>,
+
+    /// The sendmail to email the report with.  Mail servers like Postfix and msmtp come with one that works.
+    #[arg(long)]
+    #[arg(default_value = "/usr/sbin/sendmail")]
+    pub sendmail: PathBuf,
+
+    /// The heading at the top of the HTML report, and the subject of the email.  Confluence pages have a title of their
+    /// own.
+    #[arg(long)]
+    #[arg(default_value = "Status report")]
+    pub title: String,
+
+    #[command(flatten)]
+    pub contents: Contents,
+}
+
+// What goes into a report, which is the same however it's laid out and wherever it's sent.  This is a plain comment
+// since clap would show a doc comment as the summary of every command it's flattened into.
+#[derive(Debug, Args)]
+pub struct Contents {
+    /// The story points to count for issues that have none.
+    #[arg(long)]
+    #[arg(default_value_t = 3.0)]
+    pub default_story_points: f64,
+
+    #[arg(long)]
+    #[arg(default_value = "Story Points")]
+    pub jira_story_points_field: String,
+
+    #[arg(long)]
+    pub velocity_in_story_points: f64,
+
+    #[command(flatten)]
+    pub timeline: Timeline,
+
+    #[command(flatten)]
+    pub scope: Scope,
+
+    #[command(flatten)]
+    pub connection: Connection,
+}
+
+/// How far along the issues in one epic are.
+pub struct EpicProgress {
+    /// `None` for the issues that aren't in an epic.
+    pub key: Option<String>,
+    /// Empty when the epic itself wasn't part of the search, and isn't anything's parent.
+    pub summary: String,
+    pub issues: u64,
+    pub done_issues: u64,
+    pub total_points: f64,
+    pub completed_points: f64,
+}
+
+impl EpicProgress {
+    pub fn percent_done(&self) -> f64 {
+        if self.total_points > 0.0 {
+            self.completed_points / self.total_points * 100.0
+        } else {
+            0.0
+        }
+    }
+}
+
+struct UnpointedIssue {
+    key: String,
+    summary: String,
+    status: String,
+    /// To Do, In Progress, or Done.
+    status_category: String,
+}
+
+/// Everything in a report, gathered up before it is laid out.
+pub struct ReportData {
+    generated_at: DateTime,
+    results: Results,
+    days: Vec<Day>,
+    epics: Vec<EpicProgress>,
+    unpointed: Vec<UnpointedIssue>,
+}
+
+impl ReportData {
+    /// The burndown and burnup, each with a file name to save it as.
+    pub fn charts(&self) -> [(&'static str, String); 2] {
+        [
+            ("burndown.svg", burndown::draw(&self.days)),
+            ("burnup.svg", burnup::draw(&self.days)),
+        ]
+    }
+}
+
+pub fn summary(issue: &SearchIssue) -> String {
+    issue
+        .fields
+        .get("summary")
+        .and_then(|summary| summary.as_str())
+        .unwrap_or_default()
+        .to_owned()
+}
+
+pub fn is_type(issue: &SearchIssue, issue_type: &str) -> bool {
+    issue
+        .fields
+        .get("issuetype")
+        .and_then(|value| value.get("name"))
+        .and_then(|name| name.as_str())
+        == Some(issue_type)
+}
+
+fn is_subtask(issue: &SearchIssue) -> bool {
+    issue
+        .fields
+        .get("issuetype")
+        .and_then(|issue_type| issue_type.get("subtask"))
+        .and_then(|subtask| subtask.as_bool())
+        .unwrap_or(false)
+}
+
+/// Works out the epic each issue is in.  Subtasks are in the epic their parent is in, and epics are in themselves.
+///
+/// The issues need to have been fetched with the `"issuetype"` and `"parent"` fields, and the epic link fields.
+pub fn epic_keys(
+    issues: &[SearchIssue],
+    epic_link_field_ids: &[String],
+) -> HashMap<String, Option<String>> {
+    let own_epic_keys: HashMap<&str, Option<String>> = issues
+        .iter()
+        .map(|issue| {
+            let epic_key = if is_type(issue, "Epic") {
+                Some(issue.key.clone())
+            } else {
+                jira::epic_key(issue, epic_link_field_ids)
+            };
+            (issue.key.as_str(), epic_key)
+        })
+        .collect();
+
+    issues
+        .iter()
+        .map(|issue| {
+            let mut epic_key = own_epic_keys.get(issue.key.as_str()).cloned().flatten();
+            if epic_key.is_none() && is_subtask(issue) {
+                let parent_key = issue
+                    .fields
+                    .get("parent")
+                    .and_then(|parent| parent.get("key"))
+                    .and_then(|key| key.as_str());
+                if let Some(parent_key) = parent_key {
+                    epic_key = own_epic_keys.get(parent_key).cloned().flatten();
+                }
+            }
+
+            (issue.key.clone(), epic_key)
+        })
+        .collect()
+}
+
+/// Finds the summaries of the epics in the search, and of the epics that issues in it are the children of.
+pub fn epic_summaries(issues: &[SearchIssue]) -> HashMap<String, String> {
+    let mut summaries: HashMap<String, String> = HashMap::new();
+    for issue in issues {
+        if is_type(issue, "Epic") {
+            summaries.insert(issue.key.clone(), summary(issue));
+        }
+        if let Some(parent) = issue.fields.get("parent") {
+            if let (Some(key), Some(summary)) = (
+                parent.get("key").and_then(|key| key.as_str()),
+                parent
+                    .get("fields")
+                    .and_then(|fields| fields.get("summary"))
+                    .and_then(|summary| summary.as_str()),
+            ) {
+                summaries
+                    .entry(key.to_owned())
+                    .or_insert_with(|| summary.to_owned());
+            }
+        }
+    }
+
+    summaries
+}
+
+/// Adds up the issues in each epic.  Epics in the search are only used for their summaries, and subtasks count
+/// towards the epic their parent is in.
+pub fn epics(
+    issues: &[SearchIssue],
+    story_point_field_ids: &[String],
+    epic_link_field_ids: &[String],
+    default_story_points: f64,
+) -> Vec<EpicProgress> {
+    let epic_keys = epic_keys(issues, epic_link_field_ids);
+    let summaries = epic_summaries(issues);
+
+    let mut progress: BTreeMap<Option<String>, EpicProgress> = BTreeMap::new();
+    for issue in issues.iter().filter(|issue| !is_type(issue, "Epic")) {
+        let epic_key = epic_keys.get(&issue.key).cloned().flatten();
+        let epic = progress
+            .entry(epic_key.clone())
+            .or_insert_with(|| EpicProgress {
+                summary: epic_key
+                    .as_ref()
+                    .and_then(|key| summaries.get(key).cloned())
+                    .unwrap_or_default(),
+                key: epic_key,
+                issues: 0,
+                done_issues: 0,
+                total_points: 0.0,
+                completed_points: 0.0,
+            });
+        // Like the burndown, a zero is treated as not having been pointed yet.
+        let points = jira::story_points(issue, story_point_field_ids)
+            .filter(|points| *points != 0.0)
+            .unwrap_or(default_story_points);
+        epic.issues += 1;
+        epic.total_points += points;
+        if issue.status_category().as_deref() == Some("Done") {
+            epic.done_issues += 1;
+            epic.completed_points += points;
+        }
+    }
+
+    // The issues outside of any epic go last, after the epics in key order.
+    let mut epics: Vec<EpicProgress> = progress.into_values().collect();
+    let outside = epics.iter().take_while(|epic| epic.key.is_none()).count();
+    epics.rotate_left(outside);
+
+    epics
+}
+
+pub async fn gather(client: &impl JiraApi, args: &Contents) -> Result<ReportData, String> {
+    let story_point_field_ids = jira::get_field_ids_named(client, &args.jira_story_points_field)
+        .await
+        .map_err(|e| format!("Could not get the story point fields: {}", e))?;
+    let epic_link_field_ids = jira::get_field_ids_named(client, "Epic Link")
+        .await
+        .map_err(|e| format!("Could not get the epic link field: {}", e))?;
+    let mut fields = story_point_field_ids.clone();
+    fields.extend(epic_link_field_ids.iter().cloned());
+    for field in ["summary", "status", "issuetype", "parent"] {
+        fields.push(field.to_owned());
+    }
+
+    let categories = history::status_categories(client)
+        .await
+        .map_err(|e| format!("Could not get the statuses: {}", e))?;
+    let issues = timeline::search_with_changelogs(client, &args.scope, &fields).await?;
+
+    let mut results = Results::new(args.default_story_points, args.velocity_in_story_points);
+    results.tally(&issues, &story_point_field_ids);
+
+    let unpointed = issues
+        .iter()
+        .filter(|issue| {
+            matches!(
+                estimator::classify(issue, &story_point_field_ids),
+                ClassifiedIssue::IncompleteAndUnpointed
+            )
+        })
+        .map(|issue| UnpointedIssue {
+            key: issue.key.clone(),
+            summary: summary(issue),
+            status: issue.status().unwrap_or_default(),
+            status_category: issue.status_category().unwrap_or_default(),
+        })
+        .collect();
+
+    Ok(ReportData {
+        generated_at: DateTime::now(),
+        days: burndown::tally_days(
+            &issues,
+            &categories,
+            &story_point_field_ids,
+            &args.jira_story_points_field,
+            args.default_story_points,
+            &args.timeline.days()?,
+        ),
+        epics: epics(
+            &issues,
+            &story_point_field_ids,
+            &epic_link_field_ids,
+            args.default_story_points,
+        ),
+        results,
+        unpointed,
+    })
+}
+
+/// The lines of the estimate, as a label and a value for each.
+fn estimate_rows(results: &Results) -> [(String, String); 5] {
+    [
+        (
+            "Completed".to_owned(),
+            format!("{:.0} issues", results.num_complete),
+        ),
+        (
+            "Left, with estimates".to_owned(),
+            format!(
+                "{:.0} issues, {:.0} points",
+                results.num_incomplete_and_pointed, results.unfinished_estimated_story_points
+            ),
+        ),
+        (
+            "Left, without estimates".to_owned(),
+            format!(
+                "{:.0} issues × {:.0} points = {:.0} points",
+                results.num_incomplete_and_unpointed,
+                results.default_story_points,
+                results.unfinished_unestimated_story_points
+            ),
+        ),
+        (
+            "Left in all".to_owned(),
+            format!("{:.0} points", results.unfinished_story_points),
+        ),
+        (
+            format!(
+                "Sprints left at {:.0} points a sprint",
+                results.velocity_in_story_points
+            ),
+            format!("{:.1}", results.num_sprints_remaining),
+        ),
+    ]
+}
+
+const STYLE: &str =
+    "body { font-family: sans-serif; max-width: 900px; margin: 2em auto; color: #222; }
+table { border-collapse: collapse; margin-bottom: 1em; }
+th, td { border-bottom: 1px solid #ddd; padding: 4px 12px; text-align: left; }
+td.number { text-align: right; }
+.meta { color: #777; }
+.bar { width: 120px; height: 10px; background: #eee; }
+.bar div { height: 10px; background: #2ca02c; }";
+
+/// Lays a report out as a page of HTML that needs nothing else to be shown.
+fn html(report: &ReportData, title: &str, jira_url: &str) -> String {
+    let link = |key: &str| {
+        format!(
+            "<a href=\"{}/browse/{}\">{}</a>",
+            escape(jira_url),
+            escape(key),
+            escape(key)
+        )
+    };
+    let mut page = format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>\n{}\n</style>\n</head>\n<body>\n",
+        escape(title),
+        STYLE
+    );
+    page.push_str(&format!("<h1>{}</h1>\n", escape(title)));
+    page.push_str(&format!(
+        "<p class=\"meta\">From {}, as of {} UTC.</p>\n",
+        escape(jira_url),
+        escape(
+            &report
+                .generated_at
+                .format("%Y-%m-%d %H:%M")
+                .unwrap_or_default()
+        )
+    ));
+
+    page.push_str("<h2>Estimate</h2>\n<table>\n");
+    for (label, value) in estimate_rows(&report.results) {
+        page.push_str(&format!(
+            "<tr><th>{}</th><td>{}</td></tr>\n",
+            escape(&label),
+            escape(&value)
+        ));
+    }
+    page.push_str("</table>\n");
+
+    page.push_str("<h2>Progress</h2>\n");
+    for (_, chart) in report.charts() {
+        page.push_str(&chart);
+    }
+
+    page.push_str("<h2>Epics</h2>\n<table>\n<tr><th>Epic</th><th>Issues</th><th>Done</th><th>Points done</th><th>Points left</th><th>Progress</th></tr>\n");
+    for epic in &report.epics {
+        let name = match &epic.key {
+            Some(key) if epic.summary.is_empty() => link(key),
+            Some(key) => format!("{} {}", link(key), escape(&epic.summary)),
+            None => "Not in an epic".to_owned(),
+        };
+        page.push_str(&format!(
+            "<tr><td>{}</td><td class=\"number\">{}</td><td class=\"number\">{}</td><td class=\"number\">{:.0}</td><td class=\"number\">{:.0}</td><td><div class=\"bar\"><div style=\"width: {:.0}%\"></div></div></td></tr>\n",
+            name,
+            epic.issues,
+            epic.done_issues,
+            epic.completed_points,
+            epic.total_points - epic.completed_points,
+            epic.percent_done()
+        ));
+    }
+    page.push_str("</table>\n");
+
+    page.push_str("<h2>Issues without estimates</h2>\n");
+    if report.unpointed.is_empty() {
+        page.push_str("<p>Every issue that's left has an estimate.</p>\n");
+    } else {
+        page.push_str("<table>\n<tr><th>Issue</th><th>Summary</th><th>Status</th></tr>\n");
+        for issue in &report.unpointed {
+            page.push_str(&format!(
+                "<tr><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+                link(&issue.key),
+                escape(&issue.summary),
+                escape(&issue.status)
+            ));
+        }
+        page.push_str("</table>\n");
+    }
+
+    page.push_str("</body>\n</html>\n");
+
+    page
+}
+
+/// A status lozenge, in the colour Confluence uses for a status category.
+fn status_macro(title: &str, category: &str) -> String {
+    let colour = match category {
+        "Done" => "Green",
+        "In Progress" => "Blue",
+        _ => "Grey",
+    };
+    format!(
+        "<ac:structured-macro ac:name=\"status\"><ac:parameter ac:name=\"colour\">{}</ac:parameter><ac:parameter ac:name=\"title\">{}</ac:parameter></ac:structured-macro>",
+        colour,
+        escape(title)
+    )
+}
+
+/// Lays a report out in Confluence's storage format, the XHTML that pages are saved as.
+///
+/// With `attached_charts`, the charts are shown from attachments on the page, named as `ReportData::charts` names them.
+pub fn confluence(report: &ReportData, jira_url: &str, attached_charts: bool) -> String {
+    let link = |key: &str| {
+        format!(
+            "<a href=\"{}/browse/{}\">{}</a>",
+            escape(jira_url),
+            escape(key),
+            escape(key)
+        )
+    };
+
+    let mut page = format!(
+        "<p>From {}, as of {} UTC.</p>\n",
+        escape(jira_url),
+        escape(
+            &report
+                .generated_at
+                .format("%Y-%m-%d %H:%M")
+                .unwrap_or_default()
+        )
+    );
+
+    page.push_str("<h2>Estimate</h2>\n<table><tbody>\n");
+    for (label, value) in estimate_rows(&report.results) {
+        page.push_str(&format!(
+            "<tr><th>{}</th><td>{}</td></tr>\n",
+            escape(&label),
+            escape(&value)
+        ));
+    }
+    page.push_str("</tbody></table>\n");
+
+    if attached_charts {
+        page.push_str("<h2>Progress</h2>\n");
+        for (file_name, _) in report.charts() {
+            page.push_str(&format!(
+                "<p><ac:image><ri:attachment ri:filename=\"{}\"/></ac:image></p>\n",
+                escape(file_name)
+            ));
+        }
+    }
+
+    page.push_str("<h2>Epics</h2>\n<table><tbody>\n<tr><th>Epic</th><th>Status</th><th>Issues</th><th>Done</th><th>Points done</th><th>Points left</th><th>Progress</th></tr>\n");
+    for epic in &report.epics {
+        let name = match &epic.key {
+            Some(key) if epic.summary.is_empty() => link(key),
+            Some(key) => format!("{} {}", link(key), escape(&epic.summary)),
+            None => "Not in an epic".to_owned(),
+        };
+        let status = if epic.done_issues == epic.issues {
+            status_macro("Done", "Done")
+        } else if epic.done_issues > 0 {
+            status_macro("In Progress", "In Progress")
+        } else {
+            status_macro("To Do", "To Do")
+        };
+        page.push_str(&format!(
+            "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{:.0}</td><td>{:.0}</td><td>{:.0}%</td></tr>\n",
+            name,
+            status,
+            epic.issues,
+            epic.done_issues,
+            epic.completed_points,
+            epic.total_points - epic.completed_points,
+            epic.percent_done()
+        ));
+    }
+    page.push_str("</tbody></table>\n");
+
+    page.push_str("<h2>Issues without estimates</h2>\n");
+    if report.unpointed.is_empty() {
+        page.push_str("<p>Every issue that's left has an estimate.</p>\n");
+    } else {
+        page.push_str("<table><tbody>\n<tr><th>Issue</th><th>Summary</th><th>Status</th></tr>\n");
+        for issue in &report.unpointed {
+            page.push_str(&format!(
+                "<tr><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+                link(&issue.key),
+                escape(&issue.summary),
+                status_macro(&issue.status, &issue.status_category)
+            ));
+        }
+        page.push_str("</tbody></table>\n");
+    }
+
+    page
+}
+
+pub async fn run(args: &Report) {
+    if !args.email_to.is_empty() && !matches!(args.format, ReportFormat::Html) {
+        exit_with_error(
+            "Only HTML reports can be emailed, so --email-to can't be used with --format confluence.",
+        )
+    }
+
+    let client = args
+        .contents
+        .connection
+        .connect()
+        .unwrap_or_else(exit_with_error);
+    let report = gather(&client, &args.contents)
+        .await
+        .unwrap_or_else(exit_with_error);
+
+    let jira_url = &args.contents.connection.jira_url;
+    let rendered = match args.format {
+        ReportFormat::Html => html(&report, &args.title, jira_url),
+        ReportFormat::Confluence => confluence(&report, jira_url, false),
+    };
+
+    if !args.email_to.is_empty() {
+        email::send_html(
+            &args.sendmail,
+            args.email_from.as_deref(),
+            &args.email_to,
+            &args.title,
+            &rendered,
+        )
+        .unwrap_or_else(exit_with_error);
+    }
+
+    match &args.output {
+        Some(path) => fs::write(path, rendered).unwrap_or_else(|e| {
+            exit_with_error(format!(
+                "Could not save the report to {}: {}",
+                path.display(),
+                e
+            ))
+        }),
+        None if !args.email_to.is_empty() => {}
+        None => io::stdout()
+            .write_all(rendered.as_bytes())
+            .unwrap_or_else(|e| exit_with_error(format!("Could not write the report: {}", e))),
+    }
+}
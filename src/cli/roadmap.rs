@@ -0,0 +1,292 @@
+use std::collections::HashMap;
+
+use clap::Args;
+use colored::Colorize;
+use serde::Serialize;
+
+use crate::{
+    cli::{
+        connection::Connection,
+        exit_with_error,
+        output::{self, Format},
+        scope::Scope,
+    },
+    date::DateTime,
+    jira::{self, api::JiraApi, client::SearchIssue, jql::SortDirection},
+};
+
+#[derive(Debug, Args)]
+pub struct Roadmap {
+    /// The story points to count for issues that have none.
+    #[arg(long)]
+    #[arg(default_value_t = 3.0)]
+    pub default_story_points: f64,
+
+    /// csv or json, for a script to read.  Without this, the versions are shown as a table, with the ones projected to
+    /// miss their release date in red.
+    #[arg(long)]
+    pub format: Option<Format>,
+
+    #[arg(long)]
+    #[arg(default_value = "Story Points")]
+    pub jira_story_points_field: String,
+
+    #[arg(long)]
+    #[arg(default_value_t = 14)]
+    pub sprint_length_days: u32,
+
+    #[arg(long)]
+    pub velocity_in_story_points: f64,
+
+    #[command(flatten)]
+    pub scope: Scope,
+
+    #[command(flatten)]
+    pub connection: Connection,
+}
+
+/// A fix version, as found in the `"fixVersions"` field.
+struct Version {
+    name: String,
+    /// Versions are numbered as they are made, which orders the ones without a release date.
+    id: u64,
+    release_date: Option<DateTime>,
+}
+
+impl Version {
+    /// Versions go in the order they are due, with the ones that have no release date after, in the order they were
+    /// made.
+    fn order(&self) -> (bool, i64, u64) {
+        (
+            self.release_date.is_none(),
+            self.release_date
+                .map(DateTime::to_unix_seconds)
+                .unwrap_or_default(),
+            self.id,
+        )
+    }
+}
+
+/// How much is left in a version, and when it will be done at the team's velocity.
+#[derive(Debug, Serialize)]
+pub struct Release {
+    pub version: String,
+    pub release_date: String,
+    pub issues: u64,
+    pub issues_left: u64,
+    pub points_left: f64,
+    /// When the version's issues are projected to be done, or "done" when they are already.
+    pub projected_completion: String,
+    /// Whether the version is projected to be done after its release date.
+    pub late: bool,
+}
+
+fn versions(issue: &SearchIssue) -> Vec<Version> {
+    let versions = match issue
+        .fields
+        .get("fixVersions")
+        .and_then(|versions| versions.as_array())
+    {
+        Some(versions) => versions,
+        None => return vec![],
+    };
+
+    versions
+        .iter()
+        .filter_map(|version| {
+            Some(Version {
+                name: version.get("name")?.as_str()?.to_owned(),
+                id: version
+                    .get("id")
+                    .and_then(|id| id.as_str())
+                    .and_then(|id| id.parse().ok())
+                    .unwrap_or(u64::MAX),
+                release_date: version
+                    .get("releaseDate")
+                    .and_then(|date| date.as_str())
+                    .and_then(|date| date.parse().ok()),
+            })
+        })
+        .collect()
+}
+
+/// Adds up what is left in each version, and works through them in order at the team's velocity.
+///
+/// An issue in more than one version counts towards the first of them, since that is when it has to be done by.  Issues
+/// without a version go last, so that they don't push back the dates of the versions.
+///
+/// The issues need to have been fetched with the `"status"` and `"fixVersions"` fields, and the story point fields.
+pub fn roadmap(
+    issues: &[SearchIssue],
+    story_point_field_ids: &[String],
+    default_story_points: f64,
+    days_per_point: f64,
+) -> Vec<Release> {
+    let mut by_version: HashMap<Option<String>, (Option<Version>, Vec<&SearchIssue>)> =
+        HashMap::new();
+    for issue in issues {
+        let first = versions(issue)
+            .into_iter()
+            .min_by_key(|version| version.order());
+        by_version
+            .entry(first.as_ref().map(|version| version.name.clone()))
+            .or_insert_with(|| (first, vec![]))
+            .1
+            .push(issue);
+    }
+
+    let mut groups: Vec<(Option<Version>, Vec<&SearchIssue>)> = by_version.into_values().collect();
+    groups.sort_by_key(|(version, _)| {
+        (
+            version.is_none(),
+            version.as_ref().map(Version::order),
+            version.as_ref().map(|version| version.name.clone()),
+        )
+    });
+
+    let today = DateTime::now().start_of_day();
+    let mut days_so_far: f64 = 0.0;
+    groups
+        .into_iter()
+        .map(|(version, issues)| {
+            let left: Vec<&&SearchIssue> = issues
+                .iter()
+                .filter(|issue| issue.status_category().as_deref() != Some("Done"))
+                .collect();
+            // Like the burndown, a zero is treated as not having been pointed yet.
+            let points_left = left
+                .iter()
+                .map(|issue| {
+                    jira::story_points(issue, story_point_field_ids)
+                        .filter(|points| *points != 0.0)
+                        .unwrap_or(default_story_points)
+                })
+                .fold(0.0, |total, points| total + points);
+
+            let projected = if left.is_empty() {
+                None
+            } else {
+                days_so_far += points_left * days_per_point;
+                Some(today.plus_days(days_so_far.ceil() as i64))
+            };
+            let release_date = version.as_ref().and_then(|version| version.release_date);
+
+            Release {
+                version: version
+                    .map(|version| version.name)
+                    .unwrap_or_else(|| "No version".to_owned()),
+                release_date: release_date
+                    .map(DateTime::to_date_string)
+                    .unwrap_or_default(),
+                issues: issues.len() as u64,
+                issues_left: left.len() as u64,
+                points_left,
+                projected_completion: projected
+                    .map(DateTime::to_date_string)
+                    .unwrap_or_else(|| "done".to_owned()),
+                late: match (projected, release_date) {
+                    (Some(projected), Some(release_date)) => {
+                        projected.to_unix_seconds() > release_date.to_unix_seconds()
+                    }
+                    _ => false,
+                },
+            }
+        })
+        .collect()
+}
+
+async fn search(client: &impl JiraApi, args: &Roadmap) -> Result<Vec<Release>, String> {
+    let story_point_field_ids = jira::get_field_ids_named(client, &args.jira_story_points_field)
+        .await
+        .map_err(|e| format!("Could not get the story point fields: {}", e))?;
+    let mut fields = story_point_field_ids.clone();
+    fields.push("status".to_owned());
+    fields.push("fixVersions".to_owned());
+
+    let jql = args
+        .scope
+        .builder(client)
+        .await
+        .and_then(|builder| builder.order_by("key", SortDirection::Ascending).build())?;
+    if args.scope.validate_jql {
+        jira::validate_jql(client, &jql)
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+    let issues = client
+        .search_all(&fields, &jql)
+        .await
+        .map_err(|e| format!("Could not search for issues: {}", e))?;
+
+    Ok(roadmap(
+        &issues,
+        &story_point_field_ids,
+        args.default_story_points,
+        args.sprint_length_days as f64 / args.velocity_in_story_points,
+    ))
+}
+
+fn show(releases: &[Release]) {
+    let width = releases
+        .iter()
+        .map(|release| release.version.chars().count())
+        .max()
+        .unwrap_or(0)
+        .max("Version".len());
+    println!(
+        "{}",
+        format!(
+            "{:<width$}  {:>11}  {:>11}  {:<10}  {}",
+            "Version",
+            "Issues left",
+            "Points left",
+            "Projected",
+            "Release date",
+            width = width
+        )
+        .bold()
+    );
+
+    for release in releases {
+        let line = format!(
+            "{:<width$}  {:>11}  {:>11.1}  {:<10}  {}",
+            release.version,
+            format!("{} of {}", release.issues_left, release.issues),
+            release.points_left,
+            release.projected_completion,
+            release.release_date,
+            width = width
+        );
+        if release.late {
+            println!("{}", line.red().bold());
+        } else {
+            println!("{}", line);
+        }
+    }
+
+    let late = releases.iter().filter(|release| release.late).count();
+    if late > 0 {
+        println!(
+            "{} of {} versions are projected to be done after their release date.",
+            format!("{}", late).bright_red(),
+            releases.len()
+        );
+    }
+}
+
+pub async fn run(args: &Roadmap) {
+    if args.velocity_in_story_points <= 0.0 {
+        exit_with_error("--velocity-in-story-points has to be more than zero.")
+    }
+    if args.sprint_length_days == 0 {
+        exit_with_error("--sprint-length-days has to be more than zero.")
+    }
+
+    let client = args.connection.connect().unwrap_or_else(exit_with_error);
+    let releases = search(&client, args).await.unwrap_or_else(exit_with_error);
+
+    match args.format {
+        Some(format) => output::write_rows(format, &releases).unwrap_or_else(exit_with_error),
+        None => show(&releases),
+    }
+}
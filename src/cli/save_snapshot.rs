@@ -0,0 +1,72 @@
+use std::path::PathBuf;
+
+use clap::Args;
+
+use crate::{
+    cli::{connection::Connection, exit_with_error, scope::Scope},
+    jira::{
+        self,
+        api::JiraApi,
+        jql::{SerializableToJQL, SortDirection},
+    },
+    snapshot::Snapshot,
+};
+
+#[derive(Debug, Args)]
+pub struct SaveSnapshot {
+    /// Where to save the snapshot, like issues.snapshot.json.gz.  Anything already there is replaced.
+    #[arg(long)]
+    pub output: PathBuf,
+
+    #[command(flatten)]
+    pub scope: Scope,
+
+    #[command(flatten)]
+    pub connection: Connection,
+}
+
+/// Fetches every field of every issue the options pick out, along with their histories, so that anything a command
+/// could want from them is in the snapshot.
+async fn save_snapshot(client: &impl JiraApi, args: &SaveSnapshot) -> Result<usize, String> {
+    let jql = args
+        .scope
+        .builder(client)
+        .await
+        .and_then(|builder| builder.order_by("key", SortDirection::Ascending).build())?;
+
+    if args.scope.validate_jql {
+        jira::validate_jql(client, &jql)
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+
+    let fields = client
+        .get_fields()
+        .await
+        .map_err(|e| format!("Could not get the fields: {}", e))?;
+    let issues = client
+        .search_all_with_changelogs(&["*all".to_owned()], &jql)
+        .await
+        .map_err(|e| format!("Could not search for issues: {}", e))?;
+    let count = issues.len();
+
+    Snapshot::new(
+        &args.connection.jira_url,
+        jql.serialize_to_jql(),
+        fields,
+        issues,
+    )
+    .write(&args.output)
+    .map_err(|e| e.to_string())?;
+
+    Ok(count)
+}
+
+pub async fn run(args: &SaveSnapshot) {
+    let client = args.connection.connect().unwrap_or_else(exit_with_error);
+    let count = save_snapshot(&client, args)
+        .await
+        .unwrap_or_else(exit_with_error);
+
+    eprintln!("Saved {} issues to {}.", count, args.output.display());
+}
@@ -0,0 +1,124 @@
+/// The options shared by every command that searches JIRA for the issues to work on.
+use clap::Args;
+
+use crate::jira::{
+    self,
+    api::JiraApi,
+    jql::{
+        builder::{JQLBuilder, SprintFilter},
+        parser,
+    },
+};
+
+#[derive(Clone, Debug, Args)]
+pub struct Scope {
+    #[arg(long)]
+    pub exclude_issue_type: Vec<String>,
+
+    #[arg(long)]
+    pub exclude_label: Vec<String>,
+
+    #[arg(long)]
+    pub exclude_project: Vec<String>,
+
+    /// The account ID of someone to include the issues assigned to.
+    #[arg(long)]
+    pub jira_assignee: Vec<String>,
+
+    #[arg(long)]
+    pub jira_component: Vec<String>,
+
+    /// The key of an epic, like PROJ-100, to include the issues in.
+    #[arg(long)]
+    pub jira_epic: Vec<String>,
+
+    #[arg(long)]
+    pub jira_fix_version: Vec<String>,
+
+    #[arg(long)]
+    pub jira_issue_type: Vec<String>,
+
+    #[arg(long)]
+    pub jira_label: Vec<String>,
+
+    #[arg(long)]
+    pub jira_project: Vec<String>,
+
+    /// A sprint name or ID, or "open" or "future" for every open or future sprint.
+    #[arg(long)]
+    pub jira_sprint: Option<SprintFilter>,
+
+    #[arg(long)]
+    pub jql: Option<String>,
+
+    #[arg(long)]
+    #[arg(default_value_t = false)]
+    pub match_any: bool,
+
+    /// Only include issues updated since this time, like -2w or 2024-01-01.
+    #[arg(long)]
+    #[arg(allow_hyphen_values = true)]
+    pub updated_since: Option<String>,
+
+    /// Have JIRA check the search before running it, to get a clear explanation of anything wrong with it.
+    #[arg(long)]
+    #[arg(default_value_t = false)]
+    pub validate_jql: bool,
+}
+
+impl Scope {
+    /// Starts a search for the issues these options pick out, which the command can narrow down further.
+    pub async fn builder(&self, client: &impl JiraApi) -> Result<JQLBuilder, String> {
+        let mut builder = JQLBuilder::new().match_any(self.match_any);
+
+        for project in &self.jira_project {
+            builder = builder.project(project);
+        }
+        for label in &self.jira_label {
+            builder = builder.label(label);
+        }
+        for issue_type in &self.jira_issue_type {
+            builder = builder.issue_type(issue_type);
+        }
+        for account_id in &self.jira_assignee {
+            builder = builder.assignee(account_id);
+        }
+        for component in &self.jira_component {
+            builder = builder.component(component);
+        }
+        for fix_version in &self.jira_fix_version {
+            builder = builder.fix_version(fix_version);
+        }
+        for project in &self.exclude_project {
+            builder = builder.exclude_project(project);
+        }
+        for label in &self.exclude_label {
+            builder = builder.exclude_label(label);
+        }
+        for issue_type in &self.exclude_issue_type {
+            builder = builder.exclude_issue_type(issue_type);
+        }
+        for epic in &self.jira_epic {
+            builder = builder.epic(epic, jira::epic_relation(client, epic).await?);
+        }
+        if let Some(sprint) = &self.jira_sprint {
+            builder = builder.sprint(sprint.clone());
+        }
+
+        if let Some(since) = &self.updated_since {
+            let since = parser::parse_value(since).map_err(|e| {
+                format!(
+                    "--updated-since {} is not a time JQL understands.  {}",
+                    since, e
+                )
+            })?;
+            builder = builder.updated_since(since);
+        }
+
+        if let Some(jql) = &self.jql {
+            builder = builder.jql(parser::parse(jql).map_err(|e| e.to_string())?);
+        }
+
+        Ok(builder)
+    }
+}
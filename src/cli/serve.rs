@@ -0,0 +1,363 @@
+use std::{
+    io,
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use clap::Args;
+use http::StatusCode;
+use serde_json::json;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+};
+
+use crate::{
+    cli::{
+        connection::{self, Connection},
+        exit_with_error,
+        scope::Scope,
+    },
+    date::DateTime,
+    estimator::Estimator,
+    export::IssueExporter,
+    jira::{self, api::JiraApi, jql::SortDirection},
+};
+
+/// The most of a request that is read.  Every endpoint is a plain GET, so anything longer isn't meant for this.
+const MAX_REQUEST_SIZE: usize = 8 * 1024;
+
+/// How long a client gets to send its request, so that a stalled one doesn't hold a connection open forever.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Args)]
+pub struct Serve {
+    #[arg(long)]
+    #[arg(default_value_t = 3.0)]
+    pub default_story_points: f64,
+
+    #[arg(long)]
+    #[arg(default_value = "Story Points")]
+    pub jira_story_points_field: String,
+
+    /// The address to answer on.  Use 0.0.0.0:8080 to be reachable from other machines.
+    #[arg(long)]
+    #[arg(default_value = "127.0.0.1:8080")]
+    pub listen: SocketAddr,
+
+    /// How often to search JIRA again, like 5m or 1h.  Requests are answered from the last search in between.
+    #[arg(long)]
+    #[arg(default_value = "15m")]
+    #[arg(value_parser = connection::parse_duration)]
+    pub refresh_interval: Duration,
+
+    #[arg(long)]
+    pub velocity_in_story_points: f64,
+
+    #[command(flatten)]
+    pub scope: Scope,
+
+    #[command(flatten)]
+    pub connection: Connection,
+}
+
+/// What the last search found, ready to hand out.
+#[derive(Default)]
+struct State {
+    refreshed_at: Option<DateTime>,
+    /// Why the last search failed, if it did.  The results from the one before are still handed out.
+    last_error: Option<String>,
+    estimate: Option<String>,
+    csv: Option<String>,
+}
+
+/// Searches JIRA, and lays out what every endpoint hands out from it.  Returns the estimate as JSON and the issues as
+/// CSV.
+async fn refresh(client: &impl JiraApi, args: &Serve) -> Result<(String, String), String> {
+    let story_point_field_ids = jira::get_field_ids_named(client, &args.jira_story_points_field)
+        .await
+        .map_err(|e| format!("Could not get the story point fields: {}", e))?;
+    let exporter = IssueExporter::new(&args.connection.jira_url, story_point_field_ids.clone());
+
+    let jql = args
+        .scope
+        .builder(client)
+        .await
+        .and_then(|builder| builder.order_by("key", SortDirection::Ascending).build())?;
+    let issues = client
+        .search_all(&exporter.fields(), &jql)
+        .await
+        .map_err(|e| format!("Could not search for issues: {}", e))?;
+
+    let mut results = Estimator::new(args.velocity_in_story_points)
+        .default_story_points(args.default_story_points)
+        .results();
+    results.tally(&issues, &story_point_field_ids);
+    // Unwrap here is considered safe since the results are only numbers, which always serialize.
+    let estimate = serde_json::to_string(&results).unwrap();
+
+    let mut writer = csv::Writer::from_writer(vec![]);
+    exporter
+        .write_issues(&issues, &mut writer)
+        .map_err(|e| e.to_string())?;
+    let csv = writer
+        .into_inner()
+        .map_err(|e| format!("Could not write the rows: {}", e))
+        .map(|bytes| String::from_utf8_lossy(&bytes).into_owned())?;
+
+    Ok((estimate, csv))
+}
+
+fn error(status: StatusCode, message: &str) -> (StatusCode, &'static str, String) {
+    (
+        status,
+        "application/json",
+        json!({ "error": message }).to_string(),
+    )
+}
+
+/// Works out the status, content type, and body to answer a request with.
+fn respond(state: &State, method: &str, path: &str) -> (StatusCode, &'static str, String) {
+    if method != "GET" {
+        return error(
+            StatusCode::METHOD_NOT_ALLOWED,
+            "Only GET requests are answered.",
+        );
+    }
+
+    let not_ready = || {
+        error(
+            StatusCode::SERVICE_UNAVAILABLE,
+            state
+                .last_error
+                .as_deref()
+                .unwrap_or("The first search of JIRA hasn't finished yet."),
+        )
+    };
+
+    match path {
+        "/healthz" => {
+            let status = if state.estimate.is_some() {
+                StatusCode::OK
+            } else {
+                StatusCode::SERVICE_UNAVAILABLE
+            };
+            let body = json!({
+                "refreshed_at": state.refreshed_at.map(DateTime::to_rfc3339),
+                "last_error": state.last_error,
+            });
+            (status, "application/json", body.to_string())
+        }
+        "/estimate" => match &state.estimate {
+            Some(estimate) => (StatusCode::OK, "application/json", estimate.clone()),
+            None => not_ready(),
+        },
+        "/csv" => match &state.csv {
+            Some(csv) => (StatusCode::OK, "text/csv; charset=utf-8", csv.clone()),
+            None => not_ready(),
+        },
+        _ => error(
+            StatusCode::NOT_FOUND,
+            "There is nothing here.  Try /estimate, /csv, or /healthz.",
+        ),
+    }
+}
+
+/// Reads a request and answers it.  Only the request line is looked at, since none of the endpoints take anything else.
+async fn handle(mut stream: TcpStream, state: &Mutex<State>) -> io::Result<()> {
+    let mut request = vec![];
+    let mut buffer = [0; 1024];
+    while !request.windows(4).any(|window| window == b"\r\n\r\n")
+        && request.len() < MAX_REQUEST_SIZE
+    {
+        let read = stream.read(&mut buffer).await?;
+        if read == 0 {
+            break;
+        }
+        request.extend_from_slice(&buffer[..read]);
+    }
+
+    let request = String::from_utf8_lossy(&request);
+    let mut request_line = request
+        .lines()
+        .next()
+        .unwrap_or_default()
+        .split_whitespace();
+    let method = request_line.next().unwrap_or_default();
+    let path = request_line
+        .next()
+        .unwrap_or_default()
+        .split('?')
+        .next()
+        .unwrap_or_default();
+
+    // Unwrap here is considered safe since the lock is never held while something could panic.
+    let (status, content_type, body) = respond(&state.lock().unwrap(), method, path);
+    let response = format!(
+        "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status.as_u16(),
+        status.canonical_reason().unwrap_or_default(),
+        content_type,
+        body.len(),
+        body
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await
+}
+
+async fn listen(listener: TcpListener, state: Arc<Mutex<State>>) {
+    loop {
+        let stream = match listener.accept().await {
+            Ok((stream, _)) => stream,
+            Err(e) => {
+                eprintln!("Could not accept a connection: {}", e);
+                continue;
+            }
+        };
+
+        let state = state.clone();
+        tokio::spawn(async move {
+            // A client that goes away or stalls only loses its own answer.
+            let _ = tokio::time::timeout(REQUEST_TIMEOUT, handle(stream, &state)).await;
+        });
+    }
+}
+
+pub async fn run(args: &Serve) {
+    if args.refresh_interval.is_zero() {
+        exit_with_error("--refresh-interval has to be longer than zero.")
+    }
+
+    let client = args.connection.connect().unwrap_or_else(exit_with_error);
+    let listener = TcpListener::bind(args.listen)
+        .await
+        .unwrap_or_else(|e| exit_with_error(format!("Could not listen on {}: {}", args.listen, e)));
+    eprintln!("Listening on http://{}", args.listen);
+
+    let state = Arc::new(Mutex::new(State::default()));
+    tokio::spawn(listen(listener, state.clone()));
+
+    loop {
+        let refreshed = refresh(&client, args).await;
+        {
+            let mut state = state.lock().unwrap();
+            match refreshed {
+                Ok((estimate, csv)) => {
+                    state.refreshed_at = Some(DateTime::now());
+                    state.last_error = None;
+                    state.estimate = Some(estimate);
+                    state.csv = Some(csv);
+                }
+                Err(e) => {
+                    eprintln!("{}", e);
+                    state.last_error = Some(e);
+                }
+            }
+        }
+
+        tokio::time::sleep(args.refresh_interval).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures_util::future;
+
+    use super::*;
+    use crate::jira::api::fake::block_on;
+
+    fn refreshed() -> State {
+        State {
+            refreshed_at: Some(DateTime::now()),
+            last_error: None,
+            estimate: Some(r#"{"num_sprints_remaining":2.0}"#.to_owned()),
+            csv: Some("ID,Story Points,Status,Link\n".to_owned()),
+        }
+    }
+
+    #[test]
+    fn nothing_is_handed_out_before_the_first_search() {
+        let state = State::default();
+
+        let (status, _, body) = respond(&state, "GET", "/estimate");
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert!(body.contains("hasn't finished yet"));
+        assert_eq!(
+            respond(&state, "GET", "/healthz").0,
+            StatusCode::SERVICE_UNAVAILABLE
+        );
+
+        let failed = State {
+            last_error: Some("Could not search for issues: offline".to_owned()),
+            ..State::default()
+        };
+        let (status, _, body) = respond(&failed, "GET", "/csv");
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert!(body.contains("Could not search for issues"));
+    }
+
+    #[test]
+    fn each_endpoint_hands_out_the_last_search() {
+        let state = refreshed();
+
+        assert_eq!(
+            respond(&state, "GET", "/estimate"),
+            (
+                StatusCode::OK,
+                "application/json",
+                state.estimate.clone().unwrap()
+            )
+        );
+        assert_eq!(
+            respond(&state, "GET", "/csv"),
+            (
+                StatusCode::OK,
+                "text/csv; charset=utf-8",
+                state.csv.clone().unwrap()
+            )
+        );
+        assert_eq!(respond(&state, "GET", "/healthz").0, StatusCode::OK);
+        assert_eq!(respond(&state, "GET", "/").0, StatusCode::NOT_FOUND);
+        assert_eq!(
+            respond(&state, "POST", "/estimate").0,
+            StatusCode::METHOD_NOT_ALLOWED
+        );
+    }
+
+    #[test]
+    fn requests_are_answered_over_http() {
+        let state = Mutex::new(refreshed());
+
+        let response = block_on(async {
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let address = listener.local_addr().unwrap();
+            let server = async {
+                let (stream, _) = listener.accept().await.unwrap();
+                handle(stream, &state).await.unwrap();
+            };
+            let client = async {
+                let mut stream = TcpStream::connect(address).await.unwrap();
+                stream
+                    .write_all(b"GET /estimate?fresh=1 HTTP/1.1\r\nHost: localhost\r\n\r\n")
+                    .await
+                    .unwrap();
+                let mut response = String::new();
+                stream.read_to_string(&mut response).await.unwrap();
+                response
+            };
+
+            future::join(server, client).await.1
+        });
+
+        let estimate = r#"{"num_sprints_remaining":2.0}"#;
+        assert_eq!(
+            response,
+            format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                estimate.len(),
+                estimate
+            )
+        );
+    }
+}
@@ -0,0 +1,197 @@
+use std::{process, str::FromStr};
+
+use clap::Args;
+use colored::Colorize;
+use serde::Serialize;
+
+use crate::{
+    cli::{
+        connection::Connection,
+        exit_with_error,
+        output::{self, Format},
+        scope::Scope,
+    },
+    date::DateTime,
+    jira::{self, api::JiraApi, history, jql::SortDirection, AssigneeNames},
+};
+
+const SECONDS_PER_DAY: f64 = 24.0 * 60.0 * 60.0;
+
+/// The most days an issue can sit in a status, like "In Review=5".
+#[derive(Clone, Debug)]
+pub struct StatusLimit {
+    pub status: String,
+    pub max_days: f64,
+}
+
+impl FromStr for StatusLimit {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (status, max_days) = s
+            .rsplit_once('=')
+            .ok_or_else(|| format!("\"{}\" should look like \"In Review=5\"", s))?;
+        let max_days: f64 = max_days
+            .trim()
+            .parse()
+            .map_err(|_| format!("\"{}\" is not a number of days", max_days))?;
+        if max_days < 0.0 {
+            return Err(format!("\"{}\" can't be a negative number of days", s));
+        }
+
+        Ok(StatusLimit {
+            status: status.trim().to_owned(),
+            max_days,
+        })
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct Sla {
+    /// csv or json, for a script to read.  Without this, the issues over their limits are shown under whoever they're
+    /// assigned to.  Either way, the command fails if any issue is over its limit.
+    #[arg(long)]
+    pub format: Option<Format>,
+
+    /// The most days an unfinished issue can sit in a status, like "In Review=5".  Can be given once for each status.
+    /// Statuses without a limit are never over it.
+    #[arg(long)]
+    #[arg(required = true)]
+    pub max_days: Vec<StatusLimit>,
+
+    #[command(flatten)]
+    pub scope: Scope,
+
+    #[command(flatten)]
+    pub connection: Connection,
+}
+
+/// An unfinished issue that has sat in its status for longer than the status allows.
+#[derive(Debug, Serialize)]
+struct Breach {
+    assignee: String,
+    key: String,
+    status: String,
+    days_in_status: f64,
+    max_days: f64,
+    link: String,
+}
+
+/// Finds the unfinished issues over the limit for their status, grouped by assignee, and longest over first.
+async fn breaches(client: &impl JiraApi, args: &Sla) -> Result<Vec<Breach>, String> {
+    // Statuses can't be searched on by name without knowing they exist in every project, so every unfinished issue is
+    // fetched and its status checked here.
+    let jql = args.scope.builder(client).await.and_then(|builder| {
+        builder
+            .status_category("To Do")
+            .status_category("In Progress")
+            .order_by("key", SortDirection::Ascending)
+            .build()
+    })?;
+    if args.scope.validate_jql {
+        jira::validate_jql(client, &jql)
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+
+    let fields = vec![
+        "status".to_owned(),
+        "assignee".to_owned(),
+        "created".to_owned(),
+    ];
+    let issues = client
+        .search_all_with_changelogs(&fields, &jql)
+        .await
+        .map_err(|e| format!("Could not search for issues: {}", e))?;
+
+    let now = DateTime::now().to_unix_seconds();
+    let assignee_names = AssigneeNames::new();
+    let mut breaches = vec![];
+    for issue in &issues {
+        let status = issue.status().unwrap_or_default();
+        let limit = match args
+            .max_days
+            .iter()
+            .find(|limit| limit.status.eq_ignore_ascii_case(&status))
+        {
+            Some(limit) => limit,
+            None => continue,
+        };
+        let days_in_status = history::entered_status_at(issue)
+            .map(|entered_at| (now - entered_at.to_unix_seconds()) as f64 / SECONDS_PER_DAY)
+            .unwrap_or(0.0);
+        if days_in_status <= limit.max_days {
+            continue;
+        }
+
+        breaches.push(Breach {
+            assignee: assignee_names
+                .assignee(client, issue)
+                .await
+                .unwrap_or_else(|| "Unassigned".to_owned()),
+            key: issue.key.clone(),
+            status,
+            days_in_status: (days_in_status * 10.0).round() / 10.0,
+            max_days: limit.max_days,
+            link: format!("{}/browse/{}", args.connection.jira_url, issue.key),
+        });
+    }
+
+    breaches.sort_by(|a, b| {
+        a.assignee
+            .cmp(&b.assignee)
+            .then_with(|| b.days_in_status.total_cmp(&a.days_in_status))
+    });
+
+    Ok(breaches)
+}
+
+fn show(breaches: &[Breach]) {
+    let mut assignee = None;
+    for breach in breaches {
+        if assignee != Some(&breach.assignee) {
+            if assignee.is_some() {
+                println!();
+            }
+            assignee = Some(&breach.assignee);
+            println!("{}", breach.assignee.bold());
+        }
+
+        println!(
+            "  {:>7}  {:<12} {:<20} {}",
+            format!("{:.1}d", breach.days_in_status).red(),
+            breach.key,
+            breach.status,
+            format!("over {} days", breach.max_days).red()
+        );
+    }
+
+    if breaches.is_empty() {
+        println!(
+            "{}",
+            "Every issue is within its status's limit.".bright_green()
+        );
+    } else {
+        println!();
+        println!(
+            "{} issues have been in their status for longer than it allows.",
+            format!("{}", breaches.len()).bright_red()
+        );
+    }
+}
+
+pub async fn run(args: &Sla) {
+    let client = args.connection.connect().unwrap_or_else(exit_with_error);
+    let breaches = breaches(&client, args)
+        .await
+        .unwrap_or_else(exit_with_error);
+
+    match args.format {
+        Some(format) => output::write_rows(format, &breaches).unwrap_or_else(exit_with_error),
+        None => show(&breaches),
+    }
+
+    if !breaches.is_empty() {
+        process::exit(1);
+    }
+}
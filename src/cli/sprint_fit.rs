@@ -0,0 +1,174 @@
+use clap::Args;
+use colored::Colorize;
+
+use crate::{
+    cli::{connection::Connection, exit_with_error},
+    jira::{
+        self,
+        api::JiraApi,
+        client::Sprint,
+        jql::{JQLClause, JQLStatement, JQLValue, SortDirection},
+    },
+};
+
+#[derive(Debug, Args)]
+pub struct SprintFit {
+    /// The story points to count for issues that have none.
+    #[arg(long)]
+    #[arg(default_value_t = 3.0)]
+    pub default_story_points: f64,
+
+    /// The board the sprint is on.  Needed unless --sprint is a sprint ID.
+    #[arg(long)]
+    #[arg(alias = "board")]
+    pub jira_board: Option<u64>,
+
+    #[arg(long)]
+    #[arg(default_value = "Story Points")]
+    pub jira_story_points_field: String,
+
+    /// A sprint ID or name, "active" for the board's active sprint, or "next" for the first of its future sprints.
+    #[arg(long)]
+    #[arg(default_value = "next")]
+    pub sprint: String,
+
+    /// How many story points the team gets done in a sprint.
+    #[arg(long)]
+    pub velocity_in_story_points: f64,
+
+    #[command(flatten)]
+    pub connection: Connection,
+}
+
+async fn find_sprint(client: &impl JiraApi, args: &SprintFit) -> Result<Sprint, String> {
+    let board_id = || {
+        args.jira_board.ok_or_else(|| {
+            format!(
+                "--sprint {} needs --jira-board, to know which board's sprints to look at.",
+                args.sprint
+            )
+        })
+    };
+
+    if args.sprint.eq_ignore_ascii_case("active") {
+        return jira::active_sprint(client, board_id()?)
+            .await
+            .map_err(|e| e.to_string());
+    }
+
+    if args.sprint.eq_ignore_ascii_case("next") {
+        let board_id = board_id()?;
+        // The Agile API lists sprints in the order they are planned to run in.
+        return client
+            .get_sprints(board_id, "future")
+            .await
+            .map_err(|e| jira::SprintResolutionError::ProblemListingSprints(e).to_string())?
+            .into_iter()
+            .next()
+            .ok_or_else(|| format!("Board {} doesn't have any future sprints.", board_id));
+    }
+
+    let sprint_id =
+        jira::resolve_sprint_id(client, args.jira_board, None, &args.sprint, "active,future")
+            .await
+            .map_err(|e| e.to_string())?;
+
+    client
+        .get_sprint(sprint_id)
+        .await
+        .map_err(|e| jira::SprintResolutionError::ProblemGettingSprint(sprint_id, e).to_string())
+}
+
+/// Works out whether the work left in a sprint fits the team's velocity, and if not, which issues from the bottom of
+/// the sprint would have to go for it to.
+async fn fit(client: &impl JiraApi, args: &SprintFit, sprint: &Sprint) -> Result<(), String> {
+    if args.velocity_in_story_points <= 0.0 {
+        return Err("--velocity-in-story-points has to be greater than zero.".to_owned());
+    }
+
+    let field_ids = jira::get_field_ids_named(client, &args.jira_story_points_field)
+        .await
+        .map_err(|e| format!("Could not get the story point fields: {}", e))?;
+    let mut fields = field_ids.clone();
+    fields.push("status".to_owned());
+
+    // Ranked, so that the issues the team cares least about come last.
+    let jql = JQLStatement {
+        clause: JQLClause::Equals("sprint".to_owned(), JQLValue::Integer(sprint.id as i64)),
+        order_by: vec![("Rank".to_owned(), SortDirection::Ascending)],
+    };
+    let issues = client
+        .search_all(&fields, &jql)
+        .await
+        .map_err(|e| format!("Could not search for issues: {}", e))?;
+
+    // Like estimating, finished issues don't count, and a zero is treated as not having been pointed yet.
+    let planned: Vec<(String, f64)> = issues
+        .iter()
+        .filter(|issue| issue.status_category().as_deref() != Some("Done"))
+        .map(|issue| {
+            let points = jira::story_points(issue, &field_ids)
+                .filter(|points| *points != 0.0)
+                .unwrap_or(args.default_story_points);
+            (issue.key.clone(), points)
+        })
+        .collect();
+    let total = planned
+        .iter()
+        .fold(0.0, |total, (_, points)| total + points);
+
+    println!(
+        "{} has {} points of work left in {} issues, against a velocity of {} points.",
+        sprint.name.bold(),
+        format!("{:.1}", total).bright_yellow(),
+        planned.len(),
+        format!("{:.1}", args.velocity_in_story_points).magenta()
+    );
+
+    if total <= args.velocity_in_story_points {
+        println!(
+            "It {}, with {} points to spare.",
+            "fits".bright_green(),
+            format!("{:.1}", args.velocity_in_story_points - total).green()
+        );
+        return Ok(());
+    }
+
+    println!(
+        "It is {} by {} points.",
+        "over".bright_red(),
+        format!("{:.1}", total - args.velocity_in_story_points).red()
+    );
+
+    let mut remaining = total;
+    let mut dropped = vec![];
+    for (key, points) in planned.iter().rev() {
+        if remaining <= args.velocity_in_story_points {
+            break;
+        }
+        remaining -= points;
+        dropped.push((key, points));
+    }
+
+    println!(
+        "Dropping the {} lowest ranked issues would bring it down to {} points:",
+        dropped.len(),
+        format!("{:.1}", remaining).bright_green()
+    );
+    for (key, points) in dropped {
+        println!("  {:<12} {:>5.1}", key, points);
+    }
+
+    Ok(())
+}
+
+pub async fn run(args: &SprintFit) {
+    let client = args.connection.connect().unwrap_or_else(exit_with_error);
+    let sprint = find_sprint(&client, args)
+        .await
+        .unwrap_or_else(exit_with_error);
+
+    fit(&client, args, &sprint)
+        .await
+        .unwrap_or_else(exit_with_error);
+}
@@ -0,0 +1,277 @@
+use clap::Args;
+use colored::Colorize;
+use serde::Serialize;
+
+use crate::{
+    cli::{
+        connection::Connection,
+        exit_with_error,
+        output::{self, Format},
+    },
+    date::DateTime,
+    jira::{
+        self,
+        api::JiraApi,
+        client::Sprint,
+        history,
+        jql::{JQLClause, JQLStatement, JQLValue, SortDirection},
+    },
+};
+
+#[derive(Debug, Args)]
+pub struct SprintReport {
+    /// The story points to count for issues that have none.
+    #[arg(long)]
+    #[arg(default_value_t = 3.0)]
+    pub default_story_points: f64,
+
+    /// csv or json, for a script to read, with a row for each issue.  Without this, the report is written out for
+    /// people to read.
+    #[arg(long)]
+    pub format: Option<Format>,
+
+    /// The board the sprint is on.  Needed to look a sprint up by name or to find the active one, and to find issues
+    /// that were taken out of the sprint.
+    #[arg(long)]
+    #[arg(alias = "board")]
+    pub jira_board: Option<u64>,
+
+    #[arg(long)]
+    #[arg(default_value = "Story Points")]
+    pub jira_story_points_field: String,
+
+    /// A sprint ID or name, or "active" for the board's active sprint.
+    #[arg(long)]
+    #[arg(default_value = "active")]
+    pub sprint: String,
+
+    #[command(flatten)]
+    pub connection: Connection,
+}
+
+/// What happened to an issue over the sprint.
+#[derive(Debug, Serialize)]
+struct SprintIssue {
+    key: String,
+    /// In the sprint when it started.
+    committed: bool,
+    /// Put in the sprint after it started.
+    added: bool,
+    /// Taken out of the sprint before it ended.
+    removed: bool,
+    /// Done by the end of the sprint.
+    completed: bool,
+    /// In the sprint at the end without being done, so left to carry over into the next one.
+    carried_over: bool,
+    /// As of the end of the sprint, or when the issue was taken out of it.
+    points: f64,
+}
+
+async fn find_sprint(client: &impl JiraApi, args: &SprintReport) -> Result<Sprint, String> {
+    if args.sprint.eq_ignore_ascii_case("active") {
+        let board_id = args.jira_board.ok_or_else(|| {
+            "--sprint active needs --jira-board, to know which board's active sprint to use."
+                .to_owned()
+        })?;
+        return jira::active_sprint(client, board_id)
+            .await
+            .map_err(|e| e.to_string());
+    }
+
+    let sprint_id =
+        jira::resolve_sprint_id(client, args.jira_board, None, &args.sprint, "active,closed")
+            .await
+            .map_err(|e| e.to_string())?;
+
+    client
+        .get_sprint(sprint_id)
+        .await
+        .map_err(|e| jira::SprintResolutionError::ProblemGettingSprint(sprint_id, e).to_string())
+}
+
+/// Works out what happened to every issue that was in the sprint at any point, by winding their sprints, statuses, and
+/// story points back to the start and end of the sprint.
+async fn report(
+    client: &impl JiraApi,
+    args: &SprintReport,
+    sprint: &Sprint,
+) -> Result<Vec<SprintIssue>, String> {
+    let started_at = sprint.started_at().ok_or_else(|| {
+        format!(
+            "{} hasn't started yet, so there's nothing to report.",
+            sprint.name
+        )
+    })?;
+    let ended_at = sprint.completed_at().unwrap_or_else(DateTime::now);
+
+    let sprint_field_ids = jira::get_field_ids_named(client, "Sprint")
+        .await
+        .map_err(|e| format!("Could not get the sprint field: {}", e))?;
+    let story_point_field_ids = jira::get_field_ids_named(client, &args.jira_story_points_field)
+        .await
+        .map_err(|e| format!("Could not get the story point fields: {}", e))?;
+    let mut fields = sprint_field_ids.clone();
+    fields.extend(story_point_field_ids.iter().cloned());
+    fields.push("status".to_owned());
+    fields.push("created".to_owned());
+
+    // Issues taken out of the sprint aren't in it anymore, but they are still on the board, and taking them out updated
+    // them.
+    let mut clause = JQLClause::Equals("sprint".to_owned(), JQLValue::Integer(sprint.id as i64));
+    if let Some(board_id) = args.jira_board.or(sprint.origin_board_id) {
+        let filter_id = client
+            .get_board_filter_id(board_id)
+            .await
+            .map_err(|e| format!("Could not get the filter of board {}: {}", board_id, e))?;
+        clause = JQLClause::Or(vec![
+            clause,
+            JQLClause::And(vec![
+                JQLClause::Equals("filter".to_owned(), JQLValue::Integer(filter_id as i64)),
+                JQLClause::GreaterThanOrEqual(
+                    "updated".to_owned(),
+                    JQLValue::Date {
+                        year: started_at.year,
+                        month: started_at.month,
+                        day: started_at.day,
+                    },
+                ),
+            ]),
+        ]);
+    }
+    let jql = JQLStatement {
+        clause,
+        order_by: vec![("key".to_owned(), SortDirection::Ascending)],
+    };
+
+    let categories = history::status_categories(client)
+        .await
+        .map_err(|e| format!("Could not get the statuses: {}", e))?;
+    let issues = client
+        .search_all_with_changelogs(&fields, &jql)
+        .await
+        .map_err(|e| format!("Could not search for issues: {}", e))?;
+
+    let mut report = vec![];
+    for issue in &issues {
+        let in_sprint_at = |at: DateTime| {
+            history::existed_at(issue, at)
+                && history::sprint_ids_at(issue, &sprint_field_ids, at).contains(&sprint.id)
+        };
+        let committed = in_sprint_at(started_at);
+        let in_sprint_at_end = in_sprint_at(ended_at);
+
+        // Anything that came up in the search only because it changed on the board may never have been in the sprint.
+        let sprint_changes = issue
+            .changelog
+            .iter()
+            .flat_map(|changelog| changelog.histories.iter())
+            .filter_map(|entry| entry.created_at())
+            .filter(|at| {
+                at.to_unix_seconds() > started_at.to_unix_seconds()
+                    && at.to_unix_seconds() <= ended_at.to_unix_seconds()
+            });
+        let ever_in_sprint =
+            committed || in_sprint_at_end || sprint_changes.clone().any(&in_sprint_at);
+        if !ever_in_sprint {
+            continue;
+        }
+
+        // Issues taken out are counted as they were when they left.
+        let counted_at = if in_sprint_at_end {
+            ended_at
+        } else {
+            sprint_changes
+                .rev()
+                .find(|at| in_sprint_at(*at))
+                .unwrap_or(started_at)
+        };
+        let points = history::story_points_at(
+            issue,
+            &story_point_field_ids,
+            &args.jira_story_points_field,
+            counted_at,
+        )
+        .filter(|points| *points != 0.0)
+        .unwrap_or(args.default_story_points);
+        let done =
+            history::status_category_at(issue, &categories, ended_at).as_deref() == Some("Done");
+
+        report.push(SprintIssue {
+            key: issue.key.clone(),
+            committed,
+            added: !committed,
+            removed: !in_sprint_at_end,
+            completed: in_sprint_at_end && done,
+            carried_over: in_sprint_at_end && !done,
+            points,
+        });
+    }
+
+    Ok(report)
+}
+
+fn show(sprint: &Sprint, report: &[SprintIssue]) {
+    let dates = |at: Option<DateTime>| {
+        at.map(DateTime::to_date_string)
+            .unwrap_or_else(|| "?".to_owned())
+    };
+    println!(
+        "{} ({}), {} to {}",
+        sprint.name.bold(),
+        sprint.state,
+        dates(sprint.started_at()),
+        dates(sprint.completed_at().or_else(|| sprint.ends_at()))
+    );
+
+    let section = |name: &str, matches: &dyn Fn(&SprintIssue) -> bool| {
+        let issues: Vec<&SprintIssue> = report.iter().filter(|issue| matches(issue)).collect();
+        let points = issues
+            .iter()
+            .fold(0.0, |points, issue| points + issue.points);
+        let keys: Vec<&str> = issues.iter().map(|issue| issue.key.as_str()).collect();
+        println!(
+            "{:<13} {:>3} issues, {:>5} points  {}",
+            format!("{}:", name),
+            issues.len(),
+            format!("{:.1}", points),
+            keys.join(", ").dimmed()
+        );
+        points
+    };
+
+    let committed = section("Committed", &|issue| issue.committed);
+    section("Added", &|issue| issue.added);
+    section("Removed", &|issue| issue.removed);
+    let completed = section("Completed", &|issue| issue.completed);
+    // Issues only carry over once the sprint is closed.  Until then, they may still get done.
+    let unfinished = if sprint.state == "closed" {
+        "Carried over"
+    } else {
+        "Not done yet"
+    };
+    section(unfinished, &|issue| issue.carried_over);
+
+    if committed > 0.0 {
+        println!(
+            "Completed {:.1} points against the {:.1} committed to, or {}.",
+            completed,
+            committed,
+            format!("{:.0}%", completed / committed * 100.0).bright_green()
+        );
+    }
+}
+
+pub async fn run(args: &SprintReport) {
+    let client = args.connection.connect().unwrap_or_else(exit_with_error);
+    let sprint = find_sprint(&client, args)
+        .await
+        .unwrap_or_else(exit_with_error);
+    let report = report(&client, args, &sprint)
+        .await
+        .unwrap_or_else(exit_with_error);
+
+    match args.format {
+        Some(format) => output::write_rows(format, &report).unwrap_or_else(exit_with_error),
+        None => show(&sprint, &report),
+    }
+}
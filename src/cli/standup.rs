@@ -0,0 +1,229 @@
+use std::collections::BTreeMap;
+
+use clap::Args;
+use colored::Colorize;
+use serde::Serialize;
+
+use crate::{
+    cli::{
+        connection::Connection,
+        exit_with_error,
+        output::{self, Format},
+        scope::Scope,
+    },
+    date::DateTime,
+    jira::{
+        self,
+        api::JiraApi,
+        client::SearchIssue,
+        jql::{DurationUnit, JQLValue, SortDirection},
+        AssigneeNames,
+    },
+};
+
+/// How much of a comment to show, so that each change fits on a line.
+const MAX_EXCERPT_LENGTH: usize = 60;
+
+#[derive(Debug, Args)]
+pub struct Standup {
+    /// csv or json, for a script to read, with a row for each change.  Without this, the changes are listed under
+    /// each person, ready to paste into a chat.
+    #[arg(long)]
+    pub format: Option<Format>,
+
+    /// Show what changed in this many hours, up to now.
+    #[arg(long)]
+    #[arg(default_value_t = 24)]
+    pub lookback_hours: u32,
+
+    #[command(flatten)]
+    pub scope: Scope,
+
+    #[command(flatten)]
+    pub connection: Connection,
+}
+
+/// Something that happened to an issue.
+#[derive(Debug, Serialize)]
+struct Change {
+    /// Who the issue is assigned to now, which is who is expected to speak to it.
+    assignee: String,
+    key: String,
+    summary: String,
+    at: String,
+    change: String,
+}
+
+fn field_is(item_field: &str, item_field_id: Option<&str>, name: &str) -> bool {
+    item_field_id
+        .unwrap_or(item_field)
+        .eq_ignore_ascii_case(name)
+}
+
+/// The status changes, reassignments, and comments on an issue since a time, oldest first.
+fn changes_since(issue: &SearchIssue, since: DateTime) -> Vec<(DateTime, String)> {
+    let after_since = |at: &DateTime| at.to_unix_seconds() >= since.to_unix_seconds();
+    let mut changes = vec![];
+
+    for entry in issue
+        .changelog
+        .iter()
+        .flat_map(|changelog| changelog.histories.iter())
+    {
+        let at = match entry.created_at().filter(after_since) {
+            Some(at) => at,
+            None => continue,
+        };
+
+        for item in &entry.items {
+            let (from, to) = (
+                item.from_string.as_deref().unwrap_or_default(),
+                item.to_string.as_deref().unwrap_or_default(),
+            );
+
+            if field_is(&item.field, item.field_id.as_deref(), "status") {
+                changes.push((at, format!("moved from {} to {}", from, to)));
+            } else if field_is(&item.field, item.field_id.as_deref(), "assignee") {
+                let name = |name: &str| {
+                    if name.is_empty() {
+                        "nobody".to_owned()
+                    } else {
+                        name.to_owned()
+                    }
+                };
+                changes.push((
+                    at,
+                    format!("reassigned from {} to {}", name(from), name(to)),
+                ));
+            }
+        }
+    }
+
+    for comment in issue.comments() {
+        if let Some(at) = comment.created_at.filter(after_since) {
+            let author = comment.author.unwrap_or_else(|| "Anonymous".to_owned());
+            // The first line is usually enough to tell what the comment is about.
+            let first_line = comment
+                .body
+                .lines()
+                .map(str::trim)
+                .find(|line| !line.is_empty())
+                .unwrap_or_default();
+            let mut excerpt: String = first_line.chars().take(MAX_EXCERPT_LENGTH).collect();
+            if excerpt.len() < first_line.len() {
+                excerpt.push('…');
+            }
+            changes.push((at, format!("commented on by {}: \"{}\"", author, excerpt)));
+        }
+    }
+
+    changes.sort_by_key(|(at, _)| at.to_unix_seconds());
+    changes
+}
+
+async fn standup(client: &impl JiraApi, args: &Standup) -> Result<Vec<Change>, String> {
+    if args.lookback_hours == 0 {
+        return Err("--lookback-hours has to be greater than zero.".to_owned());
+    }
+
+    let since = DateTime::from_unix_seconds(
+        (DateTime::now().to_unix_seconds() - args.lookback_hours as i64 * 60 * 60) as u64,
+    );
+
+    // Every change of interest updates the issue, so nothing else could have any.
+    let jql = args.scope.builder(client).await.and_then(|builder| {
+        let builder = if args.scope.updated_since.is_some() {
+            builder
+        } else {
+            builder.updated_since(JQLValue::RelativeDuration(
+                -(args.lookback_hours as i64),
+                DurationUnit::Hours,
+            ))
+        };
+        builder.order_by("key", SortDirection::Ascending).build()
+    })?;
+
+    if args.scope.validate_jql {
+        jira::validate_jql(client, &jql)
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+
+    let fields = vec![
+        "summary".to_owned(),
+        "assignee".to_owned(),
+        "comment".to_owned(),
+    ];
+    let issues = client
+        .search_all_with_changelogs(&fields, &jql)
+        .await
+        .map_err(|e| format!("Could not search for issues: {}", e))?;
+
+    let assignee_names = AssigneeNames::new();
+    let mut standup = vec![];
+    for issue in &issues {
+        let changes = changes_since(issue, since);
+        if changes.is_empty() {
+            continue;
+        }
+
+        let assignee = assignee_names
+            .assignee(client, issue)
+            .await
+            .unwrap_or_else(|| "Unassigned".to_owned());
+        let summary = issue
+            .fields
+            .get("summary")
+            .and_then(|summary| summary.as_str())
+            .unwrap_or_default();
+        for (at, change) in changes {
+            standup.push(Change {
+                assignee: assignee.clone(),
+                key: issue.key.clone(),
+                summary: summary.to_owned(),
+                at: at.to_rfc3339(),
+                change,
+            });
+        }
+    }
+
+    Ok(standup)
+}
+
+fn show(args: &Standup, standup: &[Change]) {
+    if standup.is_empty() {
+        println!("Nothing changed in the last {} hours.", args.lookback_hours);
+        return;
+    }
+
+    // Changes are already in order within each issue, and issues in order of key.
+    let mut by_assignee: BTreeMap<&str, Vec<&Change>> = BTreeMap::new();
+    for change in standup {
+        by_assignee
+            .entry(change.assignee.as_str())
+            .or_default()
+            .push(change);
+    }
+
+    for (assignee, changes) in by_assignee {
+        println!("{}", assignee.bold());
+        let mut last_key = None;
+        for change in changes {
+            if last_key != Some(&change.key) {
+                println!("  {} {}", change.key.bright_blue(), change.summary);
+                last_key = Some(&change.key);
+            }
+            println!("    - {}", change.change);
+        }
+    }
+}
+
+pub async fn run(args: &Standup) {
+    let client = args.connection.connect().unwrap_or_else(exit_with_error);
+    let standup = standup(&client, args).await.unwrap_or_else(exit_with_error);
+
+    match args.format {
+        Some(format) => output::write_rows(format, &standup).unwrap_or_else(exit_with_error),
+        None => show(args, &standup),
+    }
+}
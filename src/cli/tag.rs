@@ -1,35 +1,1034 @@
+use std::{collections::HashSet, path::PathBuf, process, slice, str::FromStr};
+
 use clap::Args;
+use serde_json::Map;
+
+use crate::{
+    audit::AuditLog,
+    bulk::{self, Applied, Outcome, Progress},
+    checkpoint,
+    cli::{self, connection::Connection, exit_with_error},
+    date::DateTime,
+    jira::{
+        self,
+        api::JiraApi,
+        client::{
+            IssueEditUpdate, IssueEditUpdateLabel, RequestError, SearchIssue, User,
+            MAX_ISSUES_PER_SPRINT_MOVE,
+        },
+        jql,
+    },
+    journal::{self, Change, Entry, Journal},
+    template,
+};
+
+/// A label to rename on every issue, given on the command line as `old=new`.
+#[derive(Clone, Debug)]
+pub struct LabelReplacement {
+    pub old: String,
+    pub new: String,
+}
+
+impl FromStr for LabelReplacement {
+    type Err = String;
 
-use jimberlage_jira_client::{IssueEditUpdate, IssueEditUpdateLabel, RestClient};
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once('=') {
+            Some((old, new)) if !old.is_empty() && !new.is_empty() => Ok(LabelReplacement {
+                old: old.to_owned(),
+                new: new.to_owned(),
+            }),
+            _ => Err(format!(
+                "\"{}\" is not a label replacement; it should look like old-label=new-label",
+                s
+            )),
+        }
+    }
+}
 
-#[derive(Debug, Args)]
+#[derive(Clone, Debug, Args)]
 pub struct Tag {
+    #[arg(long)]
+    pub add_watcher: Vec<String>,
+
+    #[arg(long)]
+    pub audit_log: Option<PathBuf>,
+
+    #[arg(long)]
+    #[arg(conflicts_with = "resume")]
+    pub checkpoint: Option<PathBuf>,
+
+    #[arg(long)]
+    #[arg(default_value_t = 4)]
+    pub concurrency: usize,
+
+    #[arg(long)]
+    pub jira_board: Option<u64>,
+
     #[arg(long)]
     pub jira_key: Vec<String>,
 
     #[arg(long)]
-    pub jira_label: String,
+    pub jira_label: Option<String>,
+
+    #[arg(long)]
+    #[arg(default_value = "Story Points")]
+    pub jira_story_points_field: String,
+
+    #[arg(long)]
+    #[arg(conflicts_with = "undo")]
+    pub jql: Option<String>,
+
+    #[arg(long)]
+    pub journal: Option<PathBuf>,
+
+    #[arg(long)]
+    pub move_to_sprint: Option<String>,
+
+    #[arg(long)]
+    pub only_if_labeled: Vec<String>,
+
+    #[arg(long)]
+    #[arg(default_value_t = false)]
+    pub only_if_missing_points: bool,
+
+    #[arg(long)]
+    pub only_if_status_category: Vec<String>,
+
+    #[arg(long)]
+    pub only_if_unlabeled: Vec<String>,
+
+    #[arg(long)]
+    pub replace_label: Vec<LabelReplacement>,
+
+    #[arg(long)]
+    pub resume: Option<PathBuf>,
 
     #[arg(long)]
-    pub jira_token: String,
+    #[arg(conflicts_with_all = ["add_watcher", "jira_label", "move_to_sprint", "replace_label"])]
+    pub undo: Option<PathBuf>,
 
     #[arg(long)]
-    pub jira_url: String,
+    #[arg(default_value_t = false)]
+    #[arg(requires = "jql")]
+    pub validate_jql: bool,
 
     #[arg(long)]
-    pub jira_username: String,
+    #[arg(default_value_t = false)]
+    pub yes: bool,
+
+    #[command(flatten)]
+    pub connection: Connection,
 }
 
-pub fn run(args: &Tag) {
-    let client = RestClient::new(&args.jira_url, &args.jira_username, &args.jira_token).unwrap();
+/// The --only-if-* conditions an issue has to meet before tag will change it.
+///
+/// These make reruns idempotent: issues that are already in the state the run is aiming for get skipped.
+struct Conditions<'a> {
+    args: &'a Tag,
+    story_point_field_ids: Vec<String>,
+}
 
-    for key in &args.jira_key {
-        let update = IssueEditUpdate {
-            labels: vec![IssueEditUpdateLabel::Add(args.jira_label.clone())],
+impl<'a> Conditions<'a> {
+    async fn new(client: &impl JiraApi, args: &'a Tag) -> Result<Self, RequestError> {
+        let story_point_field_ids = if args.only_if_missing_points {
+            jira::field_ids_named(&client.get_fields().await?, &args.jira_story_points_field)
+        } else {
+            vec![]
         };
 
-        client.edit_issue(key, &update).unwrap();
+        Ok(Conditions {
+            args,
+            story_point_field_ids,
+        })
+    }
+
+    fn is_empty(&self) -> bool {
+        !self.args.only_if_missing_points
+            && self.args.only_if_labeled.is_empty()
+            && self.args.only_if_status_category.is_empty()
+            && self.args.only_if_unlabeled.is_empty()
+    }
+
+    /// The fields to request for an issue in order to check it.
+    fn fields(&self) -> Vec<String> {
+        let mut fields = vec!["labels".to_owned(), "status".to_owned()];
+        fields.extend(self.story_point_field_ids.iter().cloned());
+        fields
+    }
+
+    /// Returns why an issue should be left alone, if it should be.
+    fn skip_reason(&self, issue: &SearchIssue) -> Option<String> {
+        if self.args.only_if_missing_points {
+            if let Some(points) = jira::story_points(issue, &self.story_point_field_ids) {
+                if points != 0.0 {
+                    return Some(format!("it already has {} story points", points));
+                }
+            }
+        }
+
+        let labels = jira::labels(issue);
+        for label in &self.args.only_if_unlabeled {
+            if labels.contains(label) {
+                return Some(format!("it is already labeled {}", label));
+            }
+        }
+        for label in &self.args.only_if_labeled {
+            if !labels.contains(label) {
+                return Some(format!("it is not labeled {}", label));
+            }
+        }
+
+        if !self.args.only_if_status_category.is_empty() {
+            let status_category = issue.status_category();
+            let matches = status_category
+                .as_ref()
+                .map(|category| self.args.only_if_status_category.contains(category))
+                .unwrap_or(false);
+            if !matches {
+                return Some(format!(
+                    "its status category is {}",
+                    status_category.unwrap_or_else(|| "unknown".to_owned())
+                ));
+            }
+        }
+
+        None
+    }
+}
+
+async fn add_label(
+    client: &impl JiraApi,
+    conditions: &Conditions<'_>,
+    key: &str,
+    label: &str,
+) -> Result<Applied, RequestError> {
+    let issue = client.get_issue(key, &conditions.fields()).await?;
+    if let Some(reason) = conditions.skip_reason(&issue) {
+        return Ok(Applied::Skipped(reason));
+    }
+
+    let before = jira::labels(&issue);
+    if before.iter().any(|existing| existing == label) {
+        return Ok(Applied::Skipped(format!("it is already labeled {}", label)));
+    }
+
+    let update = IssueEditUpdate {
+        labels: vec![IssueEditUpdateLabel::Add(label.to_owned())],
+    };
+    client.edit_issue(key, &update).await?;
+
+    let mut after = before.clone();
+    after.push(label.to_owned());
+
+    Ok(Applied::Changed(vec![Entry {
+        key: key.to_owned(),
+        change: Change::Labels { before, after },
+    }]))
+}
+
+/// Swaps labels on an issue with a single edit, so that an interrupted rename never leaves it with both or neither.
+async fn replace_labels(
+    client: &impl JiraApi,
+    conditions: &Conditions<'_>,
+    key: &str,
+    replacements: &[LabelReplacement],
+) -> Result<Applied, RequestError> {
+    let issue = client.get_issue(key, &conditions.fields()).await?;
+    if let Some(reason) = conditions.skip_reason(&issue) {
+        return Ok(Applied::Skipped(reason));
+    }
+
+    let before = jira::labels(&issue);
+    let mut after = before.clone();
+    let mut labels = vec![];
+
+    for replacement in replacements {
+        if !after.contains(&replacement.old) {
+            continue;
+        }
+
+        labels.push(IssueEditUpdateLabel::Remove(replacement.old.clone()));
+        after.retain(|label| *label != replacement.old);
+
+        if !after.contains(&replacement.new) {
+            labels.push(IssueEditUpdateLabel::Add(replacement.new.clone()));
+            after.push(replacement.new.clone());
+        }
+    }
+
+    if labels.is_empty() {
+        return Ok(Applied::Skipped(
+            "it has none of the labels being replaced".to_owned(),
+        ));
+    }
+
+    client.edit_issue(key, &IssueEditUpdate { labels }).await?;
+
+    Ok(Applied::Changed(vec![Entry {
+        key: key.to_owned(),
+        change: Change::Labels { before, after },
+    }]))
+}
+
+/// Turns a watcher given on the command line into an account ID.
+///
+/// Email addresses are looked up, since that is how people know each other; anything else is taken to already be an
+/// account ID.
+async fn resolve_watcher(client: &impl JiraApi, watcher: &str) -> Result<String, String> {
+    if !watcher.contains('@') {
+        return Ok(watcher.to_owned());
+    }
+
+    let users = client
+        .find_users(watcher)
+        .await
+        .map_err(|e| format!("Could not look up {}: {}", watcher, e))?;
+    let exact_matches: Vec<&User> = users
+        .iter()
+        .filter(|user| {
+            user.email_address
+                .as_deref()
+                .map(|email| email.eq_ignore_ascii_case(watcher))
+                .unwrap_or(false)
+        })
+        .collect();
+
+    match (exact_matches.as_slice(), users.as_slice()) {
+        ([user], _) => Ok(user.account_id.clone()),
+        ([], [user]) => Ok(user.account_id.clone()),
+        ([], []) => Err(format!("There is no JIRA user with the email address {}.", watcher)),
+        _ => Err(format!(
+            "More than one JIRA user matches {}: {}.  Pass the account ID of the one you mean instead.",
+            watcher,
+            users
+                .iter()
+                .map(|user| format!("{} ({})", user.display_name, user.account_id))
+                .collect::<Vec<String>>()
+                .join(", ")
+        )),
+    }
+}
+
+/// Subscribes users to an issue, leaving out the ones already watching it.
+async fn add_watchers(
+    client: &impl JiraApi,
+    conditions: &Conditions<'_>,
+    key: &str,
+    account_ids: &[String],
+) -> Result<Applied, RequestError> {
+    if !conditions.is_empty() {
+        let issue = client.get_issue(key, &conditions.fields()).await?;
+        if let Some(reason) = conditions.skip_reason(&issue) {
+            return Ok(Applied::Skipped(reason));
+        }
+    }
+
+    let before = client.get_watchers(key).await?;
+    let to_add: Vec<&String> = account_ids
+        .iter()
+        .filter(|account_id| !before.contains(account_id))
+        .collect();
+    if to_add.is_empty() {
+        return Ok(Applied::Skipped(
+            "everyone is already watching it".to_owned(),
+        ));
+    }
+
+    let mut after = before.clone();
+    for account_id in to_add {
+        client.add_watcher(key, account_id).await?;
+        after.push(account_id.clone());
+    }
+
+    Ok(Applied::Changed(vec![Entry {
+        key: key.to_owned(),
+        change: Change::Watchers { before, after },
+    }]))
+}
+
+/// Moves a chunk of issues into a sprint with a single request, after leaving out the ones that should be skipped.
+///
+/// Each issue gets its own result, so that when the move fails, only the issues that were sent fail with it.
+async fn move_to_sprint(
+    client: &impl JiraApi,
+    conditions: &Conditions<'_>,
+    keys: &[String],
+    sprint_id: u64,
+    already_in_sprint: &HashSet<String>,
+) -> Vec<(String, Result<Applied, String>)> {
+    let mut results = vec![];
+    let mut to_move = vec![];
+
+    for key in keys {
+        if already_in_sprint.contains(key) {
+            let reason = format!("it is already in sprint {}", sprint_id);
+            results.push((key.clone(), Ok(Applied::Skipped(reason))));
+            continue;
+        }
+
+        if !conditions.is_empty() {
+            let issue = match client.get_issue(key, &conditions.fields()).await {
+                Ok(issue) => issue,
+                Err(e) => {
+                    results.push((key.clone(), Err(e.to_string())));
+                    continue;
+                }
+            };
+            if let Some(reason) = conditions.skip_reason(&issue) {
+                results.push((key.clone(), Ok(Applied::Skipped(reason))));
+                continue;
+            }
+        }
+
+        let before = match client.get_issue_sprint_id(key).await {
+            Ok(before) => before,
+            Err(e) => {
+                results.push((key.clone(), Err(e.to_string())));
+                continue;
+            }
+        };
+        if before == Some(sprint_id) {
+            let reason = format!("it is already in sprint {}", sprint_id);
+            results.push((key.clone(), Ok(Applied::Skipped(reason))));
+            continue;
+        }
+
+        to_move.push((key.clone(), before));
+    }
+
+    if to_move.is_empty() {
+        return results;
+    }
+
+    let keys_to_move: Vec<String> = to_move.iter().map(|(key, _)| key.clone()).collect();
+    match client.move_issues_to_sprint(sprint_id, &keys_to_move).await {
+        Ok(()) => {
+            for (key, before) in to_move {
+                let entry = Entry {
+                    key: key.clone(),
+                    change: Change::Sprint {
+                        before,
+                        after: Some(sprint_id),
+                    },
+                };
+                results.push((key, Ok(Applied::Changed(vec![entry]))));
+            }
+        }
+        // The issues are moved with a single request, so every one that was sent fails together.
+        Err(e) => {
+            for (key, _) in to_move {
+                results.push((key, Err(e.to_string())));
+            }
+        }
     }
 
-    println!("Done!");
+    results
+}
+
+/// Puts a field back the way it was before the journal entry was recorded.
+async fn restore(client: &impl JiraApi, entry: &Entry) -> Result<Entry, RequestError> {
+    let reversed = entry.change.reversed();
+
+    match &reversed {
+        Change::Labels { before, after } => {
+            let mut labels = vec![];
+            for label in before.iter().filter(|label| !after.contains(label)) {
+                labels.push(IssueEditUpdateLabel::Remove(label.clone()));
+            }
+            for label in after.iter().filter(|label| !before.contains(label)) {
+                labels.push(IssueEditUpdateLabel::Add(label.clone()));
+            }
+
+            if !labels.is_empty() {
+                client
+                    .edit_issue(&entry.key, &IssueEditUpdate { labels })
+                    .await?;
+            }
+        }
+        Change::Sprint { after, .. } => match after {
+            Some(sprint_id) => {
+                client
+                    .move_issues_to_sprint(*sprint_id, slice::from_ref(&entry.key))
+                    .await?
+            }
+            None => {
+                client
+                    .move_issues_to_backlog(slice::from_ref(&entry.key))
+                    .await?
+            }
+        },
+        Change::StoryPoints {
+            field_id, after, ..
+        } => {
+            let mut fields = Map::new();
+            fields.insert(field_id.clone(), serde_json::json!(after));
+            client
+                .edit_issue_fields(&entry.key, &fields, &IssueEditUpdate::default())
+                .await?;
+        }
+        Change::FixVersions { after, .. } => {
+            let mut fields = Map::new();
+            fields.insert("fixVersions".to_owned(), jira::fix_versions_value(after));
+            client
+                .edit_issue_fields(&entry.key, &fields, &IssueEditUpdate::default())
+                .await?;
+        }
+        Change::Watchers { before, after } => {
+            for account_id in before
+                .iter()
+                .filter(|account_id| !after.contains(account_id))
+            {
+                client.remove_watcher(&entry.key, account_id).await?;
+            }
+            for account_id in after
+                .iter()
+                .filter(|account_id| !before.contains(account_id))
+            {
+                client.add_watcher(&entry.key, account_id).await?;
+            }
+        }
+    }
+
+    Ok(Entry {
+        key: entry.key.clone(),
+        change: reversed,
+    })
+}
+
+/// Splits the journal entries to undo up by issue, keeping each issue's entries in the order given.
+///
+/// Restores set fields back to a value rather than undoing a difference, so an issue's entries have to be undone one
+/// after another, newest first, to end up the way the issue started.  Separate issues can be undone at the same time.
+fn undo_groups<'a>(entries: &[&'a Entry]) -> Vec<Vec<&'a Entry>> {
+    let mut groups: Vec<Vec<&Entry>> = vec![];
+    for entry in entries {
+        match groups.iter_mut().find(|group| group[0].key == entry.key) {
+            Some(group) => group.push(entry),
+            None => groups.push(vec![entry]),
+        }
+    }
+
+    groups
+}
+
+/// Undoes one issue's entries in order, stopping at the first that fails, since undoing the older ones then would leave
+/// the issue the way it was at neither end.
+async fn restore_all(client: &impl JiraApi, entries: &[&Entry]) -> Vec<Result<Entry, String>> {
+    let mut results = vec![];
+    for entry in entries {
+        if results.iter().any(Result::is_err) {
+            results.push(Err(
+                "it was not undone, since undoing a later change to the issue failed".to_owned(),
+            ));
+            continue;
+        }
+        results.push(restore(client, entry).await.map_err(|e| e.to_string()));
+    }
+
+    results
+}
+
+fn describe_replacements(replacements: &[LabelReplacement]) -> String {
+    let replacements: Vec<String> = replacements
+        .iter()
+        .map(|replacement| format!("{} with {}", replacement.old, replacement.new))
+        .collect();
+
+    format!("Replace label {}", replacements.join(", "))
+}
+
+fn describe_restore(change: &Change) -> &'static str {
+    match change {
+        Change::Labels { .. } => "Restore labels",
+        Change::Sprint { .. } => "Restore sprint",
+        Change::StoryPoints { .. } => "Restore story points",
+        Change::FixVersions { .. } => "Restore fix versions",
+        Change::Watchers { .. } => "Restore watchers",
+    }
+}
+
+/// The name of the sprint that `{sprint}` stands for in a label: the one the issues are being moved into, or else the
+/// active sprint on the board.
+async fn template_sprint_name(
+    client: &impl JiraApi,
+    args: &Tag,
+) -> Result<Option<String>, jira::SprintResolutionError> {
+    match (&args.move_to_sprint, args.jira_board) {
+        (Some(sprint), _) => match sprint.parse::<u64>() {
+            Ok(sprint_id) => client
+                .get_sprint(sprint_id)
+                .await
+                .map(|sprint| Some(sprint.name))
+                .map_err(|e| jira::SprintResolutionError::ProblemGettingSprint(sprint_id, e)),
+            Err(_) => Ok(Some(sprint.clone())),
+        },
+        (None, Some(board_id)) => jira::active_sprint(client, board_id)
+            .await
+            .map(|sprint| Some(sprint.name)),
+        (None, None) => Ok(None),
+    }
+}
+
+/// Fills in the placeholders in the labels to add or replace with, like `{today:%Y-%m}` and `{sprint}`.
+///
+/// JIRA rejects labels with spaces in them, so the spaces in sprint names like "Sprint 42" become dashes, and a label
+/// that still has a space in it once it's filled in stops the run before any issue is changed.
+async fn expand_label_templates(client: &impl JiraApi, args: &Tag) -> Tag {
+    let uses_sprint = args
+        .jira_label
+        .iter()
+        .chain(
+            args.replace_label
+                .iter()
+                .map(|replacement| &replacement.new),
+        )
+        .any(|label| template::uses_sprint(label));
+    let sprint = if uses_sprint {
+        template_sprint_name(client, args)
+            .await
+            .unwrap_or_else(exit_with_error)
+    } else {
+        None
+    };
+    let sprint = sprint.map(|name| name.split_whitespace().collect::<Vec<&str>>().join("-"));
+    let context = template::Context {
+        today: DateTime::now(),
+        sprint: sprint.as_deref(),
+    };
+    let expand = |label: &str| {
+        let expanded = template::expand(label, &context).unwrap_or_else(exit_with_error);
+        if expanded.contains(char::is_whitespace) {
+            exit_with_error(format!(
+                "\"{}\" becomes \"{}\", which JIRA won't take as a label since it has a space in it.",
+                label, expanded
+            ))
+        }
+        expanded
+    };
+
+    Tag {
+        jira_label: args.jira_label.as_deref().map(expand),
+        replace_label: args
+            .replace_label
+            .iter()
+            .map(|replacement| LabelReplacement {
+                old: replacement.old.clone(),
+                new: expand(&replacement.new),
+            })
+            .collect(),
+        ..args.clone()
+    }
+}
+
+/// Shows the user everything that is about to change, and asks them whether to go ahead.
+fn confirm_changes(args: &Tag, undo_entries: &[Entry], sprint_id: Option<u64>) -> bool {
+    if let Some(undo_path) = &args.undo {
+        for entry in undo_entries {
+            println!("{}/browse/{}", args.connection.jira_url, entry.key);
+        }
+
+        return cli::confirm(&format!(
+            "Undo {} changes from {}?",
+            undo_entries.len(),
+            undo_path.display()
+        ));
+    }
+
+    for key in &args.jira_key {
+        println!("{}/browse/{}", args.connection.jira_url, key);
+    }
+
+    let mut changes = vec![];
+    if let Some(label) = &args.jira_label {
+        changes.push(format!("apply label {}", label));
+    }
+    if !args.replace_label.is_empty() {
+        changes.push(describe_replacements(&args.replace_label).to_lowercase());
+    }
+    if !args.add_watcher.is_empty() {
+        changes.push(format!("add watcher {}", args.add_watcher.join(", ")));
+    }
+    if let (Some(sprint), Some(sprint_id)) = (&args.move_to_sprint, sprint_id) {
+        if *sprint == sprint_id.to_string() {
+            changes.push(format!("move into sprint {}", sprint_id));
+        } else {
+            changes.push(format!("move into sprint {} ({})", sprint, sprint_id));
+        }
+    }
+
+    let changes = changes.join(" and ");
+    cli::confirm(&format!(
+        "{}{} to {} issues?",
+        changes[..1].to_uppercase(),
+        &changes[1..],
+        args.jira_key.len()
+    ))
+}
+
+pub async fn run(args: &Tag) {
+    if args.undo.is_none()
+        && args.jira_label.is_none()
+        && args.replace_label.is_empty()
+        && args.add_watcher.is_empty()
+        && args.move_to_sprint.is_none()
+    {
+        eprintln!("There is nothing to do.  Pass --jira-label to add a label to the issues, --replace-label to rename one of their labels, --add-watcher to subscribe someone to them, --move-to-sprint to move them into a sprint, or --undo to reverse an earlier run.");
+        process::exit(1);
+    }
+
+    let mut client = args.connection.connect().unwrap_or_else(exit_with_error);
+    if let Some(path) = &args.audit_log {
+        let audit_log = AuditLog::create(path).unwrap_or_else(|e| {
+            exit_with_error(format!(
+                "Could not open the audit log at {}: {}",
+                path.display(),
+                e
+            ))
+        });
+        client = client.with_audit_log(audit_log);
+    }
+
+    let mut args = expand_label_templates(&client, args).await;
+    if let Some(jql) = &args.jql {
+        let jql = jql::parser::parse(jql).unwrap_or_else(exit_with_error);
+        if args.validate_jql {
+            jira::validate_jql(&client, &jql)
+                .await
+                .unwrap_or_else(exit_with_error);
+        }
+        let issues = client
+            .search_all(&["summary".to_owned()], &jql)
+            .await
+            .unwrap_or_else(|e| exit_with_error(format!("Could not search for issues: {}", e)));
+
+        for issue in issues {
+            if !args.jira_key.contains(&issue.key) {
+                args.jira_key.push(issue.key);
+            }
+        }
+        if args.jira_key.is_empty() {
+            println!("No issues match the search, so there is nothing to change.");
+            return;
+        }
+    }
+    let args = &args;
+
+    let mut undo_entries = match &args.undo {
+        Some(undo_path) => journal::read(undo_path).unwrap_or_else(|e| {
+            exit_with_error(format!(
+                "Could not read the journal at {}: {}",
+                undo_path.display(),
+                e
+            ))
+        }),
+        None => vec![],
+    };
+    // Changes are undone newest first, in case the same field of an issue was changed more than once.  Each issue's
+    // changes stay in this order when they are undone; see undo_groups.
+    undo_entries.reverse();
+
+    let conditions = Conditions::new(&client, args).await.unwrap_or_else(|e| {
+        exit_with_error(format!(
+            "Could not look up the {} field: {}",
+            args.jira_story_points_field, e
+        ))
+    });
+
+    // Without --jira-board, sprint names are looked up on the board of the project the issues are in.
+    let project_key = args
+        .jira_key
+        .first()
+        .and_then(|key| key.rsplit_once('-'))
+        .map(|(project_key, _)| project_key);
+    let sprint_id = match &args.move_to_sprint {
+        Some(sprint) => Some(
+            jira::resolve_sprint_id(
+                &client,
+                args.jira_board,
+                project_key,
+                sprint,
+                "active,future",
+            )
+            .await
+            .unwrap_or_else(exit_with_error),
+        ),
+        None => None,
+    };
+    let already_in_sprint: HashSet<String> = match sprint_id {
+        Some(sprint_id) => client
+            .get_sprint_issues(sprint_id, &["summary".to_owned()])
+            .await
+            .unwrap_or_else(|e| {
+                exit_with_error(format!(
+                    "Could not list the issues in sprint {}: {}",
+                    sprint_id, e
+                ))
+            })
+            .into_iter()
+            .map(|issue| issue.key)
+            .collect(),
+        None => HashSet::new(),
+    };
+
+    let mut watcher_ids: Vec<String> = vec![];
+    for watcher in &args.add_watcher {
+        watcher_ids.push(
+            resolve_watcher(&client, watcher)
+                .await
+                .unwrap_or_else(exit_with_error),
+        );
+    }
+
+    if !args.yes && !confirm_changes(args, &undo_entries, sprint_id) {
+        println!("Nothing was changed.");
+        return;
+    }
+
+    let journal_path = args
+        .journal
+        .clone()
+        .unwrap_or_else(|| journal::default_path("tag"));
+    let mut journal = Journal::create(&journal_path).unwrap_or_else(|e| {
+        exit_with_error(format!(
+            "Could not create the journal at {}: {}",
+            journal_path.display(),
+            e
+        ))
+    });
+
+    let mut checkpoint = checkpoint::open(args.checkpoint.as_deref(), args.resume.as_deref())
+        .unwrap_or_else(|e| exit_with_error(format!("Could not open the checkpoint: {}", e)));
+
+    let mut outcomes: Vec<Outcome> = vec![];
+    let mut progress = Progress::new(&args.connection.jira_url);
+
+    if args.undo.is_some() {
+        let entries = bulk::remaining(&undo_entries, &checkpoint, &mut progress, |entry| {
+            (&entry.key, describe_restore(&entry.change).to_owned())
+        });
+        let groups = undo_groups(&entries);
+
+        bulk::for_each_concurrently(
+            &groups,
+            args.concurrency,
+            |group| restore_all(&client, group),
+            |group, results| {
+                for (entry, result) in group.iter().zip(results) {
+                    outcomes.push(bulk::settle(
+                        &mut journal,
+                        &mut checkpoint,
+                        &mut progress,
+                        &entry.key,
+                        describe_restore(&entry.change),
+                        result.map(|undone| Applied::Changed(vec![undone])),
+                    ));
+                }
+            },
+        )
+        .await;
+    }
+
+    if let Some(label) = &args.jira_label {
+        let change = format!("Add label {}", label);
+        let keys = bulk::remaining(&args.jira_key, &checkpoint, &mut progress, |key| {
+            (key, change.clone())
+        });
+
+        bulk::for_each_concurrently(
+            &keys,
+            args.concurrency,
+            |key| add_label(&client, &conditions, key, label),
+            |key, result| {
+                let result = result.map_err(|e| e.to_string());
+                outcomes.push(bulk::settle(
+                    &mut journal,
+                    &mut checkpoint,
+                    &mut progress,
+                    key,
+                    &change,
+                    result,
+                ));
+            },
+        )
+        .await;
+    }
+
+    if !args.replace_label.is_empty() {
+        let change = describe_replacements(&args.replace_label);
+        let keys = bulk::remaining(&args.jira_key, &checkpoint, &mut progress, |key| {
+            (key, change.clone())
+        });
+
+        bulk::for_each_concurrently(
+            &keys,
+            args.concurrency,
+            |key| replace_labels(&client, &conditions, key, &args.replace_label),
+            |key, result| {
+                let result = result.map_err(|e| e.to_string());
+                outcomes.push(bulk::settle(
+                    &mut journal,
+                    &mut checkpoint,
+                    &mut progress,
+                    key,
+                    &change,
+                    result,
+                ));
+            },
+        )
+        .await;
+    }
+
+    if !watcher_ids.is_empty() {
+        let change = format!("Add watcher {}", args.add_watcher.join(", "));
+        let keys = bulk::remaining(&args.jira_key, &checkpoint, &mut progress, |key| {
+            (key, change.clone())
+        });
+
+        bulk::for_each_concurrently(
+            &keys,
+            args.concurrency,
+            |key| add_watchers(&client, &conditions, key, &watcher_ids),
+            |key, result| {
+                let result = result.map_err(|e| e.to_string());
+                outcomes.push(bulk::settle(
+                    &mut journal,
+                    &mut checkpoint,
+                    &mut progress,
+                    key,
+                    &change,
+                    result,
+                ));
+            },
+        )
+        .await;
+    }
+
+    if let Some(sprint_id) = sprint_id {
+        let change = format!("Move to sprint {}", sprint_id);
+        let keys: Vec<String> =
+            bulk::remaining(&args.jira_key, &checkpoint, &mut progress, |key| {
+                (key, change.clone())
+            })
+            .into_iter()
+            .cloned()
+            .collect();
+        let chunks: Vec<&[String]> = keys.chunks(MAX_ISSUES_PER_SPRINT_MOVE).collect();
+
+        bulk::for_each_concurrently(
+            &chunks,
+            args.concurrency,
+            |keys| move_to_sprint(&client, &conditions, keys, sprint_id, &already_in_sprint),
+            |_, results| {
+                for (key, result) in results {
+                    outcomes.push(bulk::settle(
+                        &mut journal,
+                        &mut checkpoint,
+                        &mut progress,
+                        &key,
+                        &change,
+                        result,
+                    ));
+                }
+            },
+        )
+        .await;
+    }
+
+    progress.finish();
+    bulk::print_summary(&outcomes);
+    println!(
+        "The changes were written to {}.  To reverse them, run this command again with --undo {}",
+        journal_path.display(),
+        journal_path.display()
+    );
+    if let Some(checkpoint) = &checkpoint {
+        println!(
+            "If this run was cut short, pick it back up with --resume {}",
+            checkpoint.path().display()
+        );
+    }
+
+    if bulk::any_failed(&outcomes) {
+        process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::jira::api::fake::{block_on, FakeJira};
+
+    fn points(key: &str, before: f64, after: f64) -> Entry {
+        Entry {
+            key: key.to_owned(),
+            change: Change::StoryPoints {
+                field_id: "customfield_10016".to_owned(),
+                before: Some(before),
+                after: Some(after),
+            },
+        }
+    }
+
+    /// Undoes a journal the way `run` does, newest entry first.
+    fn undo(client: &FakeJira, journal: &[Entry]) -> Vec<Result<Entry, String>> {
+        let newest_first: Vec<&Entry> = journal.iter().rev().collect();
+        let groups = undo_groups(&newest_first);
+
+        block_on(async {
+            let mut results = vec![];
+            for group in &groups {
+                results.extend(restore_all(client, group).await);
+            }
+            results
+        })
+    }
+
+    #[test]
+    fn an_issue_edited_twice_is_undone_newest_first() {
+        let journal = vec![
+            points("TEAM-1", 1.0, 3.0),
+            points("TEAM-2", 8.0, 5.0),
+            points("TEAM-1", 3.0, 5.0),
+        ];
+        let newest_first: Vec<&Entry> = journal.iter().rev().collect();
+
+        let groups = undo_groups(&newest_first);
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0].len(), 2);
+        assert_eq!(groups[1].len(), 1);
+
+        let client = FakeJira::default();
+        assert!(undo(&client, &journal).iter().all(Result::is_ok));
+        // The issue ends up with the points it had before either change.
+        assert_eq!(
+            client.edits_to("TEAM-1"),
+            vec![
+                json!({"customfield_10016": 3.0}),
+                json!({"customfield_10016": 1.0})
+            ]
+        );
+        assert_eq!(
+            client.edits_to("TEAM-2"),
+            vec![json!({"customfield_10016": 8.0})]
+        );
+    }
+
+    #[test]
+    fn older_changes_are_left_when_undoing_a_newer_one_fails() {
+        let journal = vec![points("TEAM-1", 1.0, 3.0), points("TEAM-1", 3.0, 5.0)];
+        let client = FakeJira {
+            uneditable: vec!["TEAM-1".to_owned()],
+            ..FakeJira::default()
+        };
+
+        let results = undo(&client, &journal);
+
+        assert_eq!(results.len(), 2);
+        assert!(results.iter().all(Result::is_err));
+        assert!(results[1].as_ref().unwrap_err().contains("later change"));
+        assert!(client.edits_to("TEAM-1").is_empty());
+    }
 }
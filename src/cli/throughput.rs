@@ -0,0 +1,254 @@
+use std::{collections::HashMap, str::FromStr};
+
+use clap::Args;
+use colored::Colorize;
+use serde::Serialize;
+use serde_json::Value as JSONValue;
+
+use crate::{
+    cli::{
+        connection::Connection,
+        exit_with_error,
+        output::{self, Format},
+        scope::Scope,
+        timeline,
+    },
+    date::DateTime,
+    jira::{self, api::JiraApi, client::SearchIssue, history},
+};
+
+/// The widest bar to draw for a period, so that the table fits on a terminal.
+const MAX_BAR_WIDTH: usize = 40;
+
+/// How to split up the issues that were finished.
+#[derive(Clone, Copy, Debug)]
+pub enum Period {
+    /// Weeks starting on Monday, in UTC.
+    Week,
+    /// The last sprint each issue was in.
+    Sprint,
+}
+
+impl FromStr for Period {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.eq_ignore_ascii_case("week") {
+            Ok(Period::Week)
+        } else if s.eq_ignore_ascii_case("sprint") {
+            Ok(Period::Sprint)
+        } else {
+            Err("the period has to be week or sprint".to_owned())
+        }
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct Throughput {
+    /// csv or json, for a script to read.  Without this, the periods are shown as a bar chart, with the trend.
+    #[arg(long)]
+    pub format: Option<Format>,
+
+    /// Count the issues finished in this many weeks, up to and including this one.
+    #[arg(long)]
+    #[arg(default_value_t = 12)]
+    pub lookback_weeks: u32,
+
+    /// week or sprint.
+    #[arg(long)]
+    #[arg(default_value = "week")]
+    pub per: Period,
+
+    #[command(flatten)]
+    pub scope: Scope,
+
+    #[command(flatten)]
+    pub connection: Connection,
+}
+
+#[derive(Debug, Serialize)]
+struct PeriodCount {
+    period: String,
+    completed: u64,
+}
+
+/// The name of the last sprint an issue was in, from the sprint field.
+///
+/// JIRA Cloud sends each sprint as an object, while older versions of JIRA Server send a string like
+/// `com.atlassian.greenhopper.service.sprint.Sprint@1f[id=1,name=Sprint 1,...]`.
+fn last_sprint(issue: &SearchIssue, field_ids: &[String]) -> Option<String> {
+    let sprints = field_ids
+        .iter()
+        .find_map(|field_id| issue.fields.get(field_id)?.as_array())?;
+
+    match sprints.last()? {
+        JSONValue::Object(sprint) => Some(sprint.get("name")?.as_str()?.to_owned()),
+        JSONValue::String(sprint) => {
+            let name = sprint.split_once("name=")?.1;
+            Some(name.split(',').next()?.to_owned())
+        }
+        _ => None,
+    }
+}
+
+async fn throughput(client: &impl JiraApi, args: &Throughput) -> Result<Vec<PeriodCount>, String> {
+    if args.lookback_weeks == 0 {
+        return Err("--lookback-weeks has to be greater than zero.".to_owned());
+    }
+
+    let this_week = DateTime::now().start_of_week();
+    let since = this_week.plus_days(-7 * (args.lookback_weeks as i64 - 1));
+
+    let sprint_field_ids = match args.per {
+        Period::Week => vec![],
+        Period::Sprint => jira::get_field_ids_named(client, "Sprint")
+            .await
+            .map_err(|e| format!("Could not get the sprint field: {}", e))?,
+    };
+    let mut fields = sprint_field_ids.clone();
+    fields.push("status".to_owned());
+
+    let categories = history::status_categories(client)
+        .await
+        .map_err(|e| format!("Could not get the statuses: {}", e))?;
+    let issues =
+        timeline::search_completed_with_changelogs(client, &args.scope, &fields, since).await?;
+
+    let completed: Vec<(&SearchIssue, DateTime)> = issues
+        .iter()
+        .filter_map(|issue| Some((issue, history::completed_at(issue, &categories)?)))
+        .filter(|(_, completed_at)| completed_at.to_unix_seconds() >= since.to_unix_seconds())
+        .collect();
+
+    Ok(match args.per {
+        Period::Week => (0..args.lookback_weeks as i64)
+            .map(|week| {
+                let start = since.plus_days(7 * week);
+                let end = start.plus_days(7);
+                PeriodCount {
+                    period: start.to_date_string(),
+                    completed: completed
+                        .iter()
+                        .filter(|(_, at)| {
+                            at.to_unix_seconds() >= start.to_unix_seconds()
+                                && at.to_unix_seconds() < end.to_unix_seconds()
+                        })
+                        .count() as u64,
+                }
+            })
+            .collect(),
+        Period::Sprint => {
+            // Sprints are put in the order their first issue was finished in, which is the order they ran in.
+            let mut by_sprint: HashMap<String, (i64, u64)> = HashMap::new();
+            for (issue, completed_at) in &completed {
+                let sprint =
+                    last_sprint(issue, &sprint_field_ids).unwrap_or_else(|| "No sprint".to_owned());
+                let entry = by_sprint
+                    .entry(sprint)
+                    .or_insert((completed_at.to_unix_seconds(), 0));
+                entry.0 = entry.0.min(completed_at.to_unix_seconds());
+                entry.1 += 1;
+            }
+
+            let mut sprints: Vec<(String, (i64, u64))> = by_sprint.into_iter().collect();
+            sprints.sort_by_key(|(name, (first, _))| (*first, name.clone()));
+            sprints
+                .into_iter()
+                .map(|(period, (_, completed))| PeriodCount { period, completed })
+                .collect()
+        }
+    })
+}
+
+/// How much the count changes from one period to the next, by a least squares fit.
+fn slope(counts: &[PeriodCount]) -> f64 {
+    let n = counts.len() as f64;
+    if n < 2.0 {
+        return 0.0;
+    }
+
+    let mean_x = (n - 1.0) / 2.0;
+    let mean_y = counts
+        .iter()
+        .map(|count| count.completed as f64)
+        .sum::<f64>()
+        / n;
+    let (mut covariance, mut variance) = (0.0, 0.0);
+    for (i, count) in counts.iter().enumerate() {
+        covariance += (i as f64 - mean_x) * (count.completed as f64 - mean_y);
+        variance += (i as f64 - mean_x).powi(2);
+    }
+
+    covariance / variance
+}
+
+fn show(args: &Throughput, counts: &[PeriodCount]) {
+    let most = counts
+        .iter()
+        .map(|count| count.completed)
+        .max()
+        .unwrap_or(0);
+    let width = counts
+        .iter()
+        .map(|count| count.period.len())
+        .max()
+        .unwrap_or(0);
+    for count in counts {
+        let bar_width = if most == 0 {
+            0
+        } else {
+            (count.completed as usize * MAX_BAR_WIDTH).div_ceil(most as usize)
+        };
+        println!(
+            "{:<width$}  {:>4}  {}",
+            count.period,
+            count.completed,
+            "█".repeat(bar_width).bright_blue(),
+            width = width
+        );
+    }
+
+    let period = match args.per {
+        Period::Week => "week",
+        Period::Sprint => "sprint",
+    };
+    let average =
+        counts.iter().map(|count| count.completed).sum::<u64>() as f64 / counts.len().max(1) as f64;
+    let slope = (slope(counts) * 10.0).round() / 10.0;
+    if slope > 0.0 {
+        println!(
+            "Throughput is {} by about {} issues a {}, from an average of {:.1}.",
+            "rising".green(),
+            slope,
+            period,
+            average
+        );
+    } else if slope < 0.0 {
+        println!(
+            "Throughput is {} by about {} issues a {}, from an average of {:.1}.",
+            "falling".red(),
+            -slope,
+            period,
+            average
+        );
+    } else {
+        println!(
+            "Throughput is {} at an average of {:.1} issues a {}.",
+            "steady".yellow(),
+            average,
+            period
+        );
+    }
+}
+
+pub async fn run(args: &Throughput) {
+    let client = args.connection.connect().unwrap_or_else(exit_with_error);
+    let counts = throughput(&client, args)
+        .await
+        .unwrap_or_else(exit_with_error);
+
+    match args.format {
+        Some(format) => output::write_rows(format, &counts).unwrap_or_else(exit_with_error),
+        None => show(args, &counts),
+    }
+}
@@ -0,0 +1,255 @@
+use std::collections::{BTreeMap, HashMap};
+
+use clap::Args;
+use colored::Colorize;
+use serde::Serialize;
+
+use crate::{
+    cli::{
+        connection::Connection,
+        cycle_time::{self, percentile},
+        exit_with_error,
+        output::{self, Format},
+        scope::Scope,
+        timeline,
+    },
+    date::DateTime,
+    jira::{api::JiraApi, history},
+};
+
+/// The order statuses are shown in, by their category.  Statuses in deleted categories go last.
+const CATEGORY_ORDER: [&str; 2] = ["To Do", "In Progress"];
+
+#[derive(Debug, Args)]
+pub struct TimeInStatus {
+    /// csv or json, for a script to read.  Without this, the times are shown as a table for every issue, and then for
+    /// each issue type.
+    #[arg(long)]
+    pub format: Option<Format>,
+
+    #[command(flatten)]
+    pub scope: Scope,
+
+    #[command(flatten)]
+    pub connection: Connection,
+}
+
+/// How long issues of a type spent in a status, in days.
+///
+/// Each issue's time is added up across every visit to the status, so an issue sent back to it counts once, for longer.
+/// Issues still in the status count up to now, since those are the ones waiting in it.
+#[derive(Debug, Serialize)]
+struct StatusTime {
+    /// The issue type, or "All" for every issue.
+    issue_type: String,
+    status: String,
+    category: String,
+    issues: usize,
+    mean_days: f64,
+    median_days: Option<f64>,
+    p85_days: Option<f64>,
+    total_days: f64,
+    /// Where the status tends to come in the flow, for ordering statuses in the same category.
+    #[serde(skip)]
+    position: f64,
+}
+
+/// Each issue's days in a status, along with where the status came in that issue's history.
+#[derive(Default)]
+struct Dwell {
+    category: Option<String>,
+    days: Vec<f64>,
+    positions: Vec<usize>,
+}
+
+fn round(days: f64) -> f64 {
+    (days * 10.0).round() / 10.0
+}
+
+fn status_times(issue_type: &str, dwells: BTreeMap<String, Dwell>) -> Vec<StatusTime> {
+    let mut times: Vec<StatusTime> = dwells
+        .into_iter()
+        .map(|(status, dwell)| {
+            let mut days = dwell.days;
+            days.sort_by(f64::total_cmp);
+            let total: f64 = days.iter().sum();
+
+            StatusTime {
+                issue_type: issue_type.to_owned(),
+                status,
+                category: dwell.category.unwrap_or_default(),
+                issues: days.len(),
+                mean_days: round(total / days.len() as f64),
+                median_days: percentile(&days, 50.0),
+                p85_days: percentile(&days, 85.0),
+                total_days: round(total),
+                position: dwell.positions.iter().sum::<usize>() as f64
+                    / dwell.positions.len() as f64,
+            }
+        })
+        .collect();
+
+    times.sort_by(|a, b| {
+        let category = |time: &StatusTime| {
+            CATEGORY_ORDER
+                .iter()
+                .position(|category| *category == time.category)
+                .unwrap_or(CATEGORY_ORDER.len())
+        };
+        category(a)
+            .cmp(&category(b))
+            .then_with(|| a.position.total_cmp(&b.position))
+    });
+
+    times
+}
+
+async fn time_in_status(
+    client: &impl JiraApi,
+    args: &TimeInStatus,
+) -> Result<Vec<StatusTime>, String> {
+    let categories = history::status_categories(client)
+        .await
+        .map_err(|e| format!("Could not get the statuses: {}", e))?;
+    let fields = vec!["status".to_owned(), "issuetype".to_owned()];
+    let issues = timeline::search_with_changelogs(client, &args.scope, &fields).await?;
+
+    let now = DateTime::now();
+    let mut all: BTreeMap<String, Dwell> = BTreeMap::new();
+    let mut by_issue_type: BTreeMap<String, BTreeMap<String, Dwell>> = BTreeMap::new();
+    for issue in &issues {
+        let issue_type = cycle_time::issue_type(issue).unwrap_or_else(|| "None".to_owned());
+
+        // Add up each visit to a status, remembering where the status first came in the issue's history.
+        let mut days_in: HashMap<String, (f64, usize, Option<String>)> = HashMap::new();
+        for (position, period) in history::status_periods(issue, &categories)
+            .into_iter()
+            .enumerate()
+        {
+            // Time spent done isn't part of the flow.
+            if period.category.as_deref() == Some("Done") {
+                continue;
+            }
+            let days = cycle_time::days_between(period.entered_at, period.left_at.unwrap_or(now));
+            let entry = days_in
+                .entry(period.status)
+                .or_insert((0.0, position, period.category));
+            entry.0 += days;
+        }
+
+        for (status, (days, position, category)) in days_in {
+            for dwells in [
+                &mut all,
+                by_issue_type.entry(issue_type.clone()).or_default(),
+            ] {
+                let dwell = dwells.entry(status.clone()).or_default();
+                if dwell.category.is_none() {
+                    dwell.category = category.clone();
+                }
+                dwell.days.push(days);
+                dwell.positions.push(position);
+            }
+        }
+    }
+
+    let mut times = status_times("All", all);
+    for (issue_type, dwells) in by_issue_type {
+        times.extend(status_times(&issue_type, dwells));
+    }
+
+    Ok(times)
+}
+
+fn show(times: &[StatusTime]) {
+    let status_width = times
+        .iter()
+        .map(|time| time.status.chars().count())
+        .max()
+        .unwrap_or(0)
+        .max("Status".len());
+    let format_days = |days: Option<f64>| {
+        days.map(|days| format!("{:.1}", days))
+            .unwrap_or_else(|| "-".to_owned())
+    };
+
+    let mut start = 0;
+    while start < times.len() {
+        let issue_type = &times[start].issue_type;
+        let end = times[start..]
+            .iter()
+            .position(|time| &time.issue_type != issue_type)
+            .map(|length| start + length)
+            .unwrap_or(times.len());
+        let group = &times[start..end];
+        // Where the most time goes is where work is queueing up.
+        let most = group.iter().map(|time| time.total_days).fold(0.0, f64::max);
+
+        if start > 0 {
+            println!();
+        }
+        println!(
+            "{}",
+            if issue_type == "All" {
+                "All issues".to_owned()
+            } else {
+                issue_type.clone()
+            }
+            .bold()
+        );
+        println!(
+            "  {:<status_width$}  {:>6}  {:>6}  {:>6}  {:>6}  {:>8}",
+            "Status",
+            "Issues",
+            "Mean",
+            "Median",
+            "85%",
+            "Total",
+            status_width = status_width
+        );
+        for time in group {
+            let line = format!(
+                "  {:<status_width$}  {:>6}  {:>6.1}  {:>6}  {:>6}  {:>8.1}",
+                time.status,
+                time.issues,
+                time.mean_days,
+                format_days(time.median_days),
+                format_days(time.p85_days),
+                time.total_days,
+                status_width = status_width
+            );
+            if time.total_days == most && most > 0.0 {
+                println!(
+                    "{}  {}",
+                    line.bright_red(),
+                    "← most time is spent here".red()
+                );
+            } else {
+                println!("{}", line);
+            }
+        }
+
+        start = end;
+    }
+
+    if times.is_empty() {
+        println!("There are no issues to look at.");
+    } else {
+        println!();
+        println!(
+            "{}",
+            "Times are in days.  Issues still in a status count up to now.".dimmed()
+        );
+    }
+}
+
+pub async fn run(args: &TimeInStatus) {
+    let client = args.connection.connect().unwrap_or_else(exit_with_error);
+    let times = time_in_status(&client, args)
+        .await
+        .unwrap_or_else(exit_with_error);
+
+    match args.format {
+        Some(format) => output::write_rows(format, &times).unwrap_or_else(exit_with_error),
+        None => show(&times),
+    }
+}
@@ -0,0 +1,114 @@
+/// The options shared by every command that looks back over a range of days, worked out from issue changelogs.
+use clap::Args;
+
+use crate::{
+    cli::scope::Scope,
+    date::DateTime,
+    jira::{
+        self,
+        api::JiraApi,
+        client::SearchIssue,
+        jql::{builder::JQLBuilder, JQLValue, SortDirection},
+    },
+};
+
+/// How many days are looked back over when --since isn't given, which is the length of a typical sprint.
+const DEFAULT_DAYS: i64 = 14;
+
+#[derive(Clone, Debug, Args)]
+pub struct Timeline {
+    /// The first day to show, like 2024-01-01.  Defaults to two weeks before --until.
+    #[arg(long)]
+    pub since: Option<DateTime>,
+
+    /// The last day to show, like 2024-01-14.  Defaults to today.
+    #[arg(long)]
+    pub until: Option<DateTime>,
+}
+
+impl Timeline {
+    /// Returns each day in the range, as midnight UTC at its start.
+    pub fn days(&self) -> Result<Vec<DateTime>, String> {
+        let until = self.until.unwrap_or_else(DateTime::now).start_of_day();
+        let since = self
+            .since
+            .map(DateTime::start_of_day)
+            .unwrap_or_else(|| until.plus_days(1 - DEFAULT_DAYS));
+        if since.to_unix_seconds() > until.to_unix_seconds() {
+            return Err("--since has to be on or before --until.".to_owned());
+        }
+
+        let mut days = vec![];
+        let mut day = since;
+        while day.to_unix_seconds() <= until.to_unix_seconds() {
+            days.push(day);
+            day = day.plus_days(1);
+        }
+
+        Ok(days)
+    }
+}
+
+/// The last second of a day, which is when the day's figures are taken, so that changes made at midnight count towards
+/// the next one.
+pub fn end_of_day(day: DateTime) -> DateTime {
+    DateTime::from_unix_seconds((day.start_of_day().plus_days(1).to_unix_seconds() - 1) as u64)
+}
+
+/// Searches for the issues the options pick out, along with their histories.
+///
+/// `created` is always fetched on top of `fields`, since issues shouldn't be counted from before they existed.
+pub async fn search_with_changelogs(
+    client: &impl JiraApi,
+    scope: &Scope,
+    fields: &[String],
+) -> Result<Vec<SearchIssue>, String> {
+    search(client, scope, fields, |builder| builder).await
+}
+
+/// Searches for the issues the options pick out that are done now, and that could have been finished since a day,
+/// along with their histories.
+pub async fn search_completed_with_changelogs(
+    client: &impl JiraApi,
+    scope: &Scope,
+    fields: &[String],
+    since: DateTime,
+) -> Result<Vec<SearchIssue>, String> {
+    search(client, scope, fields, |builder| {
+        let builder = builder.status_category("Done");
+        // Finishing an issue updates it, so nothing last updated before the day could have been finished since.
+        if scope.updated_since.is_some() {
+            builder
+        } else {
+            builder.updated_since(JQLValue::String(since.to_date_string()))
+        }
+    })
+    .await
+}
+
+async fn search<F: FnOnce(JQLBuilder) -> JQLBuilder>(
+    client: &impl JiraApi,
+    scope: &Scope,
+    fields: &[String],
+    narrow: F,
+) -> Result<Vec<SearchIssue>, String> {
+    let mut fields = fields.to_vec();
+    fields.push("created".to_owned());
+
+    let jql = scope.builder(client).await.and_then(|builder| {
+        narrow(builder)
+            .order_by("key", SortDirection::Ascending)
+            .build()
+    })?;
+
+    if scope.validate_jql {
+        jira::validate_jql(client, &jql)
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+
+    client
+        .search_all_with_changelogs(&fields, &jql)
+        .await
+        .map_err(|e| format!("Could not search for issues: {}", e))
+}
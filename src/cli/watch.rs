@@ -0,0 +1,138 @@
+/// Runs a command over and over on a schedule, for a terminal left up on a wall.
+///
+/// Each run is the same command line started again as a child process, so that a run which fails, and exits as
+/// commands do on errors, is shown and then tried again at the next interval instead of ending the watch.
+use std::{
+    env,
+    io::{self, IsTerminal, Write},
+    process::{Command, Output},
+    thread,
+    time::Duration,
+};
+
+use clap::Args;
+use serde_json::{json, Value as JSONValue};
+
+use crate::{
+    cli::{connection, exit_with_error},
+    date::DateTime,
+};
+
+/// Set on the runs a watch starts, so that they run once instead of starting a watch of their own.
+const WATCHED_RUN_VARIABLE: &str = "STATUSTRACKER_WATCHED_RUN";
+
+/// Clears the terminal and moves the cursor to the top left.
+const CLEAR_SCREEN: &str = "\x1b[2J\x1b[H";
+
+#[derive(Debug, Args)]
+pub struct Watch {
+    /// Run the command again every --interval until stopped, redrawing the terminal with each run's output.  Commands
+    /// that make changes, like tag, apply, and import, can't be watched, since each run would make them again.
+    #[arg(long)]
+    #[arg(global = true)]
+    #[arg(default_value_t = false)]
+    pub watch: bool,
+
+    /// How long to wait between runs with --watch, like 30s, 15m, or 1h.
+    #[arg(long)]
+    #[arg(global = true)]
+    #[arg(default_value = "15m")]
+    #[arg(value_parser = connection::parse_duration)]
+    pub interval: Duration,
+
+    /// With --watch, print each run as a line of JSON instead of redrawing the terminal, so the runs can be appended
+    /// to a file.  Output that is JSON itself, like the estimate, is kept as JSON rather than as text.
+    #[arg(long)]
+    #[arg(global = true)]
+    #[arg(default_value_t = false)]
+    #[arg(requires = "watch")]
+    pub jsonl: bool,
+}
+
+impl Watch {
+    /// Whether this run should start a watch, rather than being a run of one.
+    pub fn is_watching(&self) -> bool {
+        self.watch && env::var_os(WATCHED_RUN_VARIABLE).is_none()
+    }
+}
+
+/// Runs the command line this was started with once more, capturing what it prints.
+fn run_once(colored: bool) -> io::Result<Output> {
+    let mut command = Command::new(env::current_exe()?);
+    command
+        .args(env::args_os().skip(1))
+        .env(WATCHED_RUN_VARIABLE, "1");
+    if colored {
+        // The run prints to a pipe rather than the terminal, so it has to be told to keep its colors.
+        command.env("CLICOLOR_FORCE", "1");
+    }
+
+    command.output()
+}
+
+/// Reads a run's output as JSON if it is JSON, and as text otherwise.
+fn output_value(output: &[u8]) -> JSONValue {
+    let output = String::from_utf8_lossy(output);
+    serde_json::from_str(&output).unwrap_or_else(|_| JSONValue::String(output.into_owned()))
+}
+
+fn print_jsonl(ran_at: DateTime, output: &io::Result<Output>) {
+    let line = match output {
+        Ok(output) => json!({
+            "ran_at": ran_at.to_rfc3339(),
+            "success": output.status.success(),
+            "output": output_value(&output.stdout),
+            "error": String::from_utf8_lossy(&output.stderr).trim(),
+        }),
+        Err(e) => json!({
+            "ran_at": ran_at.to_rfc3339(),
+            "success": false,
+            "output": null,
+            "error": format!("Could not run the command: {}", e),
+        }),
+    };
+
+    println!("{}", line);
+}
+
+fn redraw(ran_at: DateTime, next_run_at: DateTime, output: &io::Result<Output>) {
+    print!("{}", CLEAR_SCREEN);
+    match output {
+        Ok(output) => {
+            // A failed write to the terminal has nowhere better to be reported, so it is left for the next redraw.
+            let _ = io::stdout().write_all(&output.stdout);
+            let _ = io::stdout().write_all(&output.stderr);
+        }
+        Err(e) => println!("Could not run the command: {}", e),
+    }
+    println!();
+    println!(
+        "Last run at {} UTC.  Next run at {} UTC.  Press Ctrl-C to stop.",
+        ran_at.format("%H:%M:%S").unwrap_or_default(),
+        next_run_at.format("%H:%M:%S").unwrap_or_default()
+    );
+}
+
+pub fn run(args: &Watch) -> ! {
+    if args.interval.is_zero() {
+        exit_with_error("--interval has to be longer than zero.")
+    }
+
+    let colored = io::stdout().is_terminal();
+    loop {
+        let ran_at = DateTime::now();
+        let output = run_once(colored && !args.jsonl);
+
+        if args.jsonl {
+            print_jsonl(ran_at, &output);
+        } else {
+            let next_run_at = DateTime::from_unix_seconds(
+                (DateTime::now().to_unix_seconds() as u64) + args.interval.as_secs(),
+            );
+            redraw(ran_at, next_run_at, &output);
+        }
+        let _ = io::stdout().flush();
+
+        thread::sleep(args.interval);
+    }
+}
@@ -0,0 +1,175 @@
+use clap::Args;
+use colored::Colorize;
+
+use crate::{
+    cli::{connection::Connection, exit_with_error, scope::Scope},
+    jira::{
+        self,
+        api::JiraApi,
+        client::User,
+        jql::{SerializableToJQL, SortDirection},
+    },
+};
+
+/// The permissions commands need, and what goes wrong without each.
+const PERMISSIONS: [(&str, &str, &str); 5] = [
+    (
+        "BROWSE_PROJECTS",
+        "Browse projects",
+        "searches find nothing, without an error",
+    ),
+    (
+        "CREATE_ISSUES",
+        "Create issues",
+        "import can't create anything",
+    ),
+    (
+        "EDIT_ISSUES",
+        "Edit issues",
+        "tag and apply can't change anything",
+    ),
+    (
+        "SCHEDULE_ISSUES",
+        "Schedule issues",
+        "issues can't be moved between sprints",
+    ),
+    (
+        "MANAGE_WATCHER_LIST",
+        "Manage watchers",
+        "watch can only add and remove you",
+    ),
+];
+
+#[derive(Debug, Args)]
+pub struct Whoami {
+    #[command(flatten)]
+    pub scope: Scope,
+
+    #[command(flatten)]
+    pub connection: Connection,
+}
+
+fn show_user(user: &User, jira_url: &str) {
+    println!("Signed in to {} as {}", jira_url, user.display_name.bold());
+    if let Some(email_address) = &user.email_address {
+        println!("  Email:        {}", email_address);
+    }
+    if !user.account_id.is_empty() {
+        println!("  Account ID:   {}", user.account_id);
+    }
+    match user.account_type.as_deref() {
+        // Bots can be missing permissions people get through groups, which is an easy thing to overlook.
+        Some("app") => println!(
+            "  Account type: {}",
+            "app, which only has the permissions granted to it directly".yellow()
+        ),
+        Some(account_type) => println!("  Account type: {}", account_type),
+        None => {}
+    }
+}
+
+/// Shows which of the permissions commands need the signed in user has, in a project or in any project without one.
+async fn show_permissions(client: &impl JiraApi, project: Option<&str>) -> Result<(), String> {
+    let keys: Vec<&str> = PERMISSIONS.iter().map(|(key, _, _)| *key).collect();
+    let permissions = client
+        .get_my_permissions(project, &keys)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    for (key, name, without) in PERMISSIONS {
+        if permissions.get(key).copied().unwrap_or(false) {
+            println!("  {} {}", "✓".bright_green(), name);
+        } else {
+            println!(
+                "  {} {} {}",
+                "✗".bright_red(),
+                name,
+                format!("({})", without).dimmed()
+            );
+        }
+    }
+
+    Ok(())
+}
+
+async fn whoami(client: &impl JiraApi, args: &Whoami) -> Result<(), String> {
+    let user = client
+        .get_myself()
+        .await
+        .map_err(|e| format!("Could not find out who is signed in: {}", e))?;
+    show_user(&user, &args.connection.jira_url);
+
+    if args.scope.jira_project.is_empty() {
+        println!();
+        println!("Permissions in at least one project:");
+        show_permissions(client, None)
+            .await
+            .map_err(|e| format!("Could not get your permissions: {}", e))?;
+    }
+    for project in &args.scope.jira_project {
+        println!();
+        // JIRA answers the same way for a project that doesn't exist and one the user can't see.
+        match client.get_project(project).await {
+            Ok(found) => println!("Permissions in {} ({}):", found.key, found.name),
+            Err(e) => {
+                println!(
+                    "{} {}: {}",
+                    "✗".bright_red(),
+                    project.bold(),
+                    format!("can't be seen, either because it doesn't exist or because you can't browse it ({})", e)
+                        .bright_red()
+                );
+                continue;
+            }
+        }
+        if let Err(e) = show_permissions(client, Some(project)).await {
+            println!(
+                "  {} Could not get your permissions: {}",
+                "✗".bright_red(),
+                e
+            );
+        }
+    }
+
+    // Searching is the last step, since JIRA leaves out what can't be seen rather than saying so.
+    println!();
+    let jql = match args
+        .scope
+        .builder(client)
+        .await
+        .and_then(|builder| builder.order_by("key", SortDirection::Ascending).build())
+    {
+        Ok(jql) => jql,
+        Err(e) => {
+            println!("{} Not searching: {}", "!".bright_yellow(), e);
+            return Ok(());
+        }
+    };
+    if args.scope.validate_jql {
+        if let Err(e) = jira::validate_jql(client, &jql).await {
+            println!("{} {}", "✗".bright_red(), e);
+            return Ok(());
+        }
+    }
+    match client.search_all(&["status".to_owned()], &jql).await {
+        Ok(issues) if issues.is_empty() => println!(
+            "{} The search finds no issues: {}",
+            "!".bright_yellow(),
+            jql.serialize_to_jql()
+        ),
+        Ok(issues) => println!(
+            "{} The search finds {} issues: {}",
+            "✓".bright_green(),
+            issues.len(),
+            jql.serialize_to_jql()
+        ),
+        Err(e) => println!("{} The search failed: {}", "✗".bright_red(), e),
+    }
+
+    Ok(())
+}
+
+pub async fn run(args: &Whoami) {
+    let client = args.connection.connect().unwrap_or_else(exit_with_error);
+    whoami(&client, args).await.unwrap_or_else(exit_with_error);
+}
@@ -0,0 +1,270 @@
+/// Calendar dates and times in UTC, worked out from the system clock.
+///
+/// This only covers the little the tool needs, like timestamping log lines, so it does not pull in a whole date and
+/// time library.
+use std::{
+    str::FromStr,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+/// A moment in UTC, to the second.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DateTime {
+    pub year: i64,
+    pub month: u32,
+    pub day: u32,
+    pub hour: u32,
+    pub minute: u32,
+    pub second: u32,
+}
+
+impl DateTime {
+    pub fn now() -> Self {
+        let seconds = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs())
+            .unwrap_or(0);
+
+        DateTime::from_unix_seconds(seconds)
+    }
+
+    pub fn from_unix_seconds(seconds: u64) -> Self {
+        let (year, month, day) = civil_from_days((seconds / SECONDS_PER_DAY) as i64);
+        let seconds_into_day = seconds % SECONDS_PER_DAY;
+
+        DateTime {
+            year,
+            month,
+            day,
+            hour: (seconds_into_day / 3600) as u32,
+            minute: (seconds_into_day % 3600 / 60) as u32,
+            second: (seconds_into_day % 60) as u32,
+        }
+    }
+
+    /// Formats the time with strftime-style specifiers, like `%Y-%m` for 2023-02.
+    ///
+    /// Only numeric specifiers are supported: %Y, %y, %m, %d, %H, %M, %S, %j (day of the year), %u (day of the week,
+    /// starting from Monday as 1), %G and %V (ISO 8601 year and week), and %% for a literal percent sign.  Anything
+    /// else is an error, which holds the specifier that was not understood.
+    pub fn format(self, pattern: &str) -> Result<String, String> {
+        let mut formatted = String::new();
+        let mut chars = pattern.chars();
+
+        while let Some(c) = chars.next() {
+            if c != '%' {
+                formatted.push(c);
+                continue;
+            }
+
+            let specifier = chars.next();
+            let (iso_year, iso_week) = self.iso_week();
+            match specifier {
+                Some('Y') => formatted.push_str(&format!("{:04}", self.year)),
+                Some('y') => formatted.push_str(&format!("{:02}", self.year.rem_euclid(100))),
+                Some('m') => formatted.push_str(&format!("{:02}", self.month)),
+                Some('d') => formatted.push_str(&format!("{:02}", self.day)),
+                Some('H') => formatted.push_str(&format!("{:02}", self.hour)),
+                Some('M') => formatted.push_str(&format!("{:02}", self.minute)),
+                Some('S') => formatted.push_str(&format!("{:02}", self.second)),
+                Some('j') => formatted.push_str(&format!("{:03}", self.day_of_year())),
+                Some('u') => formatted.push_str(&self.weekday().to_string()),
+                Some('G') => formatted.push_str(&format!("{:04}", iso_year)),
+                Some('V') => formatted.push_str(&format!("{:02}", iso_week)),
+                Some('%') => formatted.push('%'),
+                Some(other) => return Err(format!("%{}", other)),
+                None => return Err("%".to_owned()),
+            }
+        }
+
+        Ok(formatted)
+    }
+
+    /// The day of the year, starting from 1 on January 1st.
+    fn day_of_year(self) -> i64 {
+        days_from_civil(self.year, self.month, self.day) - days_from_civil(self.year, 1, 1) + 1
+    }
+
+    /// The day of the week, from 1 for Monday through 7 for Sunday.
+    fn weekday(self) -> i64 {
+        weekday(days_from_civil(self.year, self.month, self.day))
+    }
+
+    /// The ISO 8601 week-numbering year and week, where weeks start on Monday and week 1 is the one with the year's
+    /// first Thursday in it.
+    fn iso_week(self) -> (i64, i64) {
+        let week = (self.day_of_year() - self.weekday() + 10) / 7;
+
+        if week < 1 {
+            (self.year - 1, iso_weeks_in_year(self.year - 1))
+        } else if week > iso_weeks_in_year(self.year) {
+            (self.year + 1, 1)
+        } else {
+            (self.year, week)
+        }
+    }
+
+    /// Reads a timestamp like 2023-02-14T09:30:00.000+0100, as JIRA sends them in headers, converting it to UTC.
+    ///
+    /// Seconds and fractions of a second are optional, and fractions are dropped.  The offset can be `Z`, or hours and
+    /// minutes with or without a colon.
+    pub fn parse_rfc3339(timestamp: &str) -> Option<Self> {
+        let (date, time) = timestamp.trim().split_once(['T', 't'])?;
+
+        let mut date = date.splitn(3, '-');
+        let year: i64 = date.next()?.parse().ok()?;
+        let month: u32 = date.next()?.parse().ok()?;
+        let day: u32 = date.next()?.parse().ok()?;
+        if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+            return None;
+        }
+
+        let (time, offset_seconds) = if let Some(time) = time.strip_suffix(['Z', 'z']) {
+            (time, 0)
+        } else {
+            let sign_at = time.rfind(['+', '-'])?;
+            let (time, offset) = time.split_at(sign_at);
+            let sign = if offset.starts_with('-') { -1 } else { 1 };
+            let digits: String = offset[1..].chars().filter(|c| *c != ':').collect();
+            if digits.len() != 4 {
+                return None;
+            }
+            let hours: i64 = digits[..2].parse().ok()?;
+            let minutes: i64 = digits[2..].parse().ok()?;
+            (time, sign * (hours * 3600 + minutes * 60))
+        };
+
+        let time = time.split('.').next()?;
+        let mut time = time.splitn(3, ':');
+        let hour: i64 = time.next()?.parse().ok()?;
+        let minute: i64 = time.next()?.parse().ok()?;
+        let second: i64 = match time.next() {
+            Some(second) => second.parse().ok()?,
+            None => 0,
+        };
+        if hour > 23 || minute > 59 || second > 60 {
+            return None;
+        }
+
+        let seconds = days_from_civil(year, month, day) * SECONDS_PER_DAY as i64
+            + hour * 3600
+            + minute * 60
+            + second
+            - offset_seconds;
+
+        if seconds < 0 {
+            return None;
+        }
+
+        Some(DateTime::from_unix_seconds(seconds as u64))
+    }
+
+    pub fn to_unix_seconds(self) -> i64 {
+        days_from_civil(self.year, self.month, self.day) * SECONDS_PER_DAY as i64
+            + self.hour as i64 * 3600
+            + self.minute as i64 * 60
+            + self.second as i64
+    }
+
+    /// Midnight at the start of the same day.
+    pub fn start_of_day(self) -> Self {
+        DateTime {
+            hour: 0,
+            minute: 0,
+            second: 0,
+            ..self
+        }
+    }
+
+    /// Midnight at the start of the Monday of the same week.
+    pub fn start_of_week(self) -> Self {
+        self.start_of_day().plus_days(1 - self.weekday())
+    }
+
+    /// The same time of day, some number of days later, or earlier for a negative number.
+    pub fn plus_days(self, days: i64) -> Self {
+        let seconds = self.to_unix_seconds() + days * SECONDS_PER_DAY as i64;
+
+        DateTime::from_unix_seconds(seconds.max(0) as u64)
+    }
+
+    /// Formats the date like 2023-02-14.
+    pub fn to_date_string(self) -> String {
+        format!("{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+
+    /// Formats the time like 2023-02-14T09:30:00Z.
+    pub fn to_rfc3339(self) -> String {
+        format!(
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+            self.year, self.month, self.day, self.hour, self.minute, self.second
+        )
+    }
+}
+
+/// Reads a date like 2023-02-14 as midnight at its start, or a whole timestamp as `parse_rfc3339` does.
+impl FromStr for DateTime {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        DateTime::parse_rfc3339(s)
+            .or_else(|| DateTime::parse_rfc3339(&format!("{}T00:00:00Z", s.trim())))
+            .ok_or_else(|| format!("\"{}\" is not a date; it should look like 2023-02-14", s))
+    }
+}
+
+/// Counts the days from 1970-01-01 to a year, month, and day.
+///
+/// See http://howardhinnant.github.io/date_algorithms.html#days_from_civil
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let shifted_month = if month > 2 { month - 3 } else { month + 9 } as i64;
+    let day_of_year = (153 * shifted_month + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// The day of the week for a count of days since 1970-01-01, from 1 for Monday through 7 for Sunday.
+fn weekday(days: i64) -> i64 {
+    // 1970-01-01 was a Thursday.
+    (days + 3).rem_euclid(7) + 1
+}
+
+/// Years have 53 ISO weeks when they start on a Thursday, or when they are leap years that start on a Wednesday.
+fn iso_weeks_in_year(year: i64) -> i64 {
+    let starts_on = weekday(days_from_civil(year, 1, 1));
+    let is_leap_year = year % 4 == 0 && (year % 100 != 0 || year % 400 == 0);
+
+    if starts_on == 4 || (is_leap_year && starts_on == 3) {
+        53
+    } else {
+        52
+    }
+}
+
+/// Turns a count of days since 1970-01-01 into a year, month, and day.
+///
+/// See http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * shifted_month + 2) / 5 + 1) as u32;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    } as u32;
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    (year, month, day)
+}
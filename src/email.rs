@@ -0,0 +1,98 @@
+/// Sends email through the local sendmail, which every mail server ships a stand-in for, so that there are no SMTP
+/// servers or passwords to configure here.
+use std::{
+    io::Write,
+    path::Path,
+    process::{Command, Stdio},
+};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+
+/// How long each line of the base64 body can be, which mail servers expect to be no more than 76 characters.
+const LINE_LENGTH: usize = 76;
+
+/// Refuses header values with line breaks in them, which would let them add headers of their own.
+fn header_value<'a>(name: &str, value: &'a str) -> Result<&'a str, String> {
+    if value.contains(['\r', '\n']) {
+        return Err(format!(
+            "The email's {} can't have a line break in it.",
+            name
+        ));
+    }
+
+    Ok(value)
+}
+
+/// Encodes a subject with anything other than plain ASCII in it as RFC 2047 says to.
+fn encode_subject(subject: &str) -> String {
+    if subject.is_ascii() {
+        subject.to_owned()
+    } else {
+        format!("=?utf-8?B?{}?=", STANDARD.encode(subject))
+    }
+}
+
+/// Puts together an email with an HTML body, leaving the Date and Message-ID headers for sendmail to fill in.
+fn message(from: Option<&str>, to: &[String], subject: &str, html: &str) -> Result<String, String> {
+    let mut message = String::new();
+    if let Some(from) = from {
+        message.push_str(&format!("From: {}\r\n", header_value("sender", from)?));
+    }
+    for recipient in to {
+        header_value("recipient", recipient)?;
+    }
+    message.push_str(&format!("To: {}\r\n", to.join(", ")));
+    message.push_str(&format!(
+        "Subject: {}\r\n",
+        encode_subject(header_value("subject", subject)?)
+    ));
+    message.push_str("MIME-Version: 1.0\r\n");
+    message.push_str("Content-Type: text/html; charset=utf-8\r\n");
+    message.push_str("Content-Transfer-Encoding: base64\r\n\r\n");
+
+    let body = STANDARD.encode(html);
+    for line in body.as_bytes().chunks(LINE_LENGTH) {
+        // Unwrap here is considered safe since base64 is always ASCII.
+        message.push_str(std::str::from_utf8(line).unwrap());
+        message.push_str("\r\n");
+    }
+
+    Ok(message)
+}
+
+/// Sends an HTML email to everyone in `to` by piping it to sendmail, which reads the recipients from the headers.
+pub fn send_html(
+    sendmail: &Path,
+    from: Option<&str>,
+    to: &[String],
+    subject: &str,
+    html: &str,
+) -> Result<(), String> {
+    let message = message(from, to, subject, html)?;
+
+    let mut child = Command::new(sendmail)
+        .args(["-t", "-i"])
+        .stdin(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Could not run {}: {}", sendmail.display(), e))?;
+    // Unwrap here is considered safe since stdin was piped above.
+    let mut stdin = child.stdin.take().unwrap();
+    stdin
+        .write_all(message.as_bytes())
+        .map_err(|e| format!("Could not hand the email to {}: {}", sendmail.display(), e))?;
+    // Closing stdin is what tells sendmail the message is over.
+    drop(stdin);
+
+    let status = child
+        .wait()
+        .map_err(|e| format!("Could not run {}: {}", sendmail.display(), e))?;
+    if !status.success() {
+        return Err(format!(
+            "{} could not send the email ({}).",
+            sendmail.display(),
+            status
+        ));
+    }
+
+    Ok(())
+}
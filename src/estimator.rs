@@ -0,0 +1,238 @@
+/// Adds up how much work is left in some issues, and how many sprints it will take a team to do it.
+use serde::Serialize;
+
+use crate::jira::{
+    self,
+    api::JiraApi,
+    client::{RequestError, SearchIssue},
+    jql::JQLStatement,
+};
+
+/// The story points issues without any are counted as, unless told otherwise.
+pub const DEFAULT_STORY_POINTS: f64 = 3.0;
+
+/// Where an issue stands, for estimating.
+pub enum ClassifiedIssue {
+    Complete,
+    IncompleteAndPointed(f64),
+    IncompleteAndUnpointed,
+}
+
+/// Works out where an issue stands.  A zero is taken to mean the issue hasn't been pointed yet, rather than that it's no
+/// work at all.
+pub fn classify(issue: &SearchIssue, field_ids: &[String]) -> ClassifiedIssue {
+    if let Some(status) = &issue.status_category() {
+        if status == "Done" {
+            return ClassifiedIssue::Complete;
+        }
+    }
+
+    if let Some(points) = jira::story_points(issue, field_ids) {
+        if points == 0.0 {
+            return ClassifiedIssue::IncompleteAndUnpointed;
+        }
+
+        return ClassifiedIssue::IncompleteAndPointed(points);
+    }
+
+    ClassifiedIssue::IncompleteAndUnpointed
+}
+
+/// How much work is left in some issues.  Every count is a float, so that they can be laid out alongside the points.
+#[derive(Serialize)]
+pub struct Results {
+    pub default_story_points: f64,
+    pub num_complete: f64,
+    pub num_incomplete_and_pointed: f64,
+    pub num_incomplete_and_unpointed: f64,
+    pub num_sprints_remaining: f64,
+    pub unfinished_estimated_story_points: f64,
+    pub unfinished_story_points: f64,
+    pub unfinished_unestimated_story_points: f64,
+    pub velocity_in_story_points: f64,
+}
+
+impl Results {
+    pub fn new(default_story_points: f64, velocity_in_story_points: f64) -> Results {
+        Results {
+            default_story_points,
+            num_complete: 0.0,
+            num_incomplete_and_pointed: 0.0,
+            num_incomplete_and_unpointed: 0.0,
+            num_sprints_remaining: 0.0,
+            unfinished_estimated_story_points: 0.0,
+            unfinished_story_points: 0.0,
+            unfinished_unestimated_story_points: 0.0,
+            velocity_in_story_points,
+        }
+    }
+
+    /// Counts up the issues from one JIRA site, whose story point fields have the given IDs.
+    pub fn tally(&mut self, issues: &[SearchIssue], field_ids: &[String]) {
+        for issue in issues {
+            match classify(issue, field_ids) {
+                ClassifiedIssue::Complete => {
+                    self.num_complete += 1.0;
+                }
+                ClassifiedIssue::IncompleteAndPointed(points) => {
+                    self.num_incomplete_and_pointed += 1.0;
+                    self.unfinished_estimated_story_points += points;
+                }
+                ClassifiedIssue::IncompleteAndUnpointed => {
+                    self.num_incomplete_and_unpointed += 1.0;
+                }
+            };
+        }
+
+        self.unfinished_unestimated_story_points =
+            self.num_incomplete_and_unpointed * self.default_story_points;
+        self.unfinished_story_points =
+            self.unfinished_estimated_story_points + self.unfinished_unestimated_story_points;
+        self.num_sprints_remaining = self.unfinished_story_points / self.velocity_in_story_points;
+    }
+}
+
+/// Estimates how much work is left in the issues a search finds.
+///
+/// ```no_run
+/// # use statustracker::{jira::{client::Client, jql::JQLStatement}, Estimator};
+/// # async fn estimate(client: &Client, jql: &JQLStatement, field_ids: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+/// let results = Estimator::new(20.0)
+///     .default_story_points(5.0)
+///     .estimate(client, jql, field_ids)
+///     .await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct Estimator {
+    default_story_points: f64,
+    velocity_in_story_points: f64,
+}
+
+impl Estimator {
+    /// Estimates for a team that finishes this many story points a sprint, which has to be more than zero.
+    pub fn new(velocity_in_story_points: f64) -> Self {
+        Estimator {
+            default_story_points: DEFAULT_STORY_POINTS,
+            velocity_in_story_points,
+        }
+    }
+
+    /// Sets the story points to count issues without any as.
+    pub fn default_story_points(mut self, default_story_points: f64) -> Self {
+        self.default_story_points = default_story_points;
+        self
+    }
+
+    /// Results with nothing counted yet, to [tally](Estimator::tally) issues from several searches into.
+    pub fn results(&self) -> Results {
+        Results::new(self.default_story_points, self.velocity_in_story_points)
+    }
+
+    /// Searches JIRA and estimates the work left in the issues found.  The story points are read from the fields with the
+    /// given IDs, which [jira::get_field_ids_named] can look up.
+    pub async fn estimate(
+        &self,
+        client: &impl JiraApi,
+        jql: &JQLStatement,
+        story_point_field_ids: &[String],
+    ) -> Result<Results, RequestError> {
+        let mut results = self.results();
+        self.tally(client, jql, story_point_field_ids, &mut results)
+            .await?;
+
+        Ok(results)
+    }
+
+    /// Searches JIRA and adds the issues found to results, which may already have issues from another search or site in
+    /// them.
+    pub async fn tally(
+        &self,
+        client: &impl JiraApi,
+        jql: &JQLStatement,
+        story_point_field_ids: &[String],
+        results: &mut Results,
+    ) -> Result<(), RequestError> {
+        let mut fields = story_point_field_ids.to_vec();
+        fields.push("status".to_owned());
+        let issues = client.search_all(&fields, jql).await?;
+        results.tally(&issues, story_point_field_ids);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::jira::{
+        api::fake::{block_on, FakeJira},
+        jql::parser,
+    };
+
+    fn issue(key: &str, status_category: &str, points: Option<f64>) -> serde_json::Value {
+        json!({
+            "key": key,
+            "fields": {
+                "status": {"name": status_category, "statusCategory": {"name": status_category}},
+                "customfield_10016": points,
+            },
+        })
+    }
+
+    fn field_ids() -> Vec<String> {
+        vec!["customfield_10016".to_owned()]
+    }
+
+    #[test]
+    fn tally_counts_issues_by_where_they_stand() {
+        let client = FakeJira::with_issues(vec![
+            issue("TEAM-1", "Done", Some(8.0)),
+            issue("TEAM-2", "In Progress", Some(5.0)),
+            issue("TEAM-3", "To Do", Some(2.0)),
+            issue("TEAM-4", "To Do", None),
+            // Zero means nobody has pointed the issue yet.
+            issue("TEAM-5", "To Do", Some(0.0)),
+        ]);
+        let jql = parser::parse("project = TEAM").unwrap();
+
+        let results = block_on(Estimator::new(4.0).default_story_points(1.5).estimate(
+            &client,
+            &jql,
+            &field_ids(),
+        ))
+        .unwrap();
+
+        assert_eq!(results.num_complete, 1.0);
+        assert_eq!(results.num_incomplete_and_pointed, 2.0);
+        assert_eq!(results.num_incomplete_and_unpointed, 2.0);
+        assert_eq!(results.unfinished_estimated_story_points, 7.0);
+        assert_eq!(results.unfinished_unestimated_story_points, 3.0);
+        assert_eq!(results.unfinished_story_points, 10.0);
+        assert_eq!(results.num_sprints_remaining, 2.5);
+    }
+
+    #[test]
+    fn tally_adds_to_results_from_another_search() {
+        let estimator = Estimator::new(5.0);
+        let jql = parser::parse("project = TEAM").unwrap();
+        let mut results = estimator.results();
+
+        for client in [
+            FakeJira::with_issues(vec![issue("TEAM-1", "To Do", Some(5.0))]),
+            FakeJira::with_issues(vec![
+                issue("OPS-1", "To Do", None),
+                issue("OPS-2", "Done", None),
+            ]),
+        ] {
+            block_on(estimator.tally(&client, &jql, &field_ids(), &mut results)).unwrap();
+        }
+
+        assert_eq!(results.num_complete, 1.0);
+        assert_eq!(results.unfinished_story_points, 5.0 + DEFAULT_STORY_POINTS);
+        assert_eq!(results.num_sprints_remaining, 1.6);
+    }
+}
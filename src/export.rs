@@ -0,0 +1,217 @@
+/// Writes issues out as rows of a CSV file, with their story points, status, and a link to each.
+///
+/// This is the layout of the csv command's output and of what serve hands out at `/csv`.
+use std::{
+    error::Error,
+    fmt::{self, Display},
+    io,
+};
+
+use futures_util::StreamExt;
+use serde::Serialize;
+
+use crate::jira::{
+    self,
+    api::JiraApi,
+    client::{RequestError, SearchIssue},
+    jql::JQLStatement,
+};
+
+/// A row of the export.
+#[derive(Debug, Serialize)]
+pub struct CsvIssue {
+    #[serde(rename(serialize = "ID"))]
+    pub key: String,
+
+    #[serde(rename(serialize = "Story Points"))]
+    pub story_points: Option<f64>,
+
+    #[serde(rename(serialize = "Status"))]
+    pub status: Option<String>,
+
+    #[serde(rename(serialize = "Link"))]
+    pub link: String,
+}
+
+impl CsvIssue {
+    pub fn new(issue: &SearchIssue, field_ids: &[String], jira_url: &str) -> Self {
+        CsvIssue {
+            key: issue.key.clone(),
+            story_points: jira::story_points(issue, field_ids),
+            status: issue.status_category(),
+            link: format!("{}/browse/{}", jira_url, &issue.key),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ExportError {
+    Search(RequestError),
+    /// A row couldn't be written, as the issue with this key.
+    Write(String, csv::Error),
+}
+
+impl Display for ExportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExportError::Search(inner) => write!(f, "Could not search for issues: {}", inner),
+            ExportError::Write(key, inner) => {
+                write!(f, "Could not write a row for {}: {}", key, inner)
+            }
+        }
+    }
+}
+
+impl Error for ExportError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ExportError::Search(inner) => Some(inner),
+            ExportError::Write(_, inner) => Some(inner),
+        }
+    }
+}
+
+/// Writes a row for each issue a search finds, or for issues already fetched.
+///
+/// ```no_run
+/// # use statustracker::{jira::{client::Client, jql::JQLStatement}, IssueExporter};
+/// # async fn export(client: &Client, jql: &JQLStatement, field_ids: Vec<String>) -> Result<(), Box<dyn std::error::Error>> {
+/// let mut writer = csv::Writer::from_writer(std::io::stdout());
+/// IssueExporter::new("https://example.atlassian.net", field_ids)
+///     .export(client, jql, &mut writer)
+///     .await?;
+/// writer.flush()?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct IssueExporter {
+    jira_url: String,
+    story_point_field_ids: Vec<String>,
+}
+
+impl IssueExporter {
+    /// Exports issues from the site at `jira_url`, reading their story points from the fields with the given IDs.
+    pub fn new(jira_url: &str, story_point_field_ids: Vec<String>) -> Self {
+        IssueExporter {
+            jira_url: jira_url.to_owned(),
+            story_point_field_ids,
+        }
+    }
+
+    /// The fields issues have to be fetched with for their rows to be filled in.
+    pub fn fields(&self) -> Vec<String> {
+        let mut fields = self.story_point_field_ids.clone();
+        fields.push("status".to_owned());
+        fields
+    }
+
+    pub fn row(&self, issue: &SearchIssue) -> CsvIssue {
+        CsvIssue::new(issue, &self.story_point_field_ids, &self.jira_url)
+    }
+
+    /// Writes a row for each issue, which have to have been fetched with [the fields](IssueExporter::fields).
+    pub fn write_issues<W: io::Write>(
+        &self,
+        issues: &[SearchIssue],
+        writer: &mut csv::Writer<W>,
+    ) -> Result<(), ExportError> {
+        for issue in issues {
+            writer
+                .serialize(self.row(issue))
+                .map_err(|e| ExportError::Write(issue.key.clone(), e))?;
+        }
+
+        Ok(())
+    }
+
+    /// Searches JIRA and writes a row for every issue found, returning how many there were.
+    ///
+    /// Rows are written as each page of issues arrives, so exporting a huge project doesn't hold it all in memory.
+    pub async fn export<W: io::Write>(
+        &self,
+        client: &impl JiraApi,
+        jql: &JQLStatement,
+        writer: &mut csv::Writer<W>,
+    ) -> Result<usize, ExportError> {
+        let fields = self.fields();
+        let issues = client.search_stream(&fields, jql);
+        tokio::pin!(issues);
+        let mut exported = 0;
+        while let Some(issue) = issues.next().await {
+            let issue = issue.map_err(ExportError::Search)?;
+            writer
+                .serialize(self.row(&issue))
+                .map_err(|e| ExportError::Write(issue.key.clone(), e))?;
+            exported += 1;
+        }
+
+        Ok(exported)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::jira::{
+        api::fake::{block_on, FakeJira},
+        jql::parser,
+    };
+
+    fn client() -> FakeJira {
+        FakeJira::with_issues(vec![
+            json!({
+                "key": "TEAM-1",
+                "fields": {
+                    "status": {"name": "Closed", "statusCategory": {"name": "Done"}},
+                    "customfield_10016": 3.5,
+                },
+            }),
+            json!({
+                "key": "TEAM-2",
+                "fields": {"status": {"name": "Open", "statusCategory": {"name": "To Do"}}},
+            }),
+        ])
+    }
+
+    const EXPECTED: &str = "\
+ID,Story Points,Status,Link
+TEAM-1,3.5,Done,https://example.atlassian.net/browse/TEAM-1
+TEAM-2,,To Do,https://example.atlassian.net/browse/TEAM-2
+";
+
+    fn exporter() -> IssueExporter {
+        IssueExporter::new(
+            "https://example.atlassian.net",
+            vec!["customfield_10016".to_owned()],
+        )
+    }
+
+    fn written(writer: csv::Writer<Vec<u8>>) -> String {
+        String::from_utf8(writer.into_inner().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn write_issues_writes_a_row_for_each_issue() {
+        let jql = parser::parse("project = TEAM").unwrap();
+        let issues = block_on(client().search_all(&exporter().fields(), &jql)).unwrap();
+        let mut writer = csv::Writer::from_writer(vec![]);
+
+        exporter().write_issues(&issues, &mut writer).unwrap();
+
+        assert_eq!(written(writer), EXPECTED);
+    }
+
+    #[test]
+    fn export_writes_the_same_rows_as_write_issues() {
+        let jql = parser::parse("project = TEAM").unwrap();
+        let mut writer = csv::Writer::from_writer(vec![]);
+
+        let exported = block_on(exporter().export(&client(), &jql, &mut writer)).unwrap();
+
+        assert_eq!(exported, 2);
+        assert_eq!(written(writer), EXPECTED);
+    }
+}
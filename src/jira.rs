@@ -1,14 +1,31 @@
 /// Contains all code related to interfacing with JIRA.
 /// This includes functionality for getting projects and breaking them down into initiatives.
-use std::fmt::{self, Display};
+use std::{
+    collections::{BTreeMap, HashMap},
+    error::Error,
+    fmt::{self, Display},
+    sync::Mutex,
+};
+
+use futures_util::StreamExt;
+use serde_json::Value as JSONValue;
 
-use jimberlage_jira_client::{
-    jql::{JQLClause, JQLStatement, JQLValue},
-    RestClient, SearchIssue,
+use self::{
+    api::JiraApi,
+    client::{Field, LinkDirection, SearchIssue, Worklog},
+    jql::{builder::EpicRelation, JQLStatement, SerializableToJQL},
 };
-use reqwest;
+use crate::date::DateTime;
+
+pub mod adf;
+pub mod api;
+pub mod client;
+pub mod confluence;
+pub mod debug;
+pub mod history;
+pub mod jql;
 
-pub fn story_points(issue: &SearchIssue, field_ids: &Vec<String>) -> Option<f64> {
+pub fn story_points(issue: &SearchIssue, field_ids: &[String]) -> Option<f64> {
     for field_id in field_ids {
         if let Some(points) = issue.numeric_field(field_id) {
             return Some(points);
@@ -18,12 +35,202 @@ pub fn story_points(issue: &SearchIssue, field_ids: &Vec<String>) -> Option<f64>
     None
 }
 
-pub fn get_story_point_field_ids(
-    client: &RestClient,
+/// How much time was logged against some issues, in all and by each person who logged it.
+#[derive(Debug, Default)]
+pub struct TimeSpent {
+    pub total_seconds: u64,
+    /// Keyed by display name, which is what people will want to read in a report.
+    pub seconds_by_author: BTreeMap<String, u64>,
+}
+
+/// Adds up the time in worklogs, leaving out any work started before `since`.
+///
+/// Work with no author, or started at a time that can't be read, still counts towards the total.
+pub fn time_spent(worklogs: &[Worklog], since: Option<DateTime>) -> TimeSpent {
+    let mut time_spent = TimeSpent::default();
+
+    for worklog in worklogs {
+        if let (Some(since), Some(started_at)) = (since, worklog.started_at()) {
+            if started_at.to_unix_seconds() < since.to_unix_seconds() {
+                continue;
+            }
+        }
+
+        time_spent.total_seconds += worklog.time_spent_seconds;
+        let author = worklog
+            .author
+            .as_ref()
+            .map(|author| author.display_name.clone())
+            .unwrap_or_else(|| "Unknown".to_owned());
+        *time_spent.seconds_by_author.entry(author).or_insert(0) += worklog.time_spent_seconds;
+    }
+
+    time_spent
+}
+
+/// Works out which issues each issue is waiting on, from the `Blocks` links between them.
+///
+/// Every issue is in the map, even ones that aren't blocked by anything, along with issues outside of them that they
+/// block.  A link only has to be on one end for it to count, since a search may find the issue that is blocked without
+/// the one blocking it, or the other way around.  The issues need to have been fetched with the `issuelinks` field.
+pub fn dependencies(issues: &[SearchIssue]) -> HashMap<String, Vec<String>> {
+    let mut dependencies: HashMap<String, Vec<String>> = issues
+        .iter()
+        .map(|issue| (issue.key.clone(), vec![]))
+        .collect();
+
+    for issue in issues {
+        for link in issue.issue_links() {
+            if link.link_type != "Blocks" {
+                continue;
+            }
+
+            let (blocked, blocker) = match link.direction {
+                LinkDirection::Inward => (issue.key.clone(), link.key),
+                LinkDirection::Outward => (link.key, issue.key.clone()),
+            };
+            let blockers = dependencies.entry(blocked).or_default();
+            if !blockers.contains(&blocker) {
+                blockers.push(blocker);
+            }
+        }
+    }
+
+    dependencies
+}
+
+/// Works out the names of the people issues are assigned to, for showing in reports.
+///
+/// JIRA Cloud identifies people by account ID, and depending on their privacy settings may leave their names out of
+/// search results, so those are looked up, once each.  JIRA Data Center identifies people by username instead, which is
+/// readable enough on its own.
+#[derive(Default)]
+pub struct AssigneeNames {
+    by_account_id: Mutex<HashMap<String, String>>,
+}
+
+impl AssigneeNames {
+    pub fn new() -> Self {
+        AssigneeNames::default()
+    }
+
+    /// Returns the name of the person an issue is assigned to, or `None` if it is unassigned.  The issue needs to have
+    /// been fetched with the `assignee` field.
+    ///
+    /// If an account ID can't be looked up, it is shown as it is rather than failing the whole report.
+    pub async fn assignee(&self, client: &impl JiraApi, issue: &SearchIssue) -> Option<String> {
+        let assignee = issue.fields.get("assignee").filter(|a| !a.is_null())?;
+        let field = |name: &str| {
+            assignee
+                .get(name)
+                .and_then(|value| value.as_str())
+                .filter(|value| !value.is_empty())
+        };
+
+        if let Some(display_name) = field("displayName") {
+            return Some(display_name.to_owned());
+        }
+
+        let account_id = match field("accountId") {
+            Some(account_id) => account_id,
+            // Data Center
+            None => {
+                return field("name")
+                    .or_else(|| field("key"))
+                    .map(|name| name.to_owned())
+            }
+        };
+
+        // Unwrap here is considered safe since the lock is never held while something could panic.
+        if let Some(name) = self.by_account_id.lock().unwrap().get(account_id) {
+            return Some(name.clone());
+        }
+
+        let name = match client.get_user(account_id).await {
+            Ok(user) if !user.display_name.is_empty() => user.display_name,
+            _ => account_id.to_owned(),
+        };
+        self.by_account_id
+            .lock()
+            .unwrap()
+            .insert(account_id.to_owned(), name.clone());
+
+        Some(name)
+    }
+}
+
+/// Returns the labels on an issue, as long as the `"labels"` field was requested.
+pub fn labels(issue: &SearchIssue) -> Vec<String> {
+    match issue.fields.get("labels") {
+        Some(JSONValue::Array(labels)) => labels
+            .iter()
+            .filter_map(|label| label.as_str().map(|label| label.to_owned()))
+            .collect(),
+        _ => vec![],
+    }
+}
+
+/// Returns the names of an issue's fix versions, as long as the `"fixVersions"` field was requested.
+pub fn fix_versions(issue: &SearchIssue) -> Vec<String> {
+    match issue.fields.get("fixVersions") {
+        Some(JSONValue::Array(versions)) => versions
+            .iter()
+            .filter_map(|version| version.get("name"))
+            .filter_map(|name| name.as_str().map(|name| name.to_owned()))
+            .collect(),
+        _ => vec![],
+    }
+}
+
+/// Returns the key of the epic an issue is in, as long as the `"parent"` field and the epic link fields were requested.
+///
+/// Company-managed projects link issues to their epic with an Epic Link field, while team-managed ones make the epic
+/// the issue's parent.  Subtasks have their story as a parent rather than an epic, so they aren't in one here.
+pub fn epic_key(issue: &SearchIssue, epic_link_field_ids: &[String]) -> Option<String> {
+    for field_id in epic_link_field_ids {
+        if let Some(JSONValue::String(key)) = issue.fields.get(field_id) {
+            return Some(key.clone());
+        }
+    }
+
+    let parent = issue.fields.get("parent")?;
+    let issue_type = parent.get("fields")?.get("issuetype")?;
+    let is_epic = issue_type
+        .get("hierarchyLevel")
+        .and_then(|level| level.as_i64())
+        == Some(1)
+        || issue_type.get("name").and_then(|name| name.as_str()) == Some("Epic");
+    if is_epic {
+        parent.get("key")?.as_str().map(|key| key.to_owned())
+    } else {
+        None
+    }
+}
+
+/// The value to set the `"fixVersions"` field to, in order for an issue to have exactly the named versions.
+pub fn fix_versions_value(names: &[String]) -> JSONValue {
+    JSONValue::Array(
+        names
+            .iter()
+            .map(|name| serde_json::json!({ "name": name }))
+            .collect(),
+    )
+}
+
+pub async fn get_field_ids_named(
+    client: &impl JiraApi,
     field_name: &str,
-) -> Result<Vec<String>, reqwest::Error> {
-    let fields = client.get_fields()?;
-    let field_ids: Vec<String> = fields
+) -> Result<Vec<String>, client::RequestError> {
+    let fields = client.get_fields().await?;
+
+    Ok(field_ids_named(&fields, field_name))
+}
+
+/// Finds the IDs of every field with the given name.
+///
+/// Custom fields are not guaranteed to have unique names, so there may be more than one.
+pub fn field_ids_named(fields: &[Field], field_name: &str) -> Vec<String> {
+    fields
         .iter()
         .filter_map(|field| {
             if field.name == field_name {
@@ -32,9 +239,143 @@ pub fn get_story_point_field_ids(
                 None
             }
         })
+        .collect()
+}
+
+/// The names JIRA gives its story points field, in company-managed projects and team-managed ones.
+const STORY_POINT_FIELD_NAMES: [&str; 2] = ["Story Points", "Story point estimate"];
+
+/// How many issues to look at when finding out which fields have story points in them.
+pub const STORY_POINT_SAMPLE_SIZE: usize = 200;
+
+/// How much a field's name makes it look like where story points go.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum NameMatch {
+    /// One of the names JIRA gives the field.
+    Known,
+    /// Mentions points or estimates, like "Points" or "Estimate (points)".
+    Similar,
+    /// Any other number field someone added, which might have been made for story points.
+    Custom,
+}
+
+/// A number field that could be where a site keeps story points.
+#[derive(Debug)]
+pub struct StoryPointsCandidate {
+    pub id: String,
+    pub name: String,
+    pub name_match: NameMatch,
+    /// How many of the issues looked at have a value in the field.
+    pub issues_with_values: usize,
+}
+
+/// Finds the number fields that could hold story points.
+pub fn story_point_field_candidates(fields: &[Field]) -> Vec<&Field> {
+    fields
+        .iter()
+        .filter(|field| {
+            matches!(&field.schema, Some(schema) if schema.field_type == "number")
+                && name_match(field).is_some()
+        })
+        .collect()
+}
+
+fn name_match(field: &Field) -> Option<NameMatch> {
+    let lowercase = field.name.to_lowercase();
+    if STORY_POINT_FIELD_NAMES
+        .iter()
+        .any(|name| name.eq_ignore_ascii_case(&field.name))
+    {
+        Some(NameMatch::Known)
+    } else if lowercase.contains("point") || lowercase.contains("estimat") {
+        Some(NameMatch::Similar)
+    } else if field.custom {
+        Some(NameMatch::Custom)
+    } else {
+        // Built in number fields, like the time tracking ones, are never story points.
+        None
+    }
+}
+
+/// Ranks the fields that could hold story points, most likely first, by their names and by how many of the given issues
+/// have values in them.
+///
+/// A field named like story points wins over one that isn't, since sites often have number fields that were added for
+/// something else.  Between fields named alike, the one that's used wins, since sites that have both company-managed
+/// and team-managed projects have both of JIRA's fields.  A field that isn't named like story points is only a
+/// candidate if it's used.
+pub fn rank_story_point_fields(
+    fields: &[Field],
+    issues: &[SearchIssue],
+) -> Vec<StoryPointsCandidate> {
+    let mut candidates: Vec<StoryPointsCandidate> = story_point_field_candidates(fields)
+        .into_iter()
+        .filter_map(|field| {
+            let name_match = name_match(field)?;
+            let issues_with_values = issues
+                .iter()
+                .filter(|issue| issue.numeric_field(&field.id).is_some())
+                .count();
+            if name_match == NameMatch::Custom && issues_with_values == 0 {
+                return None;
+            }
+
+            Some(StoryPointsCandidate {
+                id: field.id.clone(),
+                name: field.name.clone(),
+                name_match,
+                issues_with_values,
+            })
+        })
         .collect();
 
-    Ok(field_ids)
+    candidates.sort_by_key(|candidate| {
+        let tier = match (candidate.name_match, candidate.issues_with_values) {
+            (NameMatch::Known | NameMatch::Similar, 0) => 1,
+            (NameMatch::Known | NameMatch::Similar, _) => 0,
+            (NameMatch::Custom, _) => 2,
+        };
+        (
+            tier,
+            std::cmp::Reverse(candidate.issues_with_values),
+            candidate.name_match,
+        )
+    });
+
+    candidates
+}
+
+/// Looks through a sample of the issues a search finds to rank the fields that could hold story points.
+pub async fn detect_story_point_fields(
+    client: &impl JiraApi,
+    jql: &JQLStatement,
+) -> Result<Vec<StoryPointsCandidate>, client::RequestError> {
+    let fields = client.get_fields().await?;
+    let candidate_ids: Vec<String> = story_point_field_candidates(&fields)
+        .into_iter()
+        .map(|field| field.id.clone())
+        .collect();
+    if candidate_ids.is_empty() {
+        return Ok(vec![]);
+    }
+
+    // The most recently updated issues are looked at, since a site that moved from one field to another will have
+    // both filled in on older issues.
+    let jql = JQLStatement {
+        order_by: vec![("updated".to_owned(), jql::SortDirection::Descending)],
+        ..jql.clone()
+    };
+    let mut issues = vec![];
+    let stream = client.search_stream(&candidate_ids, &jql);
+    tokio::pin!(stream);
+    while let Some(issue) = stream.next().await {
+        issues.push(issue?);
+        if issues.len() >= STORY_POINT_SAMPLE_SIZE {
+            break;
+        }
+    }
+
+    Ok(rank_story_point_fields(&fields, &issues))
 }
 
 #[derive(Debug)]
@@ -46,48 +387,252 @@ impl Display for RestClientInitializationError {
     }
 }
 
-pub fn build_issue_search_jql(
-    projects: &Vec<String>,
-    labels: &Vec<String>,
-    included_issue_types: &Vec<String>,
-) -> Result<JQLStatement, String> {
-    if projects.is_empty() && labels.is_empty() {
-        return Err("This command will search all projects & labels.  To avoid crawling your entire JIRA instance, you must supply at least one project or a label to narrow the search.".to_owned());
+impl Error for RestClientInitializationError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&self.0)
     }
+}
 
-    let mut clauses: Vec<Box<JQLClause>> = vec![];
+/// Builds a client for JIRA's REST API, with an error message suitable for showing to the user on failure.
+pub fn connect(
+    url: &str,
+    credentials: &client::Credentials,
+    settings: client::HttpSettings,
+) -> Result<client::Client, RestClientInitializationError> {
+    client::Client::new(url, credentials, settings).map_err(RestClientInitializationError)
+}
 
-    if !projects.is_empty() {
-        clauses.push(Box::new(JQLClause::In(
-            "project".to_owned(),
-            projects
-                .iter()
-                .map(|project| JQLValue::String(project.clone()))
-                .collect(),
-        )));
+#[derive(Debug)]
+pub enum JQLValidationError {
+    Rejected(String, Vec<String>),
+    ProblemValidating(client::RequestError),
+}
+
+impl Display for JQLValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JQLValidationError::Rejected(jql, errors) => {
+                write!(f, "JIRA would not accept this search:\n  {}\n", jql)?;
+                for error in errors {
+                    write!(f, "\n  - {}", error)?;
+                }
+                Ok(())
+            }
+            JQLValidationError::ProblemValidating(_) => {
+                write!(f, "There was a problem asking JIRA to check the search.  It's worth checking that you have the right JIRA URL and credentials.")
+            }
+        }
+    }
+}
+
+impl Error for JQLValidationError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            JQLValidationError::ProblemValidating(inner) => Some(inner),
+            _ => None,
+        }
     }
+}
 
-    if !labels.is_empty() {
-        clauses.push(Box::new(JQLClause::In(
-            "labels".to_owned(),
-            labels
+/// Has JIRA check a search before it is run, so that a mistake in it is explained rather than failing the search.
+pub async fn validate_jql(
+    client: &impl JiraApi,
+    jql: &JQLStatement,
+) -> Result<(), JQLValidationError> {
+    let errors = client
+        .parse_jql(jql)
+        .await
+        .map_err(JQLValidationError::ProblemValidating)?;
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(JQLValidationError::Rejected(jql.serialize_to_jql(), errors))
+    }
+}
+
+/// Works out how issues are linked to an epic, from the project named at the start of its key.
+pub async fn epic_relation(client: &impl JiraApi, epic_key: &str) -> Result<EpicRelation, String> {
+    let project_key = match epic_key.rsplit_once('-') {
+        Some((project_key, number))
+            if !project_key.is_empty() && number.chars().all(|c| c.is_ascii_digit()) =>
+        {
+            project_key
+        }
+        _ => {
+            return Err(format!(
+                "\"{}\" is not an issue key; epics have to be given by their key, like PROJ-100.",
+                epic_key
+            ))
+        }
+    };
+
+    let project = client.get_project(project_key).await.map_err(|e| {
+        format!(
+            "Could not look up the {} project, to find out how its epics are linked: {}",
+            project_key, e
+        )
+    })?;
+
+    Ok(if project.style == "next-gen" {
+        EpicRelation::Parent
+    } else {
+        EpicRelation::EpicLink
+    })
+}
+
+#[derive(Debug)]
+pub enum SprintResolutionError {
+    MissingBoard(String),
+    NoBoard(String),
+    SeveralBoards(String, Vec<String>),
+    NoSuchSprint(String, Vec<String>),
+    AmbiguousSprint(String, Vec<u64>),
+    NoActiveSprint(u64),
+    SeveralActiveSprints(u64, Vec<String>),
+    ProblemGettingSprint(u64, client::RequestError),
+    ProblemListingBoards(String, client::RequestError),
+    ProblemListingSprints(client::RequestError),
+}
+
+impl Display for SprintResolutionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SprintResolutionError::MissingBoard(sprint) => {
+                write!(f, "The sprint \"{}\" is not a sprint ID, so it has to be looked up by name.  Sprint names are only unique within a board; pass --jira-board with the ID of the board the sprint belongs to.", sprint)
+            }
+            SprintResolutionError::NoBoard(project_key) => {
+                write!(f, "The {} project does not have a scrum board, so its sprints can't be looked up by name.  Pass --jira-board with the ID of the board the sprint belongs to.", project_key)
+            }
+            SprintResolutionError::SeveralBoards(project_key, boards) => {
+                write!(f, "The {} project has more than one scrum board, so it isn't clear which one the sprint belongs to.  Pass --jira-board with one of these board IDs: {}", project_key, boards.join(", "))
+            }
+            SprintResolutionError::NoSuchSprint(sprint, available) => {
+                write!(f, "There is no sprint named \"{}\" on that board that can be used here.  The sprints available are: {}", sprint, available.join(", "))
+            }
+            SprintResolutionError::AmbiguousSprint(sprint, ids) => {
+                let ids = ids.iter().map(|id| id.to_string()).collect::<Vec<String>>();
+                write!(f, "There is more than one sprint named \"{}\" on that board.  Pass one of these sprint IDs instead: {}", sprint, ids.join(", "))
+            }
+            SprintResolutionError::NoActiveSprint(board_id) => {
+                write!(
+                    f,
+                    "Board {} does not have an active sprint right now.",
+                    board_id
+                )
+            }
+            SprintResolutionError::SeveralActiveSprints(board_id, names) => {
+                write!(f, "Board {} has more than one active sprint, so it isn't clear which one to use: {}", board_id, names.join(", "))
+            }
+            SprintResolutionError::ProblemGettingSprint(sprint_id, _) => {
+                write!(f, "There was a problem looking up sprint {}.  It's worth checking that the sprint ID is right, and that you have access to its board.", sprint_id)
+            }
+            SprintResolutionError::ProblemListingBoards(project_key, _) => {
+                write!(f, "There was a problem listing the boards of the {} project.  It's worth checking that the project key is right, and that you have access to it.", project_key)
+            }
+            SprintResolutionError::ProblemListingSprints(_) => {
+                write!(f, "There was a problem listing the sprints on the board.  It's worth checking that the board ID is right, and that you have access to it.")
+            }
+        }
+    }
+}
+
+impl Error for SprintResolutionError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            SprintResolutionError::ProblemGettingSprint(_, inner) => Some(inner),
+            SprintResolutionError::ProblemListingBoards(_, inner) => Some(inner),
+            SprintResolutionError::ProblemListingSprints(inner) => Some(inner),
+            _ => None,
+        }
+    }
+}
+
+/// Finds the one scrum board of a project.
+pub async fn find_board(
+    client: &impl JiraApi,
+    project_key: &str,
+) -> Result<u64, SprintResolutionError> {
+    let boards = client
+        .get_boards(project_key)
+        .await
+        .map_err(|e| SprintResolutionError::ProblemListingBoards(project_key.to_owned(), e))?;
+
+    match boards.as_slice() {
+        [] => Err(SprintResolutionError::NoBoard(project_key.to_owned())),
+        [board] => Ok(board.id),
+        _ => Err(SprintResolutionError::SeveralBoards(
+            project_key.to_owned(),
+            boards
                 .iter()
-                .map(|label| JQLValue::String(label.clone()))
+                .map(|board| format!("{} ({})", board.id, board.name))
                 .collect(),
-        )))
+        )),
+    }
+}
+
+/// Turns a sprint given on the command line into a sprint ID.
+///
+/// Numeric values are taken to be IDs as-is.  Anything else is treated as the name of a sprint in one of `states`, like
+/// `active,future`, on the given board, or else on the scrum board of the given project.
+pub async fn resolve_sprint_id(
+    client: &impl JiraApi,
+    board_id: Option<u64>,
+    project_key: Option<&str>,
+    sprint: &str,
+    states: &str,
+) -> Result<u64, SprintResolutionError> {
+    if let Ok(sprint_id) = sprint.parse::<u64>() {
+        return Ok(sprint_id);
     }
 
-    if !included_issue_types.is_empty() {
-        clauses.push(Box::new(JQLClause::In(
-            "issuetype".to_owned(),
-            included_issue_types
+    let board_id = match (board_id, project_key) {
+        (Some(board_id), _) => board_id,
+        (None, Some(project_key)) => find_board(client, project_key).await?,
+        (None, None) => return Err(SprintResolutionError::MissingBoard(sprint.to_owned())),
+    };
+    let sprints = client
+        .get_sprints(board_id, states)
+        .await
+        .map_err(SprintResolutionError::ProblemListingSprints)?;
+
+    let matching_ids: Vec<u64> = sprints
+        .iter()
+        .filter(|candidate| candidate.name == sprint)
+        .map(|candidate| candidate.id)
+        .collect();
+
+    match matching_ids.len() {
+        0 => Err(SprintResolutionError::NoSuchSprint(
+            sprint.to_owned(),
+            sprints
                 .iter()
-                .map(|issue_type| JQLValue::String(issue_type.clone()))
+                .map(|candidate| format!("{} ({})", candidate.name, candidate.state))
                 .collect(),
-        )))
+        )),
+        1 => Ok(matching_ids[0]),
+        _ => Err(SprintResolutionError::AmbiguousSprint(
+            sprint.to_owned(),
+            matching_ids,
+        )),
     }
+}
 
-    Ok(JQLStatement {
-        clause: JQLClause::And(clauses),
-    })
+/// Finds the one active sprint on a board.
+pub async fn active_sprint(
+    client: &impl JiraApi,
+    board_id: u64,
+) -> Result<client::Sprint, SprintResolutionError> {
+    let mut active: Vec<client::Sprint> = client
+        .get_sprints(board_id, "active")
+        .await
+        .map_err(SprintResolutionError::ProblemListingSprints)?;
+
+    match active.len() {
+        0 => Err(SprintResolutionError::NoActiveSprint(board_id)),
+        1 => Ok(active.remove(0)),
+        _ => Err(SprintResolutionError::SeveralActiveSprints(
+            board_id,
+            active.into_iter().map(|sprint| sprint.name).collect(),
+        )),
+    }
 }
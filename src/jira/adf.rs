@@ -0,0 +1,83 @@
+/// Renders rich text in the [Atlassian Document Format][1] as plain text, for reports and exports.
+///
+/// Version 3 of the REST API sends descriptions and comments as a tree of nodes rather than a string.  Formatting like
+/// bold text and colors is dropped, but the structure people read by is kept: paragraphs and list items go on lines of
+/// their own, and mentions, emoji, and links come out as the text they show as.
+///
+/// [1]: https://developer.atlassian.com/cloud/jira/platform/apis/document/structure/
+use serde_json::Value as JSONValue;
+
+/// Renders a document, or any node in one, as plain text.
+pub fn to_plain_text(node: &JSONValue) -> String {
+    let mut text = String::new();
+    render(node, &mut text);
+
+    text.trim_end().to_owned()
+}
+
+fn render(node: &JSONValue, text: &mut String) {
+    let attr = |name: &str| {
+        node.pointer(&format!("/attrs/{}", name))
+            .and_then(|value| value.as_str())
+            .unwrap_or_default()
+    };
+
+    match node.get("type").and_then(|kind| kind.as_str()) {
+        Some("text") => text.push_str(
+            node.get("text")
+                .and_then(|t| t.as_str())
+                .unwrap_or_default(),
+        ),
+        Some("hardBreak") => text.push('\n'),
+        Some("mention") | Some("emoji") => {
+            let shown = attr("text");
+            text.push_str(if shown.is_empty() {
+                attr("shortName")
+            } else {
+                shown
+            });
+        }
+        Some("inlineCard") | Some("blockCard") | Some("embedCard") => text.push_str(attr("url")),
+        Some("rule") => text.push_str("---\n"),
+        Some("bulletList") => render_list_items(node, text, |_| "- ".to_owned()),
+        Some("orderedList") => {
+            let start = node
+                .pointer("/attrs/order")
+                .and_then(|order| order.as_u64())
+                .unwrap_or(1);
+            render_list_items(node, text, |i| format!("{}. ", start + i as u64))
+        }
+        Some("paragraph") | Some("heading") | Some("codeBlock") | Some("blockquote")
+        | Some("panel") => {
+            render_children(node, text);
+            if !text.ends_with('\n') {
+                text.push('\n');
+            }
+        }
+        _ => render_children(node, text),
+    }
+}
+
+fn render_children(node: &JSONValue, text: &mut String) {
+    if let Some(children) = node.get("content").and_then(|content| content.as_array()) {
+        for child in children {
+            render(child, text);
+        }
+    }
+}
+
+/// Puts each item of a list on its own line, after the marker for its position in the list.
+fn render_list_items<F: Fn(usize) -> String>(list: &JSONValue, text: &mut String, marker: F) {
+    let items = match list.get("content").and_then(|content| content.as_array()) {
+        Some(items) => items,
+        None => return,
+    };
+
+    for (i, item) in items.iter().enumerate() {
+        text.push_str(&marker(i));
+        render_children(item, text);
+        if !text.ends_with('\n') {
+            text.push('\n');
+        }
+    }
+}
@@ -0,0 +1,174 @@
+/// The JIRA endpoints the commands use.
+///
+/// `Client` is the real implementation, which sends each request to JIRA.  Writing the commands against this trait
+/// instead of `Client` lets their logic run against an in-memory fake, like the one the tests use in `fake`.
+use std::collections::HashMap;
+
+use futures_util::Stream;
+use serde_json::{Map, Value as JSONValue};
+
+use super::{
+    client::{
+        Board, ChangelogEntry, Field, IssueEditUpdate, IssueTypeStatuses, Project, RequestError,
+        SearchIssue, Sprint, Status, User, Worklog,
+    },
+    jql::JQLStatement,
+};
+
+#[cfg(test)]
+pub mod fake;
+
+// The futures aren't promised to be `Send`, since every command awaits them where it makes them rather than spawning
+// them onto other threads.
+#[allow(async_fn_in_trait)]
+pub trait JiraApi {
+    /// Gets the whole history of an issue, visiting each page of results.
+    async fn get_changelog(&self, key: &str) -> Result<Vec<ChangelogEntry>, RequestError>;
+
+    /// Gets all of the time logged against an issue, visiting each page of results.
+    async fn get_worklogs(&self, key: &str) -> Result<Vec<Worklog>, RequestError>;
+
+    /// Gets the scrum boards for a project, visiting each page of results.
+    ///
+    /// Kanban boards are left out, since they don't have sprints.
+    async fn get_boards(&self, project_key: &str) -> Result<Vec<Board>, RequestError>;
+
+    /// Gets the sprints on a board in any of the given states, visiting each page of results.
+    ///
+    /// `states` is a comma-separated list of `active`, `future`, and `closed`.
+    async fn get_sprints(&self, board_id: u64, states: &str) -> Result<Vec<Sprint>, RequestError>;
+
+    /// Gets the ID of the saved filter that picks out the issues on a board.
+    async fn get_board_filter_id(&self, board_id: u64) -> Result<u64, RequestError>;
+
+    /// Gets a single sprint by its ID.
+    async fn get_sprint(&self, sprint_id: u64) -> Result<Sprint, RequestError>;
+
+    /// Gets the given fields of every issue in a sprint, visiting each page of results.
+    async fn get_sprint_issues(
+        &self,
+        sprint_id: u64,
+        fields: &[String],
+    ) -> Result<Vec<SearchIssue>, RequestError>;
+
+    /// Moves issues into a sprint.
+    ///
+    /// JIRA accepts at most `MAX_ISSUES_PER_SPRINT_MOVE` issues per request; callers are expected to chunk larger lists.
+    async fn move_issues_to_sprint(
+        &self,
+        sprint_id: u64,
+        keys: &[String],
+    ) -> Result<(), RequestError>;
+
+    /// Moves issues out of whatever sprint they are in, and back into the backlog.
+    ///
+    /// Like `move_issues_to_sprint`, this accepts at most `MAX_ISSUES_PER_SPRINT_MOVE` issues at a time.
+    async fn move_issues_to_backlog(&self, keys: &[String]) -> Result<(), RequestError>;
+
+    /// Gets the ID of the sprint an issue is currently in, if it is in one.
+    async fn get_issue_sprint_id(&self, key: &str) -> Result<Option<u64>, RequestError>;
+
+    /// Gets the given fields of a single issue.
+    ///
+    /// The response has the same shape as an issue in search results, so it reuses that type.
+    async fn get_issue(&self, key: &str, fields: &[String]) -> Result<SearchIssue, RequestError>;
+
+    /// Searches JIRA for issues matching the given JQL statement, visiting each page of results.
+    ///
+    /// The first page says how many issues there are in all, so the rest of the pages are fetched a few at a time.  The
+    /// issues still come back in the order JIRA sorted them in.
+    async fn search_all(
+        &self,
+        fields: &[String],
+        jql: &JQLStatement,
+    ) -> Result<Vec<SearchIssue>, RequestError>;
+
+    /// Searches JIRA for issues matching the given JQL statement, along with the whole history of each one, oldest
+    /// change first.
+    ///
+    /// Search results only hold the start of each issue's history, so the rest of it is fetched separately for issues
+    /// that have been changed a lot.
+    async fn search_all_with_changelogs(
+        &self,
+        fields: &[String],
+        jql: &JQLStatement,
+    ) -> Result<Vec<SearchIssue>, RequestError>;
+
+    /// Searches JIRA for issues matching the given JQL statement, fetching each page of results only once the issues
+    /// before it have been used.
+    ///
+    /// This keeps just one page in memory at a time, for commands that can handle issues one by one.  A failed request
+    /// is the last item.
+    fn search_stream<'a>(
+        &'a self,
+        fields: &'a [String],
+        jql: &'a JQLStatement,
+    ) -> impl Stream<Item = Result<SearchIssue, RequestError>> + 'a;
+
+    /// Asks JIRA to check a JQL statement without running it, returning whatever is wrong with it.
+    ///
+    /// Validation is strict, so fields and functions JIRA doesn't know about are errors rather than warnings.
+    async fn parse_jql(&self, jql: &JQLStatement) -> Result<Vec<String>, RequestError>;
+
+    /// Gets a project by its key.
+    async fn get_project(&self, key: &str) -> Result<Project, RequestError>;
+
+    /// Gets every project the signed in user can see, along with its lead.
+    async fn get_projects(&self) -> Result<Vec<Project>, RequestError>;
+
+    /// Gets all configured fields for your JIRA instance.
+    async fn get_fields(&self) -> Result<Vec<Field>, RequestError>;
+
+    /// Gets every status issues can be in, across all projects.
+    async fn get_statuses(&self) -> Result<Vec<Status>, RequestError>;
+
+    /// Gets the issue types in a project, each with the statuses its workflow uses.
+    async fn get_project_statuses(
+        &self,
+        project_key: &str,
+    ) -> Result<Vec<IssueTypeStatuses>, RequestError>;
+
+    /// Edits an issue.
+    ///
+    /// JIRA responds to a successful edit with no content, so there is nothing to return.
+    async fn edit_issue(&self, key: &str, update: &IssueEditUpdate) -> Result<(), RequestError>;
+
+    /// Edits an issue, setting some fields outright while applying update operations to others, all in one request.
+    async fn edit_issue_fields(
+        &self,
+        key: &str,
+        fields: &Map<String, JSONValue>,
+        update: &IssueEditUpdate,
+    ) -> Result<(), RequestError>;
+
+    /// Creates an issue with the given fields, which have to include at least the project, issue type, and summary.
+    ///
+    /// Returns the key JIRA gave the new issue.
+    async fn create_issue(&self, fields: &Map<String, JSONValue>) -> Result<String, RequestError>;
+
+    /// Looks a JIRA Cloud user up by account ID.
+    async fn get_user(&self, account_id: &str) -> Result<User, RequestError>;
+
+    /// Gets the user the client is signed in as.
+    async fn get_myself(&self) -> Result<User, RequestError>;
+
+    /// Finds out which of the given permissions, like `BROWSE_PROJECTS`, the signed in user has in a project, or in at
+    /// least one project without one.
+    async fn get_my_permissions(
+        &self,
+        project_key: Option<&str>,
+        permissions: &[&str],
+    ) -> Result<HashMap<String, bool>, RequestError>;
+
+    /// Finds users whose name or email address matches the query.
+    async fn find_users(&self, query: &str) -> Result<Vec<User>, RequestError>;
+
+    /// Gets the account IDs of everyone watching an issue.
+    async fn get_watchers(&self, key: &str) -> Result<Vec<String>, RequestError>;
+
+    /// Subscribes a user to an issue.
+    async fn add_watcher(&self, key: &str, account_id: &str) -> Result<(), RequestError>;
+
+    /// Unsubscribes a user from an issue.
+    async fn remove_watcher(&self, key: &str, account_id: &str) -> Result<(), RequestError>;
+}
@@ -0,0 +1,249 @@
+/// An in-memory stand-in for JIRA, for testing the logic written against `JiraApi` without a network.
+///
+/// Every search finds every issue, whatever the JQL.  Edits are recorded rather than made, and anything else the fake
+/// doesn't hold fails as if the client were offline.
+use std::{collections::HashMap, future::Future, sync::Mutex};
+
+use futures_util::{stream, Stream};
+use serde_json::{Map, Value as JSONValue};
+
+use crate::jira::{
+    api::JiraApi,
+    client::{
+        Board, ChangelogEntry, Field, IssueEditUpdate, IssueTypeStatuses, Project, RequestError,
+        SearchIssue, Sprint, Status, User, Worklog,
+    },
+    jql::JQLStatement,
+};
+
+/// Runs a future to completion, for tests, which can't be async without tokio's macros.
+pub fn block_on<F: Future>(future: F) -> F::Output {
+    tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .expect("the runtime could not be started")
+        .block_on(future)
+}
+
+#[derive(Debug, Default)]
+pub struct FakeJira {
+    /// Issues as JIRA lays them out, with a `key` and `fields`.
+    pub issues: Vec<JSONValue>,
+    /// Fields as the field endpoint lists them, with an `id` and `name`.
+    pub fields: Vec<JSONValue>,
+    /// Each edit made, in order, as the key of the issue and the fields it set or the update it made.
+    pub edits: Mutex<Vec<(String, JSONValue)>>,
+    /// The keys of issues whose edits fail.
+    pub uneditable: Vec<String>,
+}
+
+fn missing<T>(request: &str) -> Result<T, RequestError> {
+    Err(RequestError::Offline(format!(
+        "{} (the fake has no response to it)",
+        request
+    )))
+}
+
+impl FakeJira {
+    pub fn with_issues(issues: Vec<JSONValue>) -> Self {
+        FakeJira {
+            issues,
+            ..FakeJira::default()
+        }
+    }
+
+    /// The edits made to an issue, in order.
+    pub fn edits_to(&self, key: &str) -> Vec<JSONValue> {
+        self.edits
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(edited, _)| edited == key)
+            .map(|(_, edit)| edit.clone())
+            .collect()
+    }
+
+    fn edit(&self, key: &str, edit: JSONValue) -> Result<(), RequestError> {
+        if self.uneditable.iter().any(|uneditable| uneditable == key) {
+            return missing(&format!("an edit to {}", key));
+        }
+        self.edits.lock().unwrap().push((key.to_owned(), edit));
+
+        Ok(())
+    }
+
+    fn search_issues(&self) -> Vec<SearchIssue> {
+        self.issues
+            .iter()
+            .map(|issue| {
+                serde_json::from_value(issue.clone()).expect("the fake issue is malformed")
+            })
+            .collect()
+    }
+}
+
+impl JiraApi for FakeJira {
+    async fn get_changelog(&self, key: &str) -> Result<Vec<ChangelogEntry>, RequestError> {
+        missing(&format!("the changelog of {}", key))
+    }
+
+    async fn get_worklogs(&self, key: &str) -> Result<Vec<Worklog>, RequestError> {
+        missing(&format!("the worklogs of {}", key))
+    }
+
+    async fn get_boards(&self, project_key: &str) -> Result<Vec<Board>, RequestError> {
+        missing(&format!("the boards in {}", project_key))
+    }
+
+    async fn get_sprints(&self, board_id: u64, _: &str) -> Result<Vec<Sprint>, RequestError> {
+        missing(&format!("the sprints on board {}", board_id))
+    }
+
+    async fn get_board_filter_id(&self, board_id: u64) -> Result<u64, RequestError> {
+        missing(&format!("the filter of board {}", board_id))
+    }
+
+    async fn get_sprint(&self, sprint_id: u64) -> Result<Sprint, RequestError> {
+        missing(&format!("sprint {}", sprint_id))
+    }
+
+    async fn get_sprint_issues(
+        &self,
+        sprint_id: u64,
+        _: &[String],
+    ) -> Result<Vec<SearchIssue>, RequestError> {
+        missing(&format!("the issues in sprint {}", sprint_id))
+    }
+
+    async fn move_issues_to_sprint(
+        &self,
+        sprint_id: u64,
+        _: &[String],
+    ) -> Result<(), RequestError> {
+        missing(&format!("a move to sprint {}", sprint_id))
+    }
+
+    async fn move_issues_to_backlog(&self, _: &[String]) -> Result<(), RequestError> {
+        missing("a move to the backlog")
+    }
+
+    async fn get_issue_sprint_id(&self, key: &str) -> Result<Option<u64>, RequestError> {
+        missing(&format!("the sprint of {}", key))
+    }
+
+    async fn get_issue(&self, key: &str, _: &[String]) -> Result<SearchIssue, RequestError> {
+        match self
+            .search_issues()
+            .into_iter()
+            .find(|issue| issue.key == key)
+        {
+            Some(issue) => Ok(issue),
+            None => missing(&format!("the issue {}", key)),
+        }
+    }
+
+    async fn search_all(
+        &self,
+        _: &[String],
+        _: &JQLStatement,
+    ) -> Result<Vec<SearchIssue>, RequestError> {
+        Ok(self.search_issues())
+    }
+
+    async fn search_all_with_changelogs(
+        &self,
+        _: &[String],
+        _: &JQLStatement,
+    ) -> Result<Vec<SearchIssue>, RequestError> {
+        Ok(self.search_issues())
+    }
+
+    fn search_stream<'a>(
+        &'a self,
+        _: &'a [String],
+        _: &'a JQLStatement,
+    ) -> impl Stream<Item = Result<SearchIssue, RequestError>> + 'a {
+        stream::iter(self.search_issues().into_iter().map(Ok))
+    }
+
+    async fn parse_jql(&self, _: &JQLStatement) -> Result<Vec<String>, RequestError> {
+        Ok(vec![])
+    }
+
+    async fn get_project(&self, key: &str) -> Result<Project, RequestError> {
+        missing(&format!("the project {}", key))
+    }
+
+    async fn get_projects(&self) -> Result<Vec<Project>, RequestError> {
+        missing("the projects")
+    }
+
+    async fn get_fields(&self) -> Result<Vec<Field>, RequestError> {
+        self.fields
+            .iter()
+            .map(|field| {
+                serde_json::from_value(field.clone()).map_err(RequestError::UnexpectedResponse)
+            })
+            .collect()
+    }
+
+    async fn get_statuses(&self) -> Result<Vec<Status>, RequestError> {
+        missing("the statuses")
+    }
+
+    async fn get_project_statuses(
+        &self,
+        project_key: &str,
+    ) -> Result<Vec<IssueTypeStatuses>, RequestError> {
+        missing(&format!("the statuses in {}", project_key))
+    }
+
+    async fn edit_issue(&self, key: &str, update: &IssueEditUpdate) -> Result<(), RequestError> {
+        self.edit(key, serde_json::to_value(update).unwrap())
+    }
+
+    async fn edit_issue_fields(
+        &self,
+        key: &str,
+        fields: &Map<String, JSONValue>,
+        _: &IssueEditUpdate,
+    ) -> Result<(), RequestError> {
+        self.edit(key, JSONValue::Object(fields.clone()))
+    }
+
+    async fn create_issue(&self, _: &Map<String, JSONValue>) -> Result<String, RequestError> {
+        missing("a new issue")
+    }
+
+    async fn get_user(&self, account_id: &str) -> Result<User, RequestError> {
+        missing(&format!("the user {}", account_id))
+    }
+
+    async fn get_myself(&self) -> Result<User, RequestError> {
+        missing("the signed in user")
+    }
+
+    async fn get_my_permissions(
+        &self,
+        _: Option<&str>,
+        _: &[&str],
+    ) -> Result<HashMap<String, bool>, RequestError> {
+        missing("the signed in user's permissions")
+    }
+
+    async fn find_users(&self, query: &str) -> Result<Vec<User>, RequestError> {
+        missing(&format!("users matching {}", query))
+    }
+
+    async fn get_watchers(&self, key: &str) -> Result<Vec<String>, RequestError> {
+        missing(&format!("the watchers of {}", key))
+    }
+
+    async fn add_watcher(&self, key: &str, _: &str) -> Result<(), RequestError> {
+        missing(&format!("a new watcher on {}", key))
+    }
+
+    async fn remove_watcher(&self, key: &str, _: &str) -> Result<(), RequestError> {
+        missing(&format!("removing a watcher from {}", key))
+    }
+}
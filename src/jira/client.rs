@@ -0,0 +1,2265 @@
+/// Covers the parts of JIRA's REST APIs that this tool uses, including the Agile API.
+use std::{
+    collections::{hash_map::RandomState, HashMap},
+    convert::TryFrom,
+    error::Error,
+    fmt,
+    hash::{BuildHasher, Hasher},
+    str::FromStr,
+    sync::Mutex,
+    time::{Duration, Instant},
+    vec,
+};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use futures_util::{stream, Stream};
+use reqwest::{
+    header::{
+        HeaderMap, HeaderValue, ACCEPT, AUTHORIZATION, CONTENT_TYPE, ETAG, IF_MODIFIED_SINCE,
+        IF_NONE_MATCH, LAST_MODIFIED, RETRY_AFTER,
+    },
+    Certificate, Client as HttpClient, ClientBuilder, RequestBuilder, Response, StatusCode,
+};
+use serde::{de::DeserializeOwned, ser::SerializeMap, Deserialize, Serialize, Serializer};
+use serde_json::{json, Map, Value as JSONValue};
+
+use super::{adf, api::JiraApi, debug::DebugLog, jql::JQLStatement};
+use crate::{
+    audit::{AuditLog, Mutation},
+    bulk,
+    cache::ResponseCache,
+    date::DateTime,
+};
+
+/// How many issues to ask for in each page of search results, unless told otherwise.
+const DEFAULT_SEARCH_PAGE_SIZE: u64 = 100;
+
+/// How many pages of search results to fetch at once, once the first page says how many there are.
+const MAX_CONCURRENT_SEARCH_PAGES: usize = 4;
+
+/// The most issues the Agile API will move into a sprint in a single request.
+pub const MAX_ISSUES_PER_SPRINT_MOVE: usize = 50;
+
+/// Represents a sprint on a board, as returned by a [get all sprints request][1].
+///
+/// [1]: https://developer.atlassian.com/cloud/jira/software/rest/api-group-board/#api-rest-agile-1-0-board-boardid-sprint-get
+#[derive(Debug, Deserialize)]
+pub struct Sprint {
+    pub id: u64,
+
+    pub name: String,
+
+    pub state: String,
+
+    /// Future sprints haven't been given dates yet.
+    #[serde(rename(deserialize = "startDate"))]
+    #[serde(default)]
+    pub start_date: Option<String>,
+
+    #[serde(rename(deserialize = "endDate"))]
+    #[serde(default)]
+    pub end_date: Option<String>,
+
+    /// When the sprint was actually closed, which may not be its planned end date.
+    #[serde(rename(deserialize = "completeDate"))]
+    #[serde(default)]
+    pub complete_date: Option<String>,
+
+    /// The board the sprint was created on.
+    #[serde(rename(deserialize = "originBoardId"))]
+    #[serde(default)]
+    pub origin_board_id: Option<u64>,
+}
+
+impl Sprint {
+    pub fn started_at(&self) -> Option<DateTime> {
+        DateTime::parse_rfc3339(self.start_date.as_ref()?)
+    }
+
+    pub fn ends_at(&self) -> Option<DateTime> {
+        DateTime::parse_rfc3339(self.end_date.as_ref()?)
+    }
+
+    pub fn completed_at(&self) -> Option<DateTime> {
+        DateTime::parse_rfc3339(self.complete_date.as_ref()?)
+    }
+}
+
+/// Represents a scrum board, as returned by a [get all boards request][1].
+///
+/// [1]: https://developer.atlassian.com/cloud/jira/software/rest/api-group-board/#api-rest-agile-1-0-board-get
+#[derive(Debug, Deserialize)]
+pub struct Board {
+    pub id: u64,
+
+    pub name: String,
+}
+
+/// Represents a field in JIRA, as returned by a [get fields request][1].
+///
+/// [1]: https://developer.atlassian.com/cloud/jira/platform/rest/v3/api-group-issue-fields/#api-rest-api-3-field-get
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Field {
+    pub id: String,
+
+    pub name: String,
+
+    /// Whether the field was added by an administrator or an app, rather than being built into JIRA.
+    #[serde(default)]
+    pub custom: bool,
+
+    /// Fields that can't be searched or set, like `thumbnail`, have no schema.
+    #[serde(default)]
+    pub schema: Option<FieldSchema>,
+}
+
+/// What kind of value a field holds.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct FieldSchema {
+    /// Like `number`, `string`, `user`, or `array`.
+    #[serde(rename = "type")]
+    pub field_type: String,
+
+    /// The kind of value each item is, for arrays.
+    #[serde(default)]
+    pub items: Option<String>,
+}
+
+impl Field {
+    /// Describes the kind of value the field holds, like "number" or "array of string".
+    pub fn value_type(&self) -> String {
+        match &self.schema {
+            Some(FieldSchema {
+                field_type,
+                items: Some(items),
+            }) => format!("{} of {}", field_type, items),
+            Some(schema) => schema.field_type.clone(),
+            None => "".to_owned(),
+        }
+    }
+}
+
+/// A status issues can be in, as returned by a [get all statuses request][1].
+///
+/// [1]: https://developer.atlassian.com/cloud/jira/platform/rest/v3/api-group-workflow-statuses/#api-rest-api-3-status-get
+#[derive(Debug, Deserialize)]
+pub struct Status {
+    pub id: String,
+
+    pub name: String,
+
+    #[serde(rename(deserialize = "statusCategory"))]
+    pub status_category: StatusCategory,
+}
+
+/// An issue type in a project, along with the statuses its workflow can put issues in, as returned by a [get all
+/// statuses for project request][1].
+///
+/// [1]: https://developer.atlassian.com/cloud/jira/platform/rest/v3/api-group-projects/#api-rest-api-3-project-projectidorkey-statuses-get
+#[derive(Debug, Deserialize)]
+pub struct IssueTypeStatuses {
+    pub name: String,
+
+    #[serde(default)]
+    pub subtask: bool,
+
+    pub statuses: Vec<Status>,
+}
+
+/// One of the few groups every status belongs to, like "To Do", "In Progress", or "Done".
+#[derive(Debug, Deserialize)]
+pub struct StatusCategory {
+    pub name: String,
+}
+
+/// Represents an issue in JIRA, as returned by a [search request][1].
+///
+/// Only the fields asked for in the request are filled in.  Fields make no guarantees about their typing, so they are
+/// kept as JSON and read with the helpers below, rather than having a separate type for each.
+///
+/// [1]: https://developer.atlassian.com/cloud/jira/platform/rest/v3/api-group-issue-search/#api-rest-api-3-search-post
+#[derive(Debug, Deserialize, Serialize)]
+pub struct SearchIssue {
+    pub key: String,
+
+    pub fields: HashMap<String, JSONValue>,
+
+    /// Only filled in when the changelog was asked for.
+    #[serde(default)]
+    pub changelog: Option<Changelog>,
+}
+
+impl SearchIssue {
+    /// Returns the name of the issue's status, like "In Review".
+    ///
+    /// This is `None` unless the `status` field was asked for.
+    pub fn status(&self) -> Option<String> {
+        self.fields
+            .get("status")?
+            .get("name")?
+            .as_str()
+            .map(|name| name.to_owned())
+    }
+
+    /// Returns the name of the issue's status category, like "To Do", "In Progress", or "Done".
+    ///
+    /// This is `None` unless the `status` field was asked for.
+    pub fn status_category(&self) -> Option<String> {
+        self.fields
+            .get("status")?
+            .pointer("/statusCategory/name")?
+            .as_str()
+            .map(|name| name.to_owned())
+    }
+
+    /// Returns a field as a number, if that is how the JSON is laid out.
+    ///
+    /// Useful for things like story point fields.
+    pub fn numeric_field(&self, field_id: &str) -> Option<f64> {
+        self.fields.get(field_id)?.as_f64()
+    }
+
+    /// Returns a rich text field, like `description`, as plain text.
+    ///
+    /// Version 2 of the API sends these as strings of wiki markup, which are returned as they are, while version 3 sends
+    /// documents in the Atlassian Document Format, which are rendered.  This is `None` if the field is empty or wasn't
+    /// asked for.
+    pub fn text_field(&self, field_id: &str) -> Option<String> {
+        match self.fields.get(field_id)? {
+            JSONValue::String(text) => Some(text.clone()),
+            JSONValue::Null => None,
+            document => Some(adf::to_plain_text(document)),
+        }
+    }
+
+    /// Returns the comments on the issue, with their bodies as plain text in the same way as `text_field`.
+    ///
+    /// This is empty unless the `comment` field was asked for.  Search results only hold the first page of comments.
+    pub fn comments(&self) -> Vec<Comment> {
+        let comments = match self
+            .fields
+            .get("comment")
+            .and_then(|comment| comment.get("comments"))
+            .and_then(|comments| comments.as_array())
+        {
+            Some(comments) => comments,
+            None => return vec![],
+        };
+
+        comments
+            .iter()
+            .filter_map(|comment| {
+                let body = match comment.get("body")? {
+                    JSONValue::String(body) => body.clone(),
+                    JSONValue::Null => return None,
+                    document => adf::to_plain_text(document),
+                };
+
+                Some(Comment {
+                    author: comment
+                        .get("author")
+                        .and_then(|author| author.get("displayName"))
+                        .and_then(|name| name.as_str())
+                        .map(|name| name.to_owned()),
+                    created_at: comment
+                        .get("created")
+                        .and_then(|created| created.as_str())
+                        .and_then(DateTime::parse_rfc3339),
+                    body,
+                })
+            })
+            .collect()
+    }
+
+    /// Returns the issue's links to other issues, leaving out any that can't be read.
+    ///
+    /// This is empty unless the `issuelinks` field was asked for.
+    pub fn issue_links(&self) -> Vec<IssueLink> {
+        let links = match self
+            .fields
+            .get("issuelinks")
+            .and_then(|links| links.as_array())
+        {
+            Some(links) => links,
+            None => return vec![],
+        };
+
+        links
+            .iter()
+            .filter_map(|link| {
+                let link_type = link.pointer("/type/name")?.as_str()?.to_owned();
+                let (direction, other) = match (link.get("inwardIssue"), link.get("outwardIssue")) {
+                    (Some(other), _) => (LinkDirection::Inward, other),
+                    (None, Some(other)) => (LinkDirection::Outward, other),
+                    (None, None) => return None,
+                };
+                let description = match direction {
+                    LinkDirection::Inward => link.pointer("/type/inward"),
+                    LinkDirection::Outward => link.pointer("/type/outward"),
+                }
+                .and_then(|description| description.as_str())
+                .unwrap_or_default()
+                .to_owned();
+
+                Some(IssueLink {
+                    link_type,
+                    direction,
+                    description,
+                    key: other.get("key")?.as_str()?.to_owned(),
+                    status_category: other
+                        .pointer("/fields/status/statusCategory/name")
+                        .and_then(|name| name.as_str())
+                        .map(|name| name.to_owned()),
+                })
+            })
+            .collect()
+    }
+}
+
+/// A comment on an issue, as found in the [`comment` field][1].
+///
+/// [1]: https://developer.atlassian.com/cloud/jira/platform/rest/v3/api-group-issue-comments/#api-rest-api-3-issue-issueidorkey-comment-get
+#[derive(Clone, Debug)]
+pub struct Comment {
+    /// The display name of whoever wrote the comment, which is left out for anonymous comments.
+    pub author: Option<String>,
+
+    /// `None` if JIRA sent a timestamp that could not be read.
+    pub created_at: Option<DateTime>,
+
+    pub body: String,
+}
+
+/// Which end of a link an issue is at.
+///
+/// Reading a link from the issue it belongs to, an inward link is read with the type's inward description, like "is
+/// blocked by", and an outward link with its outward description, like "blocks".
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LinkDirection {
+    Inward,
+    Outward,
+}
+
+/// A link from an issue to another one, as found in the [`issuelinks` field][1].
+///
+/// [1]: https://developer.atlassian.com/cloud/jira/platform/rest/v3/api-group-issue-links/#api-rest-api-3-issuelink-linkid-get
+#[derive(Clone, Debug)]
+pub struct IssueLink {
+    /// The name of the link type, like "Blocks" or "Relates".
+    pub link_type: String,
+
+    pub direction: LinkDirection,
+
+    /// How the link reads from this issue, like "is blocked by".
+    pub description: String,
+
+    /// The key of the issue at the other end of the link.
+    pub key: String,
+
+    /// The status category of the issue at the other end, like "Done", which JIRA sends along with the link.
+    pub status_category: Option<String>,
+}
+
+/// The history of an issue, as returned in search results [with `expand=changelog`][1].
+///
+/// Search results only hold the first page of each history, so `total` may be more than the entries in `histories`.
+///
+/// [1]: https://developer.atlassian.com/cloud/jira/platform/rest/v3/api-group-issue-search/#api-rest-api-3-search-post
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Changelog {
+    #[serde(default)]
+    pub histories: Vec<ChangelogEntry>,
+
+    #[serde(default)]
+    pub total: u64,
+}
+
+/// A set of changes made to an issue at once, as returned by a [get changelogs request][1].
+///
+/// [1]: https://developer.atlassian.com/cloud/jira/platform/rest/v3/api-group-issues/#api-rest-api-3-issue-issueidorkey-changelog-get
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ChangelogEntry {
+    pub id: String,
+
+    /// Changes made by automation or an app may not have an author.
+    #[serde(default)]
+    pub author: Option<User>,
+
+    pub created: String,
+
+    pub items: Vec<ChangelogItem>,
+}
+
+impl ChangelogEntry {
+    /// When the changes were made, or `None` if JIRA sent a timestamp that could not be read.
+    pub fn created_at(&self) -> Option<DateTime> {
+        DateTime::parse_rfc3339(&self.created)
+    }
+}
+
+/// A change to a single field of an issue.
+///
+/// Fields that refer to other things, like statuses or users, have their IDs in `from` and `to`, and their names in
+/// `from_string` and `to_string`.  Plain fields only have the latter.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ChangelogItem {
+    /// The field's name, like "Story Points", which may have changed since.
+    pub field: String,
+
+    /// JIRA Server and Data Center leave this out, so those changes can only be matched up by `field`.
+    #[serde(rename = "fieldId")]
+    #[serde(default)]
+    pub field_id: Option<String>,
+
+    #[serde(default)]
+    pub from: Option<String>,
+
+    #[serde(rename = "fromString")]
+    #[serde(default)]
+    pub from_string: Option<String>,
+
+    #[serde(default)]
+    pub to: Option<String>,
+
+    #[serde(rename = "toString")]
+    #[serde(default)]
+    pub to_string: Option<String>,
+}
+
+/// Time logged against an issue, as returned by a [get issue worklogs request][1].
+///
+/// [1]: https://developer.atlassian.com/cloud/jira/platform/rest/v3/api-group-issue-worklogs/#api-rest-api-3-issue-issueidorkey-worklog-get
+#[derive(Debug, Deserialize)]
+pub struct Worklog {
+    pub id: String,
+
+    /// Time logged by a user who has since been deleted may not have an author.
+    #[serde(default)]
+    pub author: Option<User>,
+
+    /// When the work was started, which is not necessarily when it was logged.
+    pub started: String,
+
+    #[serde(rename(deserialize = "timeSpentSeconds"))]
+    pub time_spent_seconds: u64,
+}
+
+impl Worklog {
+    /// When the work was started, or `None` if JIRA sent a timestamp that could not be read.
+    pub fn started_at(&self) -> Option<DateTime> {
+        DateTime::parse_rfc3339(&self.started)
+    }
+}
+
+/// A single page of an issue's worklogs.  Unlike most of JIRA's paginated endpoints, this one doesn't say whether it is
+/// the last page, so that has to be worked out from the total.
+#[derive(Debug, Deserialize)]
+struct WorklogPage {
+    #[serde(default)]
+    worklogs: Vec<Worklog>,
+
+    #[serde(default)]
+    total: u64,
+}
+
+/// The parts of a board's configuration that say which issues are on it, as returned by a [get configuration
+/// request][1].
+///
+/// [1]: https://developer.atlassian.com/cloud/jira/software/rest/api-group-board/#api-rest-agile-1-0-board-boardid-configuration-get
+#[derive(Debug, Deserialize)]
+struct BoardConfiguration {
+    filter: BoardFilter,
+}
+
+#[derive(Debug, Deserialize)]
+struct BoardFilter {
+    /// JIRA sends the ID as a string, even though filters are looked up by number.
+    id: String,
+}
+
+/// A JIRA project, as returned by a [get project request][1].
+///
+/// [1]: https://developer.atlassian.com/cloud/jira/platform/rest/v3/api-group-projects/#api-rest-api-3-project-projectidorkey-get
+#[derive(Debug, Deserialize)]
+pub struct Project {
+    #[serde(default)]
+    pub key: String,
+
+    #[serde(default)]
+    pub name: String,
+
+    /// `software`, `business`, or `service_desk`.
+    #[serde(rename(deserialize = "projectTypeKey"))]
+    #[serde(default)]
+    pub project_type_key: String,
+
+    /// `classic` for company-managed projects, and `next-gen` for team-managed ones.
+    #[serde(default)]
+    pub style: String,
+
+    /// Only sent when asked for with `expand=lead`.
+    #[serde(default)]
+    pub lead: Option<User>,
+}
+
+/// A single page of results from one of JIRA's paginated endpoints, like the Agile API's or the changelog's.
+#[derive(Debug, Deserialize)]
+struct Page<T> {
+    #[serde(rename(deserialize = "isLast"))]
+    #[serde(default)]
+    is_last: bool,
+
+    values: Vec<T>,
+}
+
+#[derive(Debug, Serialize)]
+struct SearchRequest<'a> {
+    // Left out when empty, so that searches cached before it was added are still found.
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    expand: &'a [&'a str],
+
+    fields: &'a [String],
+
+    jql: &'a JQLStatement,
+
+    #[serde(rename(serialize = "maxResults"))]
+    max_results: u64,
+
+    #[serde(rename(serialize = "startAt"))]
+    start_at: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct SearchResponse {
+    issues: Vec<SearchIssue>,
+
+    #[serde(default)]
+    total: u64,
+}
+
+#[derive(Debug, Serialize)]
+struct ParseJQLRequest<'a> {
+    queries: [&'a JQLStatement; 1],
+}
+
+#[derive(Debug, Deserialize)]
+struct ParseJQLResponse {
+    queries: Vec<ParsedJQL>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ParsedJQL {
+    #[serde(default)]
+    errors: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct MoveIssuesRequest<'a> {
+    issues: &'a [String],
+}
+
+/// The subset of an issue's fields requested from one of the get issue endpoints.
+#[derive(Debug, Deserialize)]
+struct IssueFields<T> {
+    fields: T,
+}
+
+#[derive(Debug, Deserialize)]
+struct SprintField {
+    sprint: Option<Sprint>,
+}
+
+/// A JIRA user, as returned by a [find users request][1].
+///
+/// [1]: https://developer.atlassian.com/cloud/jira/platform/rest/v3/api-group-user-search/#api-rest-api-3-user-search-get
+#[derive(Debug, Deserialize, Serialize)]
+pub struct User {
+    /// JIRA Data Center identifies people by username instead, so this is empty there.
+    #[serde(rename = "accountId")]
+    #[serde(default)]
+    pub account_id: String,
+
+    /// `atlassian` for people, `app` for bots and integrations, and `customer` for service desk customers.  JIRA Data
+    /// Center leaves this out.
+    #[serde(rename = "accountType")]
+    #[serde(default)]
+    pub account_type: Option<String>,
+
+    #[serde(rename = "displayName")]
+    #[serde(default)]
+    pub display_name: String,
+
+    #[serde(rename = "emailAddress")]
+    #[serde(default)]
+    pub email_address: Option<String>,
+}
+
+/// Whether the signed in user has each permission asked about, as returned by a [get my permissions request][1].
+///
+/// [1]: https://developer.atlassian.com/cloud/jira/platform/rest/v3/api-group-permissions/#api-rest-api-3-mypermissions-get
+#[derive(Debug, Deserialize)]
+struct MyPermissions {
+    permissions: HashMap<String, MyPermission>,
+}
+
+#[derive(Debug, Deserialize)]
+struct MyPermission {
+    #[serde(rename(deserialize = "havePermission"))]
+    have_permission: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct Watchers {
+    watchers: Vec<User>,
+}
+
+#[derive(Clone, Debug)]
+pub enum IssueEditUpdateLabel {
+    Add(String),
+    Remove(String),
+}
+
+impl Serialize for IssueEditUpdateLabel {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut m = serializer.serialize_map(Some(1))?;
+        match self {
+            IssueEditUpdateLabel::Add(label) => m.serialize_entry("add", label)?,
+            IssueEditUpdateLabel::Remove(label) => m.serialize_entry("remove", label)?,
+        }
+        m.end()
+    }
+}
+
+/// The operations to apply to an issue's fields, as the "update" key of an [edit issue request][1].
+///
+/// [1]: https://developer.atlassian.com/cloud/jira/platform/rest/v3/api-group-issues/#api-rest-api-3-issue-issueidorkey-put
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct IssueEditUpdate {
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub labels: Vec<IssueEditUpdateLabel>,
+}
+
+/// What JIRA responds with when an issue is created.
+///
+/// See https://developer.atlassian.com/cloud/jira/platform/rest/v3/api-group-issues/#api-rest-api-3-issue-post
+#[derive(Debug, Deserialize)]
+struct CreatedIssue {
+    key: String,
+}
+
+#[derive(Debug, Serialize)]
+struct IssueEditRequest<'a> {
+    #[serde(skip_serializing_if = "Map::is_empty")]
+    fields: &'a Map<String, JSONValue>,
+
+    update: &'a IssueEditUpdate,
+}
+
+/// Spaces requests out across every task using the client, so that JIRA's rate limits are respected before they are hit.
+///
+/// Requests are never sent closer together than the user asked for.  On top of that, the throttle follows what JIRA
+/// says about its limits on every response: it holds every request back while JIRA has asked for a pause with
+/// `Retry-After`, or until the limit resets once `X-RateLimit-Remaining` runs out, and spreads requests out over the
+/// rest of the window once JIRA says the limit is close.
+struct Throttle {
+    min_interval: Duration,
+    state: Mutex<ThrottleState>,
+}
+
+struct ThrottleState {
+    next_request_at: Instant,
+    /// The spacing JIRA's rate limit headers call for right now, which is zero while there's plenty of room.
+    adaptive_interval: Duration,
+}
+
+/// What JIRA's rate limit headers say about how much of the limit is left.
+///
+/// See https://developer.atlassian.com/cloud/jira/platform/rate-limiting/
+struct RateLimit {
+    remaining: Option<u64>,
+    resets_in: Option<Duration>,
+    near_limit: bool,
+}
+
+impl RateLimit {
+    fn read(response: &Response) -> Self {
+        let header = |name: &str| {
+            response
+                .headers()
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(|value| value.trim().to_owned())
+        };
+
+        let resets_in = header("X-RateLimit-Reset")
+            .and_then(|reset| DateTime::parse_rfc3339(&reset))
+            .map(|reset| reset.to_unix_seconds() - DateTime::now().to_unix_seconds())
+            .map(|seconds| Duration::from_secs(seconds.max(0) as u64));
+
+        RateLimit {
+            remaining: header("X-RateLimit-Remaining").and_then(|remaining| remaining.parse().ok()),
+            resets_in,
+            near_limit: header("X-RateLimit-NearLimit")
+                .map(|near_limit| near_limit.eq_ignore_ascii_case("true"))
+                .unwrap_or(false),
+        }
+    }
+}
+
+impl Throttle {
+    fn new() -> Self {
+        Throttle {
+            min_interval: Duration::ZERO,
+            state: Mutex::new(ThrottleState {
+                next_request_at: Instant::now(),
+                adaptive_interval: Duration::ZERO,
+            }),
+        }
+    }
+
+    /// Waits until the caller is allowed to send its request.
+    async fn wait(&self) {
+        let now = Instant::now();
+        let slot = {
+            // Unwrap here is considered safe since nothing can panic while holding the lock.
+            let mut state = self.state.lock().unwrap();
+            let slot = state.next_request_at.max(now);
+            state.next_request_at = slot + self.min_interval.max(state.adaptive_interval);
+            slot
+        };
+
+        if slot > now {
+            tokio::time::sleep(slot - now).await;
+        }
+    }
+
+    /// Adjusts to what a response says about JIRA's rate limits.
+    fn observe(&self, response: &Response) {
+        self.adjust(
+            Instant::now(),
+            &RateLimit::read(response),
+            retry_after(response),
+        );
+    }
+
+    fn adjust(&self, now: Instant, limit: &RateLimit, retry_after: Option<Duration>) {
+        // Unwrap here is considered safe since nothing can panic while holding the lock.
+        let mut state = self.state.lock().unwrap();
+
+        if let Some(retry_after) = retry_after {
+            state.next_request_at = state.next_request_at.max(now + retry_after);
+        }
+
+        state.adaptive_interval = match (limit.remaining, limit.resets_in) {
+            // Nothing is left, so nothing is sent until the limit resets.
+            (Some(0), Some(resets_in)) => {
+                state.next_request_at = state.next_request_at.max(now + resets_in);
+                Duration::ZERO
+            }
+            (Some(0), None) => Duration::ZERO,
+            // Spread what's left of the limit evenly over the time until it resets.  More requests left than fit in a
+            // u32 is as good as no limit at all.
+            (Some(remaining), Some(resets_in)) if limit.near_limit => {
+                resets_in / u32::try_from(remaining).unwrap_or(u32::MAX)
+            }
+            _ => Duration::ZERO,
+        };
+        state.next_request_at = state.next_request_at.max(now + state.adaptive_interval);
+    }
+}
+
+/// How the client retries requests that fail for reasons that usually pass on their own: being rate limited, JIRA's
+/// gateway being briefly unavailable, or timing out.
+#[derive(Clone, Copy, Debug)]
+pub struct RetryPolicy {
+    /// How many times a request is retried before the failure is returned as an error.
+    pub max_retries: u32,
+
+    /// How long to back off before the first retry, when JIRA doesn't say how long to wait.  This doubles with every
+    /// retry of the same request.
+    pub base_delay: Duration,
+
+    /// Whether to wait a random part of the way between half the delay and all of it, so that concurrent requests
+    /// that failed together don't all retry at the same moment.
+    pub jitter: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_retries: 5,
+            base_delay: Duration::from_secs(2),
+            jitter: true,
+        }
+    }
+}
+
+impl RetryPolicy {
+    fn delay(&self, attempt: u32) -> Duration {
+        let delay = self.base_delay.saturating_mul(2u32.saturating_pow(attempt));
+        if !self.jitter {
+            return delay;
+        }
+
+        // Every RandomState is seeded differently, which is all the randomness jitter needs.
+        let random = RandomState::new().build_hasher().finish();
+        let fraction = (random % 1000) as f64 / 1000.0;
+        delay.div_f64(2.0) + delay.div_f64(2.0).mul_f64(fraction)
+    }
+}
+
+/// Which failures a request is sent again after.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(super) enum RetryOn {
+    /// Any failure that's likely to pass, for requests that can safely be made twice, like reads and edits that set
+    /// fields to a value.
+    TransientFailure,
+    /// Only failures that mean JIRA never acted on the request, for ones that would do something again if they were
+    /// sent twice, like creating an issue.  A timeout or a gateway error can come after JIRA has already made the
+    /// change.
+    Unreceived,
+}
+
+/// Whether a request should be sent again after it failed, with the status it was answered with, or how it failed to
+/// get an answer.
+fn should_retry(
+    retry_on: RetryOn,
+    status: Option<StatusCode>,
+    timed_out: bool,
+    not_connected: bool,
+) -> bool {
+    match (retry_on, status) {
+        (RetryOn::TransientFailure, Some(status)) => is_transient(status),
+        (RetryOn::TransientFailure, None) => timed_out,
+        (RetryOn::Unreceived, Some(status)) => status == StatusCode::TOO_MANY_REQUESTS,
+        (RetryOn::Unreceived, None) => not_connected,
+    }
+}
+
+/// Whether a response is worth retrying, because whatever went wrong is likely to pass.
+fn is_transient(status: StatusCode) -> bool {
+    matches!(
+        status,
+        StatusCode::TOO_MANY_REQUESTS
+            | StatusCode::BAD_GATEWAY
+            | StatusCode::SERVICE_UNAVAILABLE
+            | StatusCode::GATEWAY_TIMEOUT
+    )
+}
+
+/// Describes a request that can't be sent because the client is offline.
+fn offline_error(request: &RequestBuilder) -> RequestError {
+    let description = request
+        .try_clone()
+        .and_then(|request| request.build().ok())
+        .map(|request| format!("{} {}", request.method(), request.url()))
+        .unwrap_or_else(|| "a request".to_owned());
+
+    RequestError::Offline(description)
+}
+
+/// Reads how long JIRA wants us to back off for, when it gives the delay in seconds.
+fn retry_after(response: &Response) -> Option<Duration> {
+    let seconds = response.headers().get(RETRY_AFTER)?.to_str().ok()?;
+
+    seconds.trim().parse::<u64>().ok().map(Duration::from_secs)
+}
+
+/// A request JIRA answered with an unsuccessful status, along with whatever JIRA said about why.
+#[derive(Debug)]
+pub struct Rejection {
+    /// The method and URL of the request.
+    pub request: String,
+    pub status: StatusCode,
+    /// The `errorMessages` and `errors` from the body of the response.
+    pub messages: Vec<String>,
+    /// Where to log in to answer a CAPTCHA, if JIRA Server or Data Center wants one before it will accept the
+    /// username again.
+    pub captcha_login_url: Option<String>,
+    inner: reqwest::Error,
+}
+
+/// The body JIRA sends along with most unsuccessful statuses.
+#[derive(Debug, Default, Deserialize)]
+struct ErrorCollection {
+    #[serde(rename(deserialize = "errorMessages"))]
+    #[serde(default)]
+    error_messages: Vec<String>,
+
+    #[serde(default)]
+    errors: HashMap<String, String>,
+}
+
+impl Rejection {
+    /// Reads why JIRA rejected a request from its response.
+    async fn read(request: String, response: Response, inner: reqwest::Error) -> Self {
+        let status = response.status();
+        // JIRA Server and Data Center say why they refused a login in this header, like
+        // `CAPTCHA_CHALLENGE; login-url=https://jira.example.com/login.jsp`.
+        let captcha_login_url = response
+            .headers()
+            .get("X-Authentication-Denied-Reason")
+            .and_then(|reason| reason.to_str().ok())
+            .filter(|reason| reason.starts_with("CAPTCHA_CHALLENGE"))
+            .map(|reason| {
+                reason
+                    .split_once("login-url=")
+                    .map(|(_, url)| url.trim().to_owned())
+                    .unwrap_or_default()
+            });
+        let errors: ErrorCollection = response.json().await.unwrap_or_default();
+        let mut messages = errors.error_messages;
+        let mut field_errors: Vec<(String, String)> = errors.errors.into_iter().collect();
+        field_errors.sort();
+        messages.extend(
+            field_errors
+                .into_iter()
+                .map(|(field, message)| format!("{}: {}", field, message)),
+        );
+
+        Rejection {
+            request,
+            status,
+            messages,
+            captcha_login_url,
+            inner,
+        }
+    }
+}
+
+impl fmt::Display for Rejection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.status, &self.captcha_login_url) {
+            (StatusCode::UNAUTHORIZED, Some(login_url)) => write!(
+                f,
+                "JIRA wants a CAPTCHA answered before it will accept this username again, after too many failed logins.  Log in to JIRA in a browser{} to clear it, then check --jira-username and --jira-token.",
+                if login_url.is_empty() {
+                    String::new()
+                } else {
+                    format!(" at {}", login_url)
+                }
+            )?,
+            (StatusCode::UNAUTHORIZED, None) => write!(
+                f,
+                "JIRA did not accept the credentials for {} (401 Unauthorized).  Check that --jira-token is right and hasn't expired or been revoked, and that --jira-username is the account it belongs to.  JIRA Cloud API tokens go with --auth-scheme basic, and Data Center personal access tokens with --auth-scheme bearer.",
+                self.request
+            )?,
+            (StatusCode::FORBIDDEN, _) => write!(
+                f,
+                "JIRA refused {} (403 Forbidden), since the account you logged in as doesn't have permission for it.  Ask a JIRA administrator to give it access to the project, or use a token for an account that has it.",
+                self.request
+            )?,
+            (StatusCode::NOT_FOUND, _) => write!(
+                f,
+                "JIRA could not find {} (404 Not Found).  Check that the issue, project, board, or sprint exists, and that --jira-url is the root of the JIRA site.  JIRA also answers this way for things your account isn't allowed to see.",
+                self.request
+            )?,
+            (status, _) => write!(f, "JIRA answered {} with {}.", self.request, status)?,
+        }
+
+        if !self.messages.is_empty() {
+            write!(f, "  JIRA said: {}", self.messages.join("  "))?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub enum RequestError {
+    Request(reqwest::Error),
+    /// JIRA answered with an unsuccessful status.
+    Rejected(Rejection),
+    UnexpectedResponse(serde_json::Error),
+    /// The client is offline, and the cache has no response to the request, given by its method and URL.
+    Offline(String),
+    /// A search matched more issues than the client is allowed to fetch: the number it matched, then the limit.
+    TooManyIssues(u64, u64),
+}
+
+impl RequestError {
+    /// The status JIRA answered with, if the request got that far.
+    pub fn status(&self) -> Option<StatusCode> {
+        match self {
+            RequestError::Request(inner) => inner.status(),
+            RequestError::Rejected(rejection) => Some(rejection.status),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for RequestError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RequestError::Request(inner) => write!(f, "{}", inner),
+            RequestError::Rejected(rejection) => write!(f, "{}", rejection),
+            RequestError::UnexpectedResponse(inner) => {
+                write!(
+                    f,
+                    "JIRA's response was not in the expected shape: {}",
+                    inner
+                )
+            }
+            RequestError::Offline(request) => write!(
+                f,
+                "--offline is on, so {} could not be sent to JIRA, and there is no cached response for it.  Run the command once with --cache-ttl while JIRA is reachable to fill the cache.",
+                request
+            ),
+            RequestError::TooManyIssues(total, max_issues) => write!(
+                f,
+                "The search matches {} issues, which is more than the {} allowed by --max-issues.  Narrow the search down, or raise --max-issues if you really mean to fetch them all.",
+                total, max_issues
+            ),
+        }
+    }
+}
+
+impl Error for RequestError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            RequestError::Request(inner) => Some(inner),
+            RequestError::Rejected(rejection) => Some(&rejection.inner),
+            RequestError::UnexpectedResponse(inner) => Some(inner),
+            RequestError::Offline(_) | RequestError::TooManyIssues(_, _) => None,
+        }
+    }
+}
+
+impl From<reqwest::Error> for RequestError {
+    fn from(e: reqwest::Error) -> Self {
+        RequestError::Request(e)
+    }
+}
+
+/// How far `Client::search_stream` has gotten through the issues matching a search.
+struct SearchProgress {
+    start_at: u64,
+    page: vec::IntoIter<SearchIssue>,
+    done: bool,
+}
+
+/// How the client proves who it is to JIRA.
+#[derive(Clone, Debug)]
+pub enum Credentials {
+    /// An email address or username and an API token, which is how JIRA Cloud authenticates.
+    Basic { username: String, token: String },
+    /// A personal access token on its own, which is how JIRA Data Center authenticates.
+    Bearer { token: String },
+}
+
+impl Credentials {
+    /// Everything that would give the credentials away if it turned up in a log.
+    fn secrets(&self) -> Vec<String> {
+        let mut secrets = match self {
+            Credentials::Basic { token, .. } | Credentials::Bearer { token } => vec![token.clone()],
+        };
+        if let Ok(header) = self.header_value().to_str() {
+            secrets.push(header.to_owned());
+        }
+
+        secrets
+    }
+
+    fn header_value(&self) -> HeaderValue {
+        let value = match self {
+            Credentials::Basic { username, token } => {
+                format!(
+                    "Basic {}",
+                    STANDARD.encode(format!("{}:{}", username, token))
+                )
+            }
+            Credentials::Bearer { token } => format!("Bearer {}", token),
+        };
+
+        // A token with characters that can't go in a header can't be sent at all, so it's sent empty instead, and JIRA
+        // refuses the request like it would any other wrong token.
+        let mut value =
+            HeaderValue::from_str(&value).unwrap_or_else(|_| HeaderValue::from_static(""));
+        value.set_sensitive(true);
+        value
+    }
+}
+
+/// How the client's HTTP connections are set up.
+#[derive(Clone, Debug)]
+pub struct HttpSettings {
+    /// Applies separately to connecting and to each request as a whole, including reading the response.
+    pub timeout: Duration,
+
+    /// Certificate authorities to trust on top of the system's, for JIRA instances with certificates from a private CA.
+    pub root_certificates: Vec<Certificate>,
+
+    /// Accepts any certificate, even an expired one or one for another host.  This makes the connection open to
+    /// interception, so it's only for when there's no other way to reach JIRA.
+    pub accept_invalid_certs: bool,
+}
+
+/// The same settings the command uses when it isn't given any.
+impl Default for HttpSettings {
+    fn default() -> Self {
+        HttpSettings {
+            timeout: Duration::from_secs(30),
+            root_certificates: vec![],
+            accept_invalid_certs: false,
+        }
+    }
+}
+
+/// Which version of JIRA's platform REST API to use.  JIRA Cloud has both, but Server and Data Center only have 2.
+///
+/// The two mostly differ in how they lay out rich text: version 3 sends descriptions and comments in the Atlassian
+/// Document Format, where version 2 sends wiki markup.  Some newer features of JIRA Cloud are only in version 3.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ApiVersion {
+    /// Version 3 on JIRA Cloud, and version 2 everywhere else.
+    Auto,
+    V2,
+    V3,
+}
+
+impl FromStr for ApiVersion {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "auto" => Ok(ApiVersion::Auto),
+            "2" => Ok(ApiVersion::V2),
+            "3" => Ok(ApiVersion::V3),
+            _ => Err("the API version has to be 2, 3, or auto".to_owned()),
+        }
+    }
+}
+
+/// Just enough of a [get server info request][1] to tell JIRA Cloud apart from the rest.
+///
+/// [1]: https://developer.atlassian.com/cloud/jira/platform/rest/v2/api-group-server-info/#api-rest-api-2-serverinfo-get
+#[derive(Debug, Deserialize)]
+struct ServerInfo {
+    /// `Cloud`, `Server`, or `DataCenter`.
+    #[serde(rename(deserialize = "deploymentType"))]
+    #[serde(default)]
+    deployment_type: String,
+}
+
+/// Provides a reusable HTTP client for the endpoints this tool needs, implementing `JiraApi` against a live JIRA.
+///
+/// Paths are relative to the root of the JIRA site, since the Agile API lives outside of `/rest/api`.  Requests that
+/// are rate limited, hit an unavailable gateway, or time out are retried according to the client's `RetryPolicy`,
+/// honoring the `Retry-After` header.
+pub struct Client {
+    base_url: String,
+    client: HttpClient,
+    throttle: Throttle,
+    retry_policy: RetryPolicy,
+    cache: Option<ResponseCache>,
+    field_cache: Option<ResponseCache>,
+    offline: bool,
+    audit_log: Option<AuditLog>,
+    page_size: u64,
+    max_issues: Option<u64>,
+    api_version: ApiVersion,
+    debug_log: DebugLog,
+    /// The version `ApiVersion::Auto` settled on, once it has been worked out.
+    detected_api_version: Mutex<Option<u8>>,
+}
+
+impl Client {
+    /// Initialize a Client for the URL, with the given credentials.
+    ///
+    /// This may fail if the TLS backend cannot be initialized, or if the resolver cannot load the system
+    /// configuration.
+    pub fn new(
+        url: &str,
+        credentials: &Credentials,
+        settings: HttpSettings,
+    ) -> Result<Self, reqwest::Error> {
+        let mut default_headers = HeaderMap::new();
+        default_headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+        default_headers.insert(ACCEPT, HeaderValue::from_static("application/json"));
+        default_headers.insert(AUTHORIZATION, credentials.header_value());
+
+        // Search results are mostly the same field names over and over, so they shrink a great deal compressed.
+        let mut builder = ClientBuilder::new()
+            .default_headers(default_headers)
+            .gzip(true)
+            .deflate(true)
+            .connect_timeout(settings.timeout)
+            .timeout(settings.timeout)
+            .danger_accept_invalid_certs(settings.accept_invalid_certs);
+        for certificate in settings.root_certificates {
+            builder = builder.add_root_certificate(certificate);
+        }
+        let client = builder.build()?;
+
+        Ok(Client {
+            base_url: url.trim_end_matches('/').to_owned(),
+            client,
+            throttle: Throttle::new(),
+            retry_policy: RetryPolicy::default(),
+            cache: None,
+            field_cache: None,
+            offline: false,
+            audit_log: None,
+            page_size: DEFAULT_SEARCH_PAGE_SIZE,
+            max_issues: None,
+            api_version: ApiVersion::Auto,
+            debug_log: DebugLog::new(credentials.secrets()),
+            detected_api_version: Mutex::new(None),
+        })
+    }
+
+    /// Limits how many requests this client sends per second, shared between every task using it.
+    pub fn with_max_requests_per_second(mut self, max_requests_per_second: f64) -> Self {
+        self.throttle.min_interval = Duration::from_secs_f64(1.0 / max_requests_per_second);
+        self
+    }
+
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    /// Reuses search results from the cache while they are fresh enough, and saves new ones to it.
+    pub fn with_cache(mut self, cache: ResponseCache) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Keeps JIRA's fields in their own cache, since they change far less often than search results.
+    pub fn with_field_cache(mut self, field_cache: ResponseCache) -> Self {
+        self.field_cache = Some(field_cache);
+        self
+    }
+
+    /// Never sends anything to JIRA.  Reads are answered from the cache however old its responses are, and anything
+    /// else fails with `RequestError::Offline`.
+    pub fn with_offline(mut self, offline: bool) -> Self {
+        self.offline = offline;
+        self
+    }
+
+    /// Asks for this many issues in each page of search results.  JIRA may send fewer, if it has a lower limit.
+    pub fn with_page_size(mut self, page_size: u64) -> Self {
+        self.page_size = page_size;
+        self
+    }
+
+    /// Refuses to fetch searches that match more than this many issues, failing with `RequestError::TooManyIssues`.
+    pub fn with_max_issues(mut self, max_issues: u64) -> Self {
+        self.max_issues = Some(max_issues);
+        self
+    }
+
+    /// Writes every request and response to stderr, with the credentials redacted.
+    pub fn with_debug_http(mut self, debug_http: bool) -> Self {
+        self.debug_log.enable(debug_http);
+        self
+    }
+
+    pub fn with_api_version(mut self, api_version: ApiVersion) -> Self {
+        self.api_version = api_version;
+        self
+    }
+
+    /// Records every request this client makes that changes something in JIRA to the audit log.
+    pub fn with_audit_log(mut self, audit_log: AuditLog) -> Self {
+        self.audit_log = Some(audit_log);
+        self
+    }
+
+    /// Turns a path within the platform REST API, like `/search`, into one under the version of it being used.
+    async fn api(&self, path: &str) -> String {
+        let version = match self.api_version {
+            ApiVersion::V2 => 2,
+            ApiVersion::V3 => 3,
+            ApiVersion::Auto => self.detect_api_version().await,
+        };
+
+        format!("/rest/api/{}{}", version, path)
+    }
+
+    /// Asks JIRA whether it is JIRA Cloud, which has version 3 of the REST API, or a Server or Data Center install,
+    /// which only has version 2.
+    ///
+    /// The answer is kept in the field cache, since a site doesn't move to the cloud often.  If JIRA can't be asked,
+    /// version 3 is used, and the request that needed it reports the problem.
+    async fn detect_api_version(&self) -> u8 {
+        // Unwrap here is considered safe since the lock is never held while something could panic.
+        if let Some(version) = *self.detected_api_version.lock().unwrap() {
+            return version;
+        }
+
+        let server_info: Result<ServerInfo, RequestError> = self
+            .fetch_cached(
+                self.field_cache.as_ref(),
+                "GET /rest/api/2/serverInfo",
+                self.get("/rest/api/2/serverInfo"),
+            )
+            .await;
+        let version = match server_info {
+            Ok(server_info) if server_info.deployment_type != "Cloud" => 2,
+            _ => 3,
+        };
+        *self.detected_api_version.lock().unwrap() = Some(version);
+
+        version
+    }
+
+    pub(super) fn get(&self, path: &str) -> RequestBuilder {
+        self.client.get(format!("{}{}", self.base_url, path))
+    }
+
+    pub(super) fn post(&self, path: &str) -> RequestBuilder {
+        self.client.post(format!("{}{}", self.base_url, path))
+    }
+
+    pub(super) fn put(&self, path: &str) -> RequestBuilder {
+        self.client.put(format!("{}{}", self.base_url, path))
+    }
+
+    fn delete(&self, path: &str) -> RequestBuilder {
+        self.client.delete(format!("{}{}", self.base_url, path))
+    }
+
+    /// Sends a request, waiting on the throttle first and backing off whenever it fails in a way that's worth
+    /// retrying.
+    ///
+    /// When JIRA says how long to back off for, the throttle holds back every request, not just this one.  Any other
+    /// unsuccessful status is returned as an error.
+    pub(super) async fn send(&self, request: RequestBuilder) -> Result<Response, RequestError> {
+        self.send_retrying(request, RetryOn::TransientFailure).await
+    }
+
+    /// Like [Client::send], retrying only the failures `retry_on` allows.
+    async fn send_retrying(
+        &self,
+        request: RequestBuilder,
+        retry_on: RetryOn,
+    ) -> Result<Response, RequestError> {
+        if self.offline {
+            return Err(offline_error(&request));
+        }
+
+        let mut attempt = 0;
+
+        loop {
+            self.throttle.wait().await;
+
+            // Unwrap here is considered safe since every request this client makes has a JSON, byte, or empty body,
+            // which can always be cloned.
+            let built = request.try_clone().unwrap().build()?;
+            self.debug_log.request(&built);
+            let started = Instant::now();
+            let result = self.client.execute(built.try_clone().unwrap()).await;
+            match &result {
+                Ok(response) => {
+                    self.debug_log.response(&built, response, started.elapsed());
+                    self.throttle.observe(response);
+                }
+                Err(e) => self.debug_log.failure(&built, e, started.elapsed()),
+            }
+
+            let retry = match &result {
+                Ok(response) => should_retry(retry_on, Some(response.status()), false, false),
+                Err(e) => should_retry(retry_on, e.status(), e.is_timeout(), e.is_connect()),
+            };
+            if !retry || attempt >= self.retry_policy.max_retries {
+                let response = result?;
+                // The error has to be made before the body is logged, since that loses the response's URL.
+                let status_error = response.error_for_status_ref().err();
+                let response = self.debug_log.body(response).await?;
+
+                return match status_error {
+                    Some(e) => {
+                        let request = format!("{} {}", built.method(), built.url());
+                        Err(RequestError::Rejected(
+                            Rejection::read(request, response, e).await,
+                        ))
+                    }
+                    None => Ok(response),
+                };
+            }
+
+            let told_when = matches!(&result, Ok(response) if retry_after(response).is_some());
+            if !told_when {
+                tokio::time::sleep(self.retry_policy.delay(attempt)).await;
+            }
+            attempt += 1;
+        }
+    }
+
+    /// Sends a request that only reads from JIRA, going through the cache if there is one.
+    ///
+    /// A fresh cached response is used without asking JIRA at all.  A stale one is sent along with the `ETag` and
+    /// `Last-Modified` validators it came with, and if JIRA answers that nothing has changed, the cached response is
+    /// used and made fresh again, saving the download.
+    async fn fetch_cached<T: DeserializeOwned>(
+        &self,
+        cache: Option<&ResponseCache>,
+        cache_key: &str,
+        mut request: RequestBuilder,
+    ) -> Result<T, RequestError> {
+        let cache = match cache {
+            Some(cache) => cache,
+            None => return Ok(self.send(request).await?.json().await?),
+        };
+        // The same request means something different on another JIRA instance.
+        let cache_key = format!("{} {}", self.base_url, cache_key);
+
+        let cached = cache.get(&cache_key);
+        if self.offline {
+            return match cached {
+                Some(entry) => {
+                    serde_json::from_value(entry.response).map_err(RequestError::UnexpectedResponse)
+                }
+                None => Err(offline_error(&request)),
+            };
+        }
+        if let Some(entry) = &cached {
+            if entry.fresh {
+                if let Ok(response) = serde_json::from_value(entry.response.clone()) {
+                    return Ok(response);
+                }
+            }
+            if let Some(etag) = &entry.etag {
+                request = request.header(IF_NONE_MATCH, etag);
+            }
+            if let Some(last_modified) = &entry.last_modified {
+                request = request.header(IF_MODIFIED_SINCE, last_modified);
+            }
+        }
+
+        let response = self.send(request).await?;
+        let header = |name| {
+            response
+                .headers()
+                .get(name)
+                .and_then(|value: &HeaderValue| value.to_str().ok())
+                .map(|value| value.to_owned())
+        };
+        let etag = header(ETAG);
+        let last_modified = header(LAST_MODIFIED);
+
+        let body = match cached {
+            Some(entry) if response.status() == StatusCode::NOT_MODIFIED => entry.response,
+            _ => response.json().await?,
+        };
+
+        if let Err(e) = cache.put(&cache_key, &body, etag.as_deref(), last_modified.as_deref()) {
+            eprintln!(
+                "Could not save a response to the cache in {}: {}",
+                cache.dir().display(),
+                e
+            );
+        }
+
+        serde_json::from_value(body).map_err(RequestError::UnexpectedResponse)
+    }
+
+    /// Sends a request that changes something in JIRA, and records it to the audit log along with its payload.
+    ///
+    /// The payload is whatever describes the change, whether it was sent as the body or in the query string.  Failed
+    /// requests are recorded too.  A problem writing to the audit log is reported, but does not fail the request,
+    /// since the change has already been made by then.
+    async fn send_mutation<B: Serialize>(
+        &self,
+        operation: &str,
+        issues: &[String],
+        request: RequestBuilder,
+        payload: &B,
+        retry_on: RetryOn,
+    ) -> Result<Response, RequestError> {
+        let result = self.send_retrying(request, retry_on).await;
+
+        if let Some(audit_log) = &self.audit_log {
+            let status = match &result {
+                Ok(response) => Some(response.status()),
+                Err(e) => e.status(),
+            };
+            let mutation = Mutation {
+                timestamp: DateTime::now().to_rfc3339(),
+                issues,
+                operation,
+                request: serde_json::to_value(payload).unwrap_or(JSONValue::Null),
+                status: status.map(|status| status.as_u16()),
+            };
+
+            if let Err(e) = audit_log.record(&mutation) {
+                eprintln!(
+                    "Could not write to the audit log at {}: {}",
+                    audit_log.path().display(),
+                    e
+                );
+            }
+        }
+
+        result
+    }
+
+    /// Gets every page of results from one of JIRA's paginated endpoints.
+    async fn get_all_pages<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        query: &[(&str, &str)],
+    ) -> Result<Vec<T>, RequestError> {
+        let mut start_at = 0usize;
+        let mut result = vec![];
+
+        loop {
+            let mut page: Page<T> = self
+                .send(self.get(path).query(query).query(&[("startAt", start_at)]))
+                .await?
+                .json()
+                .await?;
+            let num_values = page.values.len();
+            result.append(&mut page.values);
+
+            if page.is_last || num_values == 0 {
+                break;
+            }
+
+            start_at += num_values;
+        }
+
+        Ok(result)
+    }
+
+    /// Gets every page of search results, with the given parts of each issue expanded.
+    async fn search_all_expanded(
+        &self,
+        fields: &[String],
+        expand: &[&str],
+        jql: &JQLStatement,
+    ) -> Result<Vec<SearchIssue>, RequestError> {
+        let first_page = self.search(fields, expand, jql, 0).await?;
+        // JIRA may send fewer issues per page than were asked for, so the rest of the pages are the size it chose.
+        let page_size = first_page.issues.len() as u64;
+        let total = first_page.total;
+        let mut result = first_page.issues;
+        if page_size == 0 || page_size >= total {
+            return Ok(result);
+        }
+
+        let starts: Vec<u64> = (page_size..total).step_by(page_size as usize).collect();
+        let mut pages: Vec<Option<Result<Vec<SearchIssue>, RequestError>>> =
+            starts.iter().map(|_| None).collect();
+
+        bulk::for_each_concurrently(
+            &starts,
+            MAX_CONCURRENT_SEARCH_PAGES,
+            |start| async move {
+                self.search(fields, expand, jql, *start)
+                    .await
+                    .map(|response| response.issues)
+            },
+            |start, page| {
+                pages[((start - page_size) / page_size) as usize] = Some(page);
+            },
+        )
+        .await;
+
+        for page in pages.into_iter().flatten() {
+            result.append(&mut page?);
+        }
+
+        Ok(result)
+    }
+
+    /// Searches JIRA for issues matching the given JQL statement, getting a single page of results.
+    ///
+    /// Every page says how many issues there are in all, so a search matching more than `max_issues` fails on its
+    /// first page.
+    ///
+    /// See https://developer.atlassian.com/cloud/jira/platform/rest/v3/api-group-issue-search/#api-rest-api-3-search-post
+    async fn search(
+        &self,
+        fields: &[String],
+        expand: &[&str],
+        jql: &JQLStatement,
+        start_at: u64,
+    ) -> Result<SearchResponse, RequestError> {
+        let body = SearchRequest {
+            expand,
+            fields,
+            jql,
+            max_results: self.page_size,
+            start_at,
+        };
+        let path = self.api("/search").await;
+        // Unwrap here is considered safe since the request is made of strings and numbers, which always serialize.
+        let cache_key = format!("POST {} {}", path, serde_json::to_string(&body).unwrap());
+
+        let response: SearchResponse = self
+            .fetch_cached(
+                self.cache.as_ref(),
+                &cache_key,
+                self.post(&path).json(&body),
+            )
+            .await?;
+
+        match self.max_issues {
+            Some(max_issues) if response.total > max_issues => {
+                Err(RequestError::TooManyIssues(response.total, max_issues))
+            }
+            _ => Ok(response),
+        }
+    }
+}
+
+impl JiraApi for Client {
+    /// See https://developer.atlassian.com/cloud/jira/platform/rest/v3/api-group-issues/#api-rest-api-3-issue-issueidorkey-changelog-get
+    async fn get_changelog(&self, key: &str) -> Result<Vec<ChangelogEntry>, RequestError> {
+        let path = self.api(&format!("/issue/{}/changelog", key)).await;
+        self.get_all_pages(&path, &[]).await
+    }
+
+    /// See https://developer.atlassian.com/cloud/jira/platform/rest/v3/api-group-issue-worklogs/#api-rest-api-3-issue-issueidorkey-worklog-get
+    async fn get_worklogs(&self, key: &str) -> Result<Vec<Worklog>, RequestError> {
+        let path = self.api(&format!("/issue/{}/worklog", key)).await;
+        let mut start_at = 0u64;
+        let mut result = vec![];
+
+        loop {
+            let page: WorklogPage = self
+                .send(self.get(&path).query(&[("startAt", start_at)]))
+                .await?
+                .json()
+                .await?;
+            let num_worklogs = page.worklogs.len() as u64;
+            result.extend(page.worklogs);
+            start_at += num_worklogs;
+
+            if num_worklogs == 0 || start_at >= page.total {
+                break;
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// See https://developer.atlassian.com/cloud/jira/software/rest/api-group-board/#api-rest-agile-1-0-board-get
+    async fn get_boards(&self, project_key: &str) -> Result<Vec<Board>, RequestError> {
+        self.get_all_pages(
+            "/rest/agile/1.0/board",
+            &[("projectKeyOrId", project_key), ("type", "scrum")],
+        )
+        .await
+    }
+
+    /// See https://developer.atlassian.com/cloud/jira/software/rest/api-group-board/#api-rest-agile-1-0-board-boardid-sprint-get
+    async fn get_sprints(&self, board_id: u64, states: &str) -> Result<Vec<Sprint>, RequestError> {
+        let path = format!("/rest/agile/1.0/board/{}/sprint", board_id);
+        self.get_all_pages(&path, &[("state", states)]).await
+    }
+
+    /// See https://developer.atlassian.com/cloud/jira/software/rest/api-group-board/#api-rest-agile-1-0-board-boardid-configuration-get
+    async fn get_board_filter_id(&self, board_id: u64) -> Result<u64, RequestError> {
+        let path = format!("/rest/agile/1.0/board/{}/configuration", board_id);
+        let configuration: BoardConfiguration = self.send(self.get(&path)).await?.json().await?;
+
+        serde_json::from_str(&configuration.filter.id).map_err(RequestError::UnexpectedResponse)
+    }
+
+    /// See https://developer.atlassian.com/cloud/jira/software/rest/api-group-sprint/#api-rest-agile-1-0-sprint-sprintid-get
+    async fn get_sprint(&self, sprint_id: u64) -> Result<Sprint, RequestError> {
+        let path = format!("/rest/agile/1.0/sprint/{}", sprint_id);
+        Ok(self.send(self.get(&path)).await?.json().await?)
+    }
+
+    /// See https://developer.atlassian.com/cloud/jira/software/rest/api-group-sprint/#api-rest-agile-1-0-sprint-sprintid-issue-get
+    async fn get_sprint_issues(
+        &self,
+        sprint_id: u64,
+        fields: &[String],
+    ) -> Result<Vec<SearchIssue>, RequestError> {
+        let path = format!("/rest/agile/1.0/sprint/{}/issue", sprint_id);
+        let fields = fields.join(",");
+        let mut start_at = 0u64;
+        let mut result = vec![];
+
+        loop {
+            let page: SearchResponse = self
+                .send(
+                    self.get(&path)
+                        .query(&[("fields", &fields)])
+                        .query(&[("startAt", start_at)]),
+                )
+                .await?
+                .json()
+                .await?;
+            let num_issues = page.issues.len() as u64;
+            result.extend(page.issues);
+            start_at += num_issues;
+
+            if num_issues == 0 || start_at >= page.total {
+                break;
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// See https://developer.atlassian.com/cloud/jira/software/rest/api-group-sprint/#api-rest-agile-1-0-sprint-sprintid-issue-post
+    async fn move_issues_to_sprint(
+        &self,
+        sprint_id: u64,
+        keys: &[String],
+    ) -> Result<(), RequestError> {
+        let path = format!("/rest/agile/1.0/sprint/{}/issue", sprint_id);
+        let body = MoveIssuesRequest { issues: keys };
+        self.send_mutation(
+            "move_issues_to_sprint",
+            keys,
+            self.post(&path).json(&body),
+            &body,
+            RetryOn::TransientFailure,
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    /// See https://developer.atlassian.com/cloud/jira/software/rest/api-group-backlog/#api-rest-agile-1-0-backlog-issue-post
+    async fn move_issues_to_backlog(&self, keys: &[String]) -> Result<(), RequestError> {
+        let body = MoveIssuesRequest { issues: keys };
+        self.send_mutation(
+            "move_issues_to_backlog",
+            keys,
+            self.post("/rest/agile/1.0/backlog/issue").json(&body),
+            &body,
+            RetryOn::TransientFailure,
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    /// See https://developer.atlassian.com/cloud/jira/software/rest/api-group-issue/#api-rest-agile-1-0-issue-issueidorkey-get
+    async fn get_issue_sprint_id(&self, key: &str) -> Result<Option<u64>, RequestError> {
+        let path = format!("/rest/agile/1.0/issue/{}", key);
+        let issue: IssueFields<SprintField> = self
+            .send(self.get(&path).query(&[("fields", "sprint")]))
+            .await?
+            .json()
+            .await?;
+
+        Ok(issue.fields.sprint.map(|sprint| sprint.id))
+    }
+
+    /// See https://developer.atlassian.com/cloud/jira/platform/rest/v3/api-group-issues/#api-rest-api-3-issue-issueidorkey-get
+    async fn get_issue(&self, key: &str, fields: &[String]) -> Result<SearchIssue, RequestError> {
+        let path = self.api(&format!("/issue/{}", key)).await;
+        Ok(self
+            .send(self.get(&path).query(&[("fields", fields.join(","))]))
+            .await?
+            .json()
+            .await?)
+    }
+
+    /// See https://developer.atlassian.com/cloud/jira/platform/rest/v3/api-group-issue-search/#api-rest-api-3-search-post
+    async fn search_all(
+        &self,
+        fields: &[String],
+        jql: &JQLStatement,
+    ) -> Result<Vec<SearchIssue>, RequestError> {
+        self.search_all_expanded(fields, &[], jql).await
+    }
+
+    async fn search_all_with_changelogs(
+        &self,
+        fields: &[String],
+        jql: &JQLStatement,
+    ) -> Result<Vec<SearchIssue>, RequestError> {
+        let mut issues = self
+            .search_all_expanded(fields, &["changelog"], jql)
+            .await?;
+
+        let truncated: Vec<usize> = issues
+            .iter()
+            .enumerate()
+            .filter(|(_, issue)| {
+                issue
+                    .changelog
+                    .as_ref()
+                    .map(|changelog| (changelog.histories.len() as u64) < changelog.total)
+                    .unwrap_or(false)
+            })
+            .map(|(i, _)| i)
+            .collect();
+        let mut histories: Vec<(usize, Result<Vec<ChangelogEntry>, RequestError>)> = vec![];
+        bulk::for_each_concurrently(
+            &truncated,
+            MAX_CONCURRENT_SEARCH_PAGES,
+            |i| self.get_changelog(&issues[*i].key),
+            |i, result| histories.push((*i, result)),
+        )
+        .await;
+
+        for (i, result) in histories {
+            let history = result?;
+            issues[i].changelog = Some(Changelog {
+                total: history.len() as u64,
+                histories: history,
+            });
+        }
+
+        for changelog in issues
+            .iter_mut()
+            .filter_map(|issue| issue.changelog.as_mut())
+        {
+            changelog
+                .histories
+                .sort_by_key(|entry| entry.created_at().map(DateTime::to_unix_seconds));
+        }
+
+        Ok(issues)
+    }
+
+    fn search_stream<'a>(
+        &'a self,
+        fields: &'a [String],
+        jql: &'a JQLStatement,
+    ) -> impl Stream<Item = Result<SearchIssue, RequestError>> + 'a {
+        let start = SearchProgress {
+            start_at: 0,
+            page: vec![].into_iter(),
+            done: false,
+        };
+
+        stream::unfold(start, move |mut progress| async move {
+            loop {
+                if let Some(issue) = progress.page.next() {
+                    return Some((Ok(issue), progress));
+                }
+                if progress.done {
+                    return None;
+                }
+
+                match self.search(fields, &[], jql, progress.start_at).await {
+                    Ok(response) => {
+                        let num_issues = response.issues.len() as u64;
+                        progress.start_at += num_issues;
+                        progress.done = num_issues == 0 || progress.start_at >= response.total;
+                        progress.page = response.issues.into_iter();
+                    }
+                    Err(e) => {
+                        progress.done = true;
+                        return Some((Err(e), progress));
+                    }
+                }
+            }
+        })
+    }
+
+    /// See https://developer.atlassian.com/cloud/jira/platform/rest/v3/api-group-jql/#api-rest-api-3-jql-parse-post
+    async fn parse_jql(&self, jql: &JQLStatement) -> Result<Vec<String>, RequestError> {
+        let body = ParseJQLRequest { queries: [jql] };
+        let path = self.api("/jql/parse").await;
+        let response: ParseJQLResponse = self
+            .send(
+                self.post(&path)
+                    .query(&[("validation", "strict")])
+                    .json(&body),
+            )
+            .await?
+            .json()
+            .await?;
+
+        Ok(response
+            .queries
+            .into_iter()
+            .flat_map(|query| query.errors)
+            .collect())
+    }
+
+    /// See https://developer.atlassian.com/cloud/jira/platform/rest/v3/api-group-projects/#api-rest-api-3-project-projectidorkey-get
+    async fn get_project(&self, key: &str) -> Result<Project, RequestError> {
+        let path = self.api(&format!("/project/{}", key)).await;
+        self.fetch_cached(
+            self.cache.as_ref(),
+            &format!("GET {}", path),
+            self.get(&path),
+        )
+        .await
+    }
+
+    /// See https://developer.atlassian.com/cloud/jira/platform/rest/v3/api-group-projects/#api-rest-api-3-project-search-get
+    async fn get_projects(&self) -> Result<Vec<Project>, RequestError> {
+        let path = self.api("/project/search").await;
+        if path.starts_with("/rest/api/2/") {
+            // Most versions of JIRA Data Center can't page through projects, and send them all at once instead.
+            let path = self.api("/project").await;
+            return Ok(self
+                .send(self.get(&path).query(&[("expand", "lead")]))
+                .await?
+                .json()
+                .await?);
+        }
+
+        self.get_all_pages(&path, &[("expand", "lead")]).await
+    }
+
+    /// See https://developer.atlassian.com/cloud/jira/platform/rest/v3/api-group-projects/#api-rest-api-3-project-projectidorkey-statuses-get
+    async fn get_project_statuses(
+        &self,
+        project_key: &str,
+    ) -> Result<Vec<IssueTypeStatuses>, RequestError> {
+        let path = self
+            .api(&format!("/project/{}/statuses", project_key))
+            .await;
+        self.fetch_cached(
+            self.field_cache.as_ref(),
+            &format!("GET {}", path),
+            self.get(&path),
+        )
+        .await
+    }
+
+    /// See https://developer.atlassian.com/cloud/jira/platform/rest/v3/api-group-issue-fields/#api-rest-api-3-field-get
+    async fn get_fields(&self) -> Result<Vec<Field>, RequestError> {
+        let path = self.api("/field").await;
+        self.fetch_cached(
+            self.field_cache.as_ref(),
+            &format!("GET {}", path),
+            self.get(&path),
+        )
+        .await
+    }
+
+    /// See https://developer.atlassian.com/cloud/jira/platform/rest/v3/api-group-workflow-statuses/#api-rest-api-3-status-get
+    async fn get_statuses(&self) -> Result<Vec<Status>, RequestError> {
+        // Statuses are set up along with workflows, so they change about as rarely as fields do.
+        let path = self.api("/status").await;
+        self.fetch_cached(
+            self.field_cache.as_ref(),
+            &format!("GET {}", path),
+            self.get(&path),
+        )
+        .await
+    }
+
+    /// See https://developer.atlassian.com/cloud/jira/platform/rest/v3/api-group-issues/#api-rest-api-3-issue-issueidorkey-put
+    async fn edit_issue(&self, key: &str, update: &IssueEditUpdate) -> Result<(), RequestError> {
+        self.edit_issue_fields(key, &Map::new(), update).await
+    }
+
+    /// See https://developer.atlassian.com/cloud/jira/platform/rest/v3/api-group-issues/#api-rest-api-3-issue-issueidorkey-put
+    async fn edit_issue_fields(
+        &self,
+        key: &str,
+        fields: &Map<String, JSONValue>,
+        update: &IssueEditUpdate,
+    ) -> Result<(), RequestError> {
+        let path = self.api(&format!("/issue/{}", key)).await;
+        let body = IssueEditRequest { fields, update };
+        self.send_mutation(
+            "edit_issue",
+            &[key.to_owned()],
+            self.put(&path).json(&body),
+            &body,
+            RetryOn::TransientFailure,
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    /// See https://developer.atlassian.com/cloud/jira/platform/rest/v3/api-group-issues/#api-rest-api-3-issue-post
+    async fn create_issue(&self, fields: &Map<String, JSONValue>) -> Result<String, RequestError> {
+        let path = self.api("/issue").await;
+        let body = json!({ "fields": fields });
+        let created: CreatedIssue = self
+            .send_mutation(
+                "create_issue",
+                &[],
+                self.post(&path).json(&body),
+                &body,
+                // Sending it again after JIRA made the issue would make another one.
+                RetryOn::Unreceived,
+            )
+            .await?
+            .json()
+            .await?;
+
+        Ok(created.key)
+    }
+
+    /// See https://developer.atlassian.com/cloud/jira/platform/rest/v3/api-group-users/#api-rest-api-3-user-get
+    async fn get_user(&self, account_id: &str) -> Result<User, RequestError> {
+        let path = self.api("/user").await;
+        self.fetch_cached(
+            self.cache.as_ref(),
+            &format!("GET {}?accountId={}", path, account_id),
+            self.get(&path).query(&[("accountId", account_id)]),
+        )
+        .await
+    }
+
+    /// See https://developer.atlassian.com/cloud/jira/platform/rest/v3/api-group-myself/#api-rest-api-3-myself-get
+    async fn get_myself(&self) -> Result<User, RequestError> {
+        // Not cached, since this is asked to check that the credentials still work.
+        let path = self.api("/myself").await;
+        Ok(self.send(self.get(&path)).await?.json().await?)
+    }
+
+    /// See https://developer.atlassian.com/cloud/jira/platform/rest/v3/api-group-permissions/#api-rest-api-3-mypermissions-get
+    async fn get_my_permissions(
+        &self,
+        project_key: Option<&str>,
+        permissions: &[&str],
+    ) -> Result<HashMap<String, bool>, RequestError> {
+        let path = self.api("/mypermissions").await;
+        let mut request = self
+            .get(&path)
+            .query(&[("permissions", permissions.join(","))]);
+        if let Some(project_key) = project_key {
+            request = request.query(&[("projectKey", project_key)]);
+        }
+        let my_permissions: MyPermissions = self.send(request).await?.json().await?;
+
+        Ok(my_permissions
+            .permissions
+            .into_iter()
+            .map(|(permission, my_permission)| (permission, my_permission.have_permission))
+            .collect())
+    }
+
+    /// See https://developer.atlassian.com/cloud/jira/platform/rest/v3/api-group-user-search/#api-rest-api-3-user-search-get
+    async fn find_users(&self, query: &str) -> Result<Vec<User>, RequestError> {
+        let path = self.api("/user/search").await;
+        Ok(self
+            .send(self.get(&path).query(&[("query", query)]))
+            .await?
+            .json()
+            .await?)
+    }
+
+    /// See https://developer.atlassian.com/cloud/jira/platform/rest/v3/api-group-issue-watchers/#api-rest-api-3-issue-issueidorkey-watchers-get
+    async fn get_watchers(&self, key: &str) -> Result<Vec<String>, RequestError> {
+        let path = self.api(&format!("/issue/{}/watchers", key)).await;
+        let watchers: Watchers = self.send(self.get(&path)).await?.json().await?;
+
+        Ok(watchers
+            .watchers
+            .into_iter()
+            .map(|user| user.account_id)
+            .collect())
+    }
+
+    /// See https://developer.atlassian.com/cloud/jira/platform/rest/v3/api-group-issue-watchers/#api-rest-api-3-issue-issueidorkey-watchers-post
+    async fn add_watcher(&self, key: &str, account_id: &str) -> Result<(), RequestError> {
+        let path = self.api(&format!("/issue/{}/watchers", key)).await;
+        self.send_mutation(
+            "add_watcher",
+            &[key.to_owned()],
+            self.post(&path).json(account_id),
+            &account_id,
+            RetryOn::TransientFailure,
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    /// See https://developer.atlassian.com/cloud/jira/platform/rest/v3/api-group-issue-watchers/#api-rest-api-3-issue-issueidorkey-watchers-delete
+    async fn remove_watcher(&self, key: &str, account_id: &str) -> Result<(), RequestError> {
+        let path = self.api(&format!("/issue/{}/watchers", key)).await;
+        self.send_mutation(
+            "remove_watcher",
+            &[key.to_owned()],
+            self.delete(&path).query(&[("accountId", account_id)]),
+            &json!({ "accountId": account_id }),
+            RetryOn::TransientFailure,
+        )
+        .await?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn near_limit(remaining: u64, resets_in: Option<Duration>) -> RateLimit {
+        RateLimit {
+            remaining: Some(remaining),
+            resets_in,
+            near_limit: true,
+        }
+    }
+
+    /// How long after `now` the throttle holds the next request back, and the spacing it keeps between them.
+    fn held_back(limit: RateLimit) -> (Duration, Duration) {
+        let throttle = Throttle::new();
+        let now = Instant::now();
+        throttle.adjust(now, &limit, None);
+        let state = throttle.state.lock().unwrap();
+
+        (state.next_request_at - now, state.adaptive_interval)
+    }
+
+    #[test]
+    fn nothing_is_sent_until_an_exhausted_limit_resets() {
+        let resets_in = Duration::from_secs(30);
+
+        assert_eq!(
+            held_back(near_limit(0, Some(resets_in))),
+            (resets_in, Duration::ZERO)
+        );
+        // Without a reset time there is nothing to wait for; a 429 will say how long to back off.
+        assert_eq!(
+            held_back(near_limit(0, None)),
+            (Duration::ZERO, Duration::ZERO)
+        );
+    }
+
+    #[test]
+    fn what_is_left_of_a_limit_is_spread_over_the_time_until_it_resets() {
+        let resets_in = Duration::from_secs(60);
+
+        assert_eq!(
+            held_back(near_limit(4, Some(resets_in))),
+            (Duration::from_secs(15), Duration::from_secs(15))
+        );
+        // Once converted to a u32, this would have been zero.
+        let huge = u64::from(u32::MAX) + 1;
+        assert_eq!(
+            held_back(near_limit(huge, Some(resets_in))).1,
+            resets_in / u32::MAX
+        );
+        assert_eq!(
+            held_back(near_limit(u64::MAX, Some(resets_in))).1,
+            resets_in / u32::MAX
+        );
+    }
+
+    #[test]
+    fn plenty_of_room_leaves_requests_unspaced() {
+        let limit = RateLimit {
+            remaining: Some(10),
+            resets_in: Some(Duration::from_secs(60)),
+            near_limit: false,
+        };
+
+        assert_eq!(held_back(limit), (Duration::ZERO, Duration::ZERO));
+    }
+
+    #[test]
+    fn reads_and_edits_retry_any_transient_failure() {
+        let retry_on = RetryOn::TransientFailure;
+
+        for status in [
+            StatusCode::TOO_MANY_REQUESTS,
+            StatusCode::BAD_GATEWAY,
+            StatusCode::SERVICE_UNAVAILABLE,
+            StatusCode::GATEWAY_TIMEOUT,
+        ] {
+            assert!(should_retry(retry_on, Some(status), false, false));
+        }
+        assert!(should_retry(retry_on, None, true, false));
+        assert!(!should_retry(
+            retry_on,
+            Some(StatusCode::BAD_REQUEST),
+            false,
+            false
+        ));
+        assert!(!should_retry(retry_on, Some(StatusCode::OK), false, false));
+    }
+
+    #[test]
+    fn creates_only_retry_what_jira_never_acted_on() {
+        let retry_on = RetryOn::Unreceived;
+
+        assert!(should_retry(
+            retry_on,
+            Some(StatusCode::TOO_MANY_REQUESTS),
+            false,
+            false
+        ));
+        assert!(should_retry(retry_on, None, false, true));
+        // JIRA may have made the issue before the gateway gave up on it.
+        for status in [
+            StatusCode::BAD_GATEWAY,
+            StatusCode::SERVICE_UNAVAILABLE,
+            StatusCode::GATEWAY_TIMEOUT,
+        ] {
+            assert!(!should_retry(retry_on, Some(status), false, false));
+        }
+        assert!(!should_retry(retry_on, None, true, false));
+    }
+
+    /// Answers each connection with the next of `responses`, then hands back the requests it was sent.
+    fn answer(responses: Vec<String>) -> (String, std::thread::JoinHandle<Vec<String>>) {
+        use std::io::{Read, Write};
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let server = std::thread::spawn(move || {
+            let mut requests = vec![];
+            for response in responses {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut request = vec![];
+                let mut buffer = [0; 1024];
+                while !request.windows(4).any(|window| window == b"\r\n\r\n") {
+                    let read = stream.read(&mut buffer).unwrap();
+                    request.extend_from_slice(&buffer[..read]);
+                }
+                stream.write_all(response.as_bytes()).unwrap();
+                requests.push(String::from_utf8_lossy(&request).to_lowercase());
+            }
+            requests
+        });
+
+        (url, server)
+    }
+
+    #[test]
+    fn stale_responses_are_checked_with_jira_and_fresh_ones_are_not() {
+        let dir = std::env::temp_dir().join(format!(
+            "statustracker-client-cache-test-{}",
+            std::process::id()
+        ));
+        let fields = r#"[{"id":"customfield_10016","name":"Story Points"}]"#;
+        let (url, server) = answer(vec![
+            format!(
+                "HTTP/1.1 200 OK\r\nETag: \"v1\"\r\nLast-Modified: Wed, 21 Oct 2015 07:28:00 GMT\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                fields.len(),
+                fields
+            ),
+            "HTTP/1.1 304 Not Modified\r\nETag: \"v1\"\r\nConnection: close\r\n\r\n".to_owned(),
+        ]);
+        let credentials = Credentials::Bearer {
+            token: "t".to_owned(),
+        };
+        let client = Client::new(&url, &credentials, HttpSettings::default()).unwrap();
+        let fetch = |ttl| {
+            let cache = ResponseCache::open(&dir, ttl).unwrap();
+            crate::jira::api::fake::block_on(client.fetch_cached::<JSONValue>(
+                Some(&cache),
+                "GET /rest/api/2/field",
+                client.get("/rest/api/2/field"),
+            ))
+            .unwrap()
+        };
+
+        let downloaded = fetch(Duration::ZERO);
+        std::thread::sleep(Duration::from_millis(20));
+        let revalidated = fetch(Duration::ZERO);
+        let requests = server.join().unwrap();
+        // The server is gone, so this can only be answered from the cache.
+        let cached = fetch(Duration::from_secs(60));
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let expected: JSONValue = serde_json::from_str(fields).unwrap();
+        assert_eq!(downloaded, expected);
+        assert_eq!(revalidated, expected);
+        assert_eq!(cached, expected);
+        assert!(!requests[0].contains("if-none-match"));
+        assert!(requests[1].contains("if-none-match: \"v1\""));
+        assert!(requests[1].contains("if-modified-since: wed, 21 oct 2015 07:28:00 gmt"));
+    }
+}
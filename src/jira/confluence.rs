@@ -0,0 +1,142 @@
+/// Confluence's REST API, spoken through the same client as JIRA's, since Atlassian sites take the same credentials for
+/// both.  The client has to be made with Confluence's address, like https://your-domain.atlassian.net/wiki.
+use serde::Deserialize;
+use serde_json::json;
+
+use super::client::{Client, RequestError};
+
+/// Separates the parts of an attachment upload.  It can't turn up in the charts that get attached.
+const BOUNDARY: &str = "statustracker-attachment-boundary";
+
+#[derive(Debug, Deserialize)]
+pub struct Page {
+    pub id: String,
+    pub version: PageVersion,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PageVersion {
+    pub number: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct Pages {
+    results: Vec<Page>,
+}
+
+impl Client {
+    /// Finds the page with a title in a space, where titles are unique.
+    ///
+    /// See https://developer.atlassian.com/cloud/confluence/rest/v1/api-group-content/#api-wiki-rest-api-content-get
+    pub async fn find_confluence_page(
+        &self,
+        space_key: &str,
+        title: &str,
+    ) -> Result<Option<Page>, RequestError> {
+        let pages: Pages = self
+            .send(self.get("/rest/api/content").query(&[
+                ("spaceKey", space_key),
+                ("title", title),
+                ("expand", "version"),
+            ]))
+            .await?
+            .json()
+            .await?;
+
+        Ok(pages.results.into_iter().next())
+    }
+
+    /// Creates a page with a body in Confluence's storage format, under another page if given.
+    ///
+    /// See https://developer.atlassian.com/cloud/confluence/rest/v1/api-group-content/#api-wiki-rest-api-content-post
+    pub async fn create_confluence_page(
+        &self,
+        space_key: &str,
+        parent_id: Option<&str>,
+        title: &str,
+        body: &str,
+    ) -> Result<Page, RequestError> {
+        let mut page = json!({
+            "type": "page",
+            "title": title,
+            "space": { "key": space_key },
+            "body": { "storage": { "value": body, "representation": "storage" } },
+        });
+        if let Some(parent_id) = parent_id {
+            page["ancestors"] = json!([{ "id": parent_id }]);
+        }
+
+        Ok(self
+            .send(self.post("/rest/api/content").json(&page))
+            .await?
+            .json()
+            .await?)
+    }
+
+    /// Replaces the title and body of a page, as a new version of it.
+    ///
+    /// See https://developer.atlassian.com/cloud/confluence/rest/v1/api-group-content/#api-wiki-rest-api-content-id-put
+    pub async fn update_confluence_page(
+        &self,
+        page: &Page,
+        title: &str,
+        body: &str,
+    ) -> Result<Page, RequestError> {
+        let update = json!({
+            "id": page.id,
+            "type": "page",
+            "title": title,
+            "body": { "storage": { "value": body, "representation": "storage" } },
+            "version": { "number": page.version.number + 1 },
+        });
+
+        Ok(self
+            .send(
+                self.put(&format!("/rest/api/content/{}", page.id))
+                    .json(&update),
+            )
+            .await?
+            .json()
+            .await?)
+    }
+
+    /// Attaches a file to a page, replacing any attachment with the same name.
+    ///
+    /// See https://developer.atlassian.com/cloud/confluence/rest/v1/api-group-content---attachments/#api-wiki-rest-api-content-id-child-attachment-put
+    pub async fn attach_to_confluence_page(
+        &self,
+        page_id: &str,
+        file_name: &str,
+        content_type: &str,
+        contents: &[u8],
+    ) -> Result<(), RequestError> {
+        // The form is put together by hand so that the body is plain bytes, which can be sent again on a retry.
+        let mut body = format!(
+            "--{}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"{}\"\r\nContent-Type: {}\r\n\r\n",
+            BOUNDARY, file_name, content_type
+        )
+        .into_bytes();
+        body.extend_from_slice(contents);
+        body.extend_from_slice(
+            format!(
+                "\r\n--{0}\r\nContent-Disposition: form-data; name=\"minorEdit\"\r\n\r\ntrue\r\n--{0}--\r\n",
+                BOUNDARY
+            )
+            .as_bytes(),
+        );
+
+        self.send(
+            self.put(&format!("/rest/api/content/{}/child/attachment", page_id))
+                .header(
+                    "Content-Type",
+                    format!("multipart/form-data; boundary={}", BOUNDARY),
+                )
+                // Confluence turns away uploads without this, to guard against cross-site requests.
+                .header("X-Atlassian-Token", "no-check")
+                .body(body),
+        )
+        .await?;
+
+        Ok(())
+    }
+}
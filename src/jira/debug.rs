@@ -0,0 +1,123 @@
+/// Writes every request the client sends and every response it gets back to stderr, for diagnosing problems with
+/// JIRA's API.
+///
+/// The output is meant to be pasted into bug reports, so anything that could give the user's credentials away is
+/// redacted: headers are never written at all, and the token is blanked out wherever else it turns up, like in a URL
+/// or in a body JIRA echoed back.
+use std::time::Duration;
+
+use reqwest::{Request, Response};
+
+/// The most of each body to write, since a page of search results can run to megabytes.
+const MAX_BODY_BYTES: usize = 4096;
+
+pub struct DebugLog {
+    enabled: bool,
+    secrets: Vec<String>,
+}
+
+impl DebugLog {
+    /// Starts a log that redacts the given secrets, but writes nothing until it is enabled.
+    pub fn new(secrets: Vec<String>) -> Self {
+        DebugLog {
+            enabled: false,
+            secrets: secrets
+                .into_iter()
+                .filter(|secret| !secret.is_empty())
+                .collect(),
+        }
+    }
+
+    pub fn enable(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    fn redact(&self, text: &str) -> String {
+        let mut redacted = text.to_owned();
+        for secret in &self.secrets {
+            redacted = redacted.replace(secret.as_str(), "[redacted]");
+        }
+
+        redacted
+    }
+
+    fn write_body(&self, body: &[u8]) {
+        if body.is_empty() {
+            return;
+        }
+
+        let shown = &body[..body.len().min(MAX_BODY_BYTES)];
+        eprintln!("[http]   {}", self.redact(&String::from_utf8_lossy(shown)));
+        if body.len() > shown.len() {
+            eprintln!("[http]   ... {} more bytes", body.len() - shown.len());
+        }
+    }
+
+    pub fn request(&self, request: &Request) {
+        if !self.enabled {
+            return;
+        }
+
+        eprintln!(
+            "[http] -> {} {}",
+            request.method(),
+            self.redact(request.url().as_str())
+        );
+        if let Some(body) = request.body().and_then(|body| body.as_bytes()) {
+            self.write_body(body);
+        }
+    }
+
+    /// Writes the status of a response.  Its body is written separately by `body`, once it's known that the response
+    /// won't be retried.
+    pub fn response(&self, request: &Request, response: &Response, elapsed: Duration) {
+        if !self.enabled {
+            return;
+        }
+
+        eprintln!(
+            "[http] <- {} from {} {} in {}ms",
+            response.status(),
+            request.method(),
+            self.redact(request.url().as_str()),
+            elapsed.as_millis()
+        );
+    }
+
+    pub fn failure(&self, request: &Request, error: &reqwest::Error, elapsed: Duration) {
+        if !self.enabled {
+            return;
+        }
+
+        eprintln!(
+            "[http] <- {} {} failed after {}ms: {}",
+            request.method(),
+            self.redact(request.url().as_str()),
+            elapsed.as_millis(),
+            self.redact(&error.to_string())
+        );
+    }
+
+    /// Writes the body of a response.
+    ///
+    /// Reading the body uses the response up, so it's put back together around the body afterwards.  Anything that
+    /// needs the response's URL, like `error_for_status`, has to happen before this.
+    pub async fn body(&self, response: Response) -> Result<Response, reqwest::Error> {
+        if !self.enabled {
+            return Ok(response);
+        }
+
+        let status = response.status();
+        let version = response.version();
+        let headers = response.headers().clone();
+        let body = response.bytes().await?;
+        self.write_body(&body);
+
+        let mut rebuilt = http::Response::new(body);
+        *rebuilt.status_mut() = status;
+        *rebuilt.version_mut() = version;
+        *rebuilt.headers_mut() = headers;
+
+        Ok(Response::from(rebuilt))
+    }
+}
@@ -0,0 +1,285 @@
+/// Works out what issues looked like at moments in the past, by undoing the changes in their changelogs.
+///
+/// Issues need to have been fetched with `search_all_with_changelogs`, along with the fields being looked back on.
+/// Anything without a changelog is taken to have always been the way it is now.
+use std::collections::HashMap;
+
+use serde_json::Value as JSONValue;
+
+use super::{
+    api::JiraApi,
+    client::{ChangelogItem, RequestError, SearchIssue},
+};
+use crate::date::DateTime;
+
+/// Gets the name of the status category each status is in, keyed by the status's ID.
+///
+/// Changelogs only say which status an issue moved between, so this is needed to tell when it was done.
+pub async fn status_categories(
+    client: &impl JiraApi,
+) -> Result<HashMap<String, String>, RequestError> {
+    Ok(client
+        .get_statuses()
+        .await?
+        .into_iter()
+        .map(|status| (status.id, status.status_category.name))
+        .collect())
+}
+
+/// When an issue was created, or `None` if it was fetched without the `created` field.
+pub fn created_at(issue: &SearchIssue) -> Option<DateTime> {
+    DateTime::parse_rfc3339(issue.fields.get("created")?.as_str()?)
+}
+
+/// Whether an issue had been created by a moment.  Issues fetched without the `created` field always count.
+pub fn existed_at(issue: &SearchIssue, at: DateTime) -> bool {
+    created_at(issue)
+        .map(|created_at| created_at.to_unix_seconds() <= at.to_unix_seconds())
+        .unwrap_or(true)
+}
+
+/// Finds the first change made to a field after a moment, whose `from` is what the field was at that moment.
+fn first_change_after<F: Fn(&ChangelogItem) -> bool>(
+    issue: &SearchIssue,
+    at: DateTime,
+    is_field: F,
+) -> Option<&ChangelogItem> {
+    let at = at.to_unix_seconds();
+    issue
+        .changelog
+        .as_ref()?
+        .histories
+        .iter()
+        .filter(|entry| {
+            entry
+                .created_at()
+                .map(|created_at| created_at.to_unix_seconds() > at)
+                .unwrap_or(false)
+        })
+        .flat_map(|entry| entry.items.iter())
+        .find(|item| is_field(item))
+}
+
+/// Returns the name of the status category an issue was in at a moment, like `status_category` does for now.
+///
+/// A status that isn't in `categories`, like one that has since been deleted, has no category.
+pub fn status_category_at(
+    issue: &SearchIssue,
+    categories: &HashMap<String, String>,
+    at: DateTime,
+) -> Option<String> {
+    match first_change_after(issue, at, |item| item.field == "status") {
+        Some(change) => categories.get(change.from.as_ref()?).cloned(),
+        None => issue.status_category(),
+    }
+}
+
+/// Every change to an issue's status, oldest first, as when it happened and the categories it moved from and to.
+fn category_changes<'a>(
+    issue: &'a SearchIssue,
+    categories: &'a HashMap<String, String>,
+) -> impl Iterator<Item = (DateTime, Option<&'a str>, Option<&'a str>)> + 'a {
+    let category = move |status_id: &Option<String>| {
+        status_id
+            .as_ref()
+            .and_then(|status_id| categories.get(status_id))
+            .map(|category| category.as_str())
+    };
+
+    issue
+        .changelog
+        .iter()
+        .flat_map(|changelog| changelog.histories.iter())
+        .filter_map(|entry| Some((entry.created_at()?, &entry.items)))
+        .flat_map(|(created_at, items)| items.iter().map(move |item| (created_at, item)))
+        .filter(|(_, item)| item.field == "status")
+        .map(move |(created_at, item)| (created_at, category(&item.from), category(&item.to)))
+}
+
+/// When an issue was last moved into the "Done" category, or `None` if it isn't done now.
+///
+/// Issues that were created done, or that have been done since before their history begins, count as done when they
+/// were created.
+pub fn completed_at(issue: &SearchIssue, categories: &HashMap<String, String>) -> Option<DateTime> {
+    if issue.status_category().as_deref() != Some("Done") {
+        return None;
+    }
+
+    category_changes(issue, categories)
+        .filter(|(_, from, to)| *to == Some("Done") && *from != Some("Done"))
+        .map(|(changed_at, _, _)| changed_at)
+        .last()
+        .or_else(|| created_at(issue))
+}
+
+/// When an issue was moved into the status it is in now, or when it was created if it has never moved.
+pub fn entered_status_at(issue: &SearchIssue) -> Option<DateTime> {
+    issue
+        .changelog
+        .iter()
+        .flat_map(|changelog| changelog.histories.iter())
+        .rev()
+        .filter(|entry| entry.items.iter().any(|item| item.field == "status"))
+        .find_map(|entry| entry.created_at())
+        .or_else(|| created_at(issue))
+}
+
+/// A stretch of time an issue spent in one status.
+#[derive(Debug)]
+pub struct StatusPeriod {
+    /// The status's name when the issue was in it.
+    pub status: String,
+    /// The name of the status's category, like "In Progress".  A status that has since been deleted has none.
+    pub category: Option<String>,
+    pub entered_at: DateTime,
+    /// `None` while the issue is still in the status.
+    pub left_at: Option<DateTime>,
+}
+
+/// Every stretch of time an issue has spent in a status, oldest first, starting from when it was created.
+///
+/// Issues fetched without the `created` field start from their first status change instead.
+pub fn status_periods(
+    issue: &SearchIssue,
+    categories: &HashMap<String, String>,
+) -> Vec<StatusPeriod> {
+    let category = |status_id: &Option<String>| {
+        status_id
+            .as_ref()
+            .and_then(|status_id| categories.get(status_id))
+            .cloned()
+    };
+    // Changes should always name the statuses, but the ID at least tells them apart when one doesn't.
+    let status_name = |name: &Option<String>, status_id: &Option<String>| {
+        name.clone()
+            .or_else(|| status_id.clone())
+            .unwrap_or_default()
+    };
+    let changes: Vec<(DateTime, &ChangelogItem)> = issue
+        .changelog
+        .iter()
+        .flat_map(|changelog| changelog.histories.iter())
+        .filter_map(|entry| Some((entry.created_at()?, &entry.items)))
+        .flat_map(|(created_at, items)| items.iter().map(move |item| (created_at, item)))
+        .filter(|(_, item)| item.field == "status")
+        .collect();
+
+    let mut periods = vec![];
+    let mut current = match (created_at(issue), changes.first()) {
+        (Some(created_at), Some((_, first))) => Some(StatusPeriod {
+            status: status_name(&first.from_string, &first.from),
+            category: category(&first.from),
+            entered_at: created_at,
+            left_at: None,
+        }),
+        (Some(created_at), None) => Some(StatusPeriod {
+            status: issue.status().unwrap_or_default(),
+            category: issue.status_category(),
+            entered_at: created_at,
+            left_at: None,
+        }),
+        (None, _) => None,
+    };
+    for (changed_at, item) in changes {
+        if let Some(mut period) = current.take() {
+            period.left_at = Some(changed_at);
+            periods.push(period);
+        }
+        current = Some(StatusPeriod {
+            status: status_name(&item.to_string, &item.to),
+            category: category(&item.to),
+            entered_at: changed_at,
+            left_at: None,
+        });
+    }
+    periods.extend(current);
+
+    periods
+}
+
+/// When a change that `matches` was last made to an issue, like a link being added.
+pub fn last_changed_at<F: Fn(&ChangelogItem) -> bool>(
+    issue: &SearchIssue,
+    matches: F,
+) -> Option<DateTime> {
+    issue
+        .changelog
+        .iter()
+        .flat_map(|changelog| changelog.histories.iter())
+        .rev()
+        .filter(|entry| entry.items.iter().any(&matches))
+        .find_map(|entry| entry.created_at())
+}
+
+/// When work on an issue started, which is when it was first moved into the "In Progress" category.
+///
+/// Issues that went straight from to do to done never started, as far as JIRA knows.
+pub fn started_at(issue: &SearchIssue, categories: &HashMap<String, String>) -> Option<DateTime> {
+    category_changes(issue, categories)
+        .find(|(_, _, to)| *to == Some("In Progress"))
+        .map(|(changed_at, _, _)| changed_at)
+}
+
+/// Returns the story points an issue had at a moment, like `story_points` does for now.
+///
+/// Changes are matched up by field ID where JIRA says what it was, and otherwise by `field_name`.
+pub fn story_points_at(
+    issue: &SearchIssue,
+    field_ids: &[String],
+    field_name: &str,
+    at: DateTime,
+) -> Option<f64> {
+    let is_story_points = |item: &ChangelogItem| match &item.field_id {
+        Some(field_id) => field_ids.contains(field_id),
+        None => item.field == field_name,
+    };
+
+    match first_change_after(issue, at, is_story_points) {
+        Some(change) => change.from_string.as_ref()?.trim().parse().ok(),
+        None => super::story_points(issue, field_ids),
+    }
+}
+
+/// Returns the IDs of the sprints an issue is in now, from the sprint field.
+///
+/// JIRA Cloud sends each sprint as an object, while older versions of JIRA Server send a string like
+/// `com.atlassian.greenhopper.service.sprint.Sprint@1f[id=1,name=Sprint 1,...]`.
+pub fn sprint_ids(issue: &SearchIssue, sprint_field_ids: &[String]) -> Vec<u64> {
+    let sprints = match sprint_field_ids
+        .iter()
+        .find_map(|field_id| issue.fields.get(field_id)?.as_array())
+    {
+        Some(sprints) => sprints,
+        None => return vec![],
+    };
+
+    sprints
+        .iter()
+        .filter_map(|sprint| match sprint {
+            JSONValue::Object(sprint) => sprint.get("id")?.as_u64(),
+            JSONValue::String(sprint) => {
+                sprint.split_once("id=")?.1.split(',').next()?.parse().ok()
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// Returns the IDs of the sprints an issue was in at a moment, like `sprint_ids` does for now.
+pub fn sprint_ids_at(issue: &SearchIssue, sprint_field_ids: &[String], at: DateTime) -> Vec<u64> {
+    let is_sprint = |item: &ChangelogItem| match &item.field_id {
+        Some(field_id) => sprint_field_ids.contains(field_id),
+        None => item.field == "Sprint",
+    };
+
+    match first_change_after(issue, at, is_sprint) {
+        // The sprints are listed by ID, like "12, 13".
+        Some(change) => change
+            .from
+            .iter()
+            .flat_map(|ids| ids.split(','))
+            .filter_map(|id| id.trim().parse().ok())
+            .collect(),
+        None => sprint_ids(issue, sprint_field_ids),
+    }
+}
@@ -0,0 +1,701 @@
+/// Models enough of [JQL][1] to build searches from command line options, without pasting strings together.
+///
+/// [1]: https://support.atlassian.com/jira-software-cloud/docs/what-is-advanced-searching-in-jira-cloud/
+use std::convert::TryFrom;
+
+use serde::{Serialize, Serializer};
+
+pub mod builder;
+pub mod parser;
+
+/// Words JQL reserves, which can't be used as a field name without quoting them.
+///
+/// See ["Restricted words and characters"][1].
+///
+/// [1]: https://support.atlassian.com/jira-software-cloud/docs/what-is-advanced-searching-in-jira-cloud/#Advancedsearching-restrictionsRestrictedwordsandcharacters
+const RESERVED_WORDS: &[&str] = &[
+    "a",
+    "abort",
+    "access",
+    "add",
+    "after",
+    "alias",
+    "all",
+    "alter",
+    "an",
+    "and",
+    "any",
+    "are",
+    "as",
+    "asc",
+    "audit",
+    "avg",
+    "before",
+    "begin",
+    "between",
+    "boolean",
+    "break",
+    "by",
+    "byte",
+    "catch",
+    "cf",
+    "changed",
+    "char",
+    "character",
+    "check",
+    "checkpoint",
+    "collate",
+    "collation",
+    "column",
+    "commit",
+    "connect",
+    "continue",
+    "count",
+    "create",
+    "current",
+    "date",
+    "decimal",
+    "declare",
+    "decrement",
+    "default",
+    "defaults",
+    "define",
+    "delete",
+    "delimiter",
+    "desc",
+    "difference",
+    "distinct",
+    "divide",
+    "do",
+    "double",
+    "drop",
+    "else",
+    "empty",
+    "encoding",
+    "end",
+    "equals",
+    "escape",
+    "exclusive",
+    "exec",
+    "execute",
+    "exists",
+    "explain",
+    "false",
+    "fetch",
+    "file",
+    "field",
+    "first",
+    "float",
+    "for",
+    "from",
+    "function",
+    "go",
+    "goto",
+    "grant",
+    "greater",
+    "group",
+    "having",
+    "identified",
+    "if",
+    "immediate",
+    "in",
+    "increment",
+    "index",
+    "initial",
+    "inner",
+    "inout",
+    "input",
+    "insert",
+    "int",
+    "integer",
+    "intersect",
+    "intersection",
+    "into",
+    "is",
+    "isempty",
+    "isnull",
+    "join",
+    "last",
+    "left",
+    "less",
+    "like",
+    "limit",
+    "lock",
+    "long",
+    "max",
+    "min",
+    "minus",
+    "mode",
+    "modify",
+    "modulo",
+    "more",
+    "multiply",
+    "next",
+    "noaudit",
+    "not",
+    "notin",
+    "nowait",
+    "null",
+    "number",
+    "object",
+    "of",
+    "on",
+    "option",
+    "or",
+    "order",
+    "outer",
+    "output",
+    "power",
+    "previous",
+    "prior",
+    "privileges",
+    "public",
+    "raise",
+    "raw",
+    "remainder",
+    "rename",
+    "resource",
+    "return",
+    "returns",
+    "row",
+    "rownum",
+    "rows",
+    "select",
+    "session",
+    "set",
+    "share",
+    "size",
+    "sqrt",
+    "start",
+    "strict",
+    "string",
+    "subtract",
+    "sum",
+    "synonym",
+    "table",
+    "then",
+    "to",
+    "trans",
+    "transaction",
+    "trigger",
+    "true",
+    "uid",
+    "union",
+    "unique",
+    "update",
+    "user",
+    "validate",
+    "values",
+    "view",
+    "was",
+    "when",
+    "whenever",
+    "where",
+    "while",
+    "with",
+];
+
+/// Whether a word is reserved in JQL, in any case.
+fn is_reserved_word(word: &str) -> bool {
+    RESERVED_WORDS
+        .iter()
+        .any(|reserved| reserved.eq_ignore_ascii_case(word))
+}
+
+/// Quotes text for use in a JQL query, as a value or a field name.
+///
+/// Inside quotes, reserved words and characters lose their meaning, so only the quote, the backslash, and control
+/// characters like newlines need escaping.  Everything else, including non-ASCII text, is kept as it is, so the parser
+/// reads back exactly the text that was given.
+///
+/// This is not the double backslash escaping JIRA's text search (`~`) needs; that would change the value being
+/// compared against.
+pub fn escape_text_field(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len() + 2);
+    escaped.push('"');
+
+    for c in s.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if c.is_control() => {
+                // Characters outside the basic multilingual plane are never control characters, so four hex digits
+                // are always enough.
+                escaped.push_str(&format!("\\u{:04x}", c as u32));
+            }
+            c => escaped.push(c),
+        }
+    }
+
+    escaped.push('"');
+    escaped
+}
+
+/// Formats the name of a field for use in a JQL query.
+///
+/// Custom field IDs like `customfield_10016`, as the REST API gives them, become `cf[10016]`, which is how JQL refers to
+/// them.  Names with spaces or other special characters in them, like `Story Points`, are quoted.
+pub fn serialize_field(field: &str) -> String {
+    if let Some(id) = field.strip_prefix("customfield_") {
+        if !id.is_empty() && id.chars().all(|c| c.is_ascii_digit()) {
+            return format!("cf[{}]", id);
+        }
+    }
+
+    let is_cf_reference = field
+        .strip_prefix("cf[")
+        .and_then(|rest| rest.strip_suffix(']'))
+        .map(|id| !id.is_empty() && id.chars().all(|c| c.is_ascii_digit()))
+        .unwrap_or(false);
+    let is_plain = !field.is_empty()
+        && !is_reserved_word(field)
+        && field
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '_' || c == '.');
+
+    if is_cf_reference || is_plain {
+        field.to_owned()
+    } else {
+        escape_text_field(field)
+    }
+}
+
+/// Represents an object that has a string representation in JQL, either as a standalone query or as part of a query.
+pub trait SerializableToJQL {
+    fn serialize_to_jql(&self) -> String;
+}
+
+/// Represents a [value][1] in JQL.
+///
+/// [1]: https://support.atlassian.com/jira-software-cloud/docs/what-is-advanced-searching-in-jira-cloud/#Advancedsearching-ConstructingJQLqueries
+#[derive(Debug, Clone)]
+pub enum JQLValue {
+    String(String),
+    /// A number, like story points.  Numbers are left unquoted, so JIRA compares them as numbers rather than text.
+    ///
+    /// JQL has no way to write NaN or infinity, so make numbers with `JQLValue::try_from`, which refuses them.
+    Number(f64),
+    Integer(i64),
+    /// `true` or `false`, left unquoted so JIRA does not compare them as text.
+    Bool(bool),
+    /// A calendar date, like `"2024-01-01"`.
+    Date {
+        year: i64,
+        month: u32,
+        day: u32,
+    },
+    /// A time relative to now, like `-30d` for thirty days ago.
+    RelativeDuration(i64, DurationUnit),
+    /// A call to one of JIRA's [functions][2], like `openSprints()` or `startOfDay(-1d)`.
+    ///
+    /// [2]: https://support.atlassian.com/jira-software-cloud/docs/jql-functions/
+    Function(String, Vec<JQLValue>),
+    /// `EMPTY`, for no value, as in `labels IN (EMPTY, backend)` or `assignee WAS EMPTY`.  `NULL` means the same.
+    Empty,
+}
+
+impl TryFrom<f64> for JQLValue {
+    type Error = String;
+
+    fn try_from(number: f64) -> Result<Self, Self::Error> {
+        if number.is_finite() {
+            Ok(JQLValue::Number(number))
+        } else {
+            Err(format!("{} is not a number JQL can search for", number))
+        }
+    }
+}
+
+impl From<bool> for JQLValue {
+    fn from(value: bool) -> Self {
+        JQLValue::Bool(value)
+    }
+}
+
+/// The units a relative duration in JQL can be given in.
+#[derive(Debug, Clone, Copy)]
+pub enum DurationUnit {
+    Years,
+    Months,
+    Weeks,
+    Days,
+    Hours,
+    Minutes,
+}
+
+impl SerializableToJQL for JQLValue {
+    /// Serialize the JQL value to its representation as part of a string.
+    ///
+    /// This involves escaping string fields appropriately.
+    fn serialize_to_jql(&self) -> String {
+        match self {
+            JQLValue::String(contents) => escape_text_field(contents),
+            JQLValue::Number(number) => number.to_string(),
+            JQLValue::Integer(integer) => integer.to_string(),
+            JQLValue::Bool(value) => value.to_string(),
+            JQLValue::Date { year, month, day } => {
+                format!("\"{:04}-{:02}-{:02}\"", year, month, day)
+            }
+            JQLValue::RelativeDuration(amount, unit) => {
+                let unit = match unit {
+                    DurationUnit::Years => "y",
+                    DurationUnit::Months => "M",
+                    DurationUnit::Weeks => "w",
+                    DurationUnit::Days => "d",
+                    DurationUnit::Hours => "h",
+                    DurationUnit::Minutes => "m",
+                };
+                format!("{}{}", amount, unit)
+            }
+            JQLValue::Function(name, arguments) => format!("{}({})", name, join_values(arguments)),
+            JQLValue::Empty => "EMPTY".to_owned(),
+        }
+    }
+}
+
+/// Represents a [clause][1] in JQL.
+///
+/// Fields are given by name or ID, unquoted; they are quoted as needed when the clause is serialized.
+///
+/// [1]: https://support.atlassian.com/jira-software-cloud/docs/what-is-advanced-searching-in-jira-cloud/#Advancedsearching-ConstructingJQLqueries
+#[derive(Debug, Clone)]
+pub enum JQLClause {
+    And(Vec<JQLClause>),
+    Or(Vec<JQLClause>),
+    Not(Box<JQLClause>),
+    In(String, Vec<JQLValue>),
+    NotIn(String, Vec<JQLValue>),
+    Equals(String, JQLValue),
+    NotEquals(String, JQLValue),
+    GreaterThan(String, JQLValue),
+    GreaterThanOrEqual(String, JQLValue),
+    LessThan(String, JQLValue),
+    LessThanOrEqual(String, JQLValue),
+    IsEmpty(String),
+    IsNotEmpty(String),
+    /// Whether the field has ever had the value, according to the issue's history.
+    Was(String, JQLValue),
+    /// Whether the field has changed, optionally narrowed down to changes between particular values or times, like
+    /// `status CHANGED TO "Done" AFTER -14d`.
+    Changed {
+        field: String,
+        from: Option<JQLValue>,
+        to: Option<JQLValue>,
+        after: Option<JQLValue>,
+        before: Option<JQLValue>,
+    },
+}
+
+impl JQLClause {
+    /// How tightly the clause's operator binds.  NOT binds tighter than AND, which binds tighter than OR, so
+    /// `NOT a OR b AND c` means `(NOT a) OR (b AND c)`.
+    fn precedence(&self) -> u8 {
+        match self {
+            JQLClause::Or(clauses) | JQLClause::And(clauses) if clauses.len() == 1 => {
+                clauses[0].precedence()
+            }
+            JQLClause::Or(_) => 1,
+            JQLClause::And(_) => 2,
+            JQLClause::Not(_) => 3,
+            JQLClause::In(_, _)
+            | JQLClause::NotIn(_, _)
+            | JQLClause::Equals(_, _)
+            | JQLClause::NotEquals(_, _)
+            | JQLClause::GreaterThan(_, _)
+            | JQLClause::GreaterThanOrEqual(_, _)
+            | JQLClause::LessThan(_, _)
+            | JQLClause::LessThanOrEqual(_, _)
+            | JQLClause::IsEmpty(_)
+            | JQLClause::IsNotEmpty(_)
+            | JQLClause::Was(_, _)
+            | JQLClause::Changed { .. } => 4,
+        }
+    }
+
+    /// Serializes the clause as an operand of an operator with the given precedence, adding parentheses only when the
+    /// clause would otherwise bind to the wrong thing.
+    fn serialize_internal(&self, parent_precedence: u8) -> String {
+        let serialized = match self {
+            JQLClause::And(clauses) => join(clauses, " AND ", self.precedence()),
+            JQLClause::Or(clauses) => join(clauses, " OR ", self.precedence()),
+            // The operand always gets parentheses unless it is a single comparison, since `NOT a AND b` would negate
+            // only `a`.
+            JQLClause::Not(clause) => format!("NOT {}", clause.serialize_internal(4)),
+            JQLClause::In(field, values) => {
+                format!("{} IN {}", serialize_field(field), list(values))
+            }
+            JQLClause::NotIn(field, values) => {
+                format!("{} NOT IN {}", serialize_field(field), list(values))
+            }
+            JQLClause::Equals(field, value) => compare(field, "=", value),
+            JQLClause::NotEquals(field, value) => compare(field, "!=", value),
+            JQLClause::GreaterThan(field, value) => compare(field, ">", value),
+            JQLClause::GreaterThanOrEqual(field, value) => compare(field, ">=", value),
+            JQLClause::LessThan(field, value) => compare(field, "<", value),
+            JQLClause::LessThanOrEqual(field, value) => compare(field, "<=", value),
+            JQLClause::IsEmpty(field) => format!("{} IS EMPTY", serialize_field(field)),
+            JQLClause::IsNotEmpty(field) => format!("{} IS NOT EMPTY", serialize_field(field)),
+            JQLClause::Was(field, value) => compare(field, "WAS", value),
+            JQLClause::Changed {
+                field,
+                from,
+                to,
+                after,
+                before,
+            } => {
+                let mut serialized = format!("{} CHANGED", serialize_field(field));
+                let predicates = [
+                    ("FROM", from),
+                    ("TO", to),
+                    ("AFTER", after),
+                    ("BEFORE", before),
+                ];
+                for (predicate, value) in predicates {
+                    if let Some(value) = value {
+                        serialized.push_str(&format!(
+                            " {} {}",
+                            predicate,
+                            value.serialize_to_jql()
+                        ));
+                    }
+                }
+                serialized
+            }
+        };
+
+        if self.precedence() < parent_precedence {
+            format!("({})", serialized)
+        } else {
+            serialized
+        }
+    }
+}
+
+fn compare(field: &str, operator: &str, value: &JQLValue) -> String {
+    format!(
+        "{} {} {}",
+        serialize_field(field),
+        operator,
+        value.serialize_to_jql()
+    )
+}
+
+/// Formats the values for IN or NOT IN.  A single function, like `openSprints()`, returns a list by itself, so it is
+/// not wrapped in parentheses.
+fn list(values: &[JQLValue]) -> String {
+    match values {
+        [function @ JQLValue::Function(_, _)] => function.serialize_to_jql(),
+        _ => format!("({})", join_values(values)),
+    }
+}
+
+fn join_values(values: &[JQLValue]) -> String {
+    values
+        .iter()
+        .map(|value| value.serialize_to_jql())
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+fn join(clauses: &[JQLClause], operator: &str, precedence: u8) -> String {
+    clauses
+        .iter()
+        .map(|clause| clause.serialize_internal(precedence))
+        .collect::<Vec<String>>()
+        .join(operator)
+}
+
+impl SerializableToJQL for JQLClause {
+    /// Serialize the JQL clause to its representation as part of a string.
+    ///
+    /// This involves formatting values correctly, and ensuring operator precedence rules are respected.
+    fn serialize_to_jql(&self) -> String {
+        self.serialize_internal(0)
+    }
+}
+
+/// Which way `ORDER BY` sorts a field.
+#[derive(Debug, Clone, Copy)]
+pub enum SortDirection {
+    Ascending,
+    Descending,
+}
+
+/// Represents a [statement][1] in JQL.
+///
+/// [1]: https://support.atlassian.com/jira-software-cloud/docs/what-is-advanced-searching-in-jira-cloud/#Advancedsearching-ConstructingJQLqueries
+#[derive(Debug, Clone)]
+pub struct JQLStatement {
+    pub clause: JQLClause,
+    /// The fields to sort the results by, most significant first.  When this is empty, JIRA picks the order.
+    pub order_by: Vec<(String, SortDirection)>,
+}
+
+impl SerializableToJQL for JQLStatement {
+    /// Serialize the JQL statement to its representation as part of a string.
+    fn serialize_to_jql(&self) -> String {
+        let clause = self.clause.serialize_to_jql();
+        if self.order_by.is_empty() {
+            return clause;
+        }
+
+        let order_by = self
+            .order_by
+            .iter()
+            .map(|(field, direction)| {
+                let direction = match direction {
+                    SortDirection::Ascending => "ASC",
+                    SortDirection::Descending => "DESC",
+                };
+                format!("{} {}", serialize_field(field), direction)
+            })
+            .collect::<Vec<String>>()
+            .join(", ");
+
+        format!("{} ORDER BY {}", clause, order_by)
+    }
+}
+
+impl Serialize for JQLStatement {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&self.serialize_to_jql())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Serializes the clause, parses it back, and checks that serializing it again gives the same JQL.
+    fn assert_round_trips(clause: JQLClause) -> String {
+        let serialized = JQLStatement {
+            clause,
+            order_by: vec![],
+        }
+        .serialize_to_jql();
+        let parsed = parser::parse(&serialized)
+            .unwrap_or_else(|e| panic!("{} could not be parsed back: {}", serialized, e));
+        assert_eq!(parsed.serialize_to_jql(), serialized);
+
+        serialized
+    }
+
+    fn equals(field: &str, value: &str) -> JQLClause {
+        JQLClause::Equals(field.to_owned(), JQLValue::String(value.to_owned()))
+    }
+
+    #[test]
+    fn reserved_words_round_trip() {
+        assert_eq!(
+            assert_round_trips(equals("empty", "order")),
+            r#""empty" = "order""#
+        );
+        assert_eq!(
+            assert_round_trips(equals("Order", "EMPTY")),
+            r#""Order" = "EMPTY""#
+        );
+        assert_round_trips(JQLClause::IsEmpty("order".to_owned()));
+        assert_round_trips(JQLClause::In(
+            "labels".to_owned(),
+            vec![JQLValue::Empty, JQLValue::String("empty".to_owned())],
+        ));
+    }
+
+    #[test]
+    fn quotes_and_backslashes_round_trip() {
+        assert_eq!(
+            assert_round_trips(equals("summary", r#"say "hi""#)),
+            r#"summary = "say \"hi\"""#
+        );
+        assert_eq!(
+            assert_round_trips(equals("summary", r"C:\temp\")),
+            r#"summary = "C:\\temp\\""#
+        );
+        assert_round_trips(equals(r#"a "quoted" \ field"#, r#"\"#));
+        assert_round_trips(equals("summary", "it's"));
+    }
+
+    #[test]
+    fn control_characters_round_trip() {
+        assert_eq!(
+            assert_round_trips(equals("summary", "one\ntwo\r\tthree")),
+            r#"summary = "one\ntwo\r\tthree""#
+        );
+        assert_eq!(
+            assert_round_trips(equals("summary", "bell\u{7}")),
+            r#"summary = "bell\u0007""#
+        );
+    }
+
+    #[test]
+    fn non_ascii_text_round_trips() {
+        assert_eq!(
+            assert_round_trips(equals("Équipe", "Überprüfung 🚀")),
+            "Équipe = \"Überprüfung 🚀\""
+        );
+        assert_round_trips(equals("Story Points", "日本語"));
+    }
+
+    #[test]
+    fn custom_fields_round_trip() {
+        assert_eq!(
+            assert_round_trips(JQLClause::GreaterThan(
+                "customfield_10016".to_owned(),
+                JQLValue::Number(2.5)
+            )),
+            "cf[10016] > 2.5"
+        );
+        assert_eq!(
+            assert_round_trips(JQLClause::IsNotEmpty("cf[10020]".to_owned())),
+            "cf[10020] IS NOT EMPTY"
+        );
+        assert_eq!(
+            assert_round_trips(equals("customfield_abc", "x")),
+            r#"customfield_abc = "x""#
+        );
+    }
+
+    #[test]
+    fn numbers_round_trip_and_non_finite_ones_are_refused() {
+        let number = JQLValue::try_from(2.5).unwrap();
+        assert_eq!(
+            assert_round_trips(JQLClause::LessThan("Story Points".to_owned(), number)),
+            r#""Story Points" < 2.5"#
+        );
+        for number in [f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+            assert!(
+                JQLValue::try_from(number).is_err(),
+                "{} was let through",
+                number
+            );
+        }
+    }
+
+    #[test]
+    fn booleans_round_trip_unquoted() {
+        assert_eq!(
+            assert_round_trips(JQLClause::Equals(
+                "cf[10030]".to_owned(),
+                JQLValue::from(true)
+            )),
+            "cf[10030] = true"
+        );
+        assert_eq!(
+            assert_round_trips(JQLClause::NotEquals(
+                "flagged".to_owned(),
+                JQLValue::from(false)
+            )),
+            "flagged != false"
+        );
+        assert_eq!(
+            assert_round_trips(equals("summary", "true")),
+            r#"summary = "true""#
+        );
+    }
+}
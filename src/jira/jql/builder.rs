@@ -0,0 +1,323 @@
+/// Builds the searches the commands run, one option at a time, so that each command only has to say which of its
+/// options narrow the search down.
+use std::str::FromStr;
+
+use super::{JQLClause, JQLStatement, JQLValue, SortDirection};
+
+/// The sprint a search is narrowed down to: one sprint, by ID or name, or every open or future sprint.
+#[derive(Clone, Debug)]
+pub enum SprintFilter {
+    Id(u64),
+    Name(String),
+    Open,
+    Future,
+}
+
+impl FromStr for SprintFilter {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.trim().is_empty() {
+            return Err("a sprint has to be a name, an ID, open, or future".to_owned());
+        }
+
+        Ok(match s.parse::<u64>() {
+            Ok(id) => SprintFilter::Id(id),
+            Err(_) if s.eq_ignore_ascii_case("open") => SprintFilter::Open,
+            Err(_) if s.eq_ignore_ascii_case("future") => SprintFilter::Future,
+            Err(_) => SprintFilter::Name(s.to_owned()),
+        })
+    }
+}
+
+/// How a project links issues to their epic.
+///
+/// Company-managed projects use the `Epic Link` field, while team-managed projects make the epic the issue's parent.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum EpicRelation {
+    EpicLink,
+    Parent,
+}
+
+impl SprintFilter {
+    fn clause(self) -> JQLClause {
+        let field = "sprint".to_owned();
+        match self {
+            SprintFilter::Id(id) => JQLClause::Equals(field, JQLValue::Integer(id as i64)),
+            // Unlike the agile API, JQL looks sprints up by name itself, so there's no need to know the board.
+            SprintFilter::Name(name) => JQLClause::Equals(field, JQLValue::String(name)),
+            SprintFilter::Open => JQLClause::In(
+                field,
+                vec![JQLValue::Function("openSprints".to_owned(), vec![])],
+            ),
+            SprintFilter::Future => JQLClause::In(
+                field,
+                vec![JQLValue::Function("futureSprints".to_owned(), vec![])],
+            ),
+        }
+    }
+}
+
+/// Collects the parts of a search, then puts them together into a `JQLStatement` with `build`.
+///
+/// Issues have to be in one of the projects and have one of the labels.  With `match_any`, having either is enough.
+/// Everything else, including any JQL the user wrote themselves, always narrows the search down further.
+#[derive(Debug, Clone, Default)]
+pub struct JQLBuilder {
+    projects: Vec<String>,
+    labels: Vec<String>,
+    match_any: bool,
+    issue_types: Vec<String>,
+    assignees: Vec<String>,
+    components: Vec<String>,
+    fix_versions: Vec<String>,
+    excluded_projects: Vec<String>,
+    excluded_labels: Vec<String>,
+    excluded_issue_types: Vec<String>,
+    sprint: Option<SprintFilter>,
+    epics: Vec<(String, EpicRelation)>,
+    updated_since: Option<JQLValue>,
+    status_categories: Vec<String>,
+    empty_fields: Vec<String>,
+    jql: Option<JQLStatement>,
+    order_by: Vec<(String, SortDirection)>,
+}
+
+fn strings(values: &[String]) -> Vec<JQLValue> {
+    values
+        .iter()
+        .map(|value| JQLValue::String(value.clone()))
+        .collect()
+}
+
+impl JQLBuilder {
+    pub fn new() -> Self {
+        JQLBuilder::default()
+    }
+
+    pub fn project(mut self, project: &str) -> Self {
+        self.projects.push(project.to_owned());
+        self
+    }
+
+    pub fn label(mut self, label: &str) -> Self {
+        self.labels.push(label.to_owned());
+        self
+    }
+
+    /// Lets issues through that are in one of the projects or have one of the labels, instead of needing both.
+    pub fn match_any(mut self, match_any: bool) -> Self {
+        self.match_any = match_any;
+        self
+    }
+
+    pub fn issue_type(mut self, issue_type: &str) -> Self {
+        self.issue_types.push(issue_type.to_owned());
+        self
+    }
+
+    /// Matches issues assigned to the user, given by account ID.
+    pub fn assignee(mut self, account_id: &str) -> Self {
+        self.assignees.push(account_id.to_owned());
+        self
+    }
+
+    pub fn component(mut self, component: &str) -> Self {
+        self.components.push(component.to_owned());
+        self
+    }
+
+    pub fn fix_version(mut self, fix_version: &str) -> Self {
+        self.fix_versions.push(fix_version.to_owned());
+        self
+    }
+
+    pub fn exclude_project(mut self, project: &str) -> Self {
+        self.excluded_projects.push(project.to_owned());
+        self
+    }
+
+    pub fn exclude_label(mut self, label: &str) -> Self {
+        self.excluded_labels.push(label.to_owned());
+        self
+    }
+
+    pub fn exclude_issue_type(mut self, issue_type: &str) -> Self {
+        self.excluded_issue_types.push(issue_type.to_owned());
+        self
+    }
+
+    pub fn sprint(mut self, sprint: SprintFilter) -> Self {
+        self.sprint = Some(sprint);
+        self
+    }
+
+    /// Matches the issues in an epic, given its key and how its project links issues to it.
+    pub fn epic(mut self, key: &str, relation: EpicRelation) -> Self {
+        self.epics.push((key.to_owned(), relation));
+        self
+    }
+
+    /// Only matches issues updated at or after the given time, like `-2w` or a date.
+    pub fn updated_since(mut self, since: JQLValue) -> Self {
+        self.updated_since = Some(since);
+        self
+    }
+
+    /// Only matches issues whose status is in one of the given categories, like "Done".
+    pub fn status_category(mut self, category: &str) -> Self {
+        self.status_categories.push(category.to_owned());
+        self
+    }
+
+    /// Only matches issues where the field has not been filled in.
+    pub fn empty(mut self, field: &str) -> Self {
+        self.empty_fields.push(field.to_owned());
+        self
+    }
+
+    /// Adds JQL the user wrote themselves.  If it has an `ORDER BY`, that order comes before any given to `order_by`.
+    pub fn jql(mut self, jql: JQLStatement) -> Self {
+        self.jql = Some(jql);
+        self
+    }
+
+    pub fn order_by(mut self, field: &str, direction: SortDirection) -> Self {
+        self.order_by.push((field.to_owned(), direction));
+        self
+    }
+
+    /// Puts the search together.
+    ///
+    /// A search with nothing narrowing it down, like a project, label, epic, single sprint, or JQL, would crawl every
+    /// issue in JIRA, so that is refused.
+    pub fn build(self) -> Result<JQLStatement, String> {
+        // One sprint is narrow enough on its own, but every open sprint in JIRA is not.
+        let single_sprint = matches!(
+            self.sprint,
+            Some(SprintFilter::Id(_)) | Some(SprintFilter::Name(_))
+        );
+        if self.projects.is_empty()
+            && self.labels.is_empty()
+            && self.epics.is_empty()
+            && self.jql.is_none()
+            && !single_sprint
+        {
+            return Err("This command will search all projects & labels.  To avoid crawling your entire JIRA instance, you must supply at least one project, a label, an epic, a sprint, or --jql to narrow the search.".to_owned());
+        }
+
+        let mut scope: Vec<JQLClause> = vec![];
+
+        if !self.projects.is_empty() {
+            scope.push(JQLClause::In("project".to_owned(), strings(&self.projects)));
+        }
+
+        if !self.labels.is_empty() {
+            scope.push(JQLClause::In("labels".to_owned(), strings(&self.labels)));
+        }
+
+        let mut clauses = if self.match_any && !scope.is_empty() {
+            vec![JQLClause::Or(scope)]
+        } else {
+            scope
+        };
+
+        if !self.issue_types.is_empty() {
+            clauses.push(JQLClause::In(
+                "issuetype".to_owned(),
+                strings(&self.issue_types),
+            ));
+        }
+
+        if !self.assignees.is_empty() {
+            clauses.push(JQLClause::In(
+                "assignee".to_owned(),
+                strings(&self.assignees),
+            ));
+        }
+
+        if !self.components.is_empty() {
+            clauses.push(JQLClause::In(
+                "component".to_owned(),
+                strings(&self.components),
+            ));
+        }
+
+        if !self.fix_versions.is_empty() {
+            clauses.push(JQLClause::In(
+                "fixVersion".to_owned(),
+                strings(&self.fix_versions),
+            ));
+        }
+
+        if !self.excluded_projects.is_empty() {
+            clauses.push(JQLClause::NotIn(
+                "project".to_owned(),
+                strings(&self.excluded_projects),
+            ));
+        }
+
+        if !self.excluded_labels.is_empty() {
+            // JIRA leaves issues without any labels out of `NOT IN`, even though they don't have the labels either.
+            clauses.push(JQLClause::Or(vec![
+                JQLClause::NotIn("labels".to_owned(), strings(&self.excluded_labels)),
+                JQLClause::IsEmpty("labels".to_owned()),
+            ]));
+        }
+
+        if !self.excluded_issue_types.is_empty() {
+            clauses.push(JQLClause::NotIn(
+                "issuetype".to_owned(),
+                strings(&self.excluded_issue_types),
+            ));
+        }
+
+        if let Some(sprint) = self.sprint {
+            clauses.push(sprint.clause());
+        }
+
+        if !self.epics.is_empty() {
+            let mut in_epics = vec![];
+            for (relation, field) in [
+                (EpicRelation::EpicLink, "Epic Link"),
+                (EpicRelation::Parent, "parent"),
+            ] {
+                let keys: Vec<JQLValue> = self
+                    .epics
+                    .iter()
+                    .filter(|(_, r)| *r == relation)
+                    .map(|(key, _)| JQLValue::String(key.clone()))
+                    .collect();
+                if !keys.is_empty() {
+                    in_epics.push(JQLClause::In(field.to_owned(), keys));
+                }
+            }
+            clauses.push(JQLClause::Or(in_epics));
+        }
+
+        if let Some(since) = self.updated_since {
+            clauses.push(JQLClause::GreaterThanOrEqual("updated".to_owned(), since));
+        }
+
+        if !self.status_categories.is_empty() {
+            clauses.push(JQLClause::In(
+                "statusCategory".to_owned(),
+                strings(&self.status_categories),
+            ));
+        }
+
+        clauses.extend(self.empty_fields.into_iter().map(JQLClause::IsEmpty));
+
+        let mut order_by = vec![];
+        if let Some(jql) = self.jql {
+            clauses.push(jql.clause);
+            order_by = jql.order_by;
+        }
+        order_by.extend(self.order_by);
+
+        Ok(JQLStatement {
+            clause: JQLClause::And(clauses),
+            order_by,
+        })
+    }
+}
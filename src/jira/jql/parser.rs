@@ -0,0 +1,640 @@
+/// Turns JQL written by the user into a `JQLStatement`, so that it can be checked before it is sent, and combined with
+/// the clauses the commands generate without any risk of changing their meaning.
+///
+/// Only the operators the JQL model supports are understood; anything else, like `~`, is reported as an error rather
+/// than passed along.
+use std::{error::Error, fmt};
+
+use super::{DurationUnit, JQLClause, JQLStatement, JQLValue, SortDirection};
+
+/// The characters that end an unquoted word.
+const WORD_BOUNDARIES: &[char] = &['(', ')', ',', '=', '!', '<', '>', '~', '"', '\'', '&', '|'];
+
+#[derive(Debug, PartialEq)]
+pub struct JQLParseError {
+    /// The character the problem was found at, counting from zero.
+    pub position: usize,
+    pub message: String,
+}
+
+impl fmt::Display for JQLParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "The JQL could not be understood at character {}: {}",
+            self.position + 1,
+            self.message
+        )
+    }
+}
+
+impl Error for JQLParseError {}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    LeftParen,
+    RightParen,
+    Comma,
+    Operator(String),
+    Quoted(String),
+    Word(String),
+}
+
+/// Splits JQL into tokens, each paired with the position it starts at.
+fn tokenize(jql: &str) -> Result<Vec<(usize, Token)>, JQLParseError> {
+    let chars: Vec<char> = jql.chars().collect();
+    let mut tokens = vec![];
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let start = i;
+
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+
+        let token = match c {
+            '(' => {
+                i += 1;
+                Token::LeftParen
+            }
+            ')' => {
+                i += 1;
+                Token::RightParen
+            }
+            ',' => {
+                i += 1;
+                Token::Comma
+            }
+            '=' | '~' => {
+                i += 1;
+                Token::Operator(c.to_string())
+            }
+            '!' | '<' | '>' => {
+                if chars.get(i + 1) == Some(&'=') || (c == '!' && chars.get(i + 1) == Some(&'~')) {
+                    i += 2;
+                    Token::Operator(chars[start..i].iter().collect())
+                } else {
+                    i += 1;
+                    Token::Operator(c.to_string())
+                }
+            }
+            '&' | '|' => {
+                if chars.get(i + 1) != Some(&c) {
+                    return Err(JQLParseError {
+                        position: start,
+                        message: format!("\"{}\" has to be doubled, as in \"{}{}\".", c, c, c),
+                    });
+                }
+                i += 2;
+                Token::Word(if c == '&' { "AND" } else { "OR" }.to_owned())
+            }
+            '"' | '\'' => {
+                let (contents, end) = read_quoted(&chars, i)?;
+                i = end;
+                Token::Quoted(contents)
+            }
+            _ => {
+                while i < chars.len()
+                    && !chars[i].is_whitespace()
+                    && !WORD_BOUNDARIES.contains(&chars[i])
+                {
+                    i += 1;
+                }
+                Token::Word(chars[start..i].iter().collect())
+            }
+        };
+
+        tokens.push((start, token));
+    }
+
+    Ok(tokens)
+}
+
+/// Reads a quoted string starting at `start`, returning its contents and the position just past the closing quote.
+///
+/// This undoes the escaping `escape_text_field` does, so that serializing a string and parsing it back gets the same
+/// string.  Any other character after a backslash, like a quote or a space, stands for itself.
+fn read_quoted(chars: &[char], start: usize) -> Result<(String, usize), JQLParseError> {
+    let quote = chars[start];
+    let mut contents = String::new();
+    let mut i = start + 1;
+
+    loop {
+        match chars.get(i) {
+            None => {
+                return Err(JQLParseError {
+                    position: start,
+                    message: format!("the string starting here has no closing {}.", quote),
+                })
+            }
+            Some(&c) if c == quote => return Ok((contents, i + 1)),
+            Some('\\') => match chars.get(i + 1) {
+                Some('n') => {
+                    contents.push('\n');
+                    i += 2;
+                }
+                Some('r') => {
+                    contents.push('\r');
+                    i += 2;
+                }
+                Some('t') => {
+                    contents.push('\t');
+                    i += 2;
+                }
+                Some('u') => {
+                    let hex: String = chars.iter().skip(i + 2).take(4).collect();
+                    match u32::from_str_radix(&hex, 16).ok().and_then(char::from_u32) {
+                        Some(escaped) if hex.len() == 4 => {
+                            contents.push(escaped);
+                            i += 6;
+                        }
+                        _ => {
+                            return Err(JQLParseError {
+                                position: i,
+                                message: "\\u has to be followed by four hexadecimal digits."
+                                    .to_owned(),
+                            })
+                        }
+                    }
+                }
+                Some(&escaped) => {
+                    contents.push(escaped);
+                    i += 2;
+                }
+                None => {
+                    i += 1;
+                }
+            },
+            Some(&c) => {
+                contents.push(c);
+                i += 1;
+            }
+        }
+    }
+}
+
+struct Parser {
+    tokens: Vec<(usize, Token)>,
+    next: usize,
+    /// Where the JQL ends, for errors about it ending too soon.
+    end: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.next).map(|(_, token)| token)
+    }
+
+    fn position(&self) -> usize {
+        self.tokens
+            .get(self.next)
+            .map(|(position, _)| *position)
+            .unwrap_or(self.end)
+    }
+
+    fn error<T>(&self, message: String) -> Result<T, JQLParseError> {
+        Err(JQLParseError {
+            position: self.position(),
+            message,
+        })
+    }
+
+    fn advance(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.next).map(|(_, token)| token.clone());
+        self.next += 1;
+        token
+    }
+
+    /// Whether the next token is the given keyword, in any case.
+    fn at_keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Some(Token::Word(word)) if word.eq_ignore_ascii_case(keyword))
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        if self.at_keyword(keyword) {
+            self.next += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, expected: Token, description: &str) -> Result<(), JQLParseError> {
+        if self.peek() == Some(&expected) {
+            self.next += 1;
+            Ok(())
+        } else {
+            self.error(format!("expected {}.", description))
+        }
+    }
+
+    fn or_clause(&mut self) -> Result<JQLClause, JQLParseError> {
+        let mut clauses = vec![self.and_clause()?];
+        while self.eat_keyword("OR") {
+            clauses.push(self.and_clause()?);
+        }
+
+        Ok(if clauses.len() == 1 {
+            clauses.remove(0)
+        } else {
+            JQLClause::Or(clauses)
+        })
+    }
+
+    fn and_clause(&mut self) -> Result<JQLClause, JQLParseError> {
+        let mut clauses = vec![self.not_clause()?];
+        while self.eat_keyword("AND") {
+            clauses.push(self.not_clause()?);
+        }
+
+        Ok(if clauses.len() == 1 {
+            clauses.remove(0)
+        } else {
+            JQLClause::And(clauses)
+        })
+    }
+
+    fn not_clause(&mut self) -> Result<JQLClause, JQLParseError> {
+        if self.eat_keyword("NOT") || self.peek() == Some(&Token::Operator("!".to_owned())) {
+            if self.peek() == Some(&Token::Operator("!".to_owned())) {
+                self.next += 1;
+            }
+            return Ok(JQLClause::Not(Box::new(self.not_clause()?)));
+        }
+
+        if self.peek() == Some(&Token::LeftParen) {
+            self.next += 1;
+            let clause = self.or_clause()?;
+            self.expect(Token::RightParen, "a closing parenthesis")?;
+            return Ok(clause);
+        }
+
+        self.comparison()
+    }
+
+    fn field(&mut self) -> Result<String, JQLParseError> {
+        match self.advance() {
+            Some(Token::Word(word)) | Some(Token::Quoted(word)) => Ok(word),
+            _ => {
+                self.next -= 1;
+                self.error("expected the name of a field.".to_owned())
+            }
+        }
+    }
+
+    fn comparison(&mut self) -> Result<JQLClause, JQLParseError> {
+        let field = self.field()?;
+
+        if self.eat_keyword("IS") {
+            let negated = self.eat_keyword("NOT");
+            if !(self.eat_keyword("EMPTY") || self.eat_keyword("NULL")) {
+                return self.error("expected EMPTY or NULL.".to_owned());
+            }
+            return Ok(if negated {
+                JQLClause::IsNotEmpty(field)
+            } else {
+                JQLClause::IsEmpty(field)
+            });
+        }
+
+        if self.eat_keyword("WAS") {
+            if self.at_keyword("NOT") || self.at_keyword("IN") {
+                return self.error("only WAS with a single value is supported.".to_owned());
+            }
+            return Ok(JQLClause::Was(field, self.value()?));
+        }
+
+        if self.eat_keyword("CHANGED") {
+            return self.changed(field);
+        }
+
+        if self.eat_keyword("IN") {
+            return Ok(JQLClause::In(field, self.list()?));
+        }
+        if self.at_keyword("NOT") {
+            self.next += 1;
+            if !self.eat_keyword("IN") {
+                return self.error("expected IN after NOT.".to_owned());
+            }
+            return Ok(JQLClause::NotIn(field, self.list()?));
+        }
+
+        let operator = match self.advance() {
+            Some(Token::Operator(operator)) => operator,
+            Some(Token::Word(word)) => {
+                self.next -= 1;
+                return self.error(format!(
+                    "{} isn't an operator this tool understands yet.",
+                    word.to_uppercase()
+                ));
+            }
+            _ => {
+                self.next -= 1;
+                return self.error(format!("expected an operator after {}.", field));
+            }
+        };
+
+        // `field = EMPTY` is another way of writing `field IS EMPTY`.
+        if (operator == "=" || operator == "!=")
+            && (self.at_keyword("EMPTY") || self.at_keyword("NULL"))
+        {
+            self.next += 1;
+            return Ok(if operator == "=" {
+                JQLClause::IsEmpty(field)
+            } else {
+                JQLClause::IsNotEmpty(field)
+            });
+        }
+
+        let value = self.value()?;
+        match operator.as_str() {
+            "=" => Ok(JQLClause::Equals(field, value)),
+            "!=" => Ok(JQLClause::NotEquals(field, value)),
+            ">" => Ok(JQLClause::GreaterThan(field, value)),
+            ">=" => Ok(JQLClause::GreaterThanOrEqual(field, value)),
+            "<" => Ok(JQLClause::LessThan(field, value)),
+            "<=" => Ok(JQLClause::LessThanOrEqual(field, value)),
+            _ => Err(JQLParseError {
+                position: self.tokens[self.next - 2].0,
+                message: format!("{} isn't an operator this tool understands yet.", operator),
+            }),
+        }
+    }
+
+    /// Reads the predicates after CHANGED, which can come in any order.
+    fn changed(&mut self, field: String) -> Result<JQLClause, JQLParseError> {
+        let (mut from, mut to, mut after, mut before) = (None, None, None, None);
+
+        loop {
+            let predicate = if self.at_keyword("FROM") {
+                &mut from
+            } else if self.at_keyword("TO") {
+                &mut to
+            } else if self.at_keyword("AFTER") {
+                &mut after
+            } else if self.at_keyword("BEFORE") {
+                &mut before
+            } else if ["BY", "DURING", "ON"]
+                .iter()
+                .any(|keyword| self.at_keyword(keyword))
+            {
+                return self.error(
+                    "only the FROM, TO, AFTER, and BEFORE predicates are supported after CHANGED."
+                        .to_owned(),
+                );
+            } else {
+                break;
+            };
+
+            self.next += 1;
+            *predicate = Some(self.value()?);
+        }
+
+        Ok(JQLClause::Changed {
+            field,
+            from,
+            to,
+            after,
+            before,
+        })
+    }
+
+    /// Reads the values for IN or NOT IN, which are either a parenthesized list or a single function call.
+    fn list(&mut self) -> Result<Vec<JQLValue>, JQLParseError> {
+        if self.peek() != Some(&Token::LeftParen) {
+            return Ok(vec![self.value()?]);
+        }
+
+        self.next += 1;
+        let mut values = vec![];
+        if self.peek() != Some(&Token::RightParen) {
+            values.push(self.value()?);
+            while self.peek() == Some(&Token::Comma) {
+                self.next += 1;
+                values.push(self.value()?);
+            }
+        }
+        self.expect(Token::RightParen, "a comma or a closing parenthesis")?;
+
+        Ok(values)
+    }
+
+    /// Reads the fields after `ORDER`, each with an optional direction.
+    fn order_by(&mut self) -> Result<Vec<(String, SortDirection)>, JQLParseError> {
+        if !self.eat_keyword("BY") {
+            return self.error("expected BY after ORDER.".to_owned());
+        }
+
+        let mut order_by = vec![];
+        loop {
+            let field = self.field()?;
+            let direction = if self.eat_keyword("DESC") {
+                SortDirection::Descending
+            } else {
+                self.eat_keyword("ASC");
+                SortDirection::Ascending
+            };
+            order_by.push((field, direction));
+
+            if self.peek() == Some(&Token::Comma) {
+                self.next += 1;
+            } else {
+                return Ok(order_by);
+            }
+        }
+    }
+
+    fn value(&mut self) -> Result<JQLValue, JQLParseError> {
+        match self.advance() {
+            Some(Token::Quoted(contents)) => Ok(quoted_value(contents)),
+            Some(Token::Word(word)) => {
+                if self.peek() == Some(&Token::LeftParen) {
+                    return Ok(JQLValue::Function(word, self.list()?));
+                }
+                Ok(word_value(word))
+            }
+            _ => {
+                self.next -= 1;
+                self.error("expected a value.".to_owned())
+            }
+        }
+    }
+}
+
+/// Dates are written as quoted strings, so those that look like one are read back as dates.
+fn quoted_value(contents: String) -> JQLValue {
+    let parts: Vec<&str> = contents.split('-').collect();
+    if let [year, month, day] = parts.as_slice() {
+        if year.len() == 4 && month.len() == 2 && day.len() == 2 {
+            if let (Ok(year), Ok(month), Ok(day)) = (year.parse(), month.parse(), day.parse()) {
+                return JQLValue::Date { year, month, day };
+            }
+        }
+    }
+
+    JQLValue::String(contents)
+}
+
+/// Unquoted words are numbers, booleans, relative durations, or `EMPTY` when they look like them, and otherwise strings,
+/// like `Done` or `10k`.
+fn word_value(word: String) -> JQLValue {
+    if let Ok(integer) = word.parse::<i64>() {
+        return JQLValue::Integer(integer);
+    }
+    if let Ok(number) = word.parse::<f64>() {
+        if number.is_finite() {
+            return JQLValue::Number(number);
+        }
+    }
+    if word.eq_ignore_ascii_case("EMPTY") || word.eq_ignore_ascii_case("NULL") {
+        return JQLValue::Empty;
+    }
+    if word.eq_ignore_ascii_case("true") || word.eq_ignore_ascii_case("false") {
+        return JQLValue::Bool(word.eq_ignore_ascii_case("true"));
+    }
+
+    // Units are case sensitive: `M` is months and `m` is minutes.
+    let unit = match word.chars().last() {
+        Some('y') => Some(DurationUnit::Years),
+        Some('M') => Some(DurationUnit::Months),
+        Some('w') => Some(DurationUnit::Weeks),
+        Some('d') => Some(DurationUnit::Days),
+        Some('h') => Some(DurationUnit::Hours),
+        Some('m') => Some(DurationUnit::Minutes),
+        _ => None,
+    };
+    if let Some(unit) = unit {
+        if let Ok(amount) = word[..word.len() - 1].parse::<i64>() {
+            return JQLValue::RelativeDuration(amount, unit);
+        }
+    }
+
+    JQLValue::String(word)
+}
+
+/// Parses a JQL statement.
+pub fn parse(jql: &str) -> Result<JQLStatement, JQLParseError> {
+    let mut parser = Parser {
+        tokens: tokenize(jql)?,
+        next: 0,
+        end: jql.chars().count(),
+    };
+
+    if parser.peek().is_none() {
+        return parser.error("the JQL is empty.".to_owned());
+    }
+
+    let clause = parser.or_clause()?;
+    let order_by = if parser.eat_keyword("ORDER") {
+        parser.order_by()?
+    } else {
+        vec![]
+    };
+    if parser.peek().is_some() {
+        return parser.error("expected AND, OR, ORDER BY, or the end of the JQL.".to_owned());
+    }
+
+    Ok(JQLStatement { clause, order_by })
+}
+
+/// Parses a single JQL value, like `-2w`, `2024-01-01`, or `startOfWeek()`, as given to a command line option.
+///
+/// Dates don't need quoting here, since there is nothing else they could be mistaken for.
+pub fn parse_value(value: &str) -> Result<JQLValue, JQLParseError> {
+    let mut parser = Parser {
+        tokens: tokenize(value)?,
+        next: 0,
+        end: value.chars().count(),
+    };
+
+    let value = parser.value()?;
+    if parser.peek().is_some() {
+        return parser.error("expected the end of the value.".to_owned());
+    }
+
+    Ok(match value {
+        JQLValue::String(contents) => quoted_value(contents),
+        value => value,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jira::jql::SerializableToJQL;
+
+    fn reparsed(jql: &str) -> String {
+        parse(jql)
+            .unwrap_or_else(|e| panic!("{} could not be parsed: {}", jql, e))
+            .serialize_to_jql()
+    }
+
+    #[test]
+    fn months_and_years_are_durations() {
+        assert_eq!(
+            reparsed("created >= startOfMonth(-1M)"),
+            "created >= startOfMonth(-1M)"
+        );
+        assert_eq!(
+            reparsed("created >= startOfYear(-1y)"),
+            "created >= startOfYear(-1y)"
+        );
+        assert!(matches!(
+            parse_value("-1M"),
+            Ok(JQLValue::RelativeDuration(-1, DurationUnit::Months))
+        ));
+        // Lower case m is minutes.
+        assert!(matches!(
+            parse_value("-1m"),
+            Ok(JQLValue::RelativeDuration(-1, DurationUnit::Minutes))
+        ));
+    }
+
+    #[test]
+    fn numbers_with_other_letters_are_text() {
+        assert_eq!(reparsed("fixVersion = 10k"), r#"fixVersion = "10k""#);
+        assert_eq!(reparsed("labels IN (2x, v2)"), r#"labels IN ("2x", "v2")"#);
+    }
+
+    #[test]
+    fn empty_and_null_stay_unquoted() {
+        assert_eq!(
+            reparsed("labels in (EMPTY, x) and assignee was null"),
+            r#"labels IN (EMPTY, "x") AND assignee WAS EMPTY"#
+        );
+        assert_eq!(reparsed(r#"labels = "EMPTY""#), r#"labels = "EMPTY""#);
+    }
+
+    #[test]
+    fn booleans_stay_unquoted() {
+        assert_eq!(
+            reparsed(r#"flagged = TRUE and cf[10030] != "false""#),
+            r#"flagged = true AND cf[10030] != "false""#
+        );
+    }
+
+    #[test]
+    fn mistakes_are_reported_where_they_are() {
+        for (jql, position, message) in [
+            (
+                r#"summary = "open"#,
+                10,
+                "the string starting here has no closing \".",
+            ),
+            ("status = Done AND", 17, "expected the name of a field."),
+            ("(status = Done", 14, "expected a closing parenthesis."),
+            ("", 0, "the JQL is empty."),
+        ] {
+            let e = parse(jql).unwrap_err();
+            assert_eq!(
+                (e.position, e.message.as_str()),
+                (position, message),
+                "{}",
+                jql
+            );
+        }
+    }
+}
@@ -0,0 +1,141 @@
+/// Records every change a mutating command makes, so that a bad run against JIRA can be reversed afterwards.
+///
+/// Journals are JSON Lines files, with one entry per change to a single field of a single issue.
+use std::{
+    fs::{File, OpenOptions},
+    io::{self, BufRead, BufReader, BufWriter, Write},
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+
+/// The value of a field before and after a change was made to it.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(tag = "field", rename_all = "snake_case")]
+pub enum Change {
+    Labels {
+        before: Vec<String>,
+        after: Vec<String>,
+    },
+    Sprint {
+        before: Option<u64>,
+        after: Option<u64>,
+    },
+    StoryPoints {
+        field_id: String,
+        before: Option<f64>,
+        after: Option<f64>,
+    },
+    FixVersions {
+        before: Vec<String>,
+        after: Vec<String>,
+    },
+    /// The account IDs of the users watching the issue.
+    Watchers {
+        before: Vec<String>,
+        after: Vec<String>,
+    },
+}
+
+impl Change {
+    /// The change that puts the field back the way it was.
+    pub fn reversed(&self) -> Change {
+        match self {
+            Change::Labels { before, after } => Change::Labels {
+                before: after.clone(),
+                after: before.clone(),
+            },
+            Change::Sprint { before, after } => Change::Sprint {
+                before: *after,
+                after: *before,
+            },
+            Change::StoryPoints {
+                field_id,
+                before,
+                after,
+            } => Change::StoryPoints {
+                field_id: field_id.clone(),
+                before: *after,
+                after: *before,
+            },
+            Change::FixVersions { before, after } => Change::FixVersions {
+                before: after.clone(),
+                after: before.clone(),
+            },
+            Change::Watchers { before, after } => Change::Watchers {
+                before: after.clone(),
+                after: before.clone(),
+            },
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Entry {
+    pub key: String,
+
+    #[serde(flatten)]
+    pub change: Change,
+}
+
+pub struct Journal {
+    path: PathBuf,
+    writer: BufWriter<File>,
+}
+
+impl Journal {
+    /// Opens a journal for writing.
+    ///
+    /// Entries are appended to any that are already there, so that a resumed run can keep using the journal of the
+    /// run it is picking up from, and undo both at once.
+    pub fn create<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+
+        Ok(Journal {
+            path: path.as_ref().to_owned(),
+            writer: BufWriter::new(file),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Appends an entry to the journal.
+    ///
+    /// The journal is flushed after every entry, so that it stays accurate even if the run is interrupted.
+    pub fn record(&mut self, entry: &Entry) -> io::Result<()> {
+        serde_json::to_writer(&mut self.writer, entry)?;
+        self.writer.write_all(b"\n")?;
+        self.writer.flush()
+    }
+}
+
+/// Where a command's journal goes when the user does not choose a path; timestamped so earlier runs are never
+/// overwritten.
+pub fn default_path(command: &str) -> PathBuf {
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or(0);
+
+    PathBuf::from(format!("{}-journal-{}.jsonl", command, seconds))
+}
+
+/// Reads every entry out of a journal, in the order they were recorded.
+pub fn read<P: AsRef<Path>>(path: P) -> io::Result<Vec<Entry>> {
+    let reader = BufReader::new(File::open(path)?);
+    let mut entries = vec![];
+
+    for line in reader.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        entries.push(serde_json::from_str(&line)?);
+    }
+
+    Ok(entries)
+}
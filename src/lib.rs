@@ -0,0 +1,55 @@
+//! Projects when work in JIRA will be done, from the story points left on its issues and a team's velocity.
+//!
+//! The `statustracker` command is a thin layer over this library, so the same calculations can be made from another
+//! program without shelling out.  The parts meant to be used that way are:
+//!
+//! - [JQLBuilder], to put together the search for the issues to look at.
+//! - [Estimator], to add up how much work is left in them and how many sprints that is.
+//! - [IssueExporter], to write a row for each of them to a CSV file.
+//!
+//! They all work against [jira::api::JiraApi], which [jira::connect] makes a client for:
+//!
+//! ```no_run
+//! use statustracker::{
+//!     jira::{self, client::{Credentials, HttpSettings}},
+//!     Estimator, JQLBuilder,
+//! };
+//!
+//! # async fn estimate() -> Result<(), Box<dyn std::error::Error>> {
+//! let credentials = Credentials::Basic {
+//!     username: "me@example.com".to_owned(),
+//!     token: "API token".to_owned(),
+//! };
+//! let client = jira::connect("https://example.atlassian.net", &credentials, HttpSettings::default())?;
+//!
+//! let jql = JQLBuilder::new().project("PROJ").build()?;
+//! let story_point_field_ids = jira::get_field_ids_named(&client, "Story Points").await?;
+//! let results = Estimator::new(20.0)
+//!     .estimate(&client, &jql, &story_point_field_ids)
+//!     .await?;
+//! println!("{:.1} sprints left", results.num_sprints_remaining);
+//! # Ok(())
+//! # }
+//! ```
+mod audit;
+mod bulk;
+mod cache;
+mod chart;
+mod checkpoint;
+/// The commands of the `statustracker` binary.  These print their results and exit on errors, so they aren't for use
+/// from other programs.
+#[doc(hidden)]
+pub mod cli;
+pub mod date;
+mod email;
+pub mod estimator;
+pub mod export;
+pub mod jira;
+mod journal;
+mod slack;
+pub mod snapshot;
+mod template;
+
+pub use estimator::Estimator;
+pub use export::IssueExporter;
+pub use jira::jql::builder::JQLBuilder;
@@ -1,20 +1,103 @@
-mod cli;
-mod jira;
-
 use std::process;
 
-use cli::{csv, estimate, tag, StatusTracker};
+use tokio::runtime;
+
+use statustracker::cli::{
+    aging, apply, blocked, bugs, burndown, burnup, cfd, config, counts, critical_path, csv,
+    cycle_time, deps, detect_fields, doctor, epics, estimate, forecast, gantt, history, hygiene,
+    ical, import, init, list_fields, list_issue_types, list_projects, list_statuses,
+    publish_confluence, rank, release_readiness, report, roadmap, save_snapshot, serve, sla,
+    sprint_fit, sprint_report, standup, tag, throughput, time_in_status, watch, whoami,
+    StatusTracker,
+};
 
 fn main() {
-    let args = cli::parse();
-    if args.is_err() {
-        println!("{}", args.unwrap_err());
-        process::exit(1)
+    let args = match statustracker::cli::parse() {
+        Ok(args) => args,
+        Err(e) => {
+            println!("{}", e);
+            process::exit(1)
+        }
+    };
+
+    if args.watch.is_watching() {
+        watch::run(&args.watch)
     }
 
-    match args.unwrap() {
-        StatusTracker::CSV(csv_args) => csv::run(&csv_args),
-        StatusTracker::Estimate(estimate_args) => estimate::run(&estimate_args),
-        StatusTracker::Tag(tag_args) => tag::run(&tag_args),
+    // Every command shares one runtime, so concurrent requests are tasks on it rather than threads of their own.
+    let runtime = match runtime::Builder::new_multi_thread().enable_all().build() {
+        Ok(runtime) => runtime,
+        Err(e) => {
+            eprintln!("Could not start the async runtime: {}", e);
+            process::exit(1)
+        }
     };
+
+    runtime.block_on(async {
+        match args.command {
+            StatusTracker::Aging(aging_args) => aging::run(&aging_args).await,
+            StatusTracker::Apply(apply_args) => apply::run(&apply_args).await,
+            StatusTracker::Audit(audit_args) => hygiene::run(&audit_args).await,
+            StatusTracker::Blocked(blocked_args) => blocked::run(&blocked_args).await,
+            StatusTracker::Bugs(bugs_args) => bugs::run(&bugs_args).await,
+            StatusTracker::Burndown(burndown_args) => burndown::run(&burndown_args).await,
+            StatusTracker::Burnup(burnup_args) => burnup::run(&burnup_args).await,
+            StatusTracker::Cfd(cfd_args) => cfd::run(&cfd_args).await,
+            StatusTracker::Config(config_args) => config::run(&config_args).await,
+            StatusTracker::Counts(counts_args) => counts::run(&counts_args).await,
+            StatusTracker::CriticalPath(critical_path_args) => {
+                critical_path::run(&critical_path_args).await
+            }
+            StatusTracker::Csv(csv_args) => csv::run(&csv_args).await,
+            StatusTracker::CycleTime(cycle_time_args) => cycle_time::run(&cycle_time_args).await,
+            StatusTracker::Deps(deps_args) => deps::run(&deps_args).await,
+            StatusTracker::DetectFields(detect_fields_args) => {
+                detect_fields::run(&detect_fields_args).await
+            }
+            StatusTracker::Doctor(doctor_args) => doctor::run(&doctor_args).await,
+            StatusTracker::Epics(epics_args) => epics::run(&epics_args).await,
+            StatusTracker::Estimate(estimate_args) => estimate::run(&estimate_args).await,
+            StatusTracker::Forecast(forecast_args) => forecast::run(&forecast_args).await,
+            StatusTracker::Gantt(gantt_args) => gantt::run(&gantt_args).await,
+            StatusTracker::History(history_args) => history::run(&history_args).await,
+            StatusTracker::Ical(ical_args) => ical::run(&ical_args).await,
+            StatusTracker::Import(import_args) => import::run(&import_args).await,
+            StatusTracker::Init(init_args) => init::run(&init_args).await,
+            StatusTracker::ListFields(list_fields_args) => {
+                list_fields::run(&list_fields_args).await
+            }
+            StatusTracker::ListIssueTypes(list_issue_types_args) => {
+                list_issue_types::run(&list_issue_types_args).await
+            }
+            StatusTracker::ListProjects(list_projects_args) => {
+                list_projects::run(&list_projects_args).await
+            }
+            StatusTracker::ListStatuses(list_statuses_args) => {
+                list_statuses::run(&list_statuses_args).await
+            }
+            StatusTracker::PublishConfluence(publish_confluence_args) => {
+                publish_confluence::run(&publish_confluence_args).await
+            }
+            StatusTracker::Rank(rank_args) => rank::run(&rank_args).await,
+            StatusTracker::ReleaseReadiness(release_readiness_args) => {
+                release_readiness::run(&release_readiness_args).await
+            }
+            StatusTracker::Report(report_args) => report::run(&report_args).await,
+            StatusTracker::Roadmap(roadmap_args) => roadmap::run(&roadmap_args).await,
+            StatusTracker::Serve(serve_args) => serve::run(&serve_args).await,
+            StatusTracker::Sla(sla_args) => sla::run(&sla_args).await,
+            StatusTracker::Snapshot(snapshot_args) => save_snapshot::run(&snapshot_args).await,
+            StatusTracker::SprintFit(sprint_fit_args) => sprint_fit::run(&sprint_fit_args).await,
+            StatusTracker::SprintReport(sprint_report_args) => {
+                sprint_report::run(&sprint_report_args).await
+            }
+            StatusTracker::Standup(standup_args) => standup::run(&standup_args).await,
+            StatusTracker::Tag(tag_args) => tag::run(&tag_args).await,
+            StatusTracker::Throughput(throughput_args) => throughput::run(&throughput_args).await,
+            StatusTracker::TimeInStatus(time_in_status_args) => {
+                time_in_status::run(&time_in_status_args).await
+            }
+            StatusTracker::Whoami(whoami_args) => whoami::run(&whoami_args).await,
+        }
+    });
 }
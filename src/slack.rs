@@ -0,0 +1,49 @@
+/// Posts messages to a Slack channel through an incoming webhook, so that scheduled runs can share what they found.
+///
+/// Messages are laid out with Block Kit, which is described at https://api.slack.com/block-kit.
+use std::time::Duration;
+
+use serde_json::Value as JSONValue;
+
+/// How long to wait for Slack before giving up on the message.
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+/// The colors of the bar down the side of a message, for how things stand.
+pub const GOOD: &str = "#2eb886";
+pub const WARNING: &str = "#daa038";
+pub const DANGER: &str = "#a30200";
+
+/// Escapes the characters that Slack's mrkdwn treats as markup.
+pub fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// Sends a message to the channel the webhook belongs to.
+pub async fn post(webhook_url: &str, message: &JSONValue) -> Result<(), String> {
+    let client = reqwest::Client::builder()
+        .timeout(TIMEOUT)
+        .build()
+        .map_err(|e| format!("Could not set up a connection to Slack: {}", e))?;
+
+    // The webhook's URL is its secret, so it's left out of every error.
+    let response = client
+        .post(webhook_url)
+        .json(message)
+        .send()
+        .await
+        .map_err(|e| format!("Could not send the message to Slack: {}", e.without_url()))?;
+    let status = response.status();
+    if status.is_success() {
+        return Ok(());
+    }
+
+    // Slack says what was wrong in a short plain text body, like invalid_blocks or no_service.
+    let reason = response.text().await.unwrap_or_default();
+    Err(if reason.is_empty() {
+        format!("Slack would not take the message ({}).", status)
+    } else {
+        format!("Slack would not take the message ({}): {}", status, reason)
+    })
+}
@@ -0,0 +1,150 @@
+/// Saves the issues a search found, as JIRA sent them, so that they can be looked at again later without asking JIRA.
+///
+/// Snapshots are gzipped JSON documents.  Along with the issues, they hold the site's fields, so that fields can still
+/// be looked up by name, and the search that found them.
+use std::{
+    error::Error,
+    fmt::{self, Display},
+    fs::File,
+    io::{self, BufReader, BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    date::DateTime,
+    jira::{
+        self,
+        client::{Field, SearchIssue},
+    },
+};
+
+/// The layout of the snapshots written by this version.  This goes up whenever the layout changes in a way that older
+/// versions couldn't read.
+pub const VERSION: u32 = 1;
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Snapshot {
+    pub version: u32,
+
+    /// When the issues were fetched, as RFC 3339.
+    pub taken_at: String,
+
+    pub jira_url: String,
+
+    /// The search that found the issues.
+    pub jql: String,
+
+    /// Every field on the site, whether or not the issues were fetched with it.
+    pub fields: Vec<Field>,
+
+    pub issues: Vec<SearchIssue>,
+}
+
+#[derive(Debug)]
+pub enum SnapshotError {
+    Io(PathBuf, io::Error),
+    Malformed(PathBuf, serde_json::Error),
+    /// The snapshot was written by a newer version, in a layout this one doesn't know.
+    UnsupportedVersion(PathBuf, u32),
+}
+
+impl Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SnapshotError::Io(path, inner) => {
+                write!(f, "Could not read or write {}: {}", path.display(), inner)
+            }
+            SnapshotError::Malformed(path, inner) => write!(
+                f,
+                "{} is not a snapshot, or is damaged: {}",
+                path.display(),
+                inner
+            ),
+            SnapshotError::UnsupportedVersion(path, version) => write!(
+                f,
+                "{} is a version {} snapshot, but this version of statustracker only reads up to version {}.  Upgrade statustracker to read it.",
+                path.display(),
+                version,
+                VERSION
+            ),
+        }
+    }
+}
+
+impl Error for SnapshotError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            SnapshotError::Io(_, inner) => Some(inner),
+            SnapshotError::Malformed(_, inner) => Some(inner),
+            SnapshotError::UnsupportedVersion(_, _) => None,
+        }
+    }
+}
+
+/// Only as much of a snapshot as it takes to tell whether the rest can be read.
+#[derive(Debug, Deserialize)]
+struct Header {
+    version: u32,
+}
+
+impl Snapshot {
+    pub fn new(jira_url: &str, jql: String, fields: Vec<Field>, issues: Vec<SearchIssue>) -> Self {
+        Snapshot {
+            version: VERSION,
+            taken_at: DateTime::now().to_rfc3339(),
+            jira_url: jira_url.to_owned(),
+            jql,
+            fields,
+            issues,
+        }
+    }
+
+    /// When the issues were fetched, or `None` if the time can't be read.
+    pub fn taken_at(&self) -> Option<DateTime> {
+        DateTime::parse_rfc3339(&self.taken_at)
+    }
+
+    /// Finds the IDs of every field with the given name, as `jira::get_field_ids_named` does on a live site.
+    pub fn field_ids_named(&self, field_name: &str) -> Vec<String> {
+        jira::field_ids_named(&self.fields, field_name)
+    }
+
+    pub fn write<P: AsRef<Path>>(&self, path: P) -> Result<(), SnapshotError> {
+        let path = path.as_ref();
+        let io_error = |e| SnapshotError::Io(path.to_owned(), e);
+
+        let file = File::create(path).map_err(io_error)?;
+        let mut encoder = GzEncoder::new(BufWriter::new(file), Compression::default());
+        serde_json::to_writer(&mut encoder, self)
+            .map_err(|e| SnapshotError::Io(path.to_owned(), e.into()))?;
+        encoder
+            .finish()
+            .map_err(io_error)?
+            .flush()
+            .map_err(io_error)
+    }
+
+    pub fn read<P: AsRef<Path>>(path: P) -> Result<Self, SnapshotError> {
+        let path = path.as_ref();
+        let open = || {
+            File::open(path)
+                .map(|file| GzDecoder::new(BufReader::new(file)))
+                .map_err(|e| SnapshotError::Io(path.to_owned(), e))
+        };
+
+        // The version is checked first, so that a newer layout is reported as that rather than as a damaged file.
+        let header: Header = serde_json::from_reader(open()?)
+            .map_err(|e| SnapshotError::Malformed(path.to_owned(), e))?;
+        if header.version > VERSION {
+            return Err(SnapshotError::UnsupportedVersion(
+                path.to_owned(),
+                header.version,
+            ));
+        }
+
+        serde_json::from_reader(open()?).map_err(|e| SnapshotError::Malformed(path.to_owned(), e))
+    }
+}
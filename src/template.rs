@@ -0,0 +1,123 @@
+/// Expands placeholders in values given on the command line, like `planned-{today:%Y-%m}` or `scope-{sprint}`, so
+/// that scheduled jobs do not need a new value hard-coded for every run.
+///
+/// The placeholders are:
+///
+/// * `{today}`, the current date in UTC, like 2023-02-14.
+/// * `{today:FORMAT}`, the current date in UTC, formatted with strftime-style specifiers like `%Y` and `%m`.
+/// * `{sprint}`, the name of the sprint the command is working with.
+use std::{error::Error, fmt};
+
+use crate::date::DateTime;
+
+/// The values placeholders can be replaced with.
+pub struct Context<'a> {
+    pub today: DateTime,
+
+    /// `None` when there is no sprint to refer to.
+    pub sprint: Option<&'a str>,
+}
+
+#[derive(Debug)]
+pub enum TemplateError {
+    UnclosedPlaceholder(String),
+    UnknownPlaceholder(String, String),
+    UnsupportedDateFormat(String, String),
+    NoSprint(String),
+}
+
+impl fmt::Display for TemplateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TemplateError::UnclosedPlaceholder(template) => {
+                write!(f, "\"{}\" has a {{ without a matching }}.", template)
+            }
+            TemplateError::UnknownPlaceholder(template, placeholder) => {
+                write!(f, "\"{}\" has a placeholder, {{{}}}, that isn't one of {{today}}, {{today:FORMAT}}, or {{sprint}}.", template, placeholder)
+            }
+            TemplateError::UnsupportedDateFormat(template, specifier) => {
+                write!(f, "\"{}\" formats the date with {}, which isn't supported.  Use %Y, %y, %m, %d, %H, %M, %S, %j, %u, %G, or %V.", template, specifier)
+            }
+            TemplateError::NoSprint(template) => {
+                write!(f, "\"{}\" uses {{sprint}}, but there is no sprint to fill in.  Pass --move-to-sprint, or --jira-board to use the board's active sprint.", template)
+            }
+        }
+    }
+}
+
+impl Error for TemplateError {}
+
+/// A run of literal text, or the inside of a placeholder, from a template.
+enum Piece<'a> {
+    Literal(&'a str),
+    Placeholder(&'a str),
+}
+
+fn parse(template: &str) -> Result<Vec<Piece<'_>>, TemplateError> {
+    let mut pieces = vec![];
+    let mut rest = template;
+
+    while let Some(start) = rest.find('{') {
+        let end = rest[start..]
+            .find('}')
+            .ok_or_else(|| TemplateError::UnclosedPlaceholder(template.to_owned()))?;
+
+        pieces.push(Piece::Literal(&rest[..start]));
+        pieces.push(Piece::Placeholder(&rest[start + 1..start + end]));
+        rest = &rest[start + end + 1..];
+    }
+    pieces.push(Piece::Literal(rest));
+
+    Ok(pieces)
+}
+
+/// Whether a template refers to the sprint, so callers only look the sprint up when they have to.
+pub fn uses_sprint(template: &str) -> bool {
+    parse(template)
+        .map(|pieces| {
+            pieces
+                .iter()
+                .any(|piece| matches!(piece, Piece::Placeholder("sprint")))
+        })
+        .unwrap_or(false)
+}
+
+/// Replaces every placeholder in a template.  Text outside of placeholders is left as-is.
+pub fn expand(template: &str, context: &Context) -> Result<String, TemplateError> {
+    let mut expanded = String::new();
+
+    for piece in parse(template)? {
+        let placeholder = match piece {
+            Piece::Literal(literal) => {
+                expanded.push_str(literal);
+                continue;
+            }
+            Piece::Placeholder(placeholder) => placeholder,
+        };
+
+        let date_format = match placeholder.split_once(':') {
+            None if placeholder == "today" => "%Y-%m-%d",
+            Some(("today", format)) => format,
+            None if placeholder == "sprint" => {
+                let sprint = context
+                    .sprint
+                    .ok_or_else(|| TemplateError::NoSprint(template.to_owned()))?;
+                expanded.push_str(sprint);
+                continue;
+            }
+            _ => {
+                return Err(TemplateError::UnknownPlaceholder(
+                    template.to_owned(),
+                    placeholder.to_owned(),
+                ))
+            }
+        };
+
+        let date = context.today.format(date_format).map_err(|specifier| {
+            TemplateError::UnsupportedDateFormat(template.to_owned(), specifier)
+        })?;
+        expanded.push_str(&date);
+    }
+
+    Ok(expanded)
+}